@@ -0,0 +1,291 @@
+use xdns_data::models::{
+    Data, Domain, DomainRenew, Signature, SubDomain, Validity, ValidityTransfer,
+};
+use xdns_data::parser::{ActionParser, DomainAction};
+
+use crate::journal::Block;
+
+/// A single change to a repository, see the [`Repository`](crate::XDNSRepository) method of the same name.
+#[derive(Debug)]
+pub enum Operation {
+    AddDomain {
+        address: String,
+        inscription: String,
+        domain: Domain,
+    },
+    RemoveDomain {
+        domain: String,
+    },
+    RemoveDomainByInscription {
+        inscription: String,
+    },
+    /// Removes a released domain together with every subdomain, validity and data stored under its name.
+    /// Rejected if the domain does not exist or is not released.
+    ExpireDomain {
+        domain: String,
+    },
+    /// Extends the registration of a domain, see [`ExpiryPolicy::renewed_valid_from`](crate::ExpiryPolicy::renewed_valid_from).
+    /// Rejected unless it was inscribed by the owner, or signed with the validity key of the domain
+    /// over the last renewal of the domain (its registration if it was never renewed), so a signature can't be replayed.
+    RenewDomain {
+        address: String,
+        inscription: String,
+        renewal: DomainRenew,
+        signature: Option<Signature>,
+    },
+    AddSubdomain {
+        address: String,
+        inscription: String,
+        subdomain: SubDomain,
+    },
+    RemoveSubdomains {
+        domain: String,
+        subdomain: String,
+    },
+    RemoveSubdomain {
+        inscription: String,
+    },
+    AddValidity {
+        address: String,
+        inscription: String,
+        validity: Validity,
+    },
+    RemoveValidity {
+        domain: String,
+    },
+    RemoveValidityByInscription {
+        inscription: String,
+    },
+    /// Replaces (or removes) the validity of a domain.
+    /// Rejected unless the address owns the domain, and the domain has a validity.
+    UpdateValidity {
+        address: String,
+        validity: ValidityTransfer,
+    },
+    UpdateValidityByInscription {
+        address: String,
+        inscription: String,
+        validity: ValidityTransfer,
+    },
+    AddData {
+        address: String,
+        inscription: String,
+        data: Data,
+    },
+    RemoveData {
+        domain: String,
+    },
+    RemoveDataByInscription {
+        inscription: String,
+    },
+    TransferInscription {
+        inscription: String,
+        new_owner: String,
+    },
+    /// Removes the subdomain, validity or data that was inscribed by an inscription.
+    /// Rejected if the inscription did not inscribe any of them, or they are not stored under the address.
+    Drop {
+        address: String,
+        inscription: String,
+    },
+}
+
+/// A unit of work: operations that are applied all together, or not at all.
+///
+/// A batch is applied with [`Repository::commit`](crate::XDNSRepository::commit).
+/// If any of its operations is rejected (eg a validity for a domain that already has one),
+/// none of the operations are applied. Dropping a batch discards it.
+///
+/// A batch committed at a [`Block`](Block) is journaled, so it can be rolled back
+/// with [`Repository::rollback_to`](crate::XDNSRepository::rollback_to) if that block is reorganized away.
+/// A batch without a block is journaled at the tip, it is rolled back together with the tip.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut batch = Batch::new().with_block(Block::new(height, hash));
+/// batch.push(Operation::AddDomain { address, inscription, domain });
+/// batch.push(Operation::AddValidity { address, inscription, validity });
+///
+/// db.commit(batch).await?;
+/// ```
+#[derive(Debug, Default)]
+pub struct Batch {
+    operations: Vec<Operation>,
+    block: Option<Block>,
+}
+
+impl Operation {
+    /// Gets the name of the operation, as used in errors.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AddDomain { .. } => "add_domain",
+            Self::RemoveDomain { .. } => "remove_domain",
+            Self::RemoveDomainByInscription { .. } => "remove_domain_by_inscription",
+            Self::ExpireDomain { .. } => "expire_domain",
+            Self::RenewDomain { .. } => "renew_domain",
+            Self::AddSubdomain { .. } => "add_subdomain",
+            Self::RemoveSubdomains { .. } => "remove_subdomains",
+            Self::RemoveSubdomain { .. } => "remove_subdomain",
+            Self::AddValidity { .. } => "add_validity",
+            Self::RemoveValidity { .. } => "remove_validity",
+            Self::RemoveValidityByInscription { .. } => "remove_validity_by_inscription",
+            Self::UpdateValidity { .. } => "update_validity",
+            Self::UpdateValidityByInscription { .. } => "update_validity_by_inscription",
+            Self::AddData { .. } => "add_data",
+            Self::RemoveData { .. } => "remove_data",
+            Self::RemoveDataByInscription { .. } => "remove_data_by_inscription",
+            Self::TransferInscription { .. } => "transfer_inscription",
+            Self::Drop { .. } => "drop",
+        }
+    }
+}
+
+impl Batch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a batch with the operations of all actions of a single inscription.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address that inscribed the actions.
+    /// * `inscription` - The inscription id the actions were inscribed in.
+    /// * `actions` - The parsed actions, in order.
+    ///
+    /// # Returns
+    ///
+    /// The batch, applying the actions in the same order.
+    pub fn from_actions(
+        address: &str,
+        inscription: &str,
+        actions: impl IntoIterator<Item = DomainAction>,
+    ) -> Self {
+        let mut batch = Self::new();
+
+        for action in actions {
+            let (address, inscription) = (address.to_string(), inscription.to_string());
+
+            batch.push(match action {
+                DomainAction::Domain(domain) => Operation::AddDomain {
+                    address,
+                    inscription,
+                    domain,
+                },
+                DomainAction::Subdomain(subdomain) => Operation::AddSubdomain {
+                    address,
+                    inscription,
+                    subdomain,
+                },
+                DomainAction::Drop(drop) => Operation::Drop {
+                    address,
+                    inscription: drop.inscription,
+                },
+                DomainAction::Validity(validity) => Operation::AddValidity {
+                    address,
+                    inscription,
+                    validity,
+                },
+                DomainAction::ValidityTransfer(validity) => {
+                    Operation::UpdateValidity { address, validity }
+                }
+                DomainAction::Data(data) => Operation::AddData {
+                    address,
+                    inscription,
+                    data,
+                },
+                DomainAction::Renew(renewal) => Operation::RenewDomain {
+                    address,
+                    inscription,
+                    renewal,
+                    signature: None,
+                },
+            });
+        }
+
+        batch
+    }
+
+    /// Creates a batch with the operations of a parsed inscription.
+    /// Unlike [`from_actions`](Self::from_actions) the signature is kept, so a renewal signed with
+    /// the validity key of a domain is accepted from any address.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address that inscribed the actions.
+    /// * `inscription` - The inscription id the actions were inscribed in.
+    /// * `parser` - The parsed inscription.
+    pub fn from_parser(address: &str, inscription: &str, parser: ActionParser) -> Self {
+        let mut batch = Self::from_actions(address, inscription, parser.actions);
+
+        for operation in batch.operations.iter_mut() {
+            if let Operation::RenewDomain { signature, .. } = operation {
+                signature.clone_from(&parser.signature);
+            }
+        }
+
+        batch
+    }
+
+    /// Sets the block the batch is committed at.
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - The block the operations were inscribed in.
+    pub fn with_block(mut self, block: Block) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Gets the block the batch is committed at, if any.
+    pub fn block(&self) -> Option<&Block> {
+        self.block.as_ref()
+    }
+
+    /// Adds an operation to the end of the batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation to add.
+    pub fn push(&mut self, operation: Operation) -> &mut Self {
+        self.operations.push(operation);
+        self
+    }
+
+    /// Gets the operations of the batch, in order.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Gets the amount of operations in the batch.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether the batch does not contain any operations.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Takes the operations out of the batch, in order.
+    pub fn into_operations(self) -> Vec<Operation> {
+        self.operations
+    }
+
+    /// Takes the block and operations out of the batch.
+    pub fn into_parts(self) -> (Option<Block>, Vec<Operation>) {
+        (self.block, self.operations)
+    }
+}
+
+impl From<Operation> for Batch {
+    /// Creates a batch of a single operation, without a block.
+    fn from(operation: Operation) -> Self {
+        Self {
+            operations: vec![operation],
+            block: None,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Exports the name state of a database into a snapshot file, or imports one into an empty database.
+//!
+//! Usage: `xdns-snapshot export <database url> <snapshot path>`
+//! or `xdns-snapshot import <snapshot path> <database url> <expected hash>`
+//! (eg `xdns-snapshot import xdns.snapshot redb://xdns.redb 3f2a...`)
+//!
+//! The expected hash has to come from a trusted source (eg the node the snapshot was exported from),
+//! a snapshot with any other hash is refused.
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::process::ExitCode;
+
+use db::{
+    DatabaseBackend, DatabaseConfig, KvRepository, SqliteRepository, StateSnapshot, XDNSRepository,
+};
+use shared::common::Result;
+
+fn describe(snapshot: &StateSnapshot) -> String {
+    match snapshot.height() {
+        Some(height) => format!(
+            "{} domains at height {} (hash {})",
+            snapshot.len(),
+            height,
+            snapshot.hash()
+        ),
+        None => format!("{} domains (hash {})", snapshot.len(), snapshot.hash()),
+    }
+}
+
+async fn export<R: XDNSRepository>(source: R, path: &str) -> Result<()> {
+    let snapshot = source.export_snapshot().await?;
+    snapshot.write_to(BufWriter::new(File::create(path)?))?;
+
+    println!("Exported {} into {}", describe(&snapshot), path);
+    Ok(())
+}
+
+async fn import<R: XDNSRepository>(path: &str, target: R, expected_hash: &str) -> Result<()> {
+    let snapshot = StateSnapshot::read_from(BufReader::new(File::open(path)?))?;
+    let description = describe(&snapshot);
+    target.import_snapshot(snapshot, expected_hash).await?;
+
+    println!("Imported and verified {}", description);
+    Ok(())
+}
+
+async fn run(command: &str, first: &str, second: &str, expected_hash: &str) -> Result<()> {
+    let url = match command {
+        "export" => first,
+        _ => second,
+    };
+    // A new database has to be migrated before anything can be imported.
+    let config = DatabaseConfig::new(url)
+        .with_logging(false)
+        .with_migrate(command == "import");
+
+    match (command, config.backend()?) {
+        ("export", DatabaseBackend::Redb) => export(KvRepository::connect(&config)?, second).await,
+        ("export", _) => export(SqliteRepository::connect(&config).await?, second).await,
+        (_, DatabaseBackend::Redb) => {
+            import(first, KvRepository::connect(&config)?, expected_hash).await
+        }
+        (_, _) => {
+            import(
+                first,
+                SqliteRepository::connect(&config).await?,
+                expected_hash,
+            )
+            .await
+        }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    let valid = match args.get(1).map(String::as_str) {
+        Some("export") => args.len() == 4,
+        Some("import") => args.len() == 5,
+        _ => false,
+    };
+
+    if !valid {
+        eprintln!(
+            "Usage: {0} export <database url> <snapshot path>\n       {0} import <snapshot path> <database url> <expected hash>",
+            args[0]
+        );
+        return ExitCode::FAILURE;
+    }
+
+    let expected_hash = args.get(4).map(String::as_str).unwrap_or_default();
+
+    match run(&args[1], &args[2], &args[3], expected_hash).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Snapshot {} failed: {}", args[1], e);
+            ExitCode::FAILURE
+        }
+    }
+}
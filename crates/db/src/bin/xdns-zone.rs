@@ -0,0 +1,44 @@
+//! Writes a domain of a database as a zone file in RFC 1035 master format, to standard output.
+//!
+//! Usage: `xdns-zone <database url> <domain>`
+//! (eg `xdns-zone sqlite://xdns.db example.o > example.o.zone`)
+
+use std::env;
+use std::process::ExitCode;
+
+use db::{
+    DatabaseBackend, DatabaseConfig, KvRepository, SqliteRepository, XDNSRepository, ZoneExporter,
+};
+use shared::common::Result;
+
+async fn export<R: XDNSRepository>(source: R, domain: &str) -> Result<()> {
+    print!("{}", ZoneExporter::new().export(&source, domain).await?);
+    Ok(())
+}
+
+async fn run(url: &str, domain: &str) -> Result<()> {
+    let config = DatabaseConfig::new(url).with_logging(false);
+
+    match config.backend()? {
+        DatabaseBackend::Redb => export(KvRepository::connect(&config)?, domain).await,
+        _ => export(SqliteRepository::connect(&config).await?, domain).await,
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 3 {
+        eprintln!("Usage: {} <database url> <domain>", args[0]);
+        return ExitCode::FAILURE;
+    }
+
+    match run(&args[1], &args[2]).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Zone export of {} failed: {}", args[2], e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,630 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use shared::common::Result;
+use xdns_data::models::{
+    Data, Domain, DomainRenew, Signature, SubDomain, Validity, ValidityTransfer,
+};
+
+use crate::batch::Batch;
+use crate::changes::ChangeFeed;
+use crate::commitment::{NameProof, StateRoot};
+use crate::expiry::ExpiryPolicy;
+use crate::history::HistoryEntry;
+use crate::journal::Block;
+use crate::query::{NameFilter, Page};
+use crate::snapshot::StateSnapshot;
+use crate::traits::Repository;
+
+/// The lookups of a single domain name that were cached.
+struct CachedName {
+    expires: Instant,
+    domain: Option<(String, Domain)>,
+    subdomain_names: Option<Vec<String>>,
+    subdomains: HashMap<String, Vec<(String, SubDomain)>>,
+    inscriptions: HashMap<String, Vec<(String, SubDomain)>>,
+    validity: Option<(String, Validity)>,
+}
+
+impl CachedName {
+    fn new(expires: Instant) -> Self {
+        Self {
+            expires,
+            domain: None,
+            subdomain_names: None,
+            subdomains: HashMap::new(),
+            inscriptions: HashMap::new(),
+            validity: None,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires <= Instant::now()
+    }
+}
+
+/// The cached names, and the generation of the cache.
+///
+/// The generation is advanced by every invalidation, a lookup that was read before it is never cached,
+/// as it can hold the rows from before the change that caused the invalidation.
+#[derive(Default)]
+struct CachedNames {
+    names: HashMap<String, CachedName>,
+    generation: u64,
+}
+
+/// Wraps any repository, caching the lookups of domains, subdomains and validities.
+///
+/// Lookups are read through: the first lookup of a name hits the wrapped repository,
+/// later lookups are answered from memory until the entry of the name is older than the [ttl](Self::with_ttl),
+/// or until the domain expires, whichever comes first.
+/// Only successful lookups are cached, errors (eg a missing domain) are never.
+///
+/// Mutations are written through to the wrapped repository, after which the cached lookups of
+/// every name they can change are dropped. Mutations made by another process are only noticed once the
+/// entries of the names they changed expired, unless the changes of a [`HistoryFollower`](crate::HistoryFollower)
+/// are [invalidated](Self::invalidate).
+///
+/// # Example
+///
+/// ```ignore
+/// let db = CachedRepository::wrap(SqliteRepository::new().await?).with_ttl(Duration::from_secs(30));
+///
+/// // Hits the database.
+/// db.get_subdomain("example.o", "www.").await?;
+/// // Answered from memory.
+/// db.get_subdomain("example.o", "www.").await?;
+/// ```
+pub struct CachedRepository<R: Repository> {
+    inner: R,
+    names: Mutex<CachedNames>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl<R: Repository> CachedRepository<R> {
+    /// How long lookups are cached by default.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+    /// The amount of names that are cached by default.
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    /// Wraps a repository, with the default ttl and capacity.
+    pub fn wrap(inner: R) -> Self {
+        Self {
+            inner,
+            names: Mutex::new(CachedNames::default()),
+            ttl: Self::DEFAULT_TTL,
+            capacity: Self::DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Sets how long the lookups of a name are cached, which bounds how long a change made by
+    /// another process (or a domain that expired) can go unnoticed.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the amount of names that are cached at most.
+    /// Once reached, expired names are dropped, or every name if none expired.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Gets the wrapped repository, lookups on it bypass the cache.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Takes the wrapped repository out of the cache.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Drops the cached lookups of a domain name.
+    /// Lookups of any name that are still being read are not cached once they complete.
+    pub fn invalidate(&self, domain: &str) {
+        let mut names = self.names();
+
+        names.names.remove(domain);
+        names.generation += 1;
+    }
+
+    /// Drops every cached lookup.
+    pub fn clear(&self) {
+        let mut names = self.names();
+
+        names.names.clear();
+        names.generation += 1;
+    }
+
+    /// Gets the amount of names with cached lookups, including expired ones.
+    pub fn len(&self) -> usize {
+        self.names().names.len()
+    }
+
+    /// Whether no lookups are cached.
+    pub fn is_empty(&self) -> bool {
+        self.names().names.is_empty()
+    }
+
+    fn names(&self) -> MutexGuard<'_, CachedNames> {
+        self.names.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Gets the current generation, which has to be read before the lookup that will be cached.
+    fn generation(&self) -> u64 {
+        self.names().generation
+    }
+
+    /// Gets when the lookups of a domain expire: after the ttl, or once the domain is no longer active.
+    fn expires(&self, domain: &Domain) -> Instant {
+        let active = (domain.valid_from + self.inner.policy().lifetime)
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO);
+
+        Instant::now() + self.ttl.min(active)
+    }
+
+    /// Gets a cached lookup of a name, if it did not expire.
+    fn cached<T, F>(&self, domain: &str, get: F) -> Option<T>
+    where
+        F: FnOnce(&CachedName) -> Option<T>,
+    {
+        self.names()
+            .names
+            .get(domain)
+            .filter(|name| !name.is_expired())
+            .and_then(get)
+    }
+
+    /// Caches a lookup of a name, the expired lookups of the name are dropped first.
+    /// Nothing is cached if the cache was invalidated since the lookup started.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain name the lookup belongs to.
+    /// * `generation` - The generation that was read before the lookup started.
+    /// * `expires` - When the lookup expires, see [`expires`](Self::expires).
+    /// * `set` - Stores the lookup in the cached lookups of the name.
+    fn cache<F>(&self, domain: &str, generation: u64, expires: Instant, set: F)
+    where
+        F: FnOnce(&mut CachedName),
+    {
+        let mut names = self.names();
+
+        if names.generation != generation {
+            return;
+        }
+
+        if !names.names.contains_key(domain) && names.names.len() >= self.capacity {
+            names.names.retain(|_, name| !name.is_expired());
+
+            if names.names.len() >= self.capacity {
+                names.names.clear();
+            }
+        }
+
+        let name = names
+            .names
+            .entry(domain.to_string())
+            .or_insert_with(|| CachedName::new(expires));
+
+        if name.is_expired() {
+            *name = CachedName::new(expires);
+        }
+
+        set(name);
+    }
+
+    /// Gets when the lookups of a domain expire, through the cached lookup of the domain.
+    /// Every other lookup of the name is only cached along with it, so it is dropped once the domain expires.
+    async fn domain_expires(&self, domain: &str) -> Result<Instant> {
+        let (_, found) = self.get_domain(domain).await?;
+
+        Ok(self.expires(&found))
+    }
+
+    /// Gets the names a batch can change, or `None` if it changes rows by inscription,
+    /// which can be rows of any name.
+    fn batch_names(batch: &Batch) -> Option<Vec<String>> {
+        let mut names = Vec::new();
+
+        for operation in batch.operations() {
+            let scope = operation.scope();
+
+            if scope.inscription.is_some() {
+                return None;
+            }
+
+            names.extend(scope.names.into_iter().map(str::to_string));
+        }
+
+        Some(names)
+    }
+}
+
+impl<R: Repository> Repository for CachedRepository<R> {
+    async fn new() -> Result<Self> {
+        Ok(Self::wrap(R::new().await?))
+    }
+
+    async fn new_memory() -> Result<Self> {
+        Ok(Self::wrap(R::new_memory().await?))
+    }
+
+    async fn get_domain(&self, domain: &str) -> Result<(String, Domain)> {
+        if let Some(cached) = self.cached(domain, |name| name.domain.clone()) {
+            return Ok(cached);
+        }
+
+        let generation = self.generation();
+        let found = self.inner.get_domain(domain).await?;
+        self.cache(domain, generation, self.expires(&found.1), |name| {
+            name.domain = Some(found.clone())
+        });
+
+        Ok(found)
+    }
+
+    async fn get_domain_by_inscription(&self, inscription: &str) -> Result<(String, Domain)> {
+        self.inner.get_domain_by_inscription(inscription).await
+    }
+
+    async fn get_domain_by_address(&self, address: &str) -> Result<Domain> {
+        self.inner.get_domain_by_address(address).await
+    }
+
+    async fn list_domains_by_address(&self, address: &str) -> Result<Vec<Domain>> {
+        self.inner.list_domains_by_address(address).await
+    }
+
+    async fn list_domains(&self, page: &Page) -> Result<Vec<(String, Domain)>> {
+        self.inner.list_domains(page).await
+    }
+
+    async fn search_domains(
+        &self,
+        filter: &NameFilter,
+        page: &Page,
+    ) -> Result<Vec<(String, Domain)>> {
+        self.inner.search_domains(filter, page).await
+    }
+
+    async fn domains_expiring_within(&self, days: u64) -> Result<Vec<(String, Domain)>> {
+        self.inner.domains_expiring_within(days).await
+    }
+
+    async fn count_domains(&self) -> Result<u64> {
+        self.inner.count_domains().await
+    }
+
+    async fn count_domains_by_address(&self, address: &str) -> Result<u64> {
+        self.inner.count_domains_by_address(address).await
+    }
+
+    async fn get_domain_address(&self, domain: &str) -> Result<String> {
+        self.inner.get_domain_address(domain).await
+    }
+
+    async fn add_domain(&self, address: &str, inscription: &str, domain: Domain) -> bool {
+        let name = domain.name.to_string();
+        let added = self.inner.add_domain(address, inscription, domain).await;
+
+        self.invalidate(&name);
+        added
+    }
+
+    async fn remove_domain(&self, domain: &str) -> bool {
+        let removed = self.inner.remove_domain(domain).await;
+
+        self.invalidate(domain);
+        removed
+    }
+
+    async fn remove_domain_by_inscription(&self, inscription: &str) -> bool {
+        let removed = self.inner.remove_domain_by_inscription(inscription).await;
+
+        self.clear();
+        removed
+    }
+
+    async fn renew_domain(
+        &self,
+        address: &str,
+        inscription: &str,
+        renewal: DomainRenew,
+        signature: Option<Signature>,
+    ) -> bool {
+        let name = renewal.domain.to_string();
+        let renewed = self
+            .inner
+            .renew_domain(address, inscription, renewal, signature)
+            .await;
+
+        self.invalidate(&name);
+        renewed
+    }
+
+    async fn expire_domain(&self, domain: &str) -> bool {
+        let expired = self.inner.expire_domain(domain).await;
+
+        self.invalidate(domain);
+        expired
+    }
+
+    async fn sweep_expired(&self) -> Result<Vec<String>> {
+        let names = self.inner.sweep_expired().await?;
+
+        for name in names.iter() {
+            self.invalidate(name);
+        }
+
+        Ok(names)
+    }
+
+    async fn add_subdomain(&self, address: &str, inscription: &str, subdomain: SubDomain) -> bool {
+        let name = subdomain.domain.to_string();
+        let added = self
+            .inner
+            .add_subdomain(address, inscription, subdomain)
+            .await;
+
+        self.invalidate(&name);
+        added
+    }
+
+    async fn get_subdomain(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        if let Some(cached) = self.cached(domain, |name| name.subdomains.get(subdomain).cloned()) {
+            return Ok(cached);
+        }
+
+        let generation = self.generation();
+        let expires = self.domain_expires(domain).await?;
+        let found = self.inner.get_subdomain(domain, subdomain).await?;
+        self.cache(domain, generation, expires, |name| {
+            name.subdomains.insert(subdomain.to_string(), found.clone());
+        });
+
+        Ok(found)
+    }
+
+    async fn get_subdomain_inscriptions(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        if let Some(cached) = self.cached(domain, |name| name.inscriptions.get(subdomain).cloned())
+        {
+            return Ok(cached);
+        }
+
+        let generation = self.generation();
+        let expires = self.domain_expires(domain).await?;
+        let found = self
+            .inner
+            .get_subdomain_inscriptions(domain, subdomain)
+            .await?;
+        self.cache(domain, generation, expires, |name| {
+            name.inscriptions
+                .insert(subdomain.to_string(), found.clone());
+        });
+
+        Ok(found)
+    }
+
+    async fn get_subdomain_names(&self, domain: &str) -> Result<Vec<String>> {
+        if let Some(cached) = self.cached(domain, |name| name.subdomain_names.clone()) {
+            return Ok(cached);
+        }
+
+        let generation = self.generation();
+        let expires = self.domain_expires(domain).await?;
+        let found = self.inner.get_subdomain_names(domain).await?;
+        self.cache(domain, generation, expires, |name| {
+            name.subdomain_names = Some(found.clone())
+        });
+
+        Ok(found)
+    }
+
+    async fn list_subdomains(&self, domain: &str) -> Result<Vec<(String, SubDomain)>> {
+        self.inner.list_subdomains(domain).await
+    }
+
+    async fn count_subdomains(&self, domain: &str) -> Result<u64> {
+        self.inner.count_subdomains(domain).await
+    }
+
+    async fn get_subdomain_by_inscription(&self, inscription: &str) -> Result<(String, SubDomain)> {
+        self.inner.get_subdomain_by_inscription(inscription).await
+    }
+
+    async fn remove_subdomains(&self, domain: &str, subdomain: &str) -> bool {
+        let removed = self.inner.remove_subdomains(domain, subdomain).await;
+
+        self.invalidate(domain);
+        removed
+    }
+
+    async fn remove_subdomain(&self, inscription: &str) -> bool {
+        let removed = self.inner.remove_subdomain(inscription).await;
+
+        self.clear();
+        removed
+    }
+
+    async fn add_validity(&self, address: &str, inscription: &str, validity: Validity) -> bool {
+        let name = validity.domain.to_string();
+        let added = self
+            .inner
+            .add_validity(address, inscription, validity)
+            .await;
+
+        self.invalidate(&name);
+        added
+    }
+
+    async fn get_validity(&self, domain: &str) -> Result<(String, Validity)> {
+        if let Some(cached) = self.cached(domain, |name| name.validity.clone()) {
+            return Ok(cached);
+        }
+
+        let generation = self.generation();
+        let expires = self.domain_expires(domain).await?;
+        let found = self.inner.get_validity(domain).await?;
+        self.cache(domain, generation, expires, |name| {
+            name.validity = Some(found.clone())
+        });
+
+        Ok(found)
+    }
+
+    async fn get_validity_by_inscription(&self, inscription: &str) -> Result<(String, Validity)> {
+        self.inner.get_validity_by_inscription(inscription).await
+    }
+
+    async fn remove_validity(&self, domain: &str) -> bool {
+        let removed = self.inner.remove_validity(domain).await;
+
+        self.invalidate(domain);
+        removed
+    }
+
+    async fn remove_validity_by_inscription(&self, inscription: &str) -> bool {
+        let removed = self.inner.remove_validity_by_inscription(inscription).await;
+
+        self.clear();
+        removed
+    }
+
+    async fn update_validity(&self, validity: ValidityTransfer) -> bool {
+        let name = validity.domain.to_string();
+        let updated = self.inner.update_validity(validity).await;
+
+        self.invalidate(&name);
+        updated
+    }
+
+    async fn update_validity_by_inscription(
+        &self,
+        address: &str,
+        inscription: &str,
+        validity: ValidityTransfer,
+    ) -> bool {
+        let updated = self
+            .inner
+            .update_validity_by_inscription(address, inscription, validity)
+            .await;
+
+        self.clear();
+        updated
+    }
+
+    async fn add_data(&self, address: &str, inscription: &str, data: Data) -> bool {
+        self.inner.add_data(address, inscription, data).await
+    }
+
+    async fn get_data(&self, domain: &str) -> Result<Vec<(String, Data)>> {
+        self.inner.get_data(domain).await
+    }
+
+    async fn get_data_by_inscription(&self, inscription: &str) -> Result<(String, Data)> {
+        self.inner.get_data_by_inscription(inscription).await
+    }
+
+    async fn remove_data(&self, domain: &str) -> bool {
+        self.inner.remove_data(domain).await
+    }
+
+    async fn remove_data_by_inscription(&self, inscription: &str) -> bool {
+        self.inner.remove_data_by_inscription(inscription).await
+    }
+
+    /// Transfers change the owner of every cached lookup of the domain, and remove its validity.
+    async fn transfer_inscription(&self, inscription: &str, new_owner: &str) -> Result<bool> {
+        let transferred = self
+            .inner
+            .transfer_inscription(inscription, new_owner)
+            .await;
+
+        self.clear();
+        transferred
+    }
+
+    async fn commit(&self, batch: Batch) -> Result<()> {
+        let names = Self::batch_names(&batch);
+        let committed = self.inner.commit(batch).await;
+
+        match names {
+            Some(names) => names.iter().for_each(|name| self.invalidate(name)),
+            None => self.clear(),
+        }
+
+        committed
+    }
+
+    async fn get_tip(&self) -> Result<Option<Block>> {
+        self.inner.get_tip().await
+    }
+
+    async fn rollback_to(&self, height: u64) -> Result<usize> {
+        let undone = self.inner.rollback_to(height).await;
+
+        self.clear();
+        undone
+    }
+
+    async fn domain_history_positions(&self, domain: &str) -> Result<Vec<(u64, HistoryEntry)>> {
+        self.inner.domain_history_positions(domain).await
+    }
+
+    async fn domain_history_position(&self, domain: &str) -> Result<Option<u64>> {
+        self.inner.domain_history_position(domain).await
+    }
+
+    async fn history_after(&self, position: u64) -> Result<Vec<(u64, HistoryEntry)>> {
+        self.inner.history_after(position).await
+    }
+
+    async fn history_position(&self) -> Result<u64> {
+        self.inner.history_position().await
+    }
+
+    async fn records_at(&self, domain: &str, height: u64) -> Result<Vec<(String, SubDomain)>> {
+        self.inner.records_at(domain, height).await
+    }
+
+    async fn export_snapshot(&self) -> Result<StateSnapshot> {
+        self.inner.export_snapshot().await
+    }
+
+    async fn import_snapshot(&self, snapshot: StateSnapshot, expected_hash: &str) -> Result<()> {
+        let imported = self.inner.import_snapshot(snapshot, expected_hash).await;
+
+        self.clear();
+        imported
+    }
+
+    async fn state_root(&self) -> Result<StateRoot> {
+        self.inner.state_root().await
+    }
+
+    async fn prove(&self, name: &str) -> Result<NameProof> {
+        self.inner.prove(name).await
+    }
+
+    fn changes(&self) -> &ChangeFeed {
+        self.inner.changes()
+    }
+
+    fn policy(&self) -> ExpiryPolicy {
+        self.inner.policy()
+    }
+}
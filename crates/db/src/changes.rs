@@ -0,0 +1,237 @@
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use shared::common::Result;
+
+use crate::batch::Operation;
+use crate::history::HistoryEntry;
+use crate::traits::Repository;
+
+/// How a [`Change`](Change) affected a domain.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ChangeKind {
+    /// A domain or record was added.
+    Added,
+    /// A domain or record was removed, dropped or expired.
+    Removed,
+    /// The owner, expiry, validity or data of a domain changed.
+    Updated,
+    /// The domain was restored to the state of an earlier block.
+    RolledBack,
+}
+
+/// A committed change to a domain, as published on a [`ChangeFeed`](ChangeFeed).
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Change {
+    /// The domain that changed.
+    pub domain: String,
+    /// The subdomain that changed (eg `www.`), or `None` if anything stored under the domain may have changed.
+    pub subdomain: Option<String>,
+    pub kind: ChangeKind,
+}
+
+/// The receiving end of a [`ChangeFeed`](ChangeFeed).
+pub type ChangeReceiver = broadcast::Receiver<Change>;
+
+/// Publishes the changes of a repository to every in-process subscriber.
+///
+/// Like the [history](crate::HistoryEntry), committed batches (including those of the standalone repository methods),
+/// rollbacks and [sweeps](crate::XDNSRepository::sweep_expired) publish changes.
+/// Changes are published once their transaction is committed, never for a rejected batch.
+/// Changes made by another process using the same database are not published, follow the history
+/// with a [`HistoryFollower`](HistoryFollower) to publish those as well.
+///
+/// A feed can be shared between repositories (eg a new connection per request) with their `with_changes` method.
+/// A subscriber that falls more than [`CAPACITY`](Self::CAPACITY) changes behind receives
+/// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) and should assume anything changed.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut changes = db.changes().subscribe();
+///
+/// while let Ok(change) = changes.recv().await {
+///     cache.evict(&change.domain);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChangeFeed {
+    sender: broadcast::Sender<Change>,
+}
+
+impl ChangeFeed {
+    /// The amount of changes kept for subscribers that fall behind.
+    pub const CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(Self::CAPACITY).0,
+        }
+    }
+
+    /// Subscribes to every change published after this call.
+    pub fn subscribe(&self) -> ChangeReceiver {
+        self.sender.subscribe()
+    }
+
+    /// Publishes committed changes, in order. Changes without subscribers are dropped.
+    pub(crate) fn publish(&self, changes: Vec<Change>) {
+        for change in changes {
+            // Only fails if nobody is subscribed.
+            let _ = self.sender.send(change);
+        }
+    }
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Change {
+    fn new(domain: &str, subdomain: Option<&str>, kind: ChangeKind) -> Self {
+        Self {
+            domain: domain.to_string(),
+            subdomain: subdomain.map(str::to_string),
+            kind,
+        }
+    }
+
+    /// The change of a domain that was restored by a rollback.
+    pub(crate) fn rolled_back(domain: &str) -> Self {
+        Self::new(domain, None, ChangeKind::RolledBack)
+    }
+
+    /// Gets the change of a recorded action, the same change its operation published when it was committed.
+    fn recorded(entry: &HistoryEntry) -> Self {
+        let kind = match entry.action.as_str() {
+            "add_domain" | "add_subdomain" | "import_snapshot" => ChangeKind::Added,
+            "remove_domain"
+            | "remove_domain_by_inscription"
+            | "expire_domain"
+            | "remove_subdomains"
+            | "remove_subdomain"
+            | "drop" => ChangeKind::Removed,
+            "rollback" => ChangeKind::RolledBack,
+            _ => ChangeKind::Updated,
+        };
+
+        // Only the actions on every record of a subdomain name it, in their payload.
+        let subdomain = match entry.action.as_str() {
+            "add_subdomain" | "remove_subdomains" => serde_json::from_str::<Value>(&entry.payload)
+                .ok()
+                .and_then(|payload| payload["subdomain"].as_str().map(str::to_string)),
+            _ => None,
+        };
+
+        Self {
+            domain: entry.domain.to_string(),
+            subdomain,
+            kind,
+        }
+    }
+}
+
+/// Follows the [history](Repository::history_after) of a repository, publishing the change of every recorded action to a feed.
+///
+/// The history is stored with the rows, so unlike the feed of a repository, a follower notices
+/// the changes made by every process using the same database (eg the indexer) once it [polls](Self::poll).
+///
+/// # Example
+///
+/// ```ignore
+/// let mut follower = HistoryFollower::new(&db, feed.clone()).await?;
+///
+/// loop {
+///     interval.tick().await;
+///     follower.poll(&db).await?;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HistoryFollower {
+    feed: ChangeFeed,
+    position: u64,
+}
+
+impl HistoryFollower {
+    /// Starts following the history after the last recorded action.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The repository to follow.
+    /// * `feed` - Where to publish the changes to.
+    pub async fn new<R: Repository>(db: &R, feed: ChangeFeed) -> Result<Self> {
+        Ok(Self {
+            feed,
+            position: db.history_position().await?,
+        })
+    }
+
+    /// Gets the position of the last action that was published.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Publishes the changes of the actions recorded since the last poll, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The amount of published changes, or an error if the history could not be read.
+    pub async fn poll<R: Repository>(&mut self, db: &R) -> Result<usize> {
+        let entries = db.history_after(self.position).await?;
+        let count = entries.len();
+
+        if let Some((position, _)) = entries.last() {
+            self.position = *position;
+        }
+
+        self.feed.publish(
+            entries
+                .iter()
+                .map(|(_, entry)| Change::recorded(entry))
+                .collect(),
+        );
+
+        Ok(count)
+    }
+}
+
+impl Operation {
+    /// Gets the changes of an applied operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The domains the operation changed.
+    pub(crate) fn changes(&self, names: &[String]) -> Vec<Change> {
+        let (subdomain, kind) = match self {
+            Self::AddDomain { .. } => (None, ChangeKind::Added),
+            Self::AddSubdomain { subdomain, .. } => {
+                (Some(subdomain.subdomain.as_str()), ChangeKind::Added)
+            }
+            Self::RemoveSubdomains { subdomain, .. } => {
+                (Some(subdomain.as_str()), ChangeKind::Removed)
+            }
+            Self::RemoveDomain { .. }
+            | Self::RemoveDomainByInscription { .. }
+            | Self::ExpireDomain { .. }
+            | Self::RemoveSubdomain { .. }
+            | Self::Drop { .. } => (None, ChangeKind::Removed),
+            Self::RenewDomain { .. }
+            | Self::TransferInscription { .. }
+            | Self::AddValidity { .. }
+            | Self::RemoveValidity { .. }
+            | Self::RemoveValidityByInscription { .. }
+            | Self::UpdateValidity { .. }
+            | Self::UpdateValidityByInscription { .. }
+            | Self::AddData { .. }
+            | Self::RemoveData { .. }
+            | Self::RemoveDataByInscription { .. } => (None, ChangeKind::Updated),
+        };
+
+        names
+            .iter()
+            .map(|name| Change::new(name, subdomain, kind))
+            .collect()
+    }
+}
@@ -0,0 +1,496 @@
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use shared::common::{DbError, Result};
+use shared::time::system_time_from_epoch_seconds;
+use xdns_data::models::{Domain, SubDomain};
+
+use crate::changes::Change;
+use crate::journal::Snapshot;
+use crate::snapshot::domain_hash;
+use crate::traits::Repository;
+
+/// The hash of an empty subtree, and thereby the root of an empty repository.
+const EMPTY: [u8; 32] = [0; 32];
+/// Prefixes the hashes of leaves and branches, so one can not be passed off as the other.
+const LEAF: u8 = 0;
+const BRANCH: u8 = 1;
+
+/// The root of the Merkle tree that commits to the rows stored under every domain name.
+///
+/// Two repositories have the same root if and only if they store the same domains, subdomains,
+/// validities and data, regardless of the order they were added in or the backend they use.
+/// See [`NameProof`](NameProof) to prove the rows of a single name against a root.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Serialize, Deserialize)]
+pub struct StateRoot(pub [u8; 32]);
+
+impl StateRoot {
+    /// Parses a hex encoded root, as displayed.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = hex::decode(hex).map_err(|e| DbError::InvalidData(e.to_string()))?;
+
+        Ok(Self(bytes.try_into().map_err(|_| {
+            DbError::InvalidData(format!("a state root is 32 bytes: {}", hex))
+        })?))
+    }
+}
+
+impl Display for StateRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// The position of a name in the tree.
+fn key(name: &str) -> [u8; 32] {
+    Sha256::digest(name.as_bytes()).into()
+}
+
+/// Whether the bit of a key at a depth is set, which means it is in the right subtree at that depth.
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    key[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn leaf_hash(key: &[u8; 32], value: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF]);
+    hasher.update(key);
+    hasher.update(value);
+    hasher.finalize().into()
+}
+
+fn branch_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([BRANCH]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// A sparse Merkle tree over the hashes of the names, in which a subtree with a single leaf is that leaf.
+/// Its shape only depends on the names in it, which keeps it as small as the amount of names.
+#[derive(Debug, Default)]
+enum Node {
+    #[default]
+    Empty,
+    Leaf {
+        key: [u8; 32],
+        /// The hash of the rows stored under the name.
+        value: [u8; 32],
+    },
+    /// A subtree with at least two leaves.
+    Branch {
+        left: Box<Node>,
+        right: Box<Node>,
+        hash: [u8; 32],
+    },
+}
+
+impl Node {
+    fn hash(&self) -> [u8; 32] {
+        match self {
+            Self::Empty => EMPTY,
+            Self::Leaf { key, value } => leaf_hash(key, value),
+            Self::Branch { hash, .. } => *hash,
+        }
+    }
+
+    fn branch(left: Node, right: Node) -> Self {
+        Self::Branch {
+            hash: branch_hash(&left.hash(), &right.hash()),
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn insert(self, depth: usize, key: [u8; 32], value: [u8; 32]) -> Self {
+        match self {
+            Self::Empty => Self::Leaf { key, value },
+            Self::Leaf { key: existing, .. } if existing == key => Self::Leaf { key, value },
+            Self::Leaf {
+                key: existing,
+                value: existing_value,
+            } => Self::branch(Self::Empty, Self::Empty)
+                .insert(depth, existing, existing_value)
+                .insert(depth, key, value),
+            Self::Branch { left, right, .. } if bit(&key, depth) => {
+                Self::branch(*left, right.insert(depth + 1, key, value))
+            }
+            Self::Branch { left, right, .. } => {
+                Self::branch(left.insert(depth + 1, key, value), *right)
+            }
+        }
+    }
+
+    fn remove(self, depth: usize, key: &[u8; 32]) -> Self {
+        match self {
+            Self::Leaf { key: existing, .. } if &existing == key => Self::Empty,
+            Self::Branch { left, right, .. } => {
+                let (left, right) = match bit(key, depth) {
+                    true => (*left, right.remove(depth + 1, key)),
+                    false => (left.remove(depth + 1, key), *right),
+                };
+
+                // A subtree that is left with a single leaf collapses into it.
+                match (left, right) {
+                    (Self::Empty, Self::Empty) => Self::Empty,
+                    (leaf @ Self::Leaf { .. }, Self::Empty)
+                    | (Self::Empty, leaf @ Self::Leaf { .. }) => leaf,
+                    (left, right) => Self::branch(left, right),
+                }
+            }
+            node => node,
+        }
+    }
+}
+
+/// The tree itself, see [`StateCommitment`](StateCommitment) for how it is kept up to date.
+#[derive(Debug, Default)]
+pub(crate) struct StateTree {
+    root: Node,
+    /// The position of the last recorded action whose changes are in the tree, see [`Repository::history_after`].
+    position: u64,
+}
+
+impl StateTree {
+    /// Builds the tree from the rows stored under every name.
+    pub fn from_snapshots(snapshots: &[Snapshot]) -> std::result::Result<Self, DbError> {
+        let mut tree = Self::default();
+
+        for snapshot in snapshots {
+            tree.update(snapshot)?;
+        }
+
+        Ok(tree)
+    }
+
+    pub fn root(&self) -> StateRoot {
+        StateRoot(self.root.hash())
+    }
+
+    /// Whether the leaf of a name holds exactly the rows of a snapshot, or there is none for an empty snapshot.
+    pub fn holds(&self, snapshot: &Snapshot) -> std::result::Result<bool, DbError> {
+        let key = key(&snapshot.name);
+        let mut node = &self.root;
+        let mut depth = 0;
+
+        while let Node::Branch { left, right, .. } = node {
+            node = match bit(&key, depth) {
+                true => right,
+                false => left,
+            };
+            depth += 1;
+        }
+
+        Ok(match node {
+            Node::Leaf { key: other, value } if other == &key => {
+                !snapshot.is_empty() && value == &domain_hash(snapshot)?
+            }
+            _ => snapshot.is_empty(),
+        })
+    }
+
+    /// Replaces the leaf of a name with the current rows stored under it, or removes it if there are none.
+    pub fn update(&mut self, snapshot: &Snapshot) -> std::result::Result<(), DbError> {
+        let key = key(&snapshot.name);
+        let root = mem::take(&mut self.root);
+
+        self.root = match snapshot.is_empty() {
+            true => root.remove(0, &key),
+            false => root.insert(0, key, domain_hash(snapshot)?),
+        };
+
+        Ok(())
+    }
+
+    /// Proves the rows stored under a name, which must be the rows the tree was last updated with.
+    pub fn prove(&self, snapshot: Snapshot) -> NameProof {
+        let key = key(&snapshot.name);
+        let mut siblings = Vec::new();
+        let mut node = &self.root;
+
+        while let Node::Branch { left, right, .. } = node {
+            let (next, sibling) = match bit(&key, siblings.len()) {
+                true => (right, left),
+                false => (left, right),
+            };
+
+            siblings.push(sibling.hash());
+            node = next;
+        }
+
+        let terminal = match node {
+            Node::Leaf { key: other, value } if other != &key => Some((*other, *value)),
+            _ => None,
+        };
+
+        NameProof {
+            name: snapshot.name.to_string(),
+            snapshot: (!snapshot.is_empty()).then_some(snapshot),
+            siblings,
+            terminal,
+        }
+    }
+}
+
+/// Proves which rows are stored under a name, or that there are none, against a [`StateRoot`](StateRoot).
+///
+/// A light client that trusts a root (eg because several nodes agree on it) can check an answer
+/// without trusting the node that gave it: [`verify`](Self::verify) the proof, then use its
+/// [`domain`](Self::domain) and [`records`](Self::records).
+///
+/// # Example
+///
+/// ```ignore
+/// let proof = db.prove("example.o").await?;
+///
+/// if proof.verify(&trusted_root) {
+///     let records = proof.records()?;
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameProof {
+    name: String,
+    /// The rows stored under the name, `None` if there are none.
+    snapshot: Option<Snapshot>,
+    /// The hashes of the other subtree of every branch on the path of the name, from the root down.
+    siblings: Vec<[u8; 32]>,
+    /// The key and value of the leaf of another name the path ends at, if the name is not in the tree.
+    terminal: Option<([u8; 32], [u8; 32])>,
+}
+
+impl NameProof {
+    /// Gets the name the proof is for.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether any rows are stored under the name, `false` for a proof of non-inclusion.
+    pub fn exists(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Checks whether the proof is valid for a root.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The trusted root to check the proof against.
+    ///
+    /// # Returns
+    ///
+    /// Whether the rows of the proof (or their absence) are committed to by the root.
+    pub fn verify(&self, root: &StateRoot) -> bool {
+        let key = key(&self.name);
+
+        if self.siblings.len() > 256 {
+            return false;
+        }
+
+        let mut hash = match (&self.snapshot, &self.terminal) {
+            (Some(snapshot), None) if snapshot.name == self.name && !snapshot.is_empty() => {
+                match domain_hash(snapshot) {
+                    Ok(value) => leaf_hash(&key, &value),
+                    Err(_) => return false,
+                }
+            }
+            // The leaf of another name can only end the path if it shares the path.
+            (None, Some((other, value)))
+                if other != &key
+                    && (0..self.siblings.len())
+                        .all(|depth| bit(other, depth) == bit(&key, depth)) =>
+            {
+                leaf_hash(other, value)
+            }
+            (None, None) => EMPTY,
+            _ => return false,
+        };
+
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            hash = match bit(&key, depth) {
+                true => branch_hash(sibling, &hash),
+                false => branch_hash(&hash, sibling),
+            };
+        }
+
+        hash == root.0
+    }
+
+    /// Gets the proven domain and its owner, if the name is registered.
+    /// Only meaningful once the proof was [verified](Self::verify).
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(Address, Domain)>` - The domain, regardless of whether it expired.
+    pub fn domain(&self) -> Option<(String, Domain)> {
+        let record = self.snapshot.as_ref()?.domains.first()?;
+
+        Some((
+            record.address.to_string(),
+            Domain {
+                name: record.name.to_string(),
+                valid_from: system_time_from_epoch_seconds(record.valid_from),
+            },
+        ))
+    }
+
+    /// Gets the proven records of the name, as long as they belong to the owner of the domain.
+    /// Only meaningful once the proof was [verified](Self::verify).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Address, SubDomain)>>` - The records, or an error if the domain is not registered.
+    pub fn records(&self) -> Result<Vec<(String, SubDomain)>> {
+        match &self.snapshot {
+            Some(snapshot) => snapshot.records(),
+            None => Err(DbError::not_found("domain", &self.name).into()),
+        }
+    }
+}
+
+/// Reads the rows stored by the repository a [`StateCommitment`](StateCommitment) belongs to.
+pub(crate) trait StateRows {
+    /// Reads the rows stored under every name.
+    async fn all_rows(&self) -> Result<Vec<Snapshot>>;
+
+    /// Reads the rows stored under each of the names.
+    async fn rows_of(&self, names: Vec<String>) -> Result<Vec<Snapshot>>;
+}
+
+/// Keeps the [`StateTree`](StateTree) of a repository.
+///
+/// The tree is built from the rows of every name the first time it is used. After that the leaves of the
+/// names a mutation changed are updated once it was committed, and before the tree is used the leaves of
+/// every name changed since are updated from the [history](Repository::history_after), which also holds the
+/// mutations of other processes using the same database. The rows are read while the tree is locked,
+/// so concurrent mutations can not update it with rows that were already replaced.
+#[derive(Debug, Default)]
+pub(crate) struct StateCommitment {
+    tree: Mutex<Option<StateTree>>,
+}
+
+impl StateCommitment {
+    /// How often the tree is brought up to date for a proof, while the name keeps changing.
+    const PROVE_ATTEMPTS: usize = 3;
+
+    /// Updates the leaves of the names a mutation changed, once it was committed.
+    /// Does nothing until the tree is built, which reads every row anyway.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshots` - Reads the current rows stored under the changed names.
+    pub async fn refresh(&self, snapshots: impl Future<Output = Result<Vec<Snapshot>>>) {
+        let mut guard = self.tree.lock().await;
+
+        if let Some(tree) = guard.as_mut() {
+            let updated = match snapshots.await {
+                Ok(snapshots) => snapshots
+                    .iter()
+                    .try_for_each(|snapshot| tree.update(snapshot)),
+                Err(e) => Err(DbError::Backend(e.to_string())),
+            };
+
+            // The mutation is committed regardless, the tree is rebuilt the next time it is used instead.
+            if updated.is_err() {
+                *guard = None;
+            }
+        }
+    }
+
+    /// Brings the tree up to date with every recorded action, or builds it if it was not built yet.
+    /// The position is read before the rows, so rows changed in between are updated again the next time.
+    async fn sync<'a, R: Repository + StateRows>(
+        tree: &'a mut Option<StateTree>,
+        db: &R,
+    ) -> Result<&'a mut StateTree> {
+        let tree = match tree {
+            Some(tree) => tree,
+            None => {
+                let position = db.history_position().await?;
+                let mut built = StateTree::from_snapshots(&db.all_rows().await?)?;
+                built.position = position;
+
+                return Ok(tree.insert(built));
+            }
+        };
+
+        let entries = db.history_after(tree.position).await?;
+        let position = match entries.last() {
+            Some((position, _)) => *position,
+            None => return Ok(tree),
+        };
+
+        let mut names: Vec<String> = Vec::new();
+
+        for (_, entry) in entries {
+            if !names.contains(&entry.domain) {
+                names.push(entry.domain);
+            }
+        }
+
+        for snapshot in db.rows_of(names).await? {
+            tree.update(&snapshot)?;
+        }
+
+        tree.position = position;
+        Ok(tree)
+    }
+
+    /// Gets the root of the tree, once it is up to date.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The repository the tree commits to.
+    pub async fn root<R: Repository + StateRows>(&self, db: &R) -> Result<StateRoot> {
+        let mut guard = self.tree.lock().await;
+
+        Ok(Self::sync(&mut guard, db).await?.root())
+    }
+
+    /// Proves the rows stored under a name against the root of the tree, once it is up to date.
+    /// The proof is only given once the tree holds the rows it contains, so it verifies against the root.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The repository the tree commits to.
+    /// * `name` - The name to prove.
+    pub async fn prove<R: Repository + StateRows>(&self, db: &R, name: &str) -> Result<NameProof> {
+        let mut guard = self.tree.lock().await;
+
+        for _ in 0..Self::PROVE_ATTEMPTS {
+            let tree = Self::sync(&mut guard, db).await?;
+            let snapshot = match db.rows_of(vec![name.to_string()]).await?.pop() {
+                Some(snapshot) => snapshot,
+                None => return Err(DbError::not_found("domain", name).into()),
+            };
+
+            // Otherwise the name changed after the tree was brought up to date.
+            if tree.holds(&snapshot)? {
+                return Ok(tree.prove(snapshot));
+            }
+        }
+
+        Err(DbError::Backend(format!(
+            "the rows of {} kept changing while they were proven",
+            name
+        ))
+        .into())
+    }
+}
+
+/// Gets the distinct domain names of committed changes, the leaves to refresh.
+pub(crate) fn changed_names(changes: &[Change]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for change in changes {
+        if !names.contains(&change.domain) {
+            names.push(change.domain.to_string());
+        }
+    }
+
+    names
+}
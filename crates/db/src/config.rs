@@ -0,0 +1,251 @@
+use std::env;
+use std::time::Duration;
+
+use shared::common::{DbError, Result};
+
+use crate::expiry::ExpiryPolicy;
+use crate::journal::DEFAULT_FINALITY_DEPTH;
+
+/// The environment variable holding the database url.
+pub const DATABASE_URL_VAR: &str = "XDNS_DATABASE_URL";
+/// The fallback environment variable holding the database url. (as used by `sea-orm-cli`)
+pub const FALLBACK_DATABASE_URL_VAR: &str = "DATABASE_URL";
+
+/// The database backends a url can point to.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DatabaseBackend {
+    Sqlite,
+    Postgres,
+    /// An embedded key-value store, see [`KvRepository`](crate::KvRepository).
+    Redb,
+}
+
+/// Describes how to connect to the database of a repository.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use db::DatabaseConfig;
+/// let config = DatabaseConfig::new("sqlite://xdns.db?mode=rwc")
+///     .with_migrate(true)
+///     .with_max_connections(4)
+///     .with_connect_timeout(Duration::from_secs(5));
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DatabaseConfig {
+    /// The url of the database. (eg `sqlite://path?mode=rwc`, `sqlite::memory:`, `postgres://...` or `redb://path`)
+    pub url: String,
+    /// Whether to run all pending migrations when connecting.
+    pub migrate: bool,
+    /// The maximum amount of pooled connections.
+    /// An in-memory sqlite database always uses a single connection, as every connection has its own database.
+    pub max_connections: Option<u32>,
+    /// The minimum amount of pooled connections kept open.
+    pub min_connections: Option<u32>,
+    /// The maximum time to wait when establishing a connection.
+    pub connect_timeout: Option<Duration>,
+    /// The time after which an idle connection is closed.
+    pub idle_timeout: Option<Duration>,
+    /// Whether to log the executed statements.
+    pub logging: bool,
+    /// How long domain registrations stay valid.
+    pub expiry: ExpiryPolicy,
+    /// The amount of blocks below the tip after which a block is final and its journal is pruned,
+    /// `None` keeps the whole journal.
+    pub finality_depth: Option<u64>,
+}
+
+impl DatabaseConfig {
+    /// Creates a configuration for a database url, without any migration or pool settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The url of the database.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            migrate: false,
+            max_connections: None,
+            min_connections: None,
+            connect_timeout: None,
+            idle_timeout: None,
+            logging: true,
+            expiry: ExpiryPolicy::default(),
+            finality_depth: Some(DEFAULT_FINALITY_DEPTH),
+        }
+    }
+
+    /// Creates a configuration for a private in-memory sqlite database, which is migrated on connect.
+    pub fn memory() -> Self {
+        Self::new("sqlite::memory:").with_migrate(true)
+    }
+
+    /// Creates a configuration from the environment.
+    ///
+    /// # Restrictions
+    ///
+    /// * The url is read from `XDNS_DATABASE_URL`, falling back to `DATABASE_URL`.
+    /// * `XDNS_DATABASE_MIGRATE` must be `true`, `false`, `1` or `0`, if set.
+    /// * `XDNS_DATABASE_MAX_CONNECTIONS` and `XDNS_DATABASE_MIN_CONNECTIONS` must be numbers, if set.
+    /// * `XDNS_DATABASE_CONNECT_TIMEOUT` and `XDNS_DATABASE_IDLE_TIMEOUT` must be a number of seconds, if set.
+    /// * `XDNS_DOMAIN_LIFETIME`, `XDNS_DOMAIN_GRACE_PERIOD` and `XDNS_DOMAIN_MAX_RENEWAL` must be a number of seconds, if set.
+    /// * `XDNS_JOURNAL_FINALITY_DEPTH` must be a number of blocks, if set.
+    ///
+    /// # Returns
+    ///
+    /// The configuration, or a [`DbError::Configuration`](DbError::Configuration) if a variable is missing or invalid.
+    pub fn from_env() -> Result<Self> {
+        let url = env::var(DATABASE_URL_VAR)
+            .or_else(|_| env::var(FALLBACK_DATABASE_URL_VAR))
+            .map_err(|_| {
+                DbError::Configuration(format!(
+                    "no database url set, set {} or {}",
+                    DATABASE_URL_VAR, FALLBACK_DATABASE_URL_VAR
+                ))
+            })?;
+
+        let mut config = Self::new(url);
+
+        if let Some(migrate) = Self::env_var("XDNS_DATABASE_MIGRATE") {
+            config.migrate = match migrate.as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(Self::invalid_var("XDNS_DATABASE_MIGRATE", &migrate)),
+            };
+        }
+
+        config.max_connections = Self::env_number("XDNS_DATABASE_MAX_CONNECTIONS")?;
+        config.min_connections = Self::env_number("XDNS_DATABASE_MIN_CONNECTIONS")?;
+        config.connect_timeout =
+            Self::env_number("XDNS_DATABASE_CONNECT_TIMEOUT")?.map(Duration::from_secs);
+        config.idle_timeout =
+            Self::env_number("XDNS_DATABASE_IDLE_TIMEOUT")?.map(Duration::from_secs);
+
+        if let Some(lifetime) = Self::env_number("XDNS_DOMAIN_LIFETIME")? {
+            config.expiry.lifetime = Duration::from_secs(lifetime);
+        }
+
+        if let Some(grace_period) = Self::env_number("XDNS_DOMAIN_GRACE_PERIOD")? {
+            config.expiry.grace_period = Duration::from_secs(grace_period);
+        }
+
+        if let Some(max_renewal) = Self::env_number("XDNS_DOMAIN_MAX_RENEWAL")? {
+            config.expiry.max_renewal = Duration::from_secs(max_renewal);
+        }
+
+        if let Some(depth) = Self::env_number("XDNS_JOURNAL_FINALITY_DEPTH")? {
+            config.finality_depth = Some(depth);
+        }
+
+        Ok(config)
+    }
+
+    /// Sets whether to run all pending migrations when connecting.
+    pub fn with_migrate(mut self, migrate: bool) -> Self {
+        self.migrate = migrate;
+        self
+    }
+
+    /// Sets the maximum amount of pooled connections.
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Sets the minimum amount of pooled connections kept open.
+    pub fn with_min_connections(mut self, min_connections: u32) -> Self {
+        self.min_connections = Some(min_connections);
+        self
+    }
+
+    /// Sets the maximum time to wait when establishing a connection.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the time after which an idle connection is closed.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether to log the executed statements.
+    pub fn with_logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    /// Sets how long domain registrations stay valid.
+    pub fn with_expiry(mut self, expiry: ExpiryPolicy) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    /// Sets the amount of blocks below the tip after which a block is final, `None` keeps the whole journal.
+    pub fn with_finality_depth(mut self, depth: Option<u64>) -> Self {
+        self.finality_depth = depth;
+        self
+    }
+
+    /// Gets the backend the url points to.
+    ///
+    /// # Returns
+    ///
+    /// The backend, or a [`DbError::Configuration`](DbError::Configuration) if the scheme is not supported.
+    pub fn backend(&self) -> Result<DatabaseBackend> {
+        if self.url.starts_with("sqlite:") {
+            return Ok(DatabaseBackend::Sqlite);
+        }
+
+        if self.url.starts_with("postgres://") || self.url.starts_with("postgresql://") {
+            return Ok(DatabaseBackend::Postgres);
+        }
+
+        if self.url.starts_with("redb://") {
+            return Ok(DatabaseBackend::Redb);
+        }
+
+        Err(DbError::Configuration(format!(
+            "unsupported database url, expected a sqlite, postgres or redb url: {}",
+            self.url
+        ))
+        .into())
+    }
+
+    /// Gets the file path of a `redb://` url.
+    ///
+    /// # Returns
+    ///
+    /// The path, or `None` if the url is not a (valid) redb url.
+    pub fn redb_path(&self) -> Option<&str> {
+        self.url
+            .strip_prefix("redb://")
+            .filter(|path| !path.is_empty())
+    }
+
+    /// Whether the url points to an in-memory sqlite database.
+    pub fn is_memory(&self) -> bool {
+        self.url.starts_with("sqlite::memory:") || self.url.contains("mode=memory")
+    }
+
+    fn env_var(name: &str) -> Option<String> {
+        env::var(name).ok().filter(|value| !value.trim().is_empty())
+    }
+
+    fn env_number<T: std::str::FromStr>(name: &'static str) -> Result<Option<T>> {
+        match Self::env_var(name) {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| Self::invalid_var(name, &value)),
+            None => Ok(None),
+        }
+    }
+
+    fn invalid_var(name: &str, value: &str) -> shared::common::Error {
+        DbError::Configuration(format!("invalid value for {}: {}", name, value)).into()
+    }
+}
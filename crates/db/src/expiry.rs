@@ -0,0 +1,194 @@
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use shared::time::epoch_seconds_now;
+use xdns_data::models::{Credentials, Signature};
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// Where a domain is in its registration lifecycle.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DomainStatus {
+    /// The domain resolves and belongs to its owner.
+    Active,
+    /// The lifetime has passed, the domain no longer resolves but only its previous owner can register it again.
+    Grace,
+    /// The grace period has passed as well, anyone can register the domain and it is removed by the next sweep.
+    Released,
+}
+
+/// How long domain registrations stay valid.
+///
+/// Expired domains are never removed by a read, they stay stored until they are released
+/// and [swept](crate::XDNSRepository::sweep_expired).
+///
+/// # Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use db::ExpiryPolicy;
+/// let policy = ExpiryPolicy::default().with_grace_period(Duration::from_secs(7 * 86400));
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ExpiryPolicy {
+    /// The time a registration stays active after it was inscribed.
+    pub lifetime: Duration,
+    /// The time after the lifetime in which only the previous owner can register the domain again.
+    pub grace_period: Duration,
+    /// How far ahead of now a registration can be renewed, measured until it expires.
+    pub max_renewal: Duration,
+}
+
+impl Default for ExpiryPolicy {
+    /// A lifetime of a year, followed by a grace period of 30 days. Renewals reach at most 10 years ahead.
+    fn default() -> Self {
+        Self {
+            lifetime: Duration::from_secs(365 * SECONDS_PER_DAY),
+            grace_period: Duration::from_secs(30 * SECONDS_PER_DAY),
+            max_renewal: Duration::from_secs(10 * 365 * SECONDS_PER_DAY),
+        }
+    }
+}
+
+impl ExpiryPolicy {
+    /// Creates a policy, renewals keep reaching the default of at most 10 years ahead.
+    ///
+    /// # Arguments
+    ///
+    /// * `lifetime` - The time a registration stays active after it was inscribed.
+    /// * `grace_period` - The time after the lifetime in which only the previous owner can register the domain again.
+    pub fn new(lifetime: Duration, grace_period: Duration) -> Self {
+        Self {
+            lifetime,
+            grace_period,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the time a registration stays active after it was inscribed.
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Sets the time after the lifetime in which only the previous owner can register the domain again.
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Sets how far ahead of now a registration can be renewed, measured until it expires.
+    pub fn with_max_renewal(mut self, max_renewal: Duration) -> Self {
+        self.max_renewal = max_renewal;
+        self
+    }
+
+    /// Gets the status of a domain at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `valid_from` - When the domain was inscribed, in epoch seconds.
+    /// * `now` - The time to get the status at, in epoch seconds. (the time of the block when indexing)
+    pub fn status(&self, valid_from: u64, now: u64) -> DomainStatus {
+        let expires = valid_from + self.lifetime.as_secs();
+
+        if now <= expires {
+            DomainStatus::Active
+        } else if now <= expires + self.grace_period.as_secs() {
+            DomainStatus::Grace
+        } else {
+            DomainStatus::Released
+        }
+    }
+
+    /// Gets the `valid_from` of a domain after it was renewed.
+    /// Every period extends the registration by one lifetime, counting from when it expires (or expired).
+    ///
+    /// # Arguments
+    ///
+    /// * `valid_from` - When the domain was inscribed or last renewed, in epoch seconds.
+    /// * `periods` - The number of lifetimes to extend the registration by.
+    /// * `now` - The time of the renewal, in epoch seconds. (the time of the block when indexing)
+    ///
+    /// # Returns
+    ///
+    /// The new epoch seconds, or `None` if the domain is released or would expire beyond [`max_renewal`](Self::max_renewal).
+    pub fn renewed_valid_from(&self, valid_from: u64, periods: u32, now: u64) -> Option<u64> {
+        if periods == 0 || self.status(valid_from, now) == DomainStatus::Released {
+            return None;
+        }
+
+        let lifetime = self.lifetime.as_secs();
+        let renewed = valid_from.checked_add(lifetime.checked_mul(periods as u64)?)?;
+
+        if renewed.checked_add(lifetime)? > now.checked_add(self.max_renewal.as_secs())? {
+            return None;
+        }
+
+        Some(renewed)
+    }
+
+    /// Gets the `valid_from` times of the domains that are active.
+    ///
+    /// # Arguments
+    ///
+    /// * `within_days` - Only include the domains that expire within this many days.
+    ///
+    /// # Returns
+    ///
+    /// The range of epoch seconds, a domain is active as long as its `valid_from` is in it.
+    pub(crate) fn active_valid_from(&self, within_days: Option<u64>) -> RangeInclusive<u64> {
+        let now = epoch_seconds_now();
+        let lifetime = self.lifetime.as_secs();
+
+        let end = match within_days {
+            Some(days) => days
+                .saturating_mul(SECONDS_PER_DAY)
+                .saturating_add(now)
+                .saturating_sub(lifetime),
+            None => u64::MAX,
+        };
+
+        now.saturating_sub(lifetime)..=end
+    }
+
+    /// Gets the latest `valid_from` of a released domain.
+    ///
+    /// # Returns
+    ///
+    /// The epoch seconds, or `None` if no domain can be released yet.
+    pub(crate) fn released_until(&self) -> Option<u64> {
+        epoch_seconds_now().checked_sub(self.lifetime.as_secs() + self.grace_period.as_secs() + 1)
+    }
+}
+
+/// Whether a renewal is authorized, either by being inscribed by the owner
+/// or by being signed with the validity key of the domain.
+///
+/// # Arguments
+///
+/// * `owner` - The address owning the domain.
+/// * `address` - The address that inscribed the renewal.
+/// * `last_inscription` - The last renewal of the domain, or its registration if it was never renewed.
+/// * `signature` - The signature of the inscription, if any.
+/// * `validity` - The validity credentials the owner set for the domain, if any.
+pub(crate) fn may_renew(
+    owner: &str,
+    address: &str,
+    last_inscription: &str,
+    signature: Option<&Signature>,
+    validity: Option<Credentials>,
+) -> bool {
+    if owner == address {
+        return true;
+    }
+
+    // The signature names the last inscription, so it is stale (and can not be replayed) once the domain was renewed.
+    match (signature, validity) {
+        (Some(signature), Some(credentials)) => {
+            signature.last_id.as_deref() == Some(last_inscription)
+                && signature.is_valid(credentials)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,223 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use shared::time::{epoch_seconds_now, system_time_from_epoch_seconds};
+use xdns_data::models::Credentials;
+
+use crate::batch::Operation;
+use crate::journal::Block;
+
+/// A single applied action, as recorded in the append-only history of a domain.
+///
+/// Every operation of a committed [`Batch`](crate::Batch) is recorded once for each domain it changed,
+/// a [rollback](crate::XDNSRepository::rollback_to) is recorded as a `rollback` action,
+/// and every name of an [imported snapshot](crate::XDNSRepository::import_snapshot) as an `import_snapshot` action.
+/// The standalone repository methods commit a batch of their operation, so they are recorded as well.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The domain the action changed.
+    pub domain: String,
+    /// The kind of action, the [name](Operation::name) of the operation. (eg `add_domain`)
+    pub action: String,
+    /// The inscription id the action was inscribed in, if the operation has one.
+    pub inscription: Option<String>,
+    /// The address that inscribed the action, or the new owner of a transfer.
+    pub address: Option<String>,
+    /// The block the batch was committed at, if any.
+    pub block: Option<Block>,
+    /// When the action was applied. (in whole seconds)
+    pub timestamp: SystemTime,
+    /// The arguments of the operation, as json.
+    pub payload: String,
+}
+
+fn credentials_payload(credentials: &Credentials) -> Value {
+    json!({
+        "algorithm": credentials.algorithm.to_string(),
+        "public_key": credentials.public_key,
+    })
+}
+
+impl Operation {
+    /// Gets the inscription id the operation was inscribed in or targets, if any.
+    pub fn inscription(&self) -> Option<&str> {
+        match self {
+            Self::AddDomain { inscription, .. }
+            | Self::RemoveDomainByInscription { inscription }
+            | Self::RenewDomain { inscription, .. }
+            | Self::AddSubdomain { inscription, .. }
+            | Self::RemoveSubdomain { inscription }
+            | Self::AddValidity { inscription, .. }
+            | Self::RemoveValidityByInscription { inscription }
+            | Self::UpdateValidityByInscription { inscription, .. }
+            | Self::AddData { inscription, .. }
+            | Self::RemoveDataByInscription { inscription }
+            | Self::TransferInscription { inscription, .. }
+            | Self::Drop { inscription, .. } => Some(inscription),
+            Self::RemoveDomain { .. }
+            | Self::ExpireDomain { .. }
+            | Self::RemoveSubdomains { .. }
+            | Self::RemoveValidity { .. }
+            | Self::UpdateValidity { .. }
+            | Self::RemoveData { .. } => None,
+        }
+    }
+
+    /// Gets the address that inscribed the operation, or the new owner of a transfer.
+    pub fn address(&self) -> Option<&str> {
+        match self {
+            Self::AddDomain { address, .. }
+            | Self::RenewDomain { address, .. }
+            | Self::AddSubdomain { address, .. }
+            | Self::AddValidity { address, .. }
+            | Self::UpdateValidity { address, .. }
+            | Self::UpdateValidityByInscription { address, .. }
+            | Self::AddData { address, .. }
+            | Self::Drop { address, .. } => Some(address),
+            Self::TransferInscription { new_owner, .. } => Some(new_owner),
+            _ => None,
+        }
+    }
+
+    /// Gets the arguments of the operation as json, binary data is hex encoded.
+    pub fn payload(&self) -> String {
+        let payload = match self {
+            Self::AddDomain { domain, .. } => json!({
+                "name": domain.name,
+                "valid_from": domain
+                    .valid_from
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+            }),
+            Self::RenewDomain { renewal, .. } => json!({
+                "domain": renewal.domain,
+                "periods": renewal.periods,
+            }),
+            Self::RemoveDomain { domain }
+            | Self::ExpireDomain { domain }
+            | Self::RemoveValidity { domain }
+            | Self::RemoveData { domain } => json!({ "domain": domain }),
+            Self::AddSubdomain { subdomain, .. } => json!({
+                "domain": subdomain.domain,
+                "subdomain": subdomain.subdomain,
+                "rtype": subdomain.rtype().to_string(),
+                "class": subdomain.class.to_string(),
+                "ttl": subdomain.ttl,
+                "rdata": subdomain.rdata.to_string(),
+            }),
+            Self::RemoveSubdomains { domain, subdomain } => json!({
+                "domain": domain,
+                "subdomain": subdomain,
+            }),
+            Self::AddValidity { validity, .. } => json!({
+                "domain": validity.domain,
+                "credentials": credentials_payload(&validity.credentials),
+            }),
+            Self::UpdateValidity { validity, .. }
+            | Self::UpdateValidityByInscription { validity, .. } => {
+                json!({
+                    "domain": validity.domain,
+                    "credentials": validity.new_credentials.as_ref().map(credentials_payload),
+                })
+            }
+            Self::AddData { data, .. } => json!({
+                "domain": data.domain,
+                "data": hex::encode(&data.data),
+            }),
+            Self::TransferInscription { new_owner, .. } => json!({ "new_owner": new_owner }),
+            Self::RemoveDomainByInscription { .. }
+            | Self::RemoveSubdomain { .. }
+            | Self::RemoveValidityByInscription { .. }
+            | Self::RemoveDataByInscription { .. }
+            | Self::Drop { .. } => json!({}),
+        };
+
+        payload.to_string()
+    }
+}
+
+/// The current time, truncated to whole seconds like every other stored time.
+pub(crate) fn now() -> SystemTime {
+    system_time_from_epoch_seconds(epoch_seconds_now())
+}
+
+/// Creates the history entries of an applied operation, one for every domain it changed.
+///
+/// # Arguments
+///
+/// * `operation` - The applied operation.
+/// * `names` - The domains the operation changed.
+/// * `block` - The block the batch was committed at.
+/// * `timestamp` - The time the batch was committed.
+pub(crate) fn entries(
+    operation: &Operation,
+    names: &[String],
+    block: Option<&Block>,
+    timestamp: SystemTime,
+) -> Vec<HistoryEntry> {
+    let payload = operation.payload();
+
+    names
+        .iter()
+        .map(|name| HistoryEntry {
+            domain: name.to_string(),
+            action: operation.name().to_string(),
+            inscription: operation.inscription().map(str::to_string),
+            address: operation.address().map(str::to_string),
+            block: block.cloned(),
+            timestamp,
+            payload: payload.to_string(),
+        })
+        .collect()
+}
+
+/// Creates the history entry of a domain that was restored by a rollback.
+///
+/// # Arguments
+///
+/// * `domain` - The restored domain.
+/// * `undone` - The block of the batch that was undone.
+/// * `height` - The height that was rolled back to.
+/// * `timestamp` - The time of the rollback.
+pub(crate) fn rollback_entry(
+    domain: &str,
+    undone: &Block,
+    height: u64,
+    timestamp: SystemTime,
+) -> HistoryEntry {
+    HistoryEntry {
+        domain: domain.to_string(),
+        action: "rollback".to_string(),
+        inscription: None,
+        address: None,
+        block: Some(undone.clone()),
+        timestamp,
+        payload: json!({ "height": height }).to_string(),
+    }
+}
+
+/// Creates the history entry of a domain that was imported from a snapshot.
+///
+/// # Arguments
+///
+/// * `domain` - The imported domain.
+/// * `block` - The block the snapshot was taken at, if any.
+/// * `timestamp` - The time of the import.
+pub(crate) fn import_entry(
+    domain: &str,
+    block: Option<&Block>,
+    timestamp: SystemTime,
+) -> HistoryEntry {
+    HistoryEntry {
+        domain: domain.to_string(),
+        action: "import_snapshot".to_string(),
+        inscription: None,
+        address: None,
+        block: block.cloned(),
+        timestamp,
+        payload: json!({}).to_string(),
+    }
+}
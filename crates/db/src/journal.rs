@@ -0,0 +1,329 @@
+use serde::{Deserialize, Serialize};
+
+use shared::common::{DbError, Result};
+use shared::time::epoch_seconds_now;
+use xdns_data::models::subdomain::{
+    Class as SubDomainClass, RData as SubDomainRData, Type as SubDomainType,
+};
+use xdns_data::models::SubDomain;
+
+use crate::batch::Operation;
+
+/// The amount of blocks below the tip after which a block is final by default, its journal is pruned.
+pub const DEFAULT_FINALITY_DEPTH: u64 = 100;
+
+/// A block of the chain, identified by its height and hash.
+///
+/// A [`Batch`](crate::Batch) committed at a block is journaled,
+/// so it can be undone with [`Repository::rollback_to`](crate::XDNSRepository::rollback_to) when the chain reorganizes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Block {
+    pub height: u64,
+    pub hash: String,
+    /// The time of the block in epoch seconds, the operations of a batch committed at the block are evaluated at it.
+    /// Only used while the batch is applied, it is not stored.
+    #[serde(skip)]
+    pub time: Option<u64>,
+}
+
+impl Block {
+    pub fn new(height: u64, hash: impl Into<String>) -> Self {
+        Self {
+            height,
+            hash: hash.into(),
+            time: None,
+        }
+    }
+
+    /// Sets the time of the block, so every node evaluates the expiry of domains at the same time.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The time of the block, in epoch seconds.
+    pub fn with_time(mut self, time: u64) -> Self {
+        self.time = Some(time);
+        self
+    }
+}
+
+impl PartialEq for Block {
+    /// Blocks are identified by their height and hash alone.
+    fn eq(&self, other: &Self) -> bool {
+        self.height == other.height && self.hash == other.hash
+    }
+}
+
+impl Eq for Block {}
+
+/// The undo information of a single journaled batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct JournalEntry {
+    pub block: Block,
+    /// The state of every domain the batch changed, as it was before the batch.
+    pub snapshots: Vec<Snapshot>,
+}
+
+/// Every row stored under a single domain name, exactly as stored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    pub name: String,
+    pub domains: Vec<DomainRecord>,
+    pub subdomains: Vec<SubDomainRecord>,
+    pub validities: Vec<ValidityRecord>,
+    pub data: Vec<DataRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DomainRecord {
+    pub inscription: String,
+    pub address: String,
+    pub name: String,
+    pub valid_from: u64,
+    /// The inscription of the last renewal, if the domain was renewed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_renewal: Option<String>,
+}
+
+/// A subdomain record, kept in its inscription form. (eg `10 mail.example.o` for MX)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SubDomainRecord {
+    pub id: u64,
+    pub inscription: String,
+    pub address: String,
+    pub domain: String,
+    pub subdomain: String,
+    pub rtype: String,
+    pub class: String,
+    pub ttl: u32,
+    pub rdata: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ValidityRecord {
+    pub inscription: String,
+    pub address: String,
+    pub domain: String,
+    pub algorithm: String,
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DataRecord {
+    pub inscription: String,
+    pub address: String,
+    pub domain: String,
+    pub data: Vec<u8>,
+}
+
+/// The domains an operation can change.
+pub(crate) struct Scope<'a> {
+    /// Domain names that are known up front.
+    pub names: Vec<&'a str>,
+    /// An inscription whose rows (of any kind) are changed, their domain names have to be looked up.
+    pub inscription: Option<&'a str>,
+}
+
+impl Operation {
+    /// Gets the domains the operation can change, so their state can be journaled before it is applied.
+    pub(crate) fn scope(&self) -> Scope<'_> {
+        let (names, inscription) = match self {
+            Self::AddDomain { domain, .. } => (vec![domain.name.as_str()], None),
+            Self::RemoveDomain { domain } => (vec![domain.as_str()], None),
+            Self::RemoveDomainByInscription { inscription } => (vec![], Some(inscription)),
+            Self::ExpireDomain { domain } => (vec![domain.as_str()], None),
+            Self::RenewDomain { renewal, .. } => (vec![renewal.domain.as_str()], None),
+            Self::AddSubdomain { subdomain, .. } => (vec![subdomain.domain.as_str()], None),
+            Self::RemoveSubdomains { domain, .. } => (vec![domain.as_str()], None),
+            Self::RemoveSubdomain { inscription } => (vec![], Some(inscription)),
+            Self::AddValidity { validity, .. } => (vec![validity.domain.as_str()], None),
+            Self::RemoveValidity { domain } => (vec![domain.as_str()], None),
+            Self::RemoveValidityByInscription { inscription } => (vec![], Some(inscription)),
+            Self::UpdateValidity { validity, .. } => (vec![validity.domain.as_str()], None),
+            Self::UpdateValidityByInscription {
+                inscription,
+                validity,
+                ..
+            } => (vec![validity.domain.as_str()], Some(inscription)),
+            Self::AddData { data, .. } => (vec![data.domain.as_str()], None),
+            Self::RemoveData { domain } => (vec![domain.as_str()], None),
+            Self::RemoveDataByInscription { inscription } => (vec![], Some(inscription)),
+            Self::TransferInscription { inscription, .. } => (vec![], Some(inscription)),
+            Self::Drop { inscription, .. } => (vec![], Some(inscription)),
+        };
+
+        Scope {
+            names,
+            inscription: inscription.map(String::as_str),
+        }
+    }
+}
+
+impl SubDomainRecord {
+    pub fn from_model(id: u64, address: &str, inscription: &str, subdomain: &SubDomain) -> Self {
+        Self {
+            id,
+            inscription: inscription.to_string(),
+            address: address.to_string(),
+            domain: subdomain.domain.to_string(),
+            subdomain: subdomain.subdomain.to_string(),
+            rtype: subdomain.rtype().to_string(),
+            class: subdomain.class.to_string(),
+            ttl: subdomain.ttl,
+            rdata: subdomain.rdata.to_string(),
+        }
+    }
+
+    pub fn to_model(&self) -> Result<SubDomain> {
+        let rtype = SubDomainType::try_from(&self.rtype as &str)?;
+        let rdata = SubDomainRData::parse(rtype, &self.rdata)
+            .map_err(|e| DbError::InvalidData(e.to_string()))?;
+
+        Ok(SubDomain {
+            domain: self.domain.to_string(),
+            subdomain: self.subdomain.to_string(),
+            class: SubDomainClass::try_from(&self.class as &str)?,
+            ttl: self.ttl,
+            rdata,
+        })
+    }
+}
+
+impl Scope<'_> {
+    /// Collects the names of the scope without duplicates.
+    ///
+    /// # Arguments
+    ///
+    /// * `inscription_names` - The domain names of the rows of the scope's inscription.
+    pub fn resolve(&self, inscription_names: Vec<String>) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+
+        for name in self
+            .names
+            .iter()
+            .map(|name| name.to_string())
+            .chain(inscription_names)
+        {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+}
+
+impl Snapshot {
+    /// Whether no rows at all are stored under the name.
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+            && self.subdomains.is_empty()
+            && self.validities.is_empty()
+            && self.data.is_empty()
+    }
+
+    /// Gets the records of the snapshot, as long as they belong to the owner of the domain.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Address, SubDomain)>>` - The records, or an error if the domain did not exist.
+    pub fn records(&self) -> Result<Vec<(String, SubDomain)>> {
+        let address = &self
+            .domains
+            .first()
+            .ok_or_else(|| DbError::not_found("domain", &self.name))?
+            .address;
+
+        self.subdomains
+            .iter()
+            .filter(|record| &record.address == address)
+            .map(|record| Ok((record.address.to_string(), record.to_model()?)))
+            .collect()
+    }
+}
+
+/// Finds the state of a domain at a height: the oldest snapshot of the domain taken by a batch above the height.
+///
+/// # Arguments
+///
+/// * `entries` - The journal entries above the height, oldest first.
+/// * `name` - The name of the domain.
+///
+/// # Returns
+///
+/// The snapshot, or `None` if the domain did not change above the height. (its current state is its state at the height)
+pub(crate) fn snapshot_at<'a>(
+    entries: impl IntoIterator<Item = &'a JournalEntry>,
+    name: &str,
+) -> Option<&'a Snapshot> {
+    entries
+        .into_iter()
+        .flat_map(|entry| entry.snapshots.iter())
+        .find(|snapshot| snapshot.name == name)
+}
+
+/// Gets the block the state a batch changes is journaled at: the block of the batch,
+/// or the tip for a batch without a block, so a rollback below the tip undoes it as well.
+///
+/// # Returns
+///
+/// The block, or `None` if the batch has no block and nothing was committed at a block yet.
+pub(crate) fn journal_block(block: Option<&Block>, tip: Option<Block>) -> Option<Block> {
+    block.cloned().or(tip)
+}
+
+/// Gets the time the operations of a batch are evaluated at: the time of its block,
+/// or the current time for a batch without a block (or a block without a time).
+pub(crate) fn batch_time(block: Option<&Block>) -> u64 {
+    block
+        .and_then(|block| block.time)
+        .unwrap_or_else(epoch_seconds_now)
+}
+
+/// Checks whether a batch at a block can be committed on top of the current tip.
+///
+/// # Returns
+///
+/// * `Result<(), DbError>` - An error if the block is below the tip, or replaces the tip at its height
+///   (a reorganization), the tip must be rolled back first.
+pub(crate) fn check_order(block: &Block, tip: Option<&Block>) -> std::result::Result<(), DbError> {
+    match tip {
+        Some(tip)
+            if block.height < tip.height
+                || (block.height == tip.height && block.hash != tip.hash) =>
+        {
+            Err(DbError::BlockOrder {
+                height: block.height,
+                tip: tip.height,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Gets the height at or below which the journal can be pruned, once a batch is committed at a block.
+///
+/// # Arguments
+///
+/// * `block` - The block the batch was committed at.
+/// * `depth` - The amount of blocks below the tip after which a block is final, `None` never prunes.
+pub(crate) fn finalized_height(block: &Block, depth: Option<u64>) -> Option<u64> {
+    depth.and_then(|depth| block.height.checked_sub(depth))
+}
+
+/// Checks whether the state at a height can still be restored from the journal.
+///
+/// # Returns
+///
+/// * `Result<(), DbError>` - An error if the height is below the newest pruned block.
+pub(crate) fn check_finalized(
+    height: u64,
+    finalized: Option<&Block>,
+) -> std::result::Result<(), DbError> {
+    match finalized {
+        Some(finalized) if height < finalized.height => Err(DbError::Finalized {
+            height,
+            finalized: finalized.height,
+        }),
+        _ => Ok(()),
+    }
+}
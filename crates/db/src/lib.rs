@@ -1,8 +1,31 @@
 #![allow(incomplete_features)]
 #![feature(async_fn_in_trait)]
 
+pub use batch::{Batch, Operation};
+pub use cache::CachedRepository;
+pub use changes::{Change, ChangeFeed, ChangeKind, ChangeReceiver, HistoryFollower};
+pub use commitment::{NameProof, StateRoot};
+pub use config::{DatabaseBackend, DatabaseConfig};
+pub use expiry::{DomainStatus, ExpiryPolicy};
+pub use history::HistoryEntry;
+pub use journal::{Block, DEFAULT_FINALITY_DEPTH};
+pub use query::{NameFilter, Page};
+pub use snapshot::StateSnapshot;
 pub use sources::SqliteRepository as Repository;
+pub use sources::{ImportSummary, KvRepository, MemoryRepository, SqliteRepository};
 pub use traits::{Repository as XDNSRepository, *};
+pub use zone::{Soa, ZoneExporter, ZoneTransfer};
 
+pub mod batch;
+pub mod cache;
+pub mod changes;
+pub mod commitment;
+pub mod config;
+pub mod expiry;
+pub mod history;
+pub mod journal;
+pub mod query;
+pub mod snapshot;
 pub mod sources;
 pub mod traits;
+pub mod zone;
@@ -0,0 +1,117 @@
+use std::collections::BTreeSet;
+
+use xdns_data::models::Domain;
+
+/// A page of a listing ordered by domain name.
+///
+/// Pages are keyed by the last name of the previous page instead of an offset,
+/// so every backend can seek in its name index and rows added in between do not shift the pages.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut page = Some(Page::first(100));
+///
+/// while let Some(current) = page {
+///     let domains = db.list_domains(&current).await?;
+///     page = current.next(&domains);
+/// }
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Page {
+    /// Only names after this one are listed.
+    pub after: Option<String>,
+    /// The maximum number of domains on the page.
+    pub limit: usize,
+}
+
+impl Page {
+    /// The first page of a listing.
+    pub fn first(limit: usize) -> Self {
+        Self { after: None, limit }
+    }
+
+    /// The page that starts after the given name.
+    pub fn after(name: impl Into<String>, limit: usize) -> Self {
+        Self {
+            after: Some(name.into()),
+            limit,
+        }
+    }
+
+    /// Gets the page after this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `domains` - The domains listed on this page.
+    ///
+    /// # Returns
+    ///
+    /// The next page, or `None` if this page was not full and thus the last one.
+    pub fn next(&self, domains: &[(String, Domain)]) -> Option<Self> {
+        if domains.len() < self.limit {
+            return None;
+        }
+
+        domains
+            .last()
+            .map(|(_, domain)| Self::after(domain.name.to_string(), self.limit))
+    }
+
+    /// Whether a name comes after the start of the page.
+    pub(crate) fn includes(&self, name: &str) -> bool {
+        self.after.as_deref().is_none_or(|after| name > after)
+    }
+}
+
+/// How a search matches domain names. Names are always lowercase, so the text should be as well.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum NameFilter {
+    /// Names starting with the given text. (eg `exa` for `example.o`)
+    Prefix(String),
+    /// Names containing the given text anywhere. (eg `ample` for `example.o`)
+    /// Text of at least 3 characters is looked up by its trigrams, shorter text visits every name.
+    Contains(String),
+}
+
+impl NameFilter {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Prefix(prefix) => name.starts_with(prefix.as_str()),
+            Self::Contains(text) => name.contains(text.as_str()),
+        }
+    }
+
+    /// Gets a trigram every matching name contains, so only the names with that trigram have to be visited.
+    pub(crate) fn trigram(&self) -> Option<String> {
+        match self {
+            Self::Prefix(_) => None,
+            Self::Contains(text) => trigrams(text).into_iter().next(),
+        }
+    }
+
+    /// The filter as a `LIKE` pattern, escaped with `\`.
+    pub(crate) fn like_pattern(&self) -> String {
+        let text = match self {
+            Self::Prefix(text) | Self::Contains(text) => text
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_"),
+        };
+
+        match self {
+            Self::Prefix(_) => format!("{}%", text),
+            Self::Contains(_) => format!("%{}%", text),
+        }
+    }
+}
+
+/// Gets every distinct sequence of 3 characters in a name, the keys of a substring index.
+pub(crate) fn trigrams(name: &str) -> BTreeSet<String> {
+    let chars: Vec<char> = name.chars().collect();
+
+    chars
+        .windows(3)
+        .map(|window| window.iter().collect())
+        .collect()
+}
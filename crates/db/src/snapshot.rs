@@ -0,0 +1,260 @@
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use shared::common::{DbError, Result};
+
+use crate::journal::{Block, Snapshot};
+
+/// The first bytes of every encoded snapshot.
+const MAGIC: &[u8; 8] = b"XDNSSNAP";
+
+/// Every domain, subdomain, validity and data row of a repository, as stored at a block.
+///
+/// A new node can [import](crate::XDNSRepository::import_snapshot) a snapshot instead of
+/// re-indexing every inscription from genesis, and continue indexing after its [`block`](Self::block).
+/// Snapshots can be imported into any backend, regardless of the backend they were exported from.
+///
+/// The [`hash`](Self::hash) is the SHA-256 of the block and the rows in a canonical order, without the
+/// ids assigned by the backends, so repositories with the same state have the same hash.
+/// It is verified when a snapshot is decoded, and again once it is imported.
+/// As anyone can advertise any hash, an import also takes the hash the snapshot is expected to have,
+/// obtained from a trusted source (eg another node), and refuses any other snapshot.
+///
+/// Encoded, a snapshot is `XDNSSNAP`, followed by the little endian `u32` [`VERSION`](Self::VERSION)
+/// and the gzip compressed JSON of the snapshot.
+///
+/// # Example
+///
+/// ```ignore
+/// let snapshot = source.export_snapshot().await?;
+/// snapshot.write_to(File::create("xdns.snapshot")?)?;
+///
+/// let snapshot = StateSnapshot::read_from(File::open("xdns.snapshot")?)?;
+/// target.import_snapshot(snapshot, trusted_hash).await?;
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    block: Option<Block>,
+    hash: String,
+    domains: Vec<Snapshot>,
+}
+
+/// Sorts the rows of a domain, the subdomains keep the order of their ids,
+/// as it is the order their records are served in.
+fn sort_rows(snapshot: &mut Snapshot) {
+    snapshot
+        .domains
+        .sort_by(|a, b| a.inscription.cmp(&b.inscription));
+    snapshot.subdomains.sort_by_key(|record| record.id);
+    snapshot
+        .validities
+        .sort_by(|a, b| a.inscription.cmp(&b.inscription));
+    snapshot
+        .data
+        .sort_by(|a, b| a.inscription.cmp(&b.inscription));
+}
+
+/// Sorts the rows of every domain, and the domains by name.
+fn canonical(mut domains: Vec<Snapshot>) -> Vec<Snapshot> {
+    domains.iter_mut().for_each(sort_rows);
+    domains.sort_by(|a, b| a.name.cmp(&b.name));
+    domains
+}
+
+/// Writes the rows of a domain in their canonical form to a hasher.
+fn hash_rows(hasher: &mut Sha256, snapshot: &Snapshot) -> std::result::Result<(), DbError> {
+    let mut snapshot = snapshot.clone();
+    sort_rows(&mut snapshot);
+
+    // Every backend assigns its own ids, only their order is part of the state.
+    for record in snapshot.subdomains.iter_mut() {
+        record.id = 0;
+    }
+
+    serde_json::to_writer(hasher, &snapshot).map_err(|e| DbError::InvalidData(e.to_string()))
+}
+
+/// Hashes the rows of a single domain, regardless of their order or ids.
+pub(crate) fn domain_hash(snapshot: &Snapshot) -> std::result::Result<[u8; 32], DbError> {
+    let mut hasher = Sha256::new();
+    hash_rows(&mut hasher, snapshot)?;
+
+    Ok(hasher.finalize().into())
+}
+
+/// Hashes the block and canonical domains, see [`StateSnapshot::hash`](StateSnapshot::hash).
+fn state_hash(block: Option<&Block>, domains: &[Snapshot]) -> std::result::Result<String, DbError> {
+    let mut hasher = Sha256::new();
+
+    // The block is part of the state, a node continues indexing after it.
+    serde_json::to_writer(&mut hasher, &block).map_err(|e| DbError::InvalidData(e.to_string()))?;
+
+    for snapshot in domains {
+        hash_rows(&mut hasher, snapshot)?;
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+impl StateSnapshot {
+    /// The version of the encoding, snapshots of any other version can not be decoded.
+    pub const VERSION: u32 = 1;
+
+    /// Creates a snapshot of the state of every domain, at the tip of the repository.
+    pub(crate) fn new(block: Option<Block>, domains: Vec<Snapshot>) -> Result<Self> {
+        let domains = canonical(domains);
+        let hash = state_hash(block.as_ref(), &domains)?;
+
+        Ok(Self {
+            block,
+            hash,
+            domains,
+        })
+    }
+
+    /// Gets the block the snapshot was taken at, or `None` if nothing was committed at a block yet.
+    pub fn block(&self) -> Option<&Block> {
+        self.block.as_ref()
+    }
+
+    /// Gets the height of the block the snapshot was taken at, if any.
+    pub fn height(&self) -> Option<u64> {
+        self.block.as_ref().map(|block| block.height)
+    }
+
+    /// Gets the hex encoded hash of the state.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Gets the names of the domains with rows in the snapshot, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.domains.iter().map(|snapshot| snapshot.name.as_str())
+    }
+
+    /// Gets the amount of domain names with rows in the snapshot.
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    /// Whether the snapshot does not contain any rows.
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// Checks whether the rows of the snapshot hash to its advertised hash.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the rows were altered.
+    pub fn verify(&self) -> Result<()> {
+        Ok(self.check(self.domains.clone())?)
+    }
+
+    /// Checks whether the snapshot advertises the hash it is expected to have, before it is imported.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_hash` - The hex encoded hash, obtained from a trusted source.
+    pub(crate) fn expect_hash(&self, expected_hash: &str) -> std::result::Result<(), DbError> {
+        if !self.hash.eq_ignore_ascii_case(expected_hash) {
+            return Err(DbError::SnapshotMismatch {
+                expected: expected_hash.to_string(),
+                actual: self.hash.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the state of a repository hashes to the advertised hash, eg after an import.
+    pub(crate) fn check(&self, domains: Vec<Snapshot>) -> std::result::Result<(), DbError> {
+        let actual = state_hash(self.block.as_ref(), &canonical(domains))?;
+
+        if actual != self.hash {
+            return Err(DbError::SnapshotMismatch {
+                expected: self.hash.to_string(),
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Takes the snapshots of every domain out of the snapshot, in order.
+    pub(crate) fn into_domains(self) -> Vec<Snapshot> {
+        self.domains
+    }
+
+    /// Encodes the snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where to write the encoded snapshot to, eg a file.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&Self::VERSION.to_le_bytes())?;
+
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        serde_json::to_writer(&mut encoder, self)
+            .map_err(|e| DbError::InvalidData(e.to_string()))?;
+        encoder.finish()?.flush()?;
+
+        Ok(())
+    }
+
+    /// Decodes a snapshot and verifies its hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Where to read the encoded snapshot from, eg a file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The snapshot, or an error if it is not a snapshot, of another version, or its rows were altered.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 12];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| DbError::InvalidSnapshot("missing header".to_string()))?;
+
+        if &header[..8] != MAGIC {
+            return Err(DbError::InvalidSnapshot("not an xdns snapshot".to_string()).into());
+        }
+
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+
+        if version != Self::VERSION {
+            return Err(
+                DbError::InvalidSnapshot(format!("unsupported version {}", version)).into(),
+            );
+        }
+
+        let snapshot: Self = serde_json::from_reader(GzDecoder::new(reader))
+            .map_err(|e| DbError::InvalidSnapshot(e.to_string()))?;
+        let snapshot = Self {
+            domains: canonical(snapshot.domains),
+            ..snapshot
+        };
+
+        snapshot.verify()?;
+        Ok(snapshot)
+    }
+
+    /// Encodes the snapshot into bytes, see [`write_to`](Self::write_to).
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Decodes a snapshot from bytes, see [`read_from`](Self::read_from).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::read_from(bytes)
+    }
+}
@@ -0,0 +1,141 @@
+use sea_orm::{EntityTrait, TransactionTrait};
+
+use entity::{data, domain, subdomain, validity};
+use shared::common::Result;
+
+use super::repository::{DataRow, DomainRow, KvError, SubDomainRow, ValidityRow};
+use super::KvRepository;
+use crate::sources::sqlite::{backend_error, parse_valid_from};
+use crate::sources::SqliteRepository;
+
+/// The amount of rows copied by [`KvRepository::import_sql`](KvRepository::import_sql).
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct ImportSummary {
+    pub domains: usize,
+    pub subdomains: usize,
+    pub validities: usize,
+    pub data: usize,
+    /// Rows that violated a constraint of the store and were left out. (eg a duplicate domain name)
+    pub skipped: usize,
+}
+
+impl KvRepository {
+    /// Copies every row of a sql database into this (empty) store, in a single transaction.
+    /// The rows are read in a single transaction as well, so they are copied as of one point in time.
+    /// Rows are copied as they are stored, so expired domains are copied as well
+    /// and expire in this store just like they would have in the sql database.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The sql repository to copy from.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ImportSummary>` - The amount of copied rows, or an error if the store is not empty or any read or write failed.
+    pub async fn import_sql(&self, source: &SqliteRepository) -> Result<ImportSummary> {
+        let txn = source.connection.begin().await.map_err(backend_error)?;
+        let domains = domain::Entity::find()
+            .all(&txn)
+            .await
+            .map_err(backend_error)?;
+        let subdomains = subdomain::Entity::find()
+            .all(&txn)
+            .await
+            .map_err(backend_error)?;
+        let validities = validity::Entity::find()
+            .all(&txn)
+            .await
+            .map_err(backend_error)?;
+        let data = data::Entity::find()
+            .all(&txn)
+            .await
+            .map_err(backend_error)?;
+        txn.commit().await.map_err(backend_error)?;
+
+        let domains = domains
+            .into_iter()
+            .map(|model| {
+                Ok(DomainRow {
+                    valid_from: parse_valid_from(&model.valid_from)?,
+                    inscription: model.inscription,
+                    address: model.address,
+                    name: model.name,
+                    last_renewal: model.last_renewal,
+                })
+            })
+            .collect::<Result<Vec<DomainRow>>>()?;
+
+        let subdomains: Vec<SubDomainRow> = subdomains
+            .into_iter()
+            .map(|model| SubDomainRow {
+                // The MX preference is stored separately, the store keeps the inscription form.
+                rdata: match model.priority {
+                    Some(priority) if model.rtype == "MX" => {
+                        format!("{} {}", priority, model.rdata)
+                    }
+                    _ => model.rdata,
+                },
+                inscription: model.inscription,
+                address: model.address,
+                domain: model.domain,
+                subdomain: model.subdomain,
+                rtype: model.rtype,
+                class: model.class,
+                ttl: model.ttl as u32,
+            })
+            .collect();
+
+        self.write(|txn| {
+            if !Self::is_empty_store(txn)? {
+                return Err(KvError::InvalidData(
+                    "the store is not empty, refusing to import".to_string(),
+                ));
+            }
+
+            let mut summary = ImportSummary::default();
+
+            for row in domains.iter() {
+                match Self::insert_domain_row(txn, row)? {
+                    true => summary.domains += 1,
+                    false => summary.skipped += 1,
+                }
+            }
+
+            for row in subdomains.iter() {
+                Self::insert_subdomain_row(txn, row)?;
+                summary.subdomains += 1;
+            }
+
+            for model in validities.iter() {
+                let row = ValidityRow {
+                    inscription: model.inscription.to_string(),
+                    address: model.address.to_string(),
+                    domain: model.domain.to_string(),
+                    algorithm: model.algorithm.to_string(),
+                    public_key: model.public_key.to_string(),
+                };
+
+                match Self::insert_validity_row(txn, &row)? {
+                    true => summary.validities += 1,
+                    false => summary.skipped += 1,
+                }
+            }
+
+            for model in data.iter() {
+                let row = DataRow {
+                    inscription: model.inscription.to_string(),
+                    address: model.address.to_string(),
+                    domain: model.domain.to_string(),
+                    data: model.data.clone(),
+                };
+
+                match Self::insert_data_row(txn, &row)? {
+                    true => summary.data += 1,
+                    false => summary.skipped += 1,
+                }
+            }
+
+            Ok(summary)
+        })
+    }
+}
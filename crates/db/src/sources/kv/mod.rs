@@ -0,0 +1,5 @@
+mod import;
+mod repository;
+
+pub use import::ImportSummary;
+pub use repository::KvRepository;
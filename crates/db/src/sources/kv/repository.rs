@@ -0,0 +1,2218 @@
+use std::collections::BTreeSet;
+use std::ops::Bound;
+use std::path::Path;
+use std::time::SystemTime;
+
+use redb::backends::InMemoryBackend;
+use redb::{
+    Database, MultimapTableDefinition, ReadTransaction, ReadableMultimapTable, ReadableTable,
+    TableDefinition, WriteTransaction,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use shared::common::{DbError, Result};
+use shared::time::{epoch_seconds_now, system_time_from_epoch_seconds};
+use xdns_data::models::subdomain::{
+    Class as SubDomainClass, RData as SubDomainRData, Type as SubDomainType,
+};
+use xdns_data::models::{
+    Credentials, Data, Domain, DomainRenew, Signature, SubDomain, Validity, ValidityTransfer,
+};
+
+use crate::batch::{Batch, Operation};
+use crate::changes::{Change, ChangeFeed};
+use crate::commitment::{changed_names, NameProof, StateCommitment, StateRoot, StateRows};
+use crate::config::{DatabaseBackend, DatabaseConfig};
+use crate::expiry::{may_renew, DomainStatus, ExpiryPolicy};
+use crate::history::{self, HistoryEntry};
+use crate::journal::{
+    batch_time, check_finalized, check_order, finalized_height, journal_block, snapshot_at, Block,
+    DataRecord, DomainRecord, JournalEntry, Snapshot, SubDomainRecord, ValidityRecord,
+    DEFAULT_FINALITY_DEPTH,
+};
+use crate::query::{trigrams, NameFilter, Page};
+use crate::snapshot::StateSnapshot;
+use crate::traits::Repository;
+
+/// inscription -> [`DomainRow`]
+const DOMAINS: TableDefinition<&str, &[u8]> = TableDefinition::new("domains");
+/// name -> inscription
+const DOMAIN_NAMES: TableDefinition<&str, &str> = TableDefinition::new("domain_names");
+/// address -> inscriptions
+const DOMAIN_ADDRESSES: MultimapTableDefinition<&str, &str> =
+    MultimapTableDefinition::new("domain_addresses");
+/// valid_from -> inscriptions
+const DOMAIN_EXPIRY: MultimapTableDefinition<u64, &str> =
+    MultimapTableDefinition::new("domain_expiry");
+/// id -> [`SubDomainRow`]
+const SUBDOMAINS: TableDefinition<u64, &[u8]> = TableDefinition::new("subdomains");
+/// domain -> ids
+const SUBDOMAIN_DOMAINS: MultimapTableDefinition<&str, u64> =
+    MultimapTableDefinition::new("subdomain_domains");
+/// inscription -> ids
+const SUBDOMAIN_INSCRIPTIONS: MultimapTableDefinition<&str, u64> =
+    MultimapTableDefinition::new("subdomain_inscriptions");
+/// inscription -> [`ValidityRow`]
+const VALIDITIES: TableDefinition<&str, &[u8]> = TableDefinition::new("validities");
+/// domain -> inscription
+const VALIDITY_DOMAINS: TableDefinition<&str, &str> = TableDefinition::new("validity_domains");
+/// inscription -> [`DataRow`]
+const DATA: TableDefinition<&str, &[u8]> = TableDefinition::new("data");
+/// trigram -> names, every sequence of 3 characters in a domain name
+const DOMAIN_TRIGRAMS: MultimapTableDefinition<&str, &str> =
+    MultimapTableDefinition::new("domain_trigrams");
+/// domain -> inscriptions
+const DATA_DOMAINS: MultimapTableDefinition<&str, &str> =
+    MultimapTableDefinition::new("data_domains");
+/// sequence -> [`JournalEntry`], oldest first
+const JOURNAL: TableDefinition<u64, &[u8]> = TableDefinition::new("journal");
+/// "finalized" -> the newest [`Block`] whose journal was pruned
+const CHECKPOINT: TableDefinition<&str, &[u8]> = TableDefinition::new("checkpoint");
+/// sequence -> [`HistoryEntry`], oldest first
+const HISTORY: TableDefinition<u64, &[u8]> = TableDefinition::new("history");
+/// domain -> sequences
+const HISTORY_DOMAINS: MultimapTableDefinition<&str, u64> =
+    MultimapTableDefinition::new("history_domains");
+/// name -> counter
+const SEQUENCES: TableDefinition<&str, u64> = TableDefinition::new("sequences");
+
+const SUBDOMAIN_SEQUENCE: &str = "subdomains";
+const FINALIZED: &str = "finalized";
+
+/// The error raised within a transaction, converted into a [`DbError`](DbError) once it completes.
+#[derive(Debug)]
+pub(crate) enum KvError {
+    Store(redb::Error),
+    InvalidData(String),
+    /// An error of the repository itself, eg a rejected batch.
+    Db(DbError),
+}
+
+impl<E: Into<redb::Error>> From<E> for KvError {
+    fn from(e: E) -> Self {
+        Self::Store(e.into())
+    }
+}
+
+impl From<KvError> for DbError {
+    fn from(e: KvError) -> Self {
+        match e {
+            KvError::Store(e) => DbError::Backend(e.to_string()),
+            KvError::InvalidData(message) => DbError::InvalidData(message),
+            KvError::Db(e) => e,
+        }
+    }
+}
+
+impl From<KvError> for shared::common::Error {
+    fn from(e: KvError) -> Self {
+        DbError::from(e).into()
+    }
+}
+
+type KvResult<T> = std::result::Result<T, KvError>;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct DomainRow {
+    pub inscription: String,
+    pub address: String,
+    pub name: String,
+    pub valid_from: u64,
+    /// The inscription of the last renewal, if the domain was renewed.
+    #[serde(default)]
+    pub last_renewal: Option<String>,
+}
+
+/// A subdomain as stored, the record is kept in its inscription form.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SubDomainRow {
+    pub inscription: String,
+    pub address: String,
+    pub domain: String,
+    pub subdomain: String,
+    pub rtype: String,
+    pub class: String,
+    pub ttl: u32,
+    pub rdata: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct ValidityRow {
+    pub inscription: String,
+    pub address: String,
+    pub domain: String,
+    pub algorithm: String,
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DataRow {
+    pub inscription: String,
+    pub address: String,
+    pub domain: String,
+    pub data: Vec<u8>,
+}
+
+/// A repository on top of an embedded, transactional key-value store. ([redb](redb))
+///
+/// Every row is stored by its inscription id, with secondary indexes for domain names,
+/// owner addresses and the domain of every record. All writes happen in a single
+/// transaction which is only visible once it has been durably committed, so a crash
+/// never leaves a half written row or a dangling index behind.
+pub struct KvRepository {
+    database: Database,
+    policy: ExpiryPolicy,
+    finality_depth: Option<u64>,
+    changes: ChangeFeed,
+    commitment: StateCommitment,
+}
+
+fn encode<T: Serialize>(row: &T) -> KvResult<Vec<u8>> {
+    serde_json::to_vec(row).map_err(|e| KvError::InvalidData(e.to_string()))
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> KvResult<T> {
+    serde_json::from_slice(bytes).map_err(|e| KvError::InvalidData(e.to_string()))
+}
+
+impl DomainRow {
+    fn from_model(address: &str, inscription: &str, domain: Domain) -> Self {
+        Self {
+            inscription: inscription.to_string(),
+            address: address.to_string(),
+            name: domain.name,
+            valid_from: domain
+                .valid_from
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            last_renewal: None,
+        }
+    }
+
+    fn to_model(&self) -> Domain {
+        Domain {
+            name: self.name.to_string(),
+            valid_from: system_time_from_epoch_seconds(self.valid_from),
+        }
+    }
+}
+
+impl SubDomainRow {
+    pub(crate) fn from_model(address: &str, inscription: &str, subdomain: SubDomain) -> Self {
+        Self {
+            inscription: inscription.to_string(),
+            address: address.to_string(),
+            domain: subdomain.domain,
+            subdomain: subdomain.subdomain,
+            rtype: subdomain.rdata.rtype().to_string(),
+            class: subdomain.class.to_string(),
+            ttl: subdomain.ttl,
+            rdata: subdomain.rdata.to_string(),
+        }
+    }
+
+    fn to_model(&self) -> Result<(String, SubDomain)> {
+        let rtype = SubDomainType::try_from(&self.rtype as &str)?;
+        let rdata = SubDomainRData::parse(rtype, &self.rdata)
+            .map_err(|e| DbError::InvalidData(e.to_string()))?;
+
+        Ok((
+            self.address.to_string(),
+            SubDomain {
+                domain: self.domain.to_string(),
+                subdomain: self.subdomain.to_string(),
+                class: SubDomainClass::try_from(&self.class as &str)?,
+                ttl: self.ttl,
+                rdata,
+            },
+        ))
+    }
+}
+
+impl ValidityRow {
+    fn from_model(address: &str, inscription: &str, validity: Validity) -> Self {
+        Self {
+            inscription: inscription.to_string(),
+            address: address.to_string(),
+            domain: validity.domain,
+            algorithm: validity.credentials.algorithm.into(),
+            public_key: validity.credentials.public_key,
+        }
+    }
+
+    fn to_model(&self) -> Result<(String, Validity)> {
+        Ok((
+            self.address.to_string(),
+            Validity {
+                domain: self.domain.to_string(),
+                credentials: Credentials::new(
+                    self.algorithm.to_string().try_into()?,
+                    self.public_key.to_string(),
+                ),
+            },
+        ))
+    }
+}
+
+impl DataRow {
+    fn from_model(address: &str, inscription: &str, data: Data) -> Self {
+        Self {
+            inscription: inscription.to_string(),
+            address: address.to_string(),
+            domain: data.domain,
+            data: data.data,
+        }
+    }
+
+    fn to_model(&self) -> (String, Data) {
+        (
+            self.address.to_string(),
+            Data {
+                domain: self.domain.to_string(),
+                data: self.data.clone(),
+            },
+        )
+    }
+}
+
+impl From<DomainRow> for DomainRecord {
+    fn from(row: DomainRow) -> Self {
+        Self {
+            inscription: row.inscription,
+            address: row.address,
+            name: row.name,
+            valid_from: row.valid_from,
+            last_renewal: row.last_renewal,
+        }
+    }
+}
+
+impl From<DomainRecord> for DomainRow {
+    fn from(record: DomainRecord) -> Self {
+        Self {
+            inscription: record.inscription,
+            address: record.address,
+            name: record.name,
+            valid_from: record.valid_from,
+            last_renewal: record.last_renewal,
+        }
+    }
+}
+
+impl SubDomainRow {
+    fn into_record(self, id: u64) -> SubDomainRecord {
+        SubDomainRecord {
+            id,
+            inscription: self.inscription,
+            address: self.address,
+            domain: self.domain,
+            subdomain: self.subdomain,
+            rtype: self.rtype,
+            class: self.class,
+            ttl: self.ttl,
+            rdata: self.rdata,
+        }
+    }
+}
+
+impl From<SubDomainRecord> for SubDomainRow {
+    fn from(record: SubDomainRecord) -> Self {
+        Self {
+            inscription: record.inscription,
+            address: record.address,
+            domain: record.domain,
+            subdomain: record.subdomain,
+            rtype: record.rtype,
+            class: record.class,
+            ttl: record.ttl,
+            rdata: record.rdata,
+        }
+    }
+}
+
+impl From<ValidityRow> for ValidityRecord {
+    fn from(row: ValidityRow) -> Self {
+        Self {
+            inscription: row.inscription,
+            address: row.address,
+            domain: row.domain,
+            algorithm: row.algorithm,
+            public_key: row.public_key,
+        }
+    }
+}
+
+impl From<ValidityRecord> for ValidityRow {
+    fn from(record: ValidityRecord) -> Self {
+        Self {
+            inscription: record.inscription,
+            address: record.address,
+            domain: record.domain,
+            algorithm: record.algorithm,
+            public_key: record.public_key,
+        }
+    }
+}
+
+impl From<DataRow> for DataRecord {
+    fn from(row: DataRow) -> Self {
+        Self {
+            inscription: row.inscription,
+            address: row.address,
+            domain: row.domain,
+            data: row.data,
+        }
+    }
+}
+
+impl From<DataRecord> for DataRow {
+    fn from(record: DataRecord) -> Self {
+        Self {
+            inscription: record.inscription,
+            address: record.address,
+            domain: record.domain,
+            data: record.data,
+        }
+    }
+}
+
+impl KvRepository {
+    /// Open (or create) a repository stored in a single file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file of the store.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The repository, or an error if the file could not be opened.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let database = Database::create(path).map_err(KvError::from)?;
+        Self::from_database(database)
+    }
+
+    /// Sets how long domain registrations stay valid, the default is [`ExpiryPolicy::default`](ExpiryPolicy::default).
+    pub fn with_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the amount of blocks below the tip after which the journal is pruned, `None` keeps the whole journal.
+    /// The default is [`DEFAULT_FINALITY_DEPTH`](crate::DEFAULT_FINALITY_DEPTH).
+    pub fn with_finality_depth(mut self, depth: Option<u64>) -> Self {
+        self.finality_depth = depth;
+        self
+    }
+
+    /// Publishes the changes to a (shared) feed instead of a feed of its own.
+    pub fn with_changes(mut self, changes: ChangeFeed) -> Self {
+        self.changes = changes;
+        self
+    }
+
+    /// Connect to the store described by a configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration, its url must be a `redb://<path>` url.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The repository, or an error if the url is not a redb url or the file could not be opened.
+    pub fn connect(config: &DatabaseConfig) -> Result<Self> {
+        match (config.backend()?, config.redb_path()) {
+            (DatabaseBackend::Redb, Some(path)) => Ok(Self::open(path)?
+                .with_policy(config.expiry)
+                .with_finality_depth(config.finality_depth)),
+            _ => Err(DbError::Configuration(format!(
+                "expected a redb://<path> url: {}",
+                config.url
+            ))
+            .into()),
+        }
+    }
+
+    fn from_database(database: Database) -> Result<Self> {
+        let repository = Self {
+            database,
+            policy: ExpiryPolicy::default(),
+            finality_depth: Some(DEFAULT_FINALITY_DEPTH),
+            changes: ChangeFeed::new(),
+            commitment: StateCommitment::default(),
+        };
+
+        // Read transactions can not open tables that do not exist yet.
+        repository.write(|txn| {
+            txn.open_table(DOMAINS)?;
+            txn.open_table(DOMAIN_NAMES)?;
+            txn.open_multimap_table(DOMAIN_ADDRESSES)?;
+            txn.open_table(SUBDOMAINS)?;
+            txn.open_multimap_table(SUBDOMAIN_DOMAINS)?;
+            txn.open_multimap_table(SUBDOMAIN_INSCRIPTIONS)?;
+            txn.open_table(VALIDITIES)?;
+            txn.open_table(VALIDITY_DOMAINS)?;
+            txn.open_table(DATA)?;
+            txn.open_multimap_table(DATA_DOMAINS)?;
+            txn.open_table(JOURNAL)?;
+            txn.open_table(CHECKPOINT)?;
+            txn.open_table(HISTORY)?;
+            txn.open_multimap_table(HISTORY_DOMAINS)?;
+            txn.open_table(SEQUENCES)?;
+
+            // Stores created before the expiry index existed get it built once.
+            let mut expiry = txn.open_multimap_table(DOMAIN_EXPIRY)?;
+
+            if expiry.is_empty()? {
+                for row in txn.open_table(DOMAINS)?.iter()? {
+                    let row: DomainRow = decode(row?.1.value())?;
+                    expiry.insert(row.valid_from, row.inscription.as_str())?;
+                }
+            }
+
+            // As does the trigram index.
+            let mut trigram_names = txn.open_multimap_table(DOMAIN_TRIGRAMS)?;
+
+            if trigram_names.is_empty()? {
+                for name in txn.open_table(DOMAIN_NAMES)?.iter()? {
+                    let name = name?.0.value().to_string();
+
+                    for trigram in trigrams(&name) {
+                        trigram_names.insert(trigram.as_str(), name.as_str())?;
+                    }
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(repository)
+    }
+
+    /// Run a closure within a read transaction.
+    fn read<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&ReadTransaction) -> KvResult<T>,
+    {
+        let txn = self.database.begin_read().map_err(KvError::from)?;
+        Ok(f(&txn)?)
+    }
+
+    /// Run a closure within a write transaction, which is committed if the closure succeeds.
+    pub(crate) fn write<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&WriteTransaction) -> KvResult<T>,
+    {
+        let txn = self.database.begin_write().map_err(KvError::from)?;
+        let value = f(&txn)?;
+        txn.commit().map_err(KvError::from)?;
+
+        Ok(value)
+    }
+
+    /// Updates the commitment to the current rows of names that were changed.
+    async fn refresh(&self, names: Vec<String>) {
+        self.commitment.refresh(self.rows_of(names)).await
+    }
+
+    /// Whether the store does not contain a single row.
+    pub(crate) fn is_empty_store(txn: &WriteTransaction) -> KvResult<bool> {
+        Ok(txn.open_table(DOMAINS)?.is_empty()?
+            && txn.open_table(SUBDOMAINS)?.is_empty()?
+            && txn.open_table(VALIDITIES)?.is_empty()?
+            && txn.open_table(DATA)?.is_empty()?)
+    }
+
+    fn find_domain_row<F>(&self, key: &str, lookup: F) -> Result<DomainRow>
+    where
+        F: FnOnce(&ReadTransaction) -> KvResult<Option<String>>,
+    {
+        let row = self.read(|txn| match lookup(txn)? {
+            Some(inscription) => Self::domain_row(&txn.open_table(DOMAINS)?, &inscription),
+            None => Ok(None),
+        })?;
+        let row = row.ok_or_else(|| DbError::not_found("domain", key))?;
+
+        if self.policy.status(row.valid_from, epoch_seconds_now()) != DomainStatus::Active {
+            return Err(DbError::Expired { domain: row.name }.into());
+        }
+
+        Ok(row)
+    }
+
+    fn get_domain_row(&self, domain: &str) -> Result<DomainRow> {
+        self.find_domain_row(domain, |txn| {
+            Ok(txn
+                .open_table(DOMAIN_NAMES)?
+                .get(domain)?
+                .map(|inscription| inscription.value().to_string()))
+        })
+    }
+
+    fn get_validity_row(&self, domain: &str) -> Result<Option<ValidityRow>> {
+        let address = self.get_domain_row(domain)?.address;
+        let row = self.read(|txn| match txn.open_table(VALIDITY_DOMAINS)?.get(domain)? {
+            Some(inscription) => {
+                Self::validity_row(&txn.open_table(VALIDITIES)?, inscription.value())
+            }
+            None => Ok(None),
+        })?;
+
+        Ok(row.filter(|row| row.address == address))
+    }
+
+    fn get_subdomain_rows(&self, domain: &str) -> Result<Vec<SubDomainRow>> {
+        self.read(|txn| {
+            let subdomains = txn.open_table(SUBDOMAINS)?;
+            let mut rows = Vec::new();
+
+            for id in txn.open_multimap_table(SUBDOMAIN_DOMAINS)?.get(domain)? {
+                if let Some(row) = subdomains.get(id?.value())? {
+                    rows.push(decode(row.value())?);
+                }
+            }
+
+            Ok(rows)
+        })
+    }
+
+    /// Get the domains that have not expired, ordered by expiry.
+    /// Unlike [`find_domain_row`](Self::find_domain_row) expired domains are left in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `within_days` - Only include the domains that expire within this many days.
+    fn live_domain_rows(
+        &self,
+        txn: &ReadTransaction,
+        within_days: Option<u64>,
+    ) -> KvResult<Vec<DomainRow>> {
+        let domains = txn.open_table(DOMAINS)?;
+        let mut rows = Vec::new();
+
+        for entry in txn
+            .open_multimap_table(DOMAIN_EXPIRY)?
+            .range(self.policy.active_valid_from(within_days))?
+        {
+            for inscription in entry?.1 {
+                if let Some(row) = Self::domain_row(&domains, inscription?.value())? {
+                    rows.push(row);
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// Get a page of the domains that have not expired, seeking in the name index.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page to get.
+    /// * `filter` - The filter the names must match, a prefix limits the names that are visited.
+    fn page_domain_rows(
+        &self,
+        txn: &ReadTransaction,
+        page: &Page,
+        filter: Option<&NameFilter>,
+    ) -> KvResult<Vec<DomainRow>> {
+        if let Some((filter, trigram)) = filter.and_then(|filter| Some((filter, filter.trigram()?)))
+        {
+            return self.trigram_domain_rows(txn, page, filter, &trigram);
+        }
+
+        let prefix = match filter {
+            Some(NameFilter::Prefix(prefix)) => Some(prefix.as_str()),
+            _ => None,
+        };
+        let start = match (page.after.as_deref(), prefix) {
+            (Some(after), Some(prefix)) if prefix > after => Bound::Included(prefix),
+            (Some(after), _) => Bound::Excluded(after),
+            (None, Some(prefix)) => Bound::Included(prefix),
+            (None, None) => Bound::Unbounded,
+        };
+
+        let live = self.policy.active_valid_from(None);
+        let domains = txn.open_table(DOMAINS)?;
+        let mut rows = Vec::new();
+
+        for entry in txn
+            .open_table(DOMAIN_NAMES)?
+            .range::<&str>((start, Bound::Unbounded))?
+        {
+            if rows.len() >= page.limit {
+                break;
+            }
+
+            let (name, inscription) = entry?;
+
+            if prefix.is_some_and(|prefix| !name.value().starts_with(prefix)) {
+                break;
+            }
+
+            if !filter.is_none_or(|filter| filter.matches(name.value())) {
+                continue;
+            }
+
+            if let Some(row) = Self::domain_row(&domains, inscription.value())? {
+                if live.contains(&row.valid_from) {
+                    rows.push(row);
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// Gets a page of the domains whose names contain a trigram and match a filter.
+    /// The names of a trigram are stored in order, so they are paged like the names themselves.
+    fn trigram_domain_rows(
+        &self,
+        txn: &ReadTransaction,
+        page: &Page,
+        filter: &NameFilter,
+        trigram: &str,
+    ) -> KvResult<Vec<DomainRow>> {
+        let live = self.policy.active_valid_from(None);
+        let domains = txn.open_table(DOMAINS)?;
+        let names = txn.open_table(DOMAIN_NAMES)?;
+        let mut rows = Vec::new();
+
+        for name in txn.open_multimap_table(DOMAIN_TRIGRAMS)?.get(trigram)? {
+            if rows.len() >= page.limit {
+                break;
+            }
+
+            let name = name?;
+
+            if !page.includes(name.value()) || !filter.matches(name.value()) {
+                continue;
+            }
+
+            if let Some(inscription) = names.get(name.value())? {
+                if let Some(row) = Self::domain_row(&domains, inscription.value())? {
+                    if live.contains(&row.valid_from) {
+                        rows.push(row);
+                    }
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+
+    fn address_domain_rows(
+        &self,
+        txn: &ReadTransaction,
+        address: &str,
+    ) -> KvResult<Vec<DomainRow>> {
+        let live = self.policy.active_valid_from(None);
+        let domains = txn.open_table(DOMAINS)?;
+        let mut rows = Vec::new();
+
+        for inscription in txn.open_multimap_table(DOMAIN_ADDRESSES)?.get(address)? {
+            if let Some(row) = Self::domain_row(&domains, inscription?.value())? {
+                if live.contains(&row.valid_from) {
+                    rows.push(row);
+                }
+            }
+        }
+
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(rows)
+    }
+
+    fn owned_subdomain_rows(&self, domain: &str) -> Result<Vec<SubDomainRow>> {
+        let address = self.get_domain_row(domain)?.address;
+        let mut rows: Vec<SubDomainRow> = self
+            .get_subdomain_rows(domain)?
+            .into_iter()
+            .filter(|row| row.address == address)
+            .collect();
+
+        // Stable, so records of the same subdomain stay in the order they were added.
+        rows.sort_by(|a, b| a.subdomain.cmp(&b.subdomain));
+        Ok(rows)
+    }
+
+    fn domain_row(
+        table: &impl ReadableTable<&'static str, &'static [u8]>,
+        inscription: &str,
+    ) -> KvResult<Option<DomainRow>> {
+        table
+            .get(inscription)?
+            .map(|row| decode(row.value()))
+            .transpose()
+    }
+
+    fn validity_row(
+        table: &impl ReadableTable<&'static str, &'static [u8]>,
+        inscription: &str,
+    ) -> KvResult<Option<ValidityRow>> {
+        table
+            .get(inscription)?
+            .map(|row| decode(row.value()))
+            .transpose()
+    }
+
+    /// Insert a domain, fails (without changes) if the inscription or name is already taken.
+    pub(crate) fn insert_domain_row(txn: &WriteTransaction, row: &DomainRow) -> KvResult<bool> {
+        let mut domains = txn.open_table(DOMAINS)?;
+        let mut names = txn.open_table(DOMAIN_NAMES)?;
+
+        if domains.get(row.inscription.as_str())?.is_some()
+            || names.get(row.name.as_str())?.is_some()
+        {
+            return Ok(false);
+        }
+
+        domains.insert(row.inscription.as_str(), encode(row)?.as_slice())?;
+        names.insert(row.name.as_str(), row.inscription.as_str())?;
+        txn.open_multimap_table(DOMAIN_ADDRESSES)?
+            .insert(row.address.as_str(), row.inscription.as_str())?;
+        txn.open_multimap_table(DOMAIN_EXPIRY)?
+            .insert(row.valid_from, row.inscription.as_str())?;
+
+        let mut trigram_names = txn.open_multimap_table(DOMAIN_TRIGRAMS)?;
+
+        for trigram in trigrams(&row.name) {
+            trigram_names.insert(trigram.as_str(), row.name.as_str())?;
+        }
+
+        Ok(true)
+    }
+
+    fn remove_domain_row(txn: &WriteTransaction, inscription: &str) -> KvResult<bool> {
+        let mut domains = txn.open_table(DOMAINS)?;
+        let row = match Self::domain_row(&domains, inscription)? {
+            Some(row) => row,
+            None => return Ok(false),
+        };
+
+        domains.remove(inscription)?;
+        txn.open_table(DOMAIN_NAMES)?.remove(row.name.as_str())?;
+        txn.open_multimap_table(DOMAIN_ADDRESSES)?
+            .remove(row.address.as_str(), inscription)?;
+        txn.open_multimap_table(DOMAIN_EXPIRY)?
+            .remove(row.valid_from, inscription)?;
+
+        let mut trigram_names = txn.open_multimap_table(DOMAIN_TRIGRAMS)?;
+
+        for trigram in trigrams(&row.name) {
+            trigram_names.remove(trigram.as_str(), row.name.as_str())?;
+        }
+
+        Ok(true)
+    }
+
+    fn set_domain_address(
+        txn: &WriteTransaction,
+        inscription: &str,
+        address: &str,
+    ) -> KvResult<bool> {
+        let mut domains = txn.open_table(DOMAINS)?;
+        let mut row = match Self::domain_row(&domains, inscription)? {
+            Some(row) => row,
+            None => return Ok(false),
+        };
+
+        let mut addresses = txn.open_multimap_table(DOMAIN_ADDRESSES)?;
+        addresses.remove(row.address.as_str(), inscription)?;
+        addresses.insert(address, inscription)?;
+
+        row.address = address.to_string();
+        domains.insert(inscription, encode(&row)?.as_slice())?;
+
+        Ok(true)
+    }
+
+    pub(crate) fn insert_subdomain_row(txn: &WriteTransaction, row: &SubDomainRow) -> KvResult<()> {
+        let mut sequences = txn.open_table(SEQUENCES)?;
+        let id = sequences
+            .get(SUBDOMAIN_SEQUENCE)?
+            .map(|id| id.value())
+            .unwrap_or(0);
+        sequences.insert(SUBDOMAIN_SEQUENCE, id + 1)?;
+
+        Self::put_subdomain_row(txn, id, row)
+    }
+
+    fn put_subdomain_row(txn: &WriteTransaction, id: u64, row: &SubDomainRow) -> KvResult<()> {
+        txn.open_table(SUBDOMAINS)?
+            .insert(id, encode(row)?.as_slice())?;
+        txn.open_multimap_table(SUBDOMAIN_DOMAINS)?
+            .insert(row.domain.as_str(), id)?;
+        txn.open_multimap_table(SUBDOMAIN_INSCRIPTIONS)?
+            .insert(row.inscription.as_str(), id)?;
+
+        Ok(())
+    }
+
+    fn remove_subdomain_row(txn: &WriteTransaction, id: u64) -> KvResult<bool> {
+        let mut subdomains = txn.open_table(SUBDOMAINS)?;
+        let row: SubDomainRow = match subdomains.remove(id)? {
+            Some(row) => decode(row.value())?,
+            None => return Ok(false),
+        };
+
+        txn.open_multimap_table(SUBDOMAIN_DOMAINS)?
+            .remove(row.domain.as_str(), id)?;
+        txn.open_multimap_table(SUBDOMAIN_INSCRIPTIONS)?
+            .remove(row.inscription.as_str(), id)?;
+
+        Ok(true)
+    }
+
+    /// Insert a validity, fails (without changes) if the inscription or domain is already taken.
+    pub(crate) fn insert_validity_row(txn: &WriteTransaction, row: &ValidityRow) -> KvResult<bool> {
+        let mut validities = txn.open_table(VALIDITIES)?;
+        let mut domains = txn.open_table(VALIDITY_DOMAINS)?;
+
+        if validities.get(row.inscription.as_str())?.is_some()
+            || domains.get(row.domain.as_str())?.is_some()
+        {
+            return Ok(false);
+        }
+
+        validities.insert(row.inscription.as_str(), encode(row)?.as_slice())?;
+        domains.insert(row.domain.as_str(), row.inscription.as_str())?;
+
+        Ok(true)
+    }
+
+    fn remove_validity_row(txn: &WriteTransaction, inscription: &str) -> KvResult<bool> {
+        let mut validities = txn.open_table(VALIDITIES)?;
+        let row = match Self::validity_row(&validities, inscription)? {
+            Some(row) => row,
+            None => return Ok(false),
+        };
+
+        validities.remove(inscription)?;
+        txn.open_table(VALIDITY_DOMAINS)?
+            .remove(row.domain.as_str())?;
+
+        Ok(true)
+    }
+
+    /// Insert data, fails (without changes) if the inscription is already taken.
+    pub(crate) fn insert_data_row(txn: &WriteTransaction, row: &DataRow) -> KvResult<bool> {
+        let mut data = txn.open_table(DATA)?;
+
+        if data.get(row.inscription.as_str())?.is_some() {
+            return Ok(false);
+        }
+
+        data.insert(row.inscription.as_str(), encode(row)?.as_slice())?;
+        txn.open_multimap_table(DATA_DOMAINS)?
+            .insert(row.domain.as_str(), row.inscription.as_str())?;
+
+        Ok(true)
+    }
+
+    fn remove_data_row(txn: &WriteTransaction, inscription: &str) -> KvResult<bool> {
+        let mut data = txn.open_table(DATA)?;
+        let row: DataRow = match data.remove(inscription)? {
+            Some(row) => decode(row.value())?,
+            None => return Ok(false),
+        };
+
+        txn.open_multimap_table(DATA_DOMAINS)?
+            .remove(row.domain.as_str(), inscription)?;
+
+        Ok(true)
+    }
+
+    fn data_inscriptions(txn: &WriteTransaction, domain: &str) -> KvResult<Vec<String>> {
+        let mut inscriptions = Vec::new();
+
+        for inscription in txn.open_multimap_table(DATA_DOMAINS)?.get(domain)? {
+            inscriptions.push(inscription?.value().to_string());
+        }
+
+        Ok(inscriptions)
+    }
+
+    /// Get a domain within a write transaction, as long as it is active.
+    fn live_domain_row(
+        &self,
+        txn: &WriteTransaction,
+        inscription: &str,
+        now: u64,
+    ) -> KvResult<Option<DomainRow>> {
+        let row = Self::domain_row(&txn.open_table(DOMAINS)?, inscription)?;
+
+        Ok(row.filter(|row| self.policy.status(row.valid_from, now) == DomainStatus::Active))
+    }
+
+    fn domain_inscription(txn: &WriteTransaction, domain: &str) -> KvResult<Option<String>> {
+        Ok(txn
+            .open_table(DOMAIN_NAMES)?
+            .get(domain)?
+            .map(|inscription| inscription.value().to_string()))
+    }
+
+    /// Get the owner of a domain within a write transaction, as long as the domain is active.
+    fn domain_owner(
+        &self,
+        txn: &WriteTransaction,
+        domain: &str,
+        now: u64,
+    ) -> KvResult<Option<String>> {
+        Ok(match Self::domain_inscription(txn, domain)? {
+            Some(inscription) => self
+                .live_domain_row(txn, &inscription, now)?
+                .map(|row| row.address),
+            None => None,
+        })
+    }
+
+    /// Get the validity of a domain within a write transaction, as long as it belongs to the owner of the domain.
+    fn domain_validity_row(
+        &self,
+        txn: &WriteTransaction,
+        domain: &str,
+        now: u64,
+    ) -> KvResult<Option<ValidityRow>> {
+        let address = match self.domain_owner(txn, domain, now)? {
+            Some(address) => address,
+            None => return Ok(None),
+        };
+
+        let inscription = txn
+            .open_table(VALIDITY_DOMAINS)?
+            .get(domain)?
+            .map(|inscription| inscription.value().to_string());
+
+        Ok(match inscription {
+            Some(inscription) => Self::validity_row(&txn.open_table(VALIDITIES)?, &inscription)?
+                .filter(|row| row.address == address),
+            None => None,
+        })
+    }
+
+    fn add_domain_in(&self, txn: &WriteTransaction, row: &DomainRow, now: u64) -> KvResult<bool> {
+        // Bound first, so the domains table is closed again before any removal.
+        let existing = match Self::domain_inscription(txn, &row.name)? {
+            Some(inscription) => Self::domain_row(&txn.open_table(DOMAINS)?, &inscription)?,
+            None => None,
+        };
+
+        if let Some(existing) = existing {
+            // Until the domain is released only its owner can register it again.
+            if self.policy.status(existing.valid_from, now) == DomainStatus::Released {
+                Self::clear_name(txn, &row.name)?;
+            } else if existing.address != row.address || existing.valid_from > row.valid_from {
+                return Ok(false);
+            } else {
+                Self::remove_domain_row(txn, &existing.inscription)?;
+            }
+        }
+
+        Self::insert_domain_row(txn, row)
+    }
+
+    fn renew_domain_in(
+        &self,
+        txn: &WriteTransaction,
+        address: &str,
+        inscription: &str,
+        renewal: &DomainRenew,
+        signature: Option<&Signature>,
+        now: u64,
+    ) -> KvResult<bool> {
+        let row = match Self::domain_inscription(txn, &renewal.domain)? {
+            Some(inscription) => Self::domain_row(&txn.open_table(DOMAINS)?, &inscription)?,
+            None => None,
+        };
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(false),
+        };
+
+        let validity = match txn.open_table(VALIDITY_DOMAINS)?.get(row.name.as_str())? {
+            Some(inscription) => {
+                Self::validity_row(&txn.open_table(VALIDITIES)?, inscription.value())?
+            }
+            None => None,
+        };
+        let credentials = validity
+            .filter(|validity| validity.address == row.address)
+            .and_then(|validity| {
+                let algorithm = validity.algorithm.as_str().try_into().ok()?;
+                Some(Credentials::new(algorithm, validity.public_key))
+            });
+
+        let last_inscription = row.last_renewal.as_deref().unwrap_or(&row.inscription);
+
+        if !may_renew(
+            &row.address,
+            address,
+            last_inscription,
+            signature,
+            credentials,
+        ) {
+            return Ok(false);
+        }
+
+        let valid_from = match self
+            .policy
+            .renewed_valid_from(row.valid_from, renewal.periods, now)
+        {
+            Some(valid_from) => valid_from,
+            None => return Ok(false),
+        };
+
+        // Reinserted, so the expiry index follows the new time.
+        Self::remove_domain_row(txn, &row.inscription)?;
+        Self::insert_domain_row(
+            txn,
+            &DomainRow {
+                valid_from,
+                last_renewal: Some(inscription.to_string()),
+                ..row
+            },
+        )
+    }
+
+    /// Removes a released domain with every row stored under its name.
+    fn expire_domain_in(&self, txn: &WriteTransaction, domain: &str, now: u64) -> KvResult<bool> {
+        let row = match Self::domain_inscription(txn, domain)? {
+            Some(inscription) => Self::domain_row(&txn.open_table(DOMAINS)?, &inscription)?,
+            None => None,
+        };
+
+        match row {
+            Some(row) if self.policy.status(row.valid_from, now) == DomainStatus::Released => {
+                Self::clear_name(txn, domain)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Gets the names of the released domains.
+    fn released_names(&self, txn: &WriteTransaction) -> KvResult<Vec<String>> {
+        let until = match self.policy.released_until() {
+            Some(until) => until,
+            None => return Ok(Vec::new()),
+        };
+
+        let domains = txn.open_table(DOMAINS)?;
+        let mut names = Vec::new();
+
+        for entry in txn.open_multimap_table(DOMAIN_EXPIRY)?.range(..=until)? {
+            for inscription in entry?.1 {
+                if let Some(row) = Self::domain_row(&domains, inscription?.value())? {
+                    names.push(row.name);
+                }
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Removes every row stored under a domain name.
+    fn clear_name(txn: &WriteTransaction, name: &str) -> KvResult<()> {
+        Self::remove_domain_in(txn, name)?;
+        Self::remove_validity_in(txn, name)?;
+        Self::remove_data_in(txn, name)?;
+
+        for id in Self::subdomain_ids(txn, name)? {
+            Self::remove_subdomain_row(txn, id)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove_domain_in(txn: &WriteTransaction, domain: &str) -> KvResult<bool> {
+        match Self::domain_inscription(txn, domain)? {
+            Some(inscription) => Self::remove_domain_row(txn, &inscription),
+            None => Ok(false),
+        }
+    }
+
+    fn remove_subdomains_in(
+        txn: &WriteTransaction,
+        domain: &str,
+        subdomain: &str,
+    ) -> KvResult<bool> {
+        let mut ids = Vec::new();
+        {
+            let subdomains = txn.open_table(SUBDOMAINS)?;
+
+            for id in txn.open_multimap_table(SUBDOMAIN_DOMAINS)?.get(domain)? {
+                let id = id?.value();
+
+                if let Some(row) = subdomains.get(id)? {
+                    let row: SubDomainRow = decode(row.value())?;
+
+                    if row.subdomain == subdomain {
+                        ids.push(id);
+                    }
+                }
+            }
+        }
+
+        for id in ids.iter() {
+            Self::remove_subdomain_row(txn, *id)?;
+        }
+
+        Ok(!ids.is_empty())
+    }
+
+    fn remove_subdomain_in(txn: &WriteTransaction, inscription: &str) -> KvResult<bool> {
+        let mut ids = Vec::new();
+
+        for id in txn
+            .open_multimap_table(SUBDOMAIN_INSCRIPTIONS)?
+            .get(inscription)?
+        {
+            ids.push(id?.value());
+        }
+
+        for id in ids.iter() {
+            Self::remove_subdomain_row(txn, *id)?;
+        }
+
+        Ok(!ids.is_empty())
+    }
+
+    /// Removes the subdomains, validity and data inscribed by an inscription, as long as they are stored under an address.
+    fn drop_in(txn: &WriteTransaction, address: &str, inscription: &str) -> KvResult<bool> {
+        let mut ids = Vec::new();
+        {
+            let subdomains = txn.open_table(SUBDOMAINS)?;
+
+            for id in txn
+                .open_multimap_table(SUBDOMAIN_INSCRIPTIONS)?
+                .get(inscription)?
+            {
+                let id = id?.value();
+
+                if let Some(row) = subdomains.get(id)? {
+                    let row: SubDomainRow = decode(row.value())?;
+
+                    if row.address == address {
+                        ids.push(id);
+                    }
+                }
+            }
+        }
+
+        for id in ids.iter() {
+            Self::remove_subdomain_row(txn, *id)?;
+        }
+
+        // Evaluate every removal, an inscription only ever inscribed one of them.
+        let validity = Self::validity_row(&txn.open_table(VALIDITIES)?, inscription)?
+            .is_some_and(|row| row.address == address)
+            && Self::remove_validity_row(txn, inscription)?;
+        let data = match txn.open_table(DATA)?.get(inscription)? {
+            Some(row) => decode::<DataRow>(row.value())?.address == address,
+            None => false,
+        } && Self::remove_data_row(txn, inscription)?;
+
+        Ok(!ids.is_empty() || validity || data)
+    }
+
+    fn remove_validity_in(txn: &WriteTransaction, domain: &str) -> KvResult<bool> {
+        let inscription = txn
+            .open_table(VALIDITY_DOMAINS)?
+            .get(domain)?
+            .map(|inscription| inscription.value().to_string());
+
+        match inscription {
+            Some(inscription) => Self::remove_validity_row(txn, &inscription),
+            None => Ok(false),
+        }
+    }
+
+    fn update_validity_in(
+        &self,
+        txn: &WriteTransaction,
+        validity: ValidityTransfer,
+        now: u64,
+    ) -> KvResult<bool> {
+        match self.domain_validity_row(txn, &validity.domain, now)? {
+            Some(current_validity) => Self::update_validity_by_inscription_in(
+                txn,
+                &current_validity.address,
+                &current_validity.inscription,
+                validity,
+            ),
+            None => Ok(false),
+        }
+    }
+
+    fn update_validity_by_inscription_in(
+        txn: &WriteTransaction,
+        address: &str,
+        inscription: &str,
+        validity: ValidityTransfer,
+    ) -> KvResult<bool> {
+        let new_credentials = match validity.new_credentials {
+            Some(new_credentials) => new_credentials,
+            None => return Self::remove_validity_row(txn, inscription),
+        };
+
+        let exists = txn.open_table(VALIDITIES)?.get(inscription)?.is_some();
+        let taken = txn
+            .open_table(VALIDITY_DOMAINS)?
+            .get(validity.domain.as_str())?
+            .is_some_and(|owner| owner.value() != inscription);
+
+        // Check before removing, so a rejected update leaves the current validity in place.
+        if !exists || taken {
+            return Ok(false);
+        }
+
+        let row = ValidityRow::from_model(
+            address,
+            inscription,
+            Validity {
+                domain: validity.domain,
+                credentials: new_credentials,
+            },
+        );
+
+        Self::remove_validity_row(txn, inscription)?;
+        Self::insert_validity_row(txn, &row)
+    }
+
+    fn remove_data_in(txn: &WriteTransaction, domain: &str) -> KvResult<bool> {
+        let inscriptions = Self::data_inscriptions(txn, domain)?;
+
+        for inscription in inscriptions.iter() {
+            Self::remove_data_row(txn, inscription)?;
+        }
+
+        Ok(!inscriptions.is_empty())
+    }
+
+    fn transfer_inscription_in(
+        &self,
+        txn: &WriteTransaction,
+        inscription: &str,
+        new_address: &str,
+        now: u64,
+    ) -> KvResult<bool> {
+        let domain = self
+            .live_domain_row(txn, inscription, now)?
+            .ok_or_else(|| KvError::Db(DbError::not_found("domain", inscription)))?;
+
+        if let Some(validity) = self.domain_validity_row(txn, &domain.name, now)? {
+            Self::remove_validity_row(txn, &validity.inscription)?;
+        }
+
+        Self::set_domain_address(txn, inscription, new_address)
+    }
+
+    fn subdomain_ids(txn: &WriteTransaction, domain: &str) -> KvResult<Vec<u64>> {
+        let mut ids = Vec::new();
+
+        for id in txn.open_multimap_table(SUBDOMAIN_DOMAINS)?.get(domain)? {
+            ids.push(id?.value());
+        }
+
+        Ok(ids)
+    }
+
+    fn last_journal_entry(
+        table: &impl ReadableTable<u64, &'static [u8]>,
+    ) -> KvResult<Option<(u64, JournalEntry)>> {
+        match table.last()? {
+            Some((sequence, entry)) => Ok(Some((sequence.value(), decode(entry.value())?))),
+            None => Ok(None),
+        }
+    }
+
+    fn checkpoint(
+        table: &impl ReadableTable<&'static str, &'static [u8]>,
+    ) -> KvResult<Option<Block>> {
+        match table.get(FINALIZED)? {
+            Some(block) => Ok(Some(decode(block.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the tip of the chain, the checkpoint if the whole journal was pruned.
+    fn tip(
+        journal: &impl ReadableTable<u64, &'static [u8]>,
+        checkpoint: &impl ReadableTable<&'static str, &'static [u8]>,
+    ) -> KvResult<Option<Block>> {
+        match Self::last_journal_entry(journal)? {
+            Some((_, entry)) => Ok(Some(entry.block)),
+            None => Self::checkpoint(checkpoint),
+        }
+    }
+
+    /// Removes the journal entries at or below the finalized height of a block,
+    /// and moves the checkpoint to the newest removed entry.
+    fn prune(&self, txn: &WriteTransaction, block: &Block) -> KvResult<()> {
+        let height = match finalized_height(block, self.finality_depth) {
+            Some(height) => height,
+            None => return Ok(()),
+        };
+        let mut journal = txn.open_table(JOURNAL)?;
+        let mut sequences = Vec::new();
+        let mut newest = None;
+
+        // The journal is ordered by height, so the finalized entries come first.
+        for entry in journal.iter()? {
+            let (sequence, entry) = entry?;
+            let entry: JournalEntry = decode(entry.value())?;
+
+            if entry.block.height > height {
+                break;
+            }
+
+            sequences.push(sequence.value());
+            newest = Some(entry.block);
+        }
+
+        for sequence in sequences {
+            journal.remove(sequence)?;
+        }
+
+        if let Some(block) = newest {
+            txn.open_table(CHECKPOINT)?
+                .insert(FINALIZED, encode(&block)?.as_slice())?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the domain names of every row inscribed by an inscription.
+    fn inscription_names(txn: &WriteTransaction, inscription: &str) -> KvResult<Vec<String>> {
+        let mut names = Vec::new();
+
+        if let Some(row) = Self::domain_row(&txn.open_table(DOMAINS)?, inscription)? {
+            names.push(row.name);
+        }
+
+        let subdomains = txn.open_table(SUBDOMAINS)?;
+
+        for id in txn
+            .open_multimap_table(SUBDOMAIN_INSCRIPTIONS)?
+            .get(inscription)?
+        {
+            if let Some(row) = subdomains.get(id?.value())? {
+                names.push(decode::<SubDomainRow>(row.value())?.domain);
+            }
+        }
+
+        if let Some(row) = Self::validity_row(&txn.open_table(VALIDITIES)?, inscription)? {
+            names.push(row.domain);
+        }
+
+        if let Some(row) = txn.open_table(DATA)?.get(inscription)? {
+            names.push(decode::<DataRow>(row.value())?.domain);
+        }
+
+        Ok(names)
+    }
+
+    /// Gets the name of every domain with rows of any kind, in order.
+    fn stored_names(txn: &WriteTransaction) -> KvResult<BTreeSet<String>> {
+        let mut names = BTreeSet::new();
+
+        for entry in txn.open_table(DOMAIN_NAMES)?.iter()? {
+            names.insert(entry?.0.value().to_string());
+        }
+
+        for entry in txn.open_multimap_table(SUBDOMAIN_DOMAINS)?.iter()? {
+            names.insert(entry?.0.value().to_string());
+        }
+
+        for entry in txn.open_table(VALIDITY_DOMAINS)?.iter()? {
+            names.insert(entry?.0.value().to_string());
+        }
+
+        for entry in txn.open_multimap_table(DATA_DOMAINS)?.iter()? {
+            names.insert(entry?.0.value().to_string());
+        }
+
+        Ok(names)
+    }
+
+    /// Snapshots every domain with rows of any kind.
+    fn snapshots(txn: &WriteTransaction) -> KvResult<Vec<Snapshot>> {
+        Self::stored_names(txn)?
+            .iter()
+            .map(|name| Self::snapshot(txn, name))
+            .collect()
+    }
+
+    fn snapshot(txn: &WriteTransaction, name: &str) -> KvResult<Snapshot> {
+        let mut snapshot = Snapshot {
+            name: name.to_string(),
+            ..Default::default()
+        };
+
+        if let Some(inscription) = Self::domain_inscription(txn, name)? {
+            if let Some(row) = Self::domain_row(&txn.open_table(DOMAINS)?, &inscription)? {
+                snapshot.domains.push(row.into());
+            }
+        }
+
+        let subdomains = txn.open_table(SUBDOMAINS)?;
+
+        for id in Self::subdomain_ids(txn, name)? {
+            if let Some(row) = subdomains.get(id)? {
+                snapshot
+                    .subdomains
+                    .push(decode::<SubDomainRow>(row.value())?.into_record(id));
+            }
+        }
+
+        let inscription = txn
+            .open_table(VALIDITY_DOMAINS)?
+            .get(name)?
+            .map(|inscription| inscription.value().to_string());
+
+        if let Some(inscription) = inscription {
+            if let Some(row) = Self::validity_row(&txn.open_table(VALIDITIES)?, &inscription)? {
+                snapshot.validities.push(row.into());
+            }
+        }
+
+        let data = txn.open_table(DATA)?;
+
+        for inscription in Self::data_inscriptions(txn, name)? {
+            if let Some(row) = data.get(inscription.as_str())? {
+                snapshot.data.push(decode::<DataRow>(row.value())?.into());
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Replaces every row stored under the name of a snapshot with the rows of the snapshot.
+    fn restore(txn: &WriteTransaction, snapshot: Snapshot) -> KvResult<()> {
+        let name = snapshot.name.as_str();
+        Self::clear_name(txn, name)?;
+
+        // Rows that took over a key of the snapshot are replaced as well.
+        let mut restored = true;
+
+        for record in snapshot.domains {
+            Self::remove_domain_row(txn, &record.inscription)?;
+            restored &= Self::insert_domain_row(txn, &record.into())?;
+        }
+
+        for record in snapshot.subdomains {
+            let id = record.id;
+            Self::remove_subdomain_row(txn, id)?;
+            Self::put_subdomain_row(txn, id, &record.into())?;
+
+            let mut sequences = txn.open_table(SEQUENCES)?;
+            let next = sequences
+                .get(SUBDOMAIN_SEQUENCE)?
+                .map(|id| id.value())
+                .unwrap_or(0);
+            sequences.insert(SUBDOMAIN_SEQUENCE, next.max(id + 1))?;
+        }
+
+        for record in snapshot.validities {
+            Self::remove_validity_row(txn, &record.inscription)?;
+            restored &= Self::insert_validity_row(txn, &record.into())?;
+        }
+
+        for record in snapshot.data {
+            Self::remove_data_row(txn, &record.inscription)?;
+            restored &= Self::insert_data_row(txn, &record.into())?;
+        }
+
+        if !restored {
+            return Err(KvError::InvalidData(format!(
+                "the journaled state of {} can not be restored",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Appends entries to the history.
+    fn record(txn: &WriteTransaction, entries: Vec<HistoryEntry>) -> KvResult<()> {
+        let mut history = txn.open_table(HISTORY)?;
+        let mut domains = txn.open_multimap_table(HISTORY_DOMAINS)?;
+        let first = history
+            .last()?
+            .map(|(sequence, _)| sequence.value() + 1)
+            .unwrap_or(0);
+
+        for (sequence, entry) in (first..).zip(entries) {
+            history.insert(sequence, encode(&entry)?.as_slice())?;
+            domains.insert(entry.domain.as_str(), sequence)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the operations of a batch within a write transaction,
+    /// journaling the state they change at its block, or the tip if it has none.
+    ///
+    /// # Returns
+    ///
+    /// * `KvResult<Vec<Change>>` - The changes of the batch, to publish once the transaction is committed.
+    fn commit_in(
+        &self,
+        txn: &WriteTransaction,
+        block: Option<Block>,
+        operations: Vec<Operation>,
+    ) -> KvResult<Vec<Change>> {
+        let last = Self::last_journal_entry(&txn.open_table(JOURNAL)?)?;
+        let (sequence, tip) = match last {
+            Some((sequence, entry)) => (sequence + 1, Some(entry.block)),
+            None => (0, Self::checkpoint(&txn.open_table(CHECKPOINT)?)?),
+        };
+
+        if let Some(block) = &block {
+            check_order(block, tip.as_ref()).map_err(KvError::Db)?;
+        }
+
+        let journaled = journal_block(block.as_ref(), tip);
+        let now = batch_time(block.as_ref());
+
+        let timestamp = history::now();
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+        let mut changes = Vec::new();
+
+        for (index, operation) in operations.into_iter().enumerate() {
+            let scope = operation.scope();
+            let inscription_names = match scope.inscription {
+                Some(inscription) => Self::inscription_names(txn, inscription)?,
+                None => Vec::new(),
+            };
+            let names = scope.resolve(inscription_names);
+
+            if journaled.is_some() {
+                for name in names.iter() {
+                    if !snapshots.iter().any(|snapshot| &snapshot.name == name) {
+                        snapshots.push(Self::snapshot(txn, name)?);
+                    }
+                }
+            }
+
+            let entries = history::entries(&operation, &names, block.as_ref(), timestamp);
+            changes.extend(operation.changes(&names));
+            let name = operation.name();
+
+            if !self.apply(txn, operation, now)? {
+                return Err(KvError::Db(DbError::Rejected {
+                    index,
+                    operation: name,
+                }));
+            }
+
+            Self::record(txn, entries)?;
+        }
+
+        // A batch without a block is only journaled if it changed anything.
+        if let Some(block) = journaled.filter(|_| block.is_some() || !snapshots.is_empty()) {
+            let entry = encode(&JournalEntry { block, snapshots })?;
+
+            txn.open_table(JOURNAL)?
+                .insert(sequence, entry.as_slice())?;
+        }
+
+        if let Some(block) = &block {
+            self.prune(txn, block)?;
+        }
+
+        Ok(changes)
+    }
+
+    /// Applies a single operation of a batch within its transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `txn` - The transaction of the batch.
+    /// * `operation` - The operation to apply.
+    /// * `now` - The time the expiry of domains is evaluated at, see [`batch_time`](batch_time).
+    ///
+    /// # Returns
+    ///
+    /// * `KvResult<bool>` - Whether the operation was applied.
+    fn apply(&self, txn: &WriteTransaction, operation: Operation, now: u64) -> KvResult<bool> {
+        match operation {
+            Operation::AddDomain {
+                address,
+                inscription,
+                domain,
+            } => self.add_domain_in(
+                txn,
+                &DomainRow::from_model(&address, &inscription, domain),
+                now,
+            ),
+            Operation::RemoveDomain { domain } => Self::remove_domain_in(txn, &domain),
+            Operation::RemoveDomainByInscription { inscription } => {
+                Self::remove_domain_row(txn, &inscription)
+            }
+            Operation::ExpireDomain { domain } => self.expire_domain_in(txn, &domain, now),
+            Operation::RenewDomain {
+                address,
+                inscription,
+                renewal,
+                signature,
+            } => self.renew_domain_in(
+                txn,
+                &address,
+                &inscription,
+                &renewal,
+                signature.as_ref(),
+                now,
+            ),
+            Operation::AddSubdomain {
+                address,
+                inscription,
+                subdomain,
+            } => {
+                Self::insert_subdomain_row(
+                    txn,
+                    &SubDomainRow::from_model(&address, &inscription, subdomain),
+                )?;
+                Ok(true)
+            }
+            Operation::RemoveSubdomains { domain, subdomain } => {
+                Self::remove_subdomains_in(txn, &domain, &subdomain)
+            }
+            Operation::RemoveSubdomain { inscription } => {
+                Self::remove_subdomain_in(txn, &inscription)
+            }
+            Operation::AddValidity {
+                address,
+                inscription,
+                validity,
+            } => Self::insert_validity_row(
+                txn,
+                &ValidityRow::from_model(&address, &inscription, validity),
+            ),
+            Operation::RemoveValidity { domain } => Self::remove_validity_in(txn, &domain),
+            Operation::RemoveValidityByInscription { inscription } => {
+                Self::remove_validity_row(txn, &inscription)
+            }
+            Operation::UpdateValidity { address, validity } => {
+                match self.domain_owner(txn, &validity.domain, now)? {
+                    Some(owner) if owner == address => self.update_validity_in(txn, validity, now),
+                    _ => Ok(false),
+                }
+            }
+            Operation::UpdateValidityByInscription {
+                address,
+                inscription,
+                validity,
+            } => Self::update_validity_by_inscription_in(txn, &address, &inscription, validity),
+            Operation::AddData {
+                address,
+                inscription,
+                data,
+            } => Self::insert_data_row(txn, &DataRow::from_model(&address, &inscription, data)),
+            Operation::RemoveData { domain } => Self::remove_data_in(txn, &domain),
+            Operation::RemoveDataByInscription { inscription } => {
+                Self::remove_data_row(txn, &inscription)
+            }
+            Operation::TransferInscription {
+                inscription,
+                new_owner,
+            } => self.transfer_inscription_in(txn, &inscription, &new_owner, now),
+            Operation::Drop {
+                address,
+                inscription,
+            } => Self::drop_in(txn, &address, &inscription),
+        }
+    }
+}
+
+/// The rows are read within a write transaction, so no batch can be committed while they are read.
+impl StateRows for KvRepository {
+    async fn all_rows(&self) -> Result<Vec<Snapshot>> {
+        self.write(Self::snapshots)
+    }
+
+    async fn rows_of(&self, names: Vec<String>) -> Result<Vec<Snapshot>> {
+        self.write(|txn| names.iter().map(|name| Self::snapshot(txn, name)).collect())
+    }
+}
+
+impl Repository for KvRepository {
+    /// Connects using the configuration from the environment. (See [`DatabaseConfig::from_env`](DatabaseConfig::from_env).)
+    async fn new() -> Result<Self> {
+        Self::connect(&DatabaseConfig::from_env()?)
+    }
+
+    async fn new_memory() -> Result<Self> {
+        let database = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .map_err(KvError::from)?;
+
+        Self::from_database(database)
+    }
+
+    async fn get_domain(&self, domain: &str) -> Result<(String, Domain)> {
+        let row = self.get_domain_row(domain)?;
+        Ok((row.address.to_string(), row.to_model()))
+    }
+
+    async fn get_domain_by_inscription(&self, inscription: &str) -> Result<(String, Domain)> {
+        let row = self.find_domain_row(inscription, |_| Ok(Some(inscription.to_string())))?;
+        Ok((row.address.to_string(), row.to_model()))
+    }
+
+    async fn get_domain_by_address(&self, address: &str) -> Result<Domain> {
+        let row = self.find_domain_row(address, |txn| {
+            match txn
+                .open_multimap_table(DOMAIN_ADDRESSES)?
+                .get(address)?
+                .next()
+            {
+                Some(inscription) => Ok(Some(inscription?.value().to_string())),
+                None => Ok(None),
+            }
+        })?;
+
+        Ok(row.to_model())
+    }
+
+    async fn list_domains_by_address(&self, address: &str) -> Result<Vec<Domain>> {
+        let rows = self.read(|txn| self.address_domain_rows(txn, address))?;
+        Ok(rows.iter().map(DomainRow::to_model).collect())
+    }
+
+    async fn list_domains(&self, page: &Page) -> Result<Vec<(String, Domain)>> {
+        let rows = self.read(|txn| self.page_domain_rows(txn, page, None))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.address.to_string(), row.to_model()))
+            .collect())
+    }
+
+    async fn search_domains(
+        &self,
+        filter: &NameFilter,
+        page: &Page,
+    ) -> Result<Vec<(String, Domain)>> {
+        let rows = self.read(|txn| self.page_domain_rows(txn, page, Some(filter)))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.address.to_string(), row.to_model()))
+            .collect())
+    }
+
+    async fn domains_expiring_within(&self, days: u64) -> Result<Vec<(String, Domain)>> {
+        let mut rows = self.read(|txn| self.live_domain_rows(txn, Some(days)))?;
+        rows.sort_by(|a, b| (a.valid_from, &a.name).cmp(&(b.valid_from, &b.name)));
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.address.to_string(), row.to_model()))
+            .collect())
+    }
+
+    async fn count_domains(&self) -> Result<u64> {
+        self.read(|txn| {
+            let mut count = 0;
+
+            for entry in txn
+                .open_multimap_table(DOMAIN_EXPIRY)?
+                .range(self.policy.active_valid_from(None))?
+            {
+                count += entry?.1.count() as u64;
+            }
+
+            Ok(count)
+        })
+    }
+
+    async fn count_domains_by_address(&self, address: &str) -> Result<u64> {
+        Ok(self
+            .read(|txn| self.address_domain_rows(txn, address))?
+            .len() as u64)
+    }
+
+    async fn get_domain_address(&self, domain: &str) -> Result<String> {
+        Ok(self.get_domain_row(domain)?.address)
+    }
+
+    /// Finds and removes the released domains within a single write transaction.
+    async fn sweep_expired(&self) -> Result<Vec<String>> {
+        let (names, changes) = self.write(|txn| {
+            let names = self.released_names(txn)?;
+            let operations = names
+                .iter()
+                .map(|domain| Operation::ExpireDomain {
+                    domain: domain.to_string(),
+                })
+                .collect();
+
+            let changes = self.commit_in(txn, None, operations)?;
+            Ok((names, changes))
+        })?;
+
+        self.refresh(changed_names(&changes)).await;
+        self.changes.publish(changes);
+        Ok(names)
+    }
+
+    async fn get_subdomain(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        self.get_subdomain_rows(domain)?
+            .iter()
+            .filter(|row| row.subdomain == subdomain && row.address == address)
+            .map(SubDomainRow::to_model)
+            .collect()
+    }
+
+    async fn get_subdomain_inscriptions(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        self.get_subdomain_rows(domain)?
+            .iter()
+            .filter(|row| row.subdomain == subdomain && row.address == address)
+            .map(|row| Ok((row.inscription.to_string(), row.to_model()?.1)))
+            .collect()
+    }
+
+    async fn get_subdomain_names(&self, domain: &str) -> Result<Vec<String>> {
+        let address = self.get_domain_address(domain).await?;
+        let mut names: Vec<String> = Vec::new();
+
+        for row in self.get_subdomain_rows(domain)? {
+            if row.address == address && !names.contains(&row.subdomain) {
+                names.push(row.subdomain);
+            }
+        }
+
+        Ok(names)
+    }
+
+    async fn list_subdomains(&self, domain: &str) -> Result<Vec<(String, SubDomain)>> {
+        self.owned_subdomain_rows(domain)?
+            .iter()
+            .map(SubDomainRow::to_model)
+            .collect()
+    }
+
+    async fn count_subdomains(&self, domain: &str) -> Result<u64> {
+        Ok(self.owned_subdomain_rows(domain)?.len() as u64)
+    }
+
+    async fn get_subdomain_by_inscription(&self, inscription: &str) -> Result<(String, SubDomain)> {
+        let row: Option<SubDomainRow> = self.read(|txn| {
+            let id = match txn
+                .open_multimap_table(SUBDOMAIN_INSCRIPTIONS)?
+                .get(inscription)?
+                .next()
+            {
+                Some(id) => id?.value(),
+                None => return Ok(None),
+            };
+
+            txn.open_table(SUBDOMAINS)?
+                .get(id)?
+                .map(|row| decode(row.value()))
+                .transpose()
+        })?;
+
+        row.ok_or_else(|| DbError::not_found("subdomain", inscription))?
+            .to_model()
+    }
+
+    async fn get_validity(&self, domain: &str) -> Result<(String, Validity)> {
+        self.get_validity_row(domain)?
+            .ok_or_else(|| DbError::not_found("validity", domain))?
+            .to_model()
+    }
+
+    async fn get_validity_by_inscription(&self, inscription: &str) -> Result<(String, Validity)> {
+        self.read(|txn| Self::validity_row(&txn.open_table(VALIDITIES)?, inscription))?
+            .ok_or_else(|| DbError::not_found("validity", inscription))?
+            .to_model()
+    }
+
+    async fn get_data(&self, domain: &str) -> Result<Vec<(String, Data)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        self.read(|txn| {
+            let data = txn.open_table(DATA)?;
+            let mut rows = Vec::new();
+
+            for inscription in txn.open_multimap_table(DATA_DOMAINS)?.get(domain)? {
+                if let Some(row) = data.get(inscription?.value())? {
+                    let row: DataRow = decode(row.value())?;
+
+                    if row.address == address {
+                        rows.push(row.to_model());
+                    }
+                }
+            }
+
+            Ok(rows)
+        })
+    }
+
+    async fn get_data_by_inscription(&self, inscription: &str) -> Result<(String, Data)> {
+        let row: Option<DataRow> = self.read(|txn| {
+            txn.open_table(DATA)?
+                .get(inscription)?
+                .map(|row| decode(row.value()))
+                .transpose()
+        })?;
+
+        Ok(row
+            .ok_or_else(|| DbError::not_found("data", inscription))?
+            .to_model())
+    }
+
+    /// Applies the batch within a single write transaction, which is dropped (and thereby rolled back)
+    /// as soon as an operation is rejected or fails.
+    async fn commit(&self, batch: Batch) -> Result<()> {
+        let (block, operations) = batch.into_parts();
+        let changes = self.write(|txn| self.commit_in(txn, block, operations))?;
+
+        self.refresh(changed_names(&changes)).await;
+        self.changes.publish(changes);
+        Ok(())
+    }
+
+    async fn get_tip(&self) -> Result<Option<Block>> {
+        self.read(|txn| Self::tip(&txn.open_table(JOURNAL)?, &txn.open_table(CHECKPOINT)?))
+    }
+
+    /// Undoes the batches within a single write transaction, so a failure leaves everything in place.
+    async fn rollback_to(&self, height: u64) -> Result<usize> {
+        let (count, changes) = self.write(|txn| {
+            check_finalized(
+                height,
+                Self::checkpoint(&txn.open_table(CHECKPOINT)?)?.as_ref(),
+            )
+            .map_err(KvError::Db)?;
+
+            let timestamp = history::now();
+            let mut count = 0;
+            let mut changes = Vec::new();
+
+            loop {
+                let (sequence, entry) = match Self::last_journal_entry(&txn.open_table(JOURNAL)?)? {
+                    Some((sequence, entry)) if entry.block.height > height => (sequence, entry),
+                    _ => break,
+                };
+
+                // Every domain was snapshotted only once per batch, so the order does not matter.
+                for snapshot in entry.snapshots {
+                    Self::record(
+                        txn,
+                        vec![history::rollback_entry(
+                            &snapshot.name,
+                            &entry.block,
+                            height,
+                            timestamp,
+                        )],
+                    )?;
+                    changes.push(Change::rolled_back(&snapshot.name));
+                    Self::restore(txn, snapshot)?;
+                }
+
+                txn.open_table(JOURNAL)?.remove(sequence)?;
+                count += 1;
+            }
+
+            Ok((count, changes))
+        })?;
+
+        self.refresh(changed_names(&changes)).await;
+        self.changes.publish(changes);
+        Ok(count)
+    }
+
+    async fn domain_history_positions(&self, domain: &str) -> Result<Vec<(u64, HistoryEntry)>> {
+        self.read(|txn| {
+            let history = txn.open_table(HISTORY)?;
+            let mut entries = Vec::new();
+
+            // The sequences of a domain are iterated in ascending order, oldest first.
+            for sequence in txn.open_multimap_table(HISTORY_DOMAINS)?.get(domain)? {
+                let sequence = sequence?.value();
+
+                if let Some(entry) = history.get(sequence)? {
+                    entries.push((sequence + 1, decode(entry.value())?));
+                }
+            }
+
+            Ok(entries)
+        })
+    }
+
+    async fn domain_history_position(&self, domain: &str) -> Result<Option<u64>> {
+        self.read(|txn| {
+            let history = txn.open_table(HISTORY)?;
+
+            // Newest first, from the end of the sequences of the domain.
+            for sequence in txn.open_multimap_table(HISTORY_DOMAINS)?.get(domain)?.rev() {
+                let sequence = sequence?.value();
+
+                if history.get(sequence)?.is_some() {
+                    return Ok(Some(sequence + 1));
+                }
+            }
+
+            Ok(None)
+        })
+    }
+
+    /// The position of an entry is its sequence plus one, as sequences start at zero.
+    async fn history_after(&self, position: u64) -> Result<Vec<(u64, HistoryEntry)>> {
+        self.read(|txn| {
+            let mut entries = Vec::new();
+
+            for entry in txn.open_table(HISTORY)?.range(position..)? {
+                let (sequence, entry) = entry?;
+                entries.push((sequence.value() + 1, decode(entry.value())?));
+            }
+
+            Ok(entries)
+        })
+    }
+
+    async fn history_position(&self) -> Result<u64> {
+        self.read(|txn| {
+            Ok(txn
+                .open_table(HISTORY)?
+                .last()?
+                .map_or(0, |(sequence, _)| sequence.value() + 1))
+        })
+    }
+
+    async fn records_at(&self, domain: &str, height: u64) -> Result<Vec<(String, SubDomain)>> {
+        let (entries, current) = self.read(|txn| {
+            check_finalized(
+                height,
+                Self::checkpoint(&txn.open_table(CHECKPOINT)?)?.as_ref(),
+            )
+            .map_err(KvError::Db)?;
+
+            let mut entries: Vec<JournalEntry> = Vec::new();
+
+            for entry in txn.open_table(JOURNAL)?.iter()? {
+                let entry: JournalEntry = decode(entry?.1.value())?;
+
+                if entry.block.height > height {
+                    entries.push(entry);
+                }
+            }
+
+            // Only the domain and its records are needed to get the records.
+            let mut current = Snapshot {
+                name: domain.to_string(),
+                ..Default::default()
+            };
+
+            if let Some(inscription) = txn.open_table(DOMAIN_NAMES)?.get(domain)? {
+                if let Some(row) = Self::domain_row(&txn.open_table(DOMAINS)?, inscription.value())?
+                {
+                    current.domains.push(row.into());
+                }
+            }
+
+            let subdomains = txn.open_table(SUBDOMAINS)?;
+
+            for id in txn.open_multimap_table(SUBDOMAIN_DOMAINS)?.get(domain)? {
+                let id = id?.value();
+
+                if let Some(row) = subdomains.get(id)? {
+                    current
+                        .subdomains
+                        .push(decode::<SubDomainRow>(row.value())?.into_record(id));
+                }
+            }
+
+            Ok((entries, current))
+        })?;
+
+        snapshot_at(entries.iter(), domain)
+            .unwrap_or(&current)
+            .records()
+    }
+
+    /// Exports within a write transaction, so no batch can be committed while the rows are read.
+    async fn export_snapshot(&self) -> Result<StateSnapshot> {
+        let (block, domains) = self.write(|txn| {
+            let block = Self::tip(&txn.open_table(JOURNAL)?, &txn.open_table(CHECKPOINT)?)?;
+            Ok((block, Self::snapshots(txn)?))
+        })?;
+
+        StateSnapshot::new(block, domains)
+    }
+
+    /// Imports within a single write transaction, which is dropped (and thereby rolled back)
+    /// if a row could not be stored or the hash does not match.
+    async fn import_snapshot(&self, snapshot: StateSnapshot, expected_hash: &str) -> Result<()> {
+        let names = snapshot.names().map(str::to_string).collect();
+
+        self.write(|txn| {
+            snapshot.expect_hash(expected_hash).map_err(KvError::Db)?;
+
+            if !Self::is_empty_store(txn)? {
+                return Err(KvError::InvalidData(
+                    "the store is not empty, refusing to import".to_string(),
+                ));
+            }
+
+            let expected = snapshot.clone();
+            let timestamp = history::now();
+
+            for domain in snapshot.into_domains() {
+                Self::record(
+                    txn,
+                    vec![history::import_entry(
+                        &domain.name,
+                        expected.block(),
+                        timestamp,
+                    )],
+                )?;
+                Self::restore(txn, domain)?;
+            }
+
+            expected.check(Self::snapshots(txn)?).map_err(KvError::Db)?;
+
+            if let Some(block) = expected.block() {
+                txn.open_table(CHECKPOINT)?
+                    .insert(FINALIZED, encode(block)?.as_slice())?;
+            }
+
+            Ok(())
+        })?;
+
+        self.refresh(names).await;
+        Ok(())
+    }
+
+    async fn state_root(&self) -> Result<StateRoot> {
+        self.commitment.root(self).await
+    }
+
+    async fn prove(&self, name: &str) -> Result<NameProof> {
+        self.commitment.prove(self, name).await
+    }
+
+    fn changes(&self) -> &ChangeFeed {
+        &self.changes
+    }
+
+    fn policy(&self) -> ExpiryPolicy {
+        self.policy
+    }
+}
@@ -0,0 +1,1463 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::slice;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::SystemTime;
+
+use shared::common::{DbError, Result};
+use shared::time::{epoch_seconds_now, system_time_from_epoch_seconds};
+use xdns_data::models::{
+    Algorithm, Credentials, Data, Domain, DomainRenew, Signature, SubDomain, Validity,
+    ValidityTransfer,
+};
+
+use crate::batch::{Batch, Operation};
+use crate::changes::{Change, ChangeFeed};
+use crate::commitment::{changed_names, NameProof, StateCommitment, StateRoot, StateRows};
+use crate::expiry::{may_renew, DomainStatus, ExpiryPolicy};
+use crate::history::{self, HistoryEntry};
+use crate::journal::{
+    batch_time, check_finalized, check_order, finalized_height, journal_block, snapshot_at, Block,
+    DataRecord, DomainRecord, JournalEntry, Snapshot, SubDomainRecord, ValidityRecord,
+    DEFAULT_FINALITY_DEPTH,
+};
+use crate::query::{NameFilter, Page};
+use crate::snapshot::StateSnapshot;
+use crate::traits::Repository;
+
+#[derive(Clone)]
+struct DomainRow {
+    inscription: String,
+    address: String,
+    name: String,
+    valid_from: u64,
+    last_renewal: Option<String>,
+}
+
+#[derive(Clone)]
+struct SubDomainRow {
+    inscription: String,
+    address: String,
+    subdomain: SubDomain,
+}
+
+#[derive(Clone)]
+struct ValidityRow {
+    inscription: String,
+    address: String,
+    domain: String,
+    algorithm: Algorithm,
+    public_key: String,
+}
+
+#[derive(Clone)]
+struct DataRow {
+    address: String,
+    domain: String,
+    data: Vec<u8>,
+}
+
+/// The tables of the repository, keyed by their primary key.
+struct State {
+    domains: BTreeMap<String, DomainRow>,
+    subdomains: BTreeMap<u64, SubDomainRow>,
+    next_subdomain_id: u64,
+    validities: BTreeMap<String, ValidityRow>,
+    data: BTreeMap<String, DataRow>,
+    /// The undo information of every batch committed at a block, oldest first.
+    journal: Vec<JournalEntry>,
+    /// The newest block whose journal was pruned.
+    checkpoint: Option<Block>,
+    /// Every applied action, oldest first.
+    history: Vec<HistoryEntry>,
+    /// How long domain registrations stay valid.
+    policy: ExpiryPolicy,
+    /// The amount of blocks below the tip after which the journal is pruned.
+    finality_depth: Option<u64>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            domains: BTreeMap::new(),
+            subdomains: BTreeMap::new(),
+            next_subdomain_id: 0,
+            validities: BTreeMap::new(),
+            data: BTreeMap::new(),
+            journal: Vec::new(),
+            checkpoint: None,
+            history: Vec::new(),
+            policy: ExpiryPolicy::default(),
+            finality_depth: Some(DEFAULT_FINALITY_DEPTH),
+        }
+    }
+}
+
+/// The rows of the names a change touched, as they were before it, to undo a change that failed halfway.
+/// Only the rows of touched names are copied, instead of the whole state.
+#[derive(Default)]
+struct UndoLog {
+    names: BTreeSet<String>,
+    domains: Vec<(String, DomainRow)>,
+    subdomains: Vec<(u64, SubDomainRow)>,
+    validities: Vec<(String, ValidityRow)>,
+    data: Vec<(String, DataRow)>,
+    next_subdomain_id: u64,
+    journal_len: usize,
+    history_len: usize,
+}
+
+/// A repository that keeps everything in memory, without any SQL involved.
+/// Has the same semantics (and constraints) as the [`SqliteRepository`](crate::sources::SqliteRepository),
+/// which makes it suitable for tests and embedded use.
+#[derive(Default)]
+pub struct MemoryRepository {
+    state: RwLock<State>,
+    changes: ChangeFeed,
+    commitment: StateCommitment,
+}
+
+impl DomainRow {
+    fn to_model(&self) -> Domain {
+        Domain {
+            name: self.name.to_string(),
+            valid_from: system_time_from_epoch_seconds(self.valid_from),
+        }
+    }
+}
+
+impl SubDomainRow {
+    fn to_model(&self) -> (String, SubDomain) {
+        (
+            self.address.to_string(),
+            SubDomain {
+                domain: self.subdomain.domain.to_string(),
+                subdomain: self.subdomain.subdomain.to_string(),
+                class: self.subdomain.class,
+                ttl: self.subdomain.ttl,
+                rdata: self.subdomain.rdata.clone(),
+            },
+        )
+    }
+}
+
+impl ValidityRow {
+    fn to_model(&self) -> (String, Validity) {
+        (
+            self.address.to_string(),
+            Validity {
+                domain: self.domain.to_string(),
+                credentials: Credentials::new(self.algorithm, self.public_key.to_string()),
+            },
+        )
+    }
+}
+
+impl DataRow {
+    fn to_model(&self) -> (String, Data) {
+        (
+            self.address.to_string(),
+            Data {
+                domain: self.domain.to_string(),
+                data: self.data.clone(),
+            },
+        )
+    }
+}
+
+impl State {
+    /// Get the first domain that matches a filter, expired domains are left in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key used for the lookup, used in the error.
+    /// * `now` - The time the expiry of the domain is evaluated at, in epoch seconds.
+    /// * `filter` - The filter the domain must match.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DomainRow>` - The domain if it exists and has not expired.
+    fn get_domain_row<F>(&self, key: &str, now: u64, filter: F) -> Result<DomainRow>
+    where
+        F: Fn(&DomainRow) -> bool,
+    {
+        let row = self
+            .domains
+            .values()
+            .find(|row| filter(row))
+            .cloned()
+            .ok_or_else(|| DbError::not_found("domain", key))?;
+
+        if self.policy.status(row.valid_from, now) != DomainStatus::Active {
+            return Err(DbError::Expired { domain: row.name }.into());
+        }
+
+        Ok(row)
+    }
+
+    /// Get the domains that have not expired and match a filter, ordered by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `within_days` - Only include the domains that expire within this many days.
+    /// * `filter` - The filter the domains must match.
+    fn live_domain_rows<F>(&self, within_days: Option<u64>, filter: F) -> Vec<&DomainRow>
+    where
+        F: Fn(&DomainRow) -> bool,
+    {
+        let live = self.policy.active_valid_from(within_days);
+        let mut rows: Vec<&DomainRow> = self
+            .domains
+            .values()
+            .filter(|row| live.contains(&row.valid_from) && filter(row))
+            .collect();
+
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        rows
+    }
+
+    fn owned_subdomain_rows(&self, domain: &str) -> Result<Vec<&SubDomainRow>> {
+        let address = self.get_domain_address(domain, epoch_seconds_now())?;
+        let mut rows: Vec<&SubDomainRow> = self
+            .subdomains
+            .values()
+            .filter(|row| row.subdomain.domain == domain && row.address == address)
+            .collect();
+
+        // Stable, so records of the same subdomain stay in the order they were added.
+        rows.sort_by(|a, b| a.subdomain.subdomain.cmp(&b.subdomain.subdomain));
+        Ok(rows)
+    }
+
+    fn get_validity_row(&self, domain: &str, now: u64) -> Result<Option<ValidityRow>> {
+        let address = self
+            .get_domain_row(domain, now, |row| row.name == domain)?
+            .address;
+
+        Ok(self
+            .validities
+            .values()
+            .find(|row| row.domain == domain && row.address == address)
+            .cloned())
+    }
+
+    fn get_domain_address(&self, domain: &str, now: u64) -> Result<String> {
+        Ok(self
+            .get_domain_row(domain, now, |row| row.name == domain)?
+            .address)
+    }
+
+    fn add_domain(&mut self, address: &str, inscription: &str, domain: Domain, now: u64) -> bool {
+        let valid_from = domain
+            .valid_from
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        if let Some(existing) = self.domains.values().find(|row| row.name == domain.name) {
+            // Until the domain is released only its owner can register it again.
+            if self.policy.status(existing.valid_from, now) == DomainStatus::Released {
+                self.clear_name(&domain.name);
+            } else if existing.address != address || existing.valid_from > valid_from {
+                return false;
+            } else {
+                self.remove_domain(&domain.name);
+            }
+        }
+
+        if self.domains.contains_key(inscription)
+            || self.domains.values().any(|row| row.name == domain.name)
+        {
+            return false;
+        }
+
+        self.domains.insert(
+            inscription.to_string(),
+            DomainRow {
+                inscription: inscription.to_string(),
+                address: address.to_string(),
+                name: domain.name,
+                valid_from,
+                last_renewal: None,
+            },
+        );
+
+        true
+    }
+
+    fn remove_domain(&mut self, domain: &str) -> bool {
+        let count = self.domains.len();
+        self.domains.retain(|_, row| row.name != domain);
+
+        self.domains.len() != count
+    }
+
+    fn remove_domain_by_inscription(&mut self, inscription: &str) -> bool {
+        self.domains.remove(inscription).is_some()
+    }
+
+    fn renew_domain(
+        &mut self,
+        address: &str,
+        inscription: &str,
+        renewal: DomainRenew,
+        signature: Option<&Signature>,
+        now: u64,
+    ) -> bool {
+        let row = match self.domains.values().find(|row| row.name == renewal.domain) {
+            Some(row) => row.clone(),
+            None => return false,
+        };
+
+        let validity = self
+            .validities
+            .values()
+            .find(|validity| validity.domain == row.name && validity.address == row.address)
+            .map(|validity| Credentials::new(validity.algorithm, validity.public_key.to_string()));
+
+        let last_inscription = row.last_renewal.as_deref().unwrap_or(&row.inscription);
+
+        if !may_renew(&row.address, address, last_inscription, signature, validity) {
+            return false;
+        }
+
+        match self
+            .policy
+            .renewed_valid_from(row.valid_from, renewal.periods, now)
+        {
+            Some(valid_from) => {
+                self.domains.insert(
+                    row.inscription.to_string(),
+                    DomainRow {
+                        valid_from,
+                        last_renewal: Some(inscription.to_string()),
+                        ..row
+                    },
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a released domain with every row stored under its name.
+    fn expire_domain(&mut self, domain: &str, now: u64) -> bool {
+        let released = self.domains.values().any(|row| {
+            row.name == domain && self.policy.status(row.valid_from, now) == DomainStatus::Released
+        });
+
+        if released {
+            self.clear_name(domain);
+        }
+
+        released
+    }
+
+    /// Gets the names of the released domains.
+    fn released_names(&self) -> Vec<String> {
+        let now = epoch_seconds_now();
+
+        self.domains
+            .values()
+            .filter(|row| self.policy.status(row.valid_from, now) == DomainStatus::Released)
+            .map(|row| row.name.to_string())
+            .collect()
+    }
+
+    /// Removes every row stored under a domain name.
+    fn clear_name(&mut self, name: &str) {
+        self.domains.retain(|_, row| row.name != name);
+        self.subdomains
+            .retain(|_, row| row.subdomain.domain != name);
+        self.validities.retain(|_, row| row.domain != name);
+        self.data.retain(|_, row| row.domain != name);
+    }
+
+    fn add_subdomain(&mut self, address: &str, inscription: &str, subdomain: SubDomain) -> bool {
+        let id = self.next_subdomain_id;
+        self.next_subdomain_id += 1;
+
+        self.subdomains.insert(
+            id,
+            SubDomainRow {
+                inscription: inscription.to_string(),
+                address: address.to_string(),
+                subdomain,
+            },
+        );
+
+        true
+    }
+
+    fn remove_subdomains(&mut self, domain: &str, subdomain: &str) -> bool {
+        let count = self.subdomains.len();
+        self.subdomains.retain(|_, row| {
+            row.subdomain.domain != domain || row.subdomain.subdomain != subdomain
+        });
+
+        self.subdomains.len() != count
+    }
+
+    fn remove_subdomain(&mut self, inscription: &str) -> bool {
+        let count = self.subdomains.len();
+        self.subdomains
+            .retain(|_, row| row.inscription != inscription);
+
+        self.subdomains.len() != count
+    }
+
+    fn add_validity(&mut self, address: &str, inscription: &str, validity: Validity) -> bool {
+        if self.validities.contains_key(inscription)
+            || self
+                .validities
+                .values()
+                .any(|row| row.domain == validity.domain)
+        {
+            return false;
+        }
+
+        self.validities.insert(
+            inscription.to_string(),
+            ValidityRow {
+                inscription: inscription.to_string(),
+                address: address.to_string(),
+                domain: validity.domain,
+                algorithm: validity.credentials.algorithm,
+                public_key: validity.credentials.public_key,
+            },
+        );
+
+        true
+    }
+
+    fn remove_validity(&mut self, domain: &str) -> bool {
+        let count = self.validities.len();
+        self.validities.retain(|_, row| row.domain != domain);
+
+        self.validities.len() != count
+    }
+
+    fn remove_validity_by_inscription(&mut self, inscription: &str) -> bool {
+        self.validities.remove(inscription).is_some()
+    }
+
+    fn update_validity(&mut self, validity: ValidityTransfer, now: u64) -> bool {
+        match self.get_validity_row(&validity.domain, now) {
+            Ok(Some(current_validity)) => self.update_validity_by_inscription(
+                &current_validity.address,
+                &current_validity.inscription,
+                validity,
+            ),
+            _ => false,
+        }
+    }
+
+    fn update_validity_by_inscription(
+        &mut self,
+        address: &str,
+        inscription: &str,
+        validity: ValidityTransfer,
+    ) -> bool {
+        if let Some(new_credentials) = validity.new_credentials {
+            // The domain of a validity is unique, just like in the SQL schema.
+            if !self.validities.contains_key(inscription)
+                || self
+                    .validities
+                    .values()
+                    .any(|row| row.domain == validity.domain && row.inscription != inscription)
+            {
+                return false;
+            }
+
+            self.validities.insert(
+                inscription.to_string(),
+                ValidityRow {
+                    inscription: inscription.to_string(),
+                    address: address.to_string(),
+                    domain: validity.domain,
+                    algorithm: new_credentials.algorithm,
+                    public_key: new_credentials.public_key,
+                },
+            );
+
+            return true;
+        }
+
+        self.remove_validity_by_inscription(inscription)
+    }
+
+    fn add_data(&mut self, address: &str, inscription: &str, data: Data) -> bool {
+        if self.data.contains_key(inscription) {
+            return false;
+        }
+
+        self.data.insert(
+            inscription.to_string(),
+            DataRow {
+                address: address.to_string(),
+                domain: data.domain,
+                data: data.data,
+            },
+        );
+
+        true
+    }
+
+    fn remove_data(&mut self, domain: &str) -> bool {
+        let count = self.data.len();
+        self.data.retain(|_, row| row.domain != domain);
+
+        self.data.len() != count
+    }
+
+    fn remove_data_by_inscription(&mut self, inscription: &str) -> bool {
+        self.data.remove(inscription).is_some()
+    }
+
+    /// Removes the subdomains, validity and data inscribed by an inscription, as long as they are stored under an address.
+    fn drop_inscription(&mut self, address: &str, inscription: &str) -> bool {
+        let count = self.subdomains.len();
+        self.subdomains
+            .retain(|_, row| row.inscription != inscription || row.address != address);
+        let subdomain = self.subdomains.len() != count;
+
+        let validity = self
+            .validities
+            .get(inscription)
+            .is_some_and(|row| row.address == address)
+            && self.remove_validity_by_inscription(inscription);
+        let data = self
+            .data
+            .get(inscription)
+            .is_some_and(|row| row.address == address)
+            && self.remove_data_by_inscription(inscription);
+
+        subdomain || validity || data
+    }
+
+    /// Just like the sqlite implementation, only transfers of domains are allowed.
+    /// When a domain is transfered the dns validity is removed.
+    fn transfer_inscription(
+        &mut self,
+        inscription: &str,
+        new_address: &str,
+        now: u64,
+    ) -> Result<bool> {
+        let domain = self.get_domain_row(inscription, now, |row| row.inscription == inscription)?;
+
+        if self.get_validity_row(&domain.name, now)?.is_some() {
+            self.remove_validity(&domain.name);
+        }
+
+        Ok(match self.domains.get_mut(inscription) {
+            Some(row) => {
+                row.address = new_address.to_string();
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Applies a single operation of a batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation to apply.
+    /// * `now` - The time the expiry of domains is evaluated at, see [`batch_time`](batch_time).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool>` - Whether the operation was applied.
+    fn apply(&mut self, operation: Operation, now: u64) -> Result<bool> {
+        Ok(match operation {
+            Operation::AddDomain {
+                address,
+                inscription,
+                domain,
+            } => self.add_domain(&address, &inscription, domain, now),
+            Operation::RemoveDomain { domain } => self.remove_domain(&domain),
+            Operation::RemoveDomainByInscription { inscription } => {
+                self.remove_domain_by_inscription(&inscription)
+            }
+            Operation::ExpireDomain { domain } => self.expire_domain(&domain, now),
+            Operation::RenewDomain {
+                address,
+                inscription,
+                renewal,
+                signature,
+            } => self.renew_domain(&address, &inscription, renewal, signature.as_ref(), now),
+            Operation::AddSubdomain {
+                address,
+                inscription,
+                subdomain,
+            } => self.add_subdomain(&address, &inscription, subdomain),
+            Operation::RemoveSubdomains { domain, subdomain } => {
+                self.remove_subdomains(&domain, &subdomain)
+            }
+            Operation::RemoveSubdomain { inscription } => self.remove_subdomain(&inscription),
+            Operation::AddValidity {
+                address,
+                inscription,
+                validity,
+            } => self.add_validity(&address, &inscription, validity),
+            Operation::RemoveValidity { domain } => self.remove_validity(&domain),
+            Operation::RemoveValidityByInscription { inscription } => {
+                self.remove_validity_by_inscription(&inscription)
+            }
+            Operation::UpdateValidity { address, validity } => {
+                self.get_domain_address(&validity.domain, now)
+                    .is_ok_and(|owner| owner == address)
+                    && self.update_validity(validity, now)
+            }
+            Operation::UpdateValidityByInscription {
+                address,
+                inscription,
+                validity,
+            } => self.update_validity_by_inscription(&address, &inscription, validity),
+            Operation::AddData {
+                address,
+                inscription,
+                data,
+            } => self.add_data(&address, &inscription, data),
+            Operation::RemoveData { domain } => self.remove_data(&domain),
+            Operation::RemoveDataByInscription { inscription } => {
+                self.remove_data_by_inscription(&inscription)
+            }
+            Operation::TransferInscription {
+                inscription,
+                new_owner,
+            } => self.transfer_inscription(&inscription, &new_owner, now)?,
+            Operation::Drop {
+                address,
+                inscription,
+            } => self.drop_inscription(&address, &inscription),
+        })
+    }
+
+    /// Starts an undo log of the state, nothing is copied until names are logged.
+    fn undo_log(&self) -> UndoLog {
+        UndoLog {
+            next_subdomain_id: self.next_subdomain_id,
+            journal_len: self.journal.len(),
+            history_len: self.history.len(),
+            ..Default::default()
+        }
+    }
+
+    /// Copies the rows of names into an undo log, names that were logged before are skipped.
+    fn log_names(&self, log: &mut UndoLog, names: &[String]) {
+        for name in names {
+            if !log.names.insert(name.to_string()) {
+                continue;
+            }
+
+            log.domains.extend(
+                self.domains
+                    .iter()
+                    .filter(|(_, row)| &row.name == name)
+                    .map(|(key, row)| (key.to_string(), row.clone())),
+            );
+            log.subdomains.extend(
+                self.subdomains
+                    .iter()
+                    .filter(|(_, row)| &row.subdomain.domain == name)
+                    .map(|(id, row)| (*id, row.clone())),
+            );
+            log.validities.extend(
+                self.validities
+                    .iter()
+                    .filter(|(_, row)| &row.domain == name)
+                    .map(|(key, row)| (key.to_string(), row.clone())),
+            );
+            log.data.extend(
+                self.data
+                    .iter()
+                    .filter(|(_, row)| &row.domain == name)
+                    .map(|(key, row)| (key.to_string(), row.clone())),
+            );
+        }
+    }
+
+    /// Puts back the logged rows, and drops what was appended to the journal and history since the log started.
+    fn undo(&mut self, log: UndoLog) {
+        for name in log.names.iter() {
+            self.clear_name(name);
+        }
+
+        self.domains.extend(log.domains);
+        self.subdomains.extend(log.subdomains);
+        self.validities.extend(log.validities);
+        self.data.extend(log.data);
+        self.next_subdomain_id = log.next_subdomain_id;
+        self.journal.truncate(log.journal_len);
+        self.history.truncate(log.history_len);
+    }
+
+    /// Applies the operations of a batch, journaling the state they change at its block, or the tip if it has none.
+    /// The rows of every name are logged before they change, so a batch that fails halfway can be undone.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Change>>` - The changes of the batch, to publish once it was applied.
+    fn apply_batch(
+        &mut self,
+        block: Option<Block>,
+        operations: Vec<Operation>,
+        log: &mut UndoLog,
+    ) -> Result<Vec<Change>> {
+        let tip = self.tip();
+
+        if let Some(block) = &block {
+            check_order(block, tip.as_ref())?;
+        }
+
+        let journaled = journal_block(block.as_ref(), tip);
+        let now = batch_time(block.as_ref());
+
+        let timestamp = history::now();
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+        let mut changes = Vec::new();
+
+        for (index, operation) in operations.into_iter().enumerate() {
+            let scope = operation.scope();
+            let inscription_names = scope
+                .inscription
+                .map(|inscription| self.inscription_names(inscription))
+                .unwrap_or_default();
+            let names = scope.resolve(inscription_names);
+            self.log_names(log, &names);
+
+            if journaled.is_some() {
+                for name in names.iter() {
+                    if !snapshots.iter().any(|snapshot| &snapshot.name == name) {
+                        snapshots.push(self.snapshot(name));
+                    }
+                }
+            }
+
+            let entries = history::entries(&operation, &names, block.as_ref(), timestamp);
+            changes.extend(operation.changes(&names));
+            let name = operation.name();
+
+            if !self.apply(operation, now)? {
+                return Err(DbError::Rejected {
+                    index,
+                    operation: name,
+                }
+                .into());
+            }
+
+            self.history.extend(entries);
+        }
+
+        // A batch without a block is only journaled if it changed anything.
+        if let Some(block) = journaled.filter(|_| block.is_some() || !snapshots.is_empty()) {
+            self.journal.push(JournalEntry { block, snapshots });
+        }
+
+        if let Some(block) = &block {
+            self.prune(block);
+        }
+
+        Ok(changes)
+    }
+
+    /// Gets the tip of the chain, the checkpoint if the whole journal was pruned.
+    fn tip(&self) -> Option<Block> {
+        self.journal
+            .last()
+            .map(|entry| entry.block.clone())
+            .or_else(|| self.checkpoint.clone())
+    }
+
+    /// Removes the journal entries at or below the finalized height of a block,
+    /// and moves the checkpoint to the newest removed entry.
+    fn prune(&mut self, block: &Block) {
+        let height = match finalized_height(block, self.finality_depth) {
+            Some(height) => height,
+            None => return,
+        };
+        // The journal is ordered by height, so the finalized entries come first.
+        let pruned = self
+            .journal
+            .iter()
+            .rposition(|entry| entry.block.height <= height)
+            .map_or(0, |index| index + 1);
+
+        if let Some(newest) = self.journal.drain(..pruned).next_back() {
+            self.checkpoint = Some(newest.block);
+        }
+    }
+
+    /// Gets the domain names of every row inscribed by an inscription.
+    fn inscription_names(&self, inscription: &str) -> Vec<String> {
+        let domains = self.domains.get(inscription).map(|row| &row.name);
+        let subdomains = self
+            .subdomains
+            .values()
+            .filter(|row| row.inscription == inscription)
+            .map(|row| &row.subdomain.domain);
+        let validities = self.validities.get(inscription).map(|row| &row.domain);
+        let data = self.data.get(inscription).map(|row| &row.domain);
+
+        domains
+            .into_iter()
+            .chain(subdomains)
+            .chain(validities)
+            .chain(data)
+            .cloned()
+            .collect()
+    }
+
+    fn snapshot(&self, name: &str) -> Snapshot {
+        Snapshot {
+            name: name.to_string(),
+            domains: self
+                .domains
+                .values()
+                .filter(|row| row.name == name)
+                .map(|row| DomainRecord {
+                    inscription: row.inscription.to_string(),
+                    address: row.address.to_string(),
+                    name: row.name.to_string(),
+                    valid_from: row.valid_from,
+                    last_renewal: row.last_renewal.clone(),
+                })
+                .collect(),
+            subdomains: self
+                .subdomains
+                .iter()
+                .filter(|(_, row)| row.subdomain.domain == name)
+                .map(|(id, row)| {
+                    SubDomainRecord::from_model(*id, &row.address, &row.inscription, &row.subdomain)
+                })
+                .collect(),
+            validities: self
+                .validities
+                .values()
+                .filter(|row| row.domain == name)
+                .map(|row| ValidityRecord {
+                    inscription: row.inscription.to_string(),
+                    address: row.address.to_string(),
+                    domain: row.domain.to_string(),
+                    algorithm: row.algorithm.to_string(),
+                    public_key: row.public_key.to_string(),
+                })
+                .collect(),
+            data: self
+                .data
+                .iter()
+                .filter(|(_, row)| row.domain == name)
+                .map(|(inscription, row)| DataRecord {
+                    inscription: inscription.to_string(),
+                    address: row.address.to_string(),
+                    domain: row.domain.to_string(),
+                    data: row.data.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Gets the name of every domain with rows of any kind, in order.
+    fn stored_names(&self) -> BTreeSet<String> {
+        let domains = self.domains.values().map(|row| &row.name);
+        let subdomains = self.subdomains.values().map(|row| &row.subdomain.domain);
+        let validities = self.validities.values().map(|row| &row.domain);
+        let data = self.data.values().map(|row| &row.domain);
+
+        domains
+            .chain(subdomains)
+            .chain(validities)
+            .chain(data)
+            .cloned()
+            .collect()
+    }
+
+    /// Snapshots every domain with rows of any kind.
+    fn snapshots(&self) -> Vec<Snapshot> {
+        self.stored_names()
+            .iter()
+            .map(|name| self.snapshot(name))
+            .collect()
+    }
+
+    /// Restores every domain of a snapshot into an empty state, and checks the hash of the result.
+    /// The names are logged before they are restored, so a failed import can be undone.
+    fn import_snapshot(
+        &mut self,
+        snapshot: StateSnapshot,
+        expected_hash: &str,
+        log: &mut UndoLog,
+    ) -> Result<()> {
+        snapshot.expect_hash(expected_hash)?;
+
+        if !self.stored_names().is_empty() {
+            return Err(DbError::InvalidData(
+                "the repository is not empty, refusing to import".to_string(),
+            )
+            .into());
+        }
+
+        let expected = snapshot.clone();
+        let timestamp = history::now();
+
+        for domain in snapshot.into_domains() {
+            self.log_names(log, slice::from_ref(&domain.name));
+            self.history.push(history::import_entry(
+                &domain.name,
+                expected.block(),
+                timestamp,
+            ));
+            self.restore(domain)?;
+        }
+
+        expected.check(self.snapshots())?;
+        self.checkpoint = expected.block().cloned();
+        Ok(())
+    }
+
+    /// Replaces every row stored under the name of a snapshot with the rows of the snapshot.
+    fn restore(&mut self, snapshot: Snapshot) -> Result<()> {
+        let name = snapshot.name.as_str();
+
+        // Rows that took over a key of the snapshot are replaced as well.
+        self.domains.retain(|inscription, row| {
+            row.name != name
+                && !snapshot
+                    .domains
+                    .iter()
+                    .any(|record| &record.inscription == inscription)
+        });
+        self.subdomains.retain(|id, row| {
+            row.subdomain.domain != name
+                && !snapshot.subdomains.iter().any(|record| record.id == *id)
+        });
+        self.validities.retain(|inscription, row| {
+            row.domain != name
+                && !snapshot
+                    .validities
+                    .iter()
+                    .any(|record| &record.inscription == inscription)
+        });
+        self.data.retain(|inscription, row| {
+            row.domain != name
+                && !snapshot
+                    .data
+                    .iter()
+                    .any(|record| &record.inscription == inscription)
+        });
+
+        for record in snapshot.domains {
+            self.domains.insert(
+                record.inscription.to_string(),
+                DomainRow {
+                    inscription: record.inscription,
+                    address: record.address,
+                    name: record.name,
+                    valid_from: record.valid_from,
+                    last_renewal: record.last_renewal,
+                },
+            );
+        }
+
+        for record in snapshot.subdomains {
+            self.next_subdomain_id = self.next_subdomain_id.max(record.id + 1);
+            self.subdomains.insert(
+                record.id,
+                SubDomainRow {
+                    subdomain: record.to_model()?,
+                    inscription: record.inscription,
+                    address: record.address,
+                },
+            );
+        }
+
+        for record in snapshot.validities {
+            self.validities.insert(
+                record.inscription.to_string(),
+                ValidityRow {
+                    algorithm: record.algorithm.try_into()?,
+                    inscription: record.inscription,
+                    address: record.address,
+                    domain: record.domain,
+                    public_key: record.public_key,
+                },
+            );
+        }
+
+        for record in snapshot.data {
+            self.data.insert(
+                record.inscription,
+                DataRow {
+                    address: record.address,
+                    domain: record.domain,
+                    data: record.data,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Undoes every journaled batch above a height, newest first.
+    /// The names are logged before they are restored, so a failed rollback can be undone.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(usize, Vec<Change>)>` - The amount of undone batches and the changes of the restored domains.
+    fn rollback_to(&mut self, height: u64, log: &mut UndoLog) -> Result<(usize, Vec<Change>)> {
+        check_finalized(height, self.checkpoint.as_ref())?;
+
+        let keep = self
+            .journal
+            .iter()
+            .rposition(|entry| entry.block.height <= height)
+            .map_or(0, |index| index + 1);
+        let undone = self.journal.split_off(keep);
+
+        match self.revert(&undone, height, log) {
+            Ok(changes) => Ok((undone.len(), changes)),
+            Err(e) => {
+                self.journal.extend(undone);
+                Err(e)
+            }
+        }
+    }
+
+    /// Restores the snapshots of journal entries, newest entry first.
+    fn revert(
+        &mut self,
+        entries: &[JournalEntry],
+        height: u64,
+        log: &mut UndoLog,
+    ) -> Result<Vec<Change>> {
+        let timestamp = history::now();
+        let mut changes = Vec::new();
+
+        for entry in entries.iter().rev() {
+            // Every domain was snapshotted only once per batch, so the order does not matter.
+            for snapshot in entry.snapshots.iter() {
+                self.log_names(log, slice::from_ref(&snapshot.name));
+                self.history.push(history::rollback_entry(
+                    &snapshot.name,
+                    &entry.block,
+                    height,
+                    timestamp,
+                ));
+                changes.push(Change::rolled_back(&snapshot.name));
+                self.restore(snapshot.clone())?;
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+impl MemoryRepository {
+    /// Sets how long domain registrations stay valid, the default is [`ExpiryPolicy::default`](ExpiryPolicy::default).
+    pub fn with_policy(self, policy: ExpiryPolicy) -> Self {
+        self.write().policy = policy;
+        self
+    }
+
+    /// Sets the amount of blocks below the tip after which the journal is pruned, `None` keeps the whole journal.
+    /// The default is [`DEFAULT_FINALITY_DEPTH`](crate::DEFAULT_FINALITY_DEPTH).
+    pub fn with_finality_depth(self, depth: Option<u64>) -> Self {
+        self.write().finality_depth = depth;
+        self
+    }
+
+    /// Publishes the changes to a (shared) feed instead of a feed of its own.
+    pub fn with_changes(mut self, changes: ChangeFeed) -> Self {
+        self.changes = changes;
+        self
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, State> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Updates the commitment to the current rows of names that were changed.
+    async fn refresh(&self, names: Vec<String>) {
+        self.commitment.refresh(self.rows_of(names)).await
+    }
+
+    /// Changes the state, the rows it touched are put back if it fails halfway.
+    fn undoable<T, F>(state: &mut State, change: F) -> Result<T>
+    where
+        F: FnOnce(&mut State, &mut UndoLog) -> Result<T>,
+    {
+        let mut log = state.undo_log();
+        let changed = change(state, &mut log);
+
+        if changed.is_err() {
+            state.undo(log);
+        }
+
+        changed
+    }
+
+    /// Applies a batch, which is undone if any of its operations is rejected.
+    fn commit_to(
+        state: &mut State,
+        block: Option<Block>,
+        operations: Vec<Operation>,
+    ) -> Result<Vec<Change>> {
+        Self::undoable(state, |state, log| {
+            state.apply_batch(block, operations, log)
+        })
+    }
+}
+
+impl StateRows for MemoryRepository {
+    async fn all_rows(&self) -> Result<Vec<Snapshot>> {
+        Ok(self.read().snapshots())
+    }
+
+    async fn rows_of(&self, names: Vec<String>) -> Result<Vec<Snapshot>> {
+        let state = self.read();
+
+        Ok(names.iter().map(|name| state.snapshot(name)).collect())
+    }
+}
+
+impl Repository for MemoryRepository {
+    /// The memory repository has nothing to configure, so this is the same as [`new_memory`](Repository::new_memory).
+    async fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    async fn new_memory() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    async fn get_domain(&self, domain: &str) -> Result<(String, Domain)> {
+        let row = self
+            .read()
+            .get_domain_row(domain, epoch_seconds_now(), |row| row.name == domain)?;
+        Ok((row.address.to_string(), row.to_model()))
+    }
+
+    async fn get_domain_by_inscription(&self, inscription: &str) -> Result<(String, Domain)> {
+        let row = self
+            .read()
+            .get_domain_row(inscription, epoch_seconds_now(), |row| {
+                row.inscription == inscription
+            })?;
+        Ok((row.address.to_string(), row.to_model()))
+    }
+
+    async fn get_domain_by_address(&self, address: &str) -> Result<Domain> {
+        let row = self
+            .read()
+            .get_domain_row(address, epoch_seconds_now(), |row| row.address == address)?;
+        Ok(row.to_model())
+    }
+
+    async fn list_domains_by_address(&self, address: &str) -> Result<Vec<Domain>> {
+        Ok(self
+            .read()
+            .live_domain_rows(None, |row| row.address == address)
+            .into_iter()
+            .map(DomainRow::to_model)
+            .collect())
+    }
+
+    async fn list_domains(&self, page: &Page) -> Result<Vec<(String, Domain)>> {
+        Ok(self
+            .read()
+            .live_domain_rows(None, |row| page.includes(&row.name))
+            .into_iter()
+            .take(page.limit)
+            .map(|row| (row.address.to_string(), row.to_model()))
+            .collect())
+    }
+
+    async fn search_domains(
+        &self,
+        filter: &NameFilter,
+        page: &Page,
+    ) -> Result<Vec<(String, Domain)>> {
+        Ok(self
+            .read()
+            .live_domain_rows(None, |row| {
+                page.includes(&row.name) && filter.matches(&row.name)
+            })
+            .into_iter()
+            .take(page.limit)
+            .map(|row| (row.address.to_string(), row.to_model()))
+            .collect())
+    }
+
+    async fn domains_expiring_within(&self, days: u64) -> Result<Vec<(String, Domain)>> {
+        let state = self.read();
+        let mut rows = state.live_domain_rows(Some(days), |_| true);
+        rows.sort_by_key(|row| row.valid_from);
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.address.to_string(), row.to_model()))
+            .collect())
+    }
+
+    async fn count_domains(&self) -> Result<u64> {
+        Ok(self.read().live_domain_rows(None, |_| true).len() as u64)
+    }
+
+    async fn count_domains_by_address(&self, address: &str) -> Result<u64> {
+        Ok(self
+            .read()
+            .live_domain_rows(None, |row| row.address == address)
+            .len() as u64)
+    }
+
+    async fn get_domain_address(&self, domain: &str) -> Result<String> {
+        self.read().get_domain_address(domain, epoch_seconds_now())
+    }
+
+    /// Finds and removes the released domains while holding the lock.
+    async fn sweep_expired(&self) -> Result<Vec<String>> {
+        let (names, changes) = {
+            let mut state = self.write();
+            let names = state.released_names();
+            let operations = names
+                .iter()
+                .map(|domain| Operation::ExpireDomain {
+                    domain: domain.to_string(),
+                })
+                .collect();
+
+            let changes = Self::commit_to(&mut state, None, operations)?;
+            (names, changes)
+        };
+
+        self.refresh(changed_names(&changes)).await;
+        self.changes.publish(changes);
+        Ok(names)
+    }
+
+    async fn get_subdomain(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        Ok(self
+            .read()
+            .subdomains
+            .values()
+            .filter(|row| {
+                row.subdomain.domain == domain
+                    && row.subdomain.subdomain == subdomain
+                    && row.address == address
+            })
+            .map(SubDomainRow::to_model)
+            .collect())
+    }
+
+    async fn get_subdomain_inscriptions(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        Ok(self
+            .read()
+            .subdomains
+            .values()
+            .filter(|row| {
+                row.subdomain.domain == domain
+                    && row.subdomain.subdomain == subdomain
+                    && row.address == address
+            })
+            .map(|row| (row.inscription.to_string(), row.to_model().1))
+            .collect())
+    }
+
+    async fn get_subdomain_names(&self, domain: &str) -> Result<Vec<String>> {
+        let address = self.get_domain_address(domain).await?;
+        let mut names: Vec<String> = Vec::new();
+
+        for row in self.read().subdomains.values() {
+            if row.subdomain.domain == domain
+                && row.address == address
+                && !names.contains(&row.subdomain.subdomain)
+            {
+                names.push(row.subdomain.subdomain.to_string());
+            }
+        }
+
+        Ok(names)
+    }
+
+    async fn list_subdomains(&self, domain: &str) -> Result<Vec<(String, SubDomain)>> {
+        Ok(self
+            .read()
+            .owned_subdomain_rows(domain)?
+            .into_iter()
+            .map(SubDomainRow::to_model)
+            .collect())
+    }
+
+    async fn count_subdomains(&self, domain: &str) -> Result<u64> {
+        Ok(self.read().owned_subdomain_rows(domain)?.len() as u64)
+    }
+
+    async fn get_subdomain_by_inscription(&self, inscription: &str) -> Result<(String, SubDomain)> {
+        self.read()
+            .subdomains
+            .values()
+            .find(|row| row.inscription == inscription)
+            .map(SubDomainRow::to_model)
+            .ok_or_else(|| DbError::not_found("subdomain", inscription).into())
+    }
+
+    async fn get_validity(&self, domain: &str) -> Result<(String, Validity)> {
+        self.read()
+            .get_validity_row(domain, epoch_seconds_now())?
+            .map(|row| row.to_model())
+            .ok_or_else(|| DbError::not_found("validity", domain).into())
+    }
+
+    async fn get_validity_by_inscription(&self, inscription: &str) -> Result<(String, Validity)> {
+        self.read()
+            .validities
+            .get(inscription)
+            .map(ValidityRow::to_model)
+            .ok_or_else(|| DbError::not_found("validity", inscription).into())
+    }
+
+    async fn get_data(&self, domain: &str) -> Result<Vec<(String, Data)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        Ok(self
+            .read()
+            .data
+            .values()
+            .filter(|row| row.domain == domain && row.address == address)
+            .map(DataRow::to_model)
+            .collect())
+    }
+
+    async fn get_data_by_inscription(&self, inscription: &str) -> Result<(String, Data)> {
+        self.read()
+            .data
+            .get(inscription)
+            .map(DataRow::to_model)
+            .ok_or_else(|| DbError::not_found("data", inscription).into())
+    }
+
+    /// Applies the batch while holding the lock, it is undone if any of its operations is rejected.
+    async fn commit(&self, batch: Batch) -> Result<()> {
+        let (block, operations) = batch.into_parts();
+        let changes = Self::commit_to(&mut self.write(), block, operations)?;
+
+        self.refresh(changed_names(&changes)).await;
+        self.changes.publish(changes);
+        Ok(())
+    }
+
+    async fn get_tip(&self) -> Result<Option<Block>> {
+        Ok(self.read().tip())
+    }
+
+    /// Undoes the batches while holding the lock, the state is left untouched if any of them fails.
+    async fn rollback_to(&self, height: u64) -> Result<usize> {
+        let (count, changes) = Self::undoable(&mut self.write(), |state, log| {
+            state.rollback_to(height, log)
+        })?;
+
+        self.refresh(changed_names(&changes)).await;
+        self.changes.publish(changes);
+        Ok(count)
+    }
+
+    async fn domain_history_positions(&self, domain: &str) -> Result<Vec<(u64, HistoryEntry)>> {
+        Ok(self
+            .read()
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.domain == domain)
+            .map(|(i, entry)| (i as u64 + 1, entry.clone()))
+            .collect())
+    }
+
+    async fn domain_history_position(&self, domain: &str) -> Result<Option<u64>> {
+        Ok(self
+            .read()
+            .history
+            .iter()
+            .rposition(|entry| entry.domain == domain)
+            .map(|i| i as u64 + 1))
+    }
+
+    /// The position of an entry is its index in the history, counted from one.
+    async fn history_after(&self, position: u64) -> Result<Vec<(u64, HistoryEntry)>> {
+        Ok(self
+            .read()
+            .history
+            .iter()
+            .enumerate()
+            .skip(position as usize)
+            .map(|(i, entry)| (i as u64 + 1, entry.clone()))
+            .collect())
+    }
+
+    async fn history_position(&self) -> Result<u64> {
+        Ok(self.read().history.len() as u64)
+    }
+
+    async fn records_at(&self, domain: &str, height: u64) -> Result<Vec<(String, SubDomain)>> {
+        let state = self.read();
+        check_finalized(height, state.checkpoint.as_ref())?;
+
+        let entries = state
+            .journal
+            .iter()
+            .filter(|entry| entry.block.height > height);
+
+        match snapshot_at(entries, domain) {
+            Some(snapshot) => snapshot.records(),
+            None => state.snapshot(domain).records(),
+        }
+    }
+
+    async fn export_snapshot(&self) -> Result<StateSnapshot> {
+        let state = self.read();
+
+        StateSnapshot::new(state.tip(), state.snapshots())
+    }
+
+    /// Imports while holding the lock, the state is left empty if the import fails its check.
+    async fn import_snapshot(&self, snapshot: StateSnapshot, expected_hash: &str) -> Result<()> {
+        let names = snapshot.names().map(str::to_string).collect();
+        Self::undoable(&mut self.write(), |state, log| {
+            state.import_snapshot(snapshot, expected_hash, log)
+        })?;
+
+        self.refresh(names).await;
+        Ok(())
+    }
+
+    async fn state_root(&self) -> Result<StateRoot> {
+        self.commitment.root(self).await
+    }
+
+    async fn prove(&self, name: &str) -> Result<NameProof> {
+        self.commitment.prove(self, name).await
+    }
+
+    fn changes(&self) -> &ChangeFeed {
+        &self.changes
+    }
+
+    fn policy(&self) -> ExpiryPolicy {
+        self.read().policy
+    }
+}
@@ -1,3 +1,7 @@
+mod kv;
+mod memory;
 mod sqlite;
 
+pub use kv::{ImportSummary, KvRepository};
+pub use memory::MemoryRepository;
 pub use sqlite::SqliteRepository;
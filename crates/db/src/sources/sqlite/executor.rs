@@ -0,0 +1,806 @@
+use migration::IntoCondition;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use std::default::Default;
+use std::time::SystemTime;
+
+use entity::{data, subdomain};
+use entity::{domain, validity};
+use shared::common::{DbError, Result};
+use shared::time::system_time_from_epoch_seconds;
+use xdns_data::models::subdomain::{
+    Class as SubDomainClass, RData as SubDomainRData, Type as SubDomainType,
+};
+use xdns_data::models::{
+    Credentials, Data, Domain, DomainRenew, Signature, SubDomain, Validity, ValidityTransfer,
+};
+
+use crate::batch::Operation;
+use crate::expiry::{may_renew, DomainStatus, ExpiryPolicy};
+
+/// Wraps a sea-orm error into a backend [`DbError`](DbError).
+pub(crate) fn backend_error(e: DbErr) -> DbError {
+    DbError::Backend(e.to_string())
+}
+
+/// Parses a stored epoch timestamp, failing with [`DbError::InvalidData`](DbError::InvalidData).
+pub(crate) fn parse_valid_from(valid_from: &str) -> Result<u64> {
+    Ok(valid_from
+        .parse::<u64>()
+        .map_err(|e| DbError::InvalidData(format!("valid_from {}: {}", valid_from, e)))?)
+}
+
+/// Executes the repository operations on any connection, be it a pooled connection
+/// or a transaction. This allows a [`Batch`](crate::batch::Batch) to run the exact same
+/// queries as the standalone [`Repository`](crate::traits::Repository) methods.
+pub(crate) struct SqlExecutor<'a, C: ConnectionTrait> {
+    pub connection: &'a C,
+    pub policy: ExpiryPolicy,
+    /// The amount of blocks below the tip after which the journal is pruned.
+    pub finality_depth: Option<u64>,
+    /// The time the expiry of domains is evaluated at, in epoch seconds: the time of the block of a batch,
+    /// or the current time.
+    pub now: u64,
+}
+
+impl<'a, C: ConnectionTrait> SqlExecutor<'a, C> {
+    async fn get_validity_model(&self, domain: &str) -> Result<Option<validity::Model>> {
+        let address = self.get_domain_address(domain).await?;
+        Ok(self
+            .get_first_entity_by(
+                validity::Entity,
+                validity::Column::Domain
+                    .eq(domain)
+                    .and(validity::Column::Address.eq(address)),
+            )
+            .await?)
+    }
+
+    /// Fails with [`DbError::Expired`](DbError::Expired) once the domain is no longer active.
+    /// The domain is left in place, it is only removed once it is released and swept.
+    fn domain_lifetime_check(&self, model: &domain::Model) -> Result<()> {
+        let valid_from = parse_valid_from(&model.valid_from)?;
+
+        if self.policy.status(valid_from, self.now) != DomainStatus::Active {
+            return Err(DbError::Expired {
+                domain: model.name.to_string(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    async fn parse_domain_model(
+        &self,
+        domain_data: Option<domain::Model>,
+        key: &str,
+    ) -> Result<(String, Domain)> {
+        let domain_data = domain_data.ok_or_else(|| DbError::not_found("domain", key))?;
+        let valid_from = parse_valid_from(&domain_data.valid_from)?;
+        self.domain_lifetime_check(&domain_data)?;
+
+        Ok((
+            domain_data.address,
+            Domain {
+                name: domain_data.name,
+                valid_from: system_time_from_epoch_seconds(valid_from),
+            },
+        ))
+    }
+
+    fn parse_validity_model(
+        validity_data: Option<validity::Model>,
+        key: &str,
+    ) -> Result<(String, Validity)> {
+        let validity = validity_data.ok_or_else(|| DbError::not_found("validity", key))?;
+
+        Ok((
+            validity.address,
+            Validity {
+                domain: validity.domain.to_string(),
+                credentials: Credentials::new(validity.algorithm.try_into()?, validity.public_key),
+            },
+        ))
+    }
+
+    pub(crate) fn parse_subdomain_model(
+        subdomain_data: subdomain::Model,
+    ) -> Result<(String, SubDomain)> {
+        let rtype = SubDomainType::try_from(&subdomain_data.rtype as &str)?;
+
+        // The MX preference is stored separately, rebuild the inscription form to parse it.
+        let rdata = match (rtype, subdomain_data.priority) {
+            (SubDomainType::MX, Some(priority)) => {
+                format!("{} {}", priority, subdomain_data.rdata)
+            }
+            _ => subdomain_data.rdata,
+        };
+        let rdata = SubDomainRData::parse(rtype, &rdata)
+            .map_err(|e| DbError::InvalidData(e.to_string()))?;
+
+        Ok((
+            subdomain_data.address,
+            SubDomain {
+                domain: subdomain_data.domain,
+                subdomain: subdomain_data.subdomain,
+                class: SubDomainClass::try_from(&subdomain_data.class as &str)?,
+                ttl: subdomain_data.ttl as u32,
+                rdata,
+            },
+        ))
+    }
+
+    /// Get the first entity by a filter.
+    /// This is a workaround for the lack of a `find_by` method in sea_orm.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to get.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<<T as EntityTrait>::Model>` - The entity if it exists.
+    async fn get_first_entity_by<T, F>(
+        &self,
+        _entity: T,
+        filter: F,
+    ) -> Result<Option<<T as EntityTrait>::Model>>
+    where
+        T: EntityTrait,
+        F: IntoCondition,
+    {
+        Ok(T::find()
+            .filter(filter)
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?)
+    }
+
+    pub async fn get_domain(&self, domain: &str) -> Result<(String, Domain)> {
+        let domain_data = self
+            .get_first_entity_by(domain::Entity, domain::Column::Name.eq(domain))
+            .await?;
+        self.parse_domain_model(domain_data, domain).await
+    }
+
+    pub async fn get_domain_by_inscription(&self, inscription: &str) -> Result<(String, Domain)> {
+        let domain_data = self
+            .get_first_entity_by(domain::Entity, domain::Column::Inscription.eq(inscription))
+            .await?;
+        self.parse_domain_model(domain_data, inscription).await
+    }
+
+    pub async fn get_domain_by_address(&self, address: &str) -> Result<Domain> {
+        let domain_data = self
+            .get_first_entity_by(domain::Entity, domain::Column::Address.eq(address))
+            .await?;
+        self.parse_domain_model(domain_data, address)
+            .await
+            .map(|(_, domain)| domain)
+    }
+
+    pub async fn get_domain_address(&self, domain: &str) -> Result<String> {
+        let domain_data = self
+            .get_first_entity_by(domain::Entity, domain::Column::Name.eq(domain))
+            .await?;
+
+        if domain_data.is_none() {
+            return Err(DbError::not_found("domain", domain).into());
+        }
+
+        let domain_data = domain_data.unwrap();
+        self.domain_lifetime_check(&domain_data)?;
+
+        Ok(domain_data.address)
+    }
+
+    pub async fn add_domain(&self, address: &str, inscription: &str, domain: Domain) -> bool {
+        let valid_from = domain
+            .valid_from
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let domain_model = domain::ActiveModel {
+            address: Set(address.to_string()),
+            name: Set(domain.name.to_string()),
+            valid_from: Set(valid_from.to_string()),
+            inscription: Set(inscription.to_string()),
+            last_renewal: Set(None),
+        };
+
+        let existing_domain = self
+            .get_first_entity_by(domain::Entity, domain::Column::Name.eq(&domain.name))
+            .await;
+
+        if let Ok(Some(existing_domain)) = existing_domain {
+            let existing_valid_from = match parse_valid_from(&existing_domain.valid_from) {
+                Ok(existing_valid_from) => existing_valid_from,
+                Err(_) => return false,
+            };
+
+            // Until the domain is released only its owner can register it again.
+            if self.policy.status(existing_valid_from, self.now) == DomainStatus::Released {
+                if self.clear_name(&domain.name).await.is_err() {
+                    return false;
+                }
+            } else if existing_domain.address != address || existing_valid_from > valid_from {
+                return false;
+            } else {
+                self.remove_domain(domain.name.as_ref()).await;
+            }
+        }
+
+        let res = domain::Entity::insert(domain_model)
+            .exec(self.connection)
+            .await;
+
+        res.is_ok()
+    }
+
+    pub async fn remove_domain(&self, domain: &str) -> bool {
+        let res = domain::Entity::delete_many()
+            .filter(domain::Column::Name.eq(domain))
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    pub async fn remove_domain_by_inscription(&self, inscription: &str) -> bool {
+        let res = domain::Entity::delete_many()
+            .filter(domain::Column::Inscription.eq(inscription))
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    pub async fn renew_domain(
+        &self,
+        address: &str,
+        inscription: &str,
+        renewal: DomainRenew,
+        signature: Option<&Signature>,
+    ) -> bool {
+        let model = match self
+            .get_first_entity_by(domain::Entity, domain::Column::Name.eq(&renewal.domain))
+            .await
+        {
+            Ok(Some(model)) => model,
+            _ => return false,
+        };
+
+        let validity = self
+            .get_first_entity_by(
+                validity::Entity,
+                validity::Column::Domain
+                    .eq(&model.name)
+                    .and(validity::Column::Address.eq(&model.address)),
+            )
+            .await;
+        let credentials = match validity {
+            Ok(validity) => validity.and_then(|validity| {
+                let algorithm = validity.algorithm.try_into().ok()?;
+                Some(Credentials::new(algorithm, validity.public_key))
+            }),
+            Err(_) => return false,
+        };
+
+        let last_inscription = model.last_renewal.as_deref().unwrap_or(&model.inscription);
+
+        if !may_renew(
+            &model.address,
+            address,
+            last_inscription,
+            signature,
+            credentials,
+        ) {
+            return false;
+        }
+
+        let valid_from = match parse_valid_from(&model.valid_from)
+            .ok()
+            .and_then(|valid_from| {
+                self.policy
+                    .renewed_valid_from(valid_from, renewal.periods, self.now)
+            }) {
+            Some(valid_from) => valid_from,
+            None => return false,
+        };
+
+        let res = domain::Entity::update(domain::ActiveModel {
+            inscription: Set(model.inscription.to_string()),
+            valid_from: Set(valid_from.to_string()),
+            last_renewal: Set(Some(inscription.to_string())),
+            ..Default::default()
+        })
+        .filter(domain::Column::Inscription.eq(model.inscription))
+        .exec(self.connection)
+        .await;
+
+        res.is_ok()
+    }
+
+    /// Removes a released domain with every row stored under its name.
+    pub async fn expire_domain(&self, domain: &str) -> bool {
+        let model = self
+            .get_first_entity_by(domain::Entity, domain::Column::Name.eq(domain))
+            .await;
+
+        let released = match model {
+            Ok(Some(model)) => parse_valid_from(&model.valid_from).is_ok_and(|valid_from| {
+                self.policy.status(valid_from, self.now) == DomainStatus::Released
+            }),
+            _ => false,
+        };
+
+        released && self.clear_name(domain).await.is_ok()
+    }
+
+    /// Removes every row stored under a domain name.
+    async fn clear_name(&self, name: &str) -> std::result::Result<(), DbErr> {
+        domain::Entity::delete_many()
+            .filter(domain::Column::Name.eq(name))
+            .exec(self.connection)
+            .await?;
+        subdomain::Entity::delete_many()
+            .filter(subdomain::Column::Domain.eq(name))
+            .exec(self.connection)
+            .await?;
+        validity::Entity::delete_many()
+            .filter(validity::Column::Domain.eq(name))
+            .exec(self.connection)
+            .await?;
+        data::Entity::delete_many()
+            .filter(data::Column::Domain.eq(name))
+            .exec(self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_subdomain(
+        &self,
+        address: &str,
+        inscription: &str,
+        subdomain: SubDomain,
+    ) -> bool {
+        let rtype = subdomain.rtype();
+        let (rdata, priority) = match subdomain.rdata {
+            SubDomainRData::MX { priority, host } => (host, Some(priority as i32)),
+            rdata => (rdata.to_string(), None),
+        };
+
+        let subdomain = subdomain::ActiveModel {
+            address: Set(address.to_string()),
+            inscription: Set(inscription.to_string()),
+            domain: Set(subdomain.domain),
+            subdomain: Set(subdomain.subdomain),
+            rtype: Set(rtype.to_string()),
+            class: Set(subdomain.class.to_string()),
+            ttl: Set(subdomain.ttl as i32),
+            rdata: Set(rdata),
+            priority: Set(priority),
+            ..Default::default()
+        };
+
+        let res = subdomain::Entity::insert(subdomain)
+            .exec(self.connection)
+            .await;
+
+        res.is_ok()
+    }
+
+    pub async fn get_subdomain(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        let subdomains = subdomain::Entity::find()
+            .filter(
+                subdomain::Column::Domain
+                    .eq(domain)
+                    .and(subdomain::Column::Subdomain.eq(subdomain))
+                    .and(subdomain::Column::Address.eq(address)),
+            )
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        subdomains
+            .into_iter()
+            .map(Self::parse_subdomain_model)
+            .collect()
+    }
+
+    pub async fn get_subdomain_inscriptions(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        let subdomains = subdomain::Entity::find()
+            .filter(
+                subdomain::Column::Domain
+                    .eq(domain)
+                    .and(subdomain::Column::Subdomain.eq(subdomain))
+                    .and(subdomain::Column::Address.eq(address)),
+            )
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        subdomains
+            .into_iter()
+            .map(|model| {
+                let inscription = model.inscription.to_string();
+                Self::parse_subdomain_model(model).map(|(_, subdomain)| (inscription, subdomain))
+            })
+            .collect()
+    }
+
+    pub async fn get_subdomain_names(&self, domain: &str) -> Result<Vec<String>> {
+        let address = self.get_domain_address(domain).await?;
+
+        let subdomains = subdomain::Entity::find()
+            .select_only()
+            .column(subdomain::Column::Subdomain)
+            .distinct()
+            .filter(
+                subdomain::Column::Domain
+                    .eq(domain)
+                    .and(subdomain::Column::Address.eq(address)),
+            )
+            .into_tuple::<String>()
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(subdomains)
+    }
+
+    pub async fn get_subdomain_by_inscription(
+        &self,
+        inscription: &str,
+    ) -> Result<(String, SubDomain)> {
+        let subdomain_data = self
+            .get_first_entity_by(
+                subdomain::Entity,
+                subdomain::Column::Inscription.eq(inscription),
+            )
+            .await?;
+
+        let subdomain_data =
+            subdomain_data.ok_or_else(|| DbError::not_found("subdomain", inscription))?;
+
+        Self::parse_subdomain_model(subdomain_data)
+    }
+
+    pub async fn remove_subdomains(&self, domain: &str, subdomain: &str) -> bool {
+        let res = subdomain::Entity::delete_many()
+            .filter(
+                subdomain::Column::Domain
+                    .eq(domain)
+                    .and(subdomain::Column::Subdomain.eq(subdomain)),
+            )
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    pub async fn remove_subdomain(&self, inscription: &str) -> bool {
+        let res = subdomain::Entity::delete_many()
+            .filter(subdomain::Column::Inscription.eq(inscription))
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    pub async fn add_validity(&self, address: &str, inscription: &str, validity: Validity) -> bool {
+        let validity = validity::ActiveModel {
+            address: Set(address.to_string()),
+            inscription: Set(inscription.to_string()),
+            domain: Set(validity.domain),
+            algorithm: Set(validity.credentials.algorithm.into()),
+            public_key: Set(validity.credentials.public_key.into()),
+        };
+
+        let res = validity::Entity::insert(validity)
+            .exec(self.connection)
+            .await;
+
+        res.is_ok()
+    }
+
+    pub async fn get_validity(&self, domain: &str) -> Result<(String, Validity)> {
+        Self::parse_validity_model(self.get_validity_model(domain).await?, domain)
+    }
+
+    pub async fn get_validity_by_inscription(
+        &self,
+        inscription: &str,
+    ) -> Result<(String, Validity)> {
+        let validity_data = self
+            .get_first_entity_by(
+                validity::Entity,
+                validity::Column::Inscription.eq(inscription),
+            )
+            .await?;
+
+        Self::parse_validity_model(validity_data, inscription)
+    }
+
+    pub async fn remove_validity(&self, domain: &str) -> bool {
+        let res = validity::Entity::delete_many()
+            .filter(validity::Column::Domain.eq(domain))
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    pub async fn remove_validity_by_inscription(&self, inscription: &str) -> bool {
+        let res = validity::Entity::delete_by_id(inscription)
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    pub async fn update_validity(&self, validity: ValidityTransfer) -> bool {
+        let current_validity = match self.get_validity_model(&validity.domain).await {
+            Ok(Some(current_validity)) => current_validity,
+            _ => return false,
+        };
+
+        self.update_validity_by_inscription(
+            &current_validity.address,
+            &current_validity.inscription,
+            validity,
+        )
+        .await
+    }
+
+    pub async fn update_validity_by_inscription(
+        &self,
+        address: &str,
+        inscription: &str,
+        validity: ValidityTransfer,
+    ) -> bool {
+        if let Some(new_credentials) = validity.new_credentials {
+            let raw = validity::ActiveModel {
+                address: Set(address.to_string()),
+                inscription: Set(inscription.to_string()),
+                domain: Set(validity.domain),
+                algorithm: Set(new_credentials.algorithm.into()),
+                public_key: Set(new_credentials.public_key),
+            };
+
+            let res = validity::Entity::update(raw)
+                .filter(validity::Column::Inscription.eq(inscription))
+                .exec(self.connection)
+                .await;
+
+            return res.is_ok();
+        }
+
+        self.remove_validity_by_inscription(inscription).await
+    }
+
+    /// Removes the subdomains, validity and data inscribed by an inscription, as long as they are stored under an address.
+    pub async fn drop_inscription(&self, address: &str, inscription: &str) -> bool {
+        // Evaluate every removal, an inscription only ever inscribed one of them.
+        let subdomain = subdomain::Entity::delete_many()
+            .filter(
+                subdomain::Column::Inscription
+                    .eq(inscription)
+                    .and(subdomain::Column::Address.eq(address)),
+            )
+            .exec(self.connection)
+            .await;
+        let validity = validity::Entity::delete_many()
+            .filter(
+                validity::Column::Inscription
+                    .eq(inscription)
+                    .and(validity::Column::Address.eq(address)),
+            )
+            .exec(self.connection)
+            .await;
+        let data = data::Entity::delete_many()
+            .filter(
+                data::Column::Inscription
+                    .eq(inscription)
+                    .and(data::Column::Address.eq(address)),
+            )
+            .exec(self.connection)
+            .await;
+
+        [subdomain, validity, data]
+            .into_iter()
+            .any(|res| res.is_ok_and(|res| res.rows_affected != 0))
+    }
+
+    pub async fn add_data(&self, address: &str, inscription: &str, data: Data) -> bool {
+        let data = data::ActiveModel {
+            address: Set(address.to_string()),
+            inscription: Set(inscription.to_string()),
+            domain: Set(data.domain),
+            data: Set(data.data),
+        };
+
+        let res = data::Entity::insert(data).exec(self.connection).await;
+
+        res.is_ok()
+    }
+
+    pub async fn get_data(&self, domain: &str) -> Result<Vec<(String, Data)>> {
+        let address = self.get_domain_address(domain).await?;
+        let data = data::Entity::find()
+            .filter(
+                data::Column::Domain
+                    .eq(domain)
+                    .and(data::Column::Address.eq(address)),
+            )
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        data.into_iter()
+            .map(|data| {
+                Ok((
+                    data.address,
+                    Data {
+                        domain: data.domain,
+                        data: data.data,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    pub async fn get_data_by_inscription(&self, inscription: &str) -> Result<(String, Data)> {
+        let data = self
+            .get_first_entity_by(data::Entity, data::Column::Inscription.eq(inscription))
+            .await?;
+
+        if data.is_none() {
+            return Err(DbError::not_found("data", inscription).into());
+        }
+
+        let data = data.unwrap();
+
+        Ok((
+            data.address,
+            Data {
+                domain: data.domain,
+                data: data.data,
+            },
+        ))
+    }
+
+    pub async fn remove_data(&self, domain: &str) -> bool {
+        let res = data::Entity::delete_many()
+            .filter(data::Column::Domain.eq(domain))
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    pub async fn remove_data_by_inscription(&self, inscription: &str) -> bool {
+        let res = data::Entity::delete_by_id(inscription)
+            .exec(self.connection)
+            .await;
+
+        res.is_ok_and(|res| res.rows_affected != 0)
+    }
+
+    /// The current sqlite implementation only allows transfers of domains.
+    /// When a domain is transfered all dns records are deleted and the dns validity is removed.
+    pub async fn transfer_inscription(&self, inscription: &str, new_address: &str) -> Result<bool> {
+        let domain = self.get_domain_by_inscription(inscription).await?.1;
+        let dns_validity = self.get_validity_model(&domain.name).await?;
+
+        if dns_validity.is_some() {
+            self.remove_validity(&domain.name).await;
+        }
+
+        let entity = domain::Entity::update(domain::ActiveModel {
+            inscription: Set(inscription.to_string()),
+            address: Set(new_address.to_string()),
+            ..Default::default()
+        });
+
+        let res = entity
+            .filter(domain::Column::Inscription.eq(inscription))
+            .exec(self.connection)
+            .await;
+
+        Ok(res.is_ok())
+    }
+
+    /// Applies a single operation of a batch.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool>` - Whether the operation was applied.
+    pub async fn apply(&self, operation: Operation) -> Result<bool> {
+        Ok(match operation {
+            Operation::AddDomain {
+                address,
+                inscription,
+                domain,
+            } => self.add_domain(&address, &inscription, domain).await,
+            Operation::RemoveDomain { domain } => self.remove_domain(&domain).await,
+            Operation::RemoveDomainByInscription { inscription } => {
+                self.remove_domain_by_inscription(&inscription).await
+            }
+            Operation::ExpireDomain { domain } => self.expire_domain(&domain).await,
+            Operation::RenewDomain {
+                address,
+                inscription,
+                renewal,
+                signature,
+            } => {
+                self.renew_domain(&address, &inscription, renewal, signature.as_ref())
+                    .await
+            }
+            Operation::AddSubdomain {
+                address,
+                inscription,
+                subdomain,
+            } => self.add_subdomain(&address, &inscription, subdomain).await,
+            Operation::RemoveSubdomains { domain, subdomain } => {
+                self.remove_subdomains(&domain, &subdomain).await
+            }
+            Operation::RemoveSubdomain { inscription } => self.remove_subdomain(&inscription).await,
+            Operation::AddValidity {
+                address,
+                inscription,
+                validity,
+            } => self.add_validity(&address, &inscription, validity).await,
+            Operation::RemoveValidity { domain } => self.remove_validity(&domain).await,
+            Operation::RemoveValidityByInscription { inscription } => {
+                self.remove_validity_by_inscription(&inscription).await
+            }
+            Operation::UpdateValidity { address, validity } => {
+                self.get_domain_address(&validity.domain)
+                    .await
+                    .is_ok_and(|owner| owner == address)
+                    && self.update_validity(validity).await
+            }
+            Operation::UpdateValidityByInscription {
+                address,
+                inscription,
+                validity,
+            } => {
+                self.update_validity_by_inscription(&address, &inscription, validity)
+                    .await
+            }
+            Operation::AddData {
+                address,
+                inscription,
+                data,
+            } => self.add_data(&address, &inscription, data).await,
+            Operation::RemoveData { domain } => self.remove_data(&domain).await,
+            Operation::RemoveDataByInscription { inscription } => {
+                self.remove_data_by_inscription(&inscription).await
+            }
+            Operation::TransferInscription {
+                inscription,
+                new_owner,
+            } => self.transfer_inscription(&inscription, &new_owner).await?,
+            Operation::Drop {
+                address,
+                inscription,
+            } => self.drop_inscription(&address, &inscription).await,
+        })
+    }
+}
@@ -0,0 +1,769 @@
+use sea_orm::ActiveValue::Set;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, SystemTime};
+
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    Statement,
+};
+
+use entity::{checkpoint, data, domain, history, journal, subdomain, validity};
+use shared::common::{DbError, Result};
+use xdns_data::models::subdomain::RData as SubDomainRData;
+use xdns_data::models::SubDomain;
+
+use super::executor::{backend_error, parse_valid_from, SqlExecutor};
+use crate::batch::Operation;
+use crate::changes::Change;
+use crate::history::{self as actions, HistoryEntry};
+use crate::journal::{
+    check_finalized, check_order, finalized_height, journal_block, snapshot_at, Block, DataRecord,
+    DomainRecord, JournalEntry, Snapshot, SubDomainRecord, ValidityRecord,
+};
+use crate::snapshot::StateSnapshot;
+
+/// The id of the single checkpoint row.
+const CHECKPOINT_ID: i32 = 1;
+/// The key of the Postgres advisory lock serializing the writers of the history. (`xdns` in ascii)
+const HISTORY_LOCK: i64 = 0x78646e73;
+
+fn parse_entry(model: journal::Model) -> Result<JournalEntry> {
+    Ok(JournalEntry {
+        block: Block::new(model.height as u64, model.hash),
+        snapshots: serde_json::from_str(&model.snapshots)
+            .map_err(|e| DbError::InvalidData(format!("journal {}: {}", model.id, e)))?,
+    })
+}
+
+fn parse_history(model: history::Model) -> HistoryEntry {
+    HistoryEntry {
+        domain: model.domain,
+        action: model.action,
+        inscription: model.inscription,
+        address: model.address,
+        block: match (model.height, model.hash) {
+            (Some(height), Some(hash)) => Some(Block::new(height as u64, hash)),
+            _ => None,
+        },
+        timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(model.timestamp as u64),
+        payload: model.payload,
+    }
+}
+
+/// Groups rows by the name they are stored under, into a snapshot per name.
+fn group_snapshots(
+    domains: Vec<domain::Model>,
+    subdomains: Vec<subdomain::Model>,
+    validities: Vec<validity::Model>,
+    data: Vec<data::Model>,
+) -> Result<BTreeMap<String, Snapshot>> {
+    fn snapshot<'a>(snapshots: &'a mut BTreeMap<String, Snapshot>, name: &str) -> &'a mut Snapshot {
+        snapshots
+            .entry(name.to_string())
+            .or_insert_with(|| Snapshot {
+                name: name.to_string(),
+                ..Default::default()
+            })
+    }
+
+    let mut snapshots = BTreeMap::new();
+
+    for model in domains {
+        let record = DomainRecord {
+            valid_from: parse_valid_from(&model.valid_from)?,
+            inscription: model.inscription,
+            address: model.address,
+            name: model.name,
+            last_renewal: model.last_renewal,
+        };
+        snapshot(&mut snapshots, &record.name).domains.push(record);
+    }
+
+    for model in subdomains {
+        let record = SubDomainRecord {
+            id: model.id as u64,
+            // The MX preference is stored separately, the journal keeps the inscription form.
+            rdata: match model.priority {
+                Some(priority) if model.rtype == "MX" => format!("{} {}", priority, model.rdata),
+                _ => model.rdata,
+            },
+            inscription: model.inscription,
+            address: model.address,
+            domain: model.domain,
+            subdomain: model.subdomain,
+            rtype: model.rtype,
+            class: model.class,
+            ttl: model.ttl as u32,
+        };
+        snapshot(&mut snapshots, &record.domain)
+            .subdomains
+            .push(record);
+    }
+
+    for model in validities {
+        let record = ValidityRecord {
+            inscription: model.inscription,
+            address: model.address,
+            domain: model.domain,
+            algorithm: model.algorithm,
+            public_key: model.public_key,
+        };
+        snapshot(&mut snapshots, &record.domain)
+            .validities
+            .push(record);
+    }
+
+    for model in data {
+        let record = DataRecord {
+            inscription: model.inscription,
+            address: model.address,
+            domain: model.domain,
+            data: model.data,
+        };
+        snapshot(&mut snapshots, &record.domain).data.push(record);
+    }
+
+    Ok(snapshots)
+}
+
+impl<C: ConnectionTrait> SqlExecutor<'_, C> {
+    /// Gets the newest journal entry, the tip of the chain.
+    pub async fn last_journal_model(&self) -> Result<Option<journal::Model>> {
+        Ok(journal::Entity::find()
+            .order_by_desc(journal::Column::Id)
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?)
+    }
+
+    /// Gets the newest block whose journal was pruned, if any.
+    pub async fn get_checkpoint(&self) -> Result<Option<Block>> {
+        Ok(checkpoint::Entity::find_by_id(CHECKPOINT_ID)
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?
+            .map(|model| Block::new(model.height as u64, model.hash)))
+    }
+
+    /// Gets the tip of the chain, the checkpoint if the whole journal was pruned.
+    pub async fn get_tip(&self) -> Result<Option<Block>> {
+        match self.last_journal_model().await? {
+            Some(model) => Ok(Some(Block::new(model.height as u64, model.hash))),
+            None => self.get_checkpoint().await,
+        }
+    }
+
+    /// Removes the journal entries at or below the finalized height of a block,
+    /// and moves the checkpoint to the newest removed entry.
+    async fn prune(&self, block: &Block) -> Result<()> {
+        let height = match finalized_height(block, self.finality_depth) {
+            Some(height) => height as i64,
+            None => return Ok(()),
+        };
+        let newest = match journal::Entity::find()
+            .filter(journal::Column::Height.lte(height))
+            .order_by_desc(journal::Column::Id)
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?
+        {
+            Some(newest) => newest,
+            None => return Ok(()),
+        };
+
+        journal::Entity::delete_many()
+            .filter(journal::Column::Height.lte(height))
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        self.set_checkpoint(Block::new(newest.height as u64, newest.hash))
+            .await
+    }
+
+    /// Moves the checkpoint to a block, the newest block whose journal was pruned.
+    async fn set_checkpoint(&self, block: Block) -> Result<()> {
+        checkpoint::Entity::insert(checkpoint::ActiveModel {
+            id: Set(CHECKPOINT_ID),
+            height: Set(block.height as i64),
+            hash: Set(block.hash),
+        })
+        .on_conflict(
+            OnConflict::column(checkpoint::Column::Id)
+                .update_columns([checkpoint::Column::Height, checkpoint::Column::Hash])
+                .to_owned(),
+        )
+        .exec(self.connection)
+        .await
+        .map_err(backend_error)?;
+
+        Ok(())
+    }
+
+    /// Applies the operations of a batch, journaling the state they change at its block, or the tip if it has none.
+    /// Leaves the batch half applied on an error, so it must run within a transaction.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Change>>` - The changes of the batch, to publish once the transaction is committed.
+    pub async fn apply_batch(
+        &self,
+        block: Option<Block>,
+        operations: Vec<Operation>,
+    ) -> Result<Vec<Change>> {
+        self.lock_history().await?;
+        let tip = self.get_tip().await?;
+
+        if let Some(block) = &block {
+            check_order(block, tip.as_ref())?;
+        }
+
+        let journaled = journal_block(block.as_ref(), tip);
+        let timestamp = actions::now();
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+        let mut changes = Vec::new();
+
+        for (index, operation) in operations.into_iter().enumerate() {
+            let scope = operation.scope();
+            let inscription_names = match scope.inscription {
+                Some(inscription) => self.inscription_names(inscription).await?,
+                None => Vec::new(),
+            };
+            let names = scope.resolve(inscription_names);
+
+            if journaled.is_some() {
+                for name in names.iter() {
+                    if !snapshots.iter().any(|snapshot| &snapshot.name == name) {
+                        snapshots.push(self.snapshot(name).await?);
+                    }
+                }
+            }
+
+            let entries = actions::entries(&operation, &names, block.as_ref(), timestamp);
+            changes.extend(operation.changes(&names));
+            let name = operation.name();
+
+            if !self.apply(operation).await? {
+                return Err(DbError::Rejected {
+                    index,
+                    operation: name,
+                }
+                .into());
+            }
+
+            self.record(entries).await?;
+        }
+
+        // A batch without a block is only journaled if it changed anything.
+        if let Some(block) = journaled.filter(|_| block.is_some() || !snapshots.is_empty()) {
+            let snapshots = serde_json::to_string(&snapshots)
+                .map_err(|e| DbError::InvalidData(e.to_string()))?;
+
+            journal::Entity::insert(journal::ActiveModel {
+                height: Set(block.height as i64),
+                hash: Set(block.hash),
+                snapshots: Set(snapshots),
+                ..Default::default()
+            })
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        }
+
+        if let Some(block) = &block {
+            self.prune(block).await?;
+        }
+
+        Ok(changes)
+    }
+
+    /// Undoes every journaled batch above a height, newest first.
+    /// Leaves the state half undone on an error, so it must run within a transaction.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(usize, Vec<Change>)>` - The amount of undone batches and the changes of the restored domains.
+    pub async fn rollback_to(&self, height: u64) -> Result<(usize, Vec<Change>)> {
+        self.lock_history().await?;
+        check_finalized(height, self.get_checkpoint().await?.as_ref())?;
+
+        let timestamp = actions::now();
+        let mut count = 0;
+        let mut changes = Vec::new();
+
+        while let Some(model) = self.last_journal_model().await? {
+            if model.height as u64 <= height {
+                break;
+            }
+
+            let id = model.id;
+            let entry = parse_entry(model)?;
+
+            // Every domain was snapshotted only once per batch, so the order does not matter.
+            for snapshot in entry.snapshots {
+                self.record(vec![actions::rollback_entry(
+                    &snapshot.name,
+                    &entry.block,
+                    height,
+                    timestamp,
+                )])
+                .await?;
+                changes.push(Change::rolled_back(&snapshot.name));
+                self.restore(snapshot).await?;
+            }
+
+            journal::Entity::delete_by_id(id)
+                .exec(self.connection)
+                .await
+                .map_err(backend_error)?;
+            count += 1;
+        }
+
+        Ok((count, changes))
+    }
+
+    /// Waits until no other transaction writes to the history, and keeps it that way until this transaction ends.
+    /// A Postgres sequence hands out the ids of the history when they are inserted rather than committed, so without
+    /// the lock a transaction could commit a higher id before a lower one, which followers of the history then skip.
+    /// Taken before anything is written, so it never waits for a transaction that waits for this one.
+    /// Sqlite allows a single writer at a time already, but a transaction that started out reading fails rather than
+    /// waits once it writes while another one does. Writing nothing takes the write lock up front instead.
+    async fn lock_history(&self) -> Result<()> {
+        let backend = self.connection.get_database_backend();
+        let sql = match backend {
+            DbBackend::Postgres => format!("SELECT pg_advisory_xact_lock({})", HISTORY_LOCK),
+            _ => "DELETE FROM history WHERE 0 = 1".to_string(),
+        };
+
+        self.connection
+            .execute(Statement::from_string(backend, sql))
+            .await
+            .map_err(backend_error)?;
+
+        Ok(())
+    }
+
+    /// Appends entries to the history.
+    async fn record(&self, entries: Vec<HistoryEntry>) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let models = entries.into_iter().map(|entry| history::ActiveModel {
+            domain: Set(entry.domain),
+            action: Set(entry.action),
+            inscription: Set(entry.inscription),
+            address: Set(entry.address),
+            height: Set(entry.block.as_ref().map(|block| block.height as i64)),
+            hash: Set(entry.block.map(|block| block.hash)),
+            timestamp: Set(entry
+                .timestamp
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64),
+            payload: Set(entry.payload),
+            ..Default::default()
+        });
+
+        history::Entity::insert_many(models)
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(())
+    }
+
+    pub async fn domain_history_positions(&self, domain: &str) -> Result<Vec<(u64, HistoryEntry)>> {
+        let models = history::Entity::find()
+            .filter(history::Column::Domain.eq(domain))
+            .order_by_asc(history::Column::Id)
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(models
+            .into_iter()
+            .map(|model| (model.id as u64, parse_history(model)))
+            .collect())
+    }
+
+    pub async fn domain_history_position(&self, domain: &str) -> Result<Option<u64>> {
+        let last = history::Entity::find()
+            .select_only()
+            .column(history::Column::Id)
+            .filter(history::Column::Domain.eq(domain))
+            .order_by_desc(history::Column::Id)
+            .into_tuple::<i32>()
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(last.map(|id| id as u64))
+    }
+
+    pub async fn history_after(&self, position: u64) -> Result<Vec<(u64, HistoryEntry)>> {
+        let models = history::Entity::find()
+            .filter(history::Column::Id.gt(position as i32))
+            .order_by_asc(history::Column::Id)
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(models
+            .into_iter()
+            .map(|model| (model.id as u64, parse_history(model)))
+            .collect())
+    }
+
+    pub async fn history_position(&self) -> Result<u64> {
+        let last = history::Entity::find()
+            .select_only()
+            .column(history::Column::Id)
+            .order_by_desc(history::Column::Id)
+            .into_tuple::<i32>()
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(last.map_or(0, |id| id as u64))
+    }
+
+    pub async fn records_at(&self, domain: &str, height: u64) -> Result<Vec<(String, SubDomain)>> {
+        check_finalized(height, self.get_checkpoint().await?.as_ref())?;
+
+        let models = journal::Entity::find()
+            .filter(journal::Column::Height.gt(height as i64))
+            .order_by_asc(journal::Column::Id)
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let entries = models
+            .into_iter()
+            .map(parse_entry)
+            .collect::<Result<Vec<JournalEntry>>>()?;
+
+        match snapshot_at(entries.iter(), domain) {
+            Some(snapshot) => snapshot.records(),
+            None => self.snapshot(domain).await?.records(),
+        }
+    }
+
+    /// Gets the name of every domain with rows of any kind, in order.
+    async fn stored_names(&self) -> Result<BTreeSet<String>> {
+        let mut names = BTreeSet::new();
+
+        names.extend(
+            domain::Entity::find()
+                .select_only()
+                .column(domain::Column::Name)
+                .distinct()
+                .into_tuple::<String>()
+                .all(self.connection)
+                .await
+                .map_err(backend_error)?,
+        );
+        names.extend(
+            subdomain::Entity::find()
+                .select_only()
+                .column(subdomain::Column::Domain)
+                .distinct()
+                .into_tuple::<String>()
+                .all(self.connection)
+                .await
+                .map_err(backend_error)?,
+        );
+        names.extend(
+            validity::Entity::find()
+                .select_only()
+                .column(validity::Column::Domain)
+                .distinct()
+                .into_tuple::<String>()
+                .all(self.connection)
+                .await
+                .map_err(backend_error)?,
+        );
+        names.extend(
+            data::Entity::find()
+                .select_only()
+                .column(data::Column::Domain)
+                .distinct()
+                .into_tuple::<String>()
+                .all(self.connection)
+                .await
+                .map_err(backend_error)?,
+        );
+
+        Ok(names)
+    }
+
+    /// Snapshots every domain with rows of any kind, reading every table once.
+    pub async fn snapshots(&self) -> Result<Vec<Snapshot>> {
+        let domains = domain::Entity::find()
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let subdomains = subdomain::Entity::find()
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let validities = validity::Entity::find()
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let data = data::Entity::find()
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(group_snapshots(domains, subdomains, validities, data)?
+            .into_values()
+            .collect())
+    }
+
+    /// Snapshots the state of every domain at the current tip.
+    /// Should run within a transaction, so the state matches the tip.
+    pub async fn export_snapshot(&self) -> Result<StateSnapshot> {
+        StateSnapshot::new(self.get_tip().await?, self.snapshots().await?)
+    }
+
+    /// Restores every domain of a snapshot into an empty database, and checks the hash of the result.
+    /// Leaves the snapshot half imported on an error, so it must run within a transaction.
+    pub async fn import_snapshot(&self, snapshot: StateSnapshot) -> Result<()> {
+        self.lock_history().await?;
+
+        if !self.stored_names().await?.is_empty() {
+            return Err(DbError::InvalidData(
+                "the database is not empty, refusing to import".to_string(),
+            )
+            .into());
+        }
+
+        let expected = snapshot.clone();
+        let timestamp = actions::now();
+
+        for domain in snapshot.into_domains() {
+            self.record(vec![actions::import_entry(
+                &domain.name,
+                expected.block(),
+                timestamp,
+            )])
+            .await?;
+            self.restore(domain).await?;
+        }
+
+        expected.check(self.snapshots().await?)?;
+        self.reset_subdomain_ids().await?;
+
+        match expected.block() {
+            Some(block) => self.set_checkpoint(block.clone()).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Continues the ids of new subdomains after the highest restored id.
+    /// Sqlite does so by itself, a Postgres sequence is not advanced by rows inserted with an id.
+    async fn reset_subdomain_ids(&self) -> Result<()> {
+        let backend = self.connection.get_database_backend();
+
+        if backend != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        self.connection
+            .execute(Statement::from_string(
+                backend,
+                "SELECT setval(pg_get_serial_sequence('subdomain', 'id'), \
+                 (SELECT COALESCE(MAX(id), 0) + 1 FROM subdomain), false)",
+            ))
+            .await
+            .map_err(backend_error)?;
+
+        Ok(())
+    }
+
+    /// Gets the domain names of every row inscribed by an inscription.
+    pub async fn inscription_names(&self, inscription: &str) -> Result<Vec<String>> {
+        let domains = domain::Entity::find_by_id(inscription)
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let subdomains = subdomain::Entity::find()
+            .filter(subdomain::Column::Inscription.eq(inscription))
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let validities = validity::Entity::find_by_id(inscription)
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let data = data::Entity::find_by_id(inscription)
+            .one(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(domains
+            .map(|model| model.name)
+            .into_iter()
+            .chain(subdomains.into_iter().map(|model| model.domain))
+            .chain(validities.map(|model| model.domain))
+            .chain(data.map(|model| model.domain))
+            .collect())
+    }
+
+    pub async fn snapshot(&self, name: &str) -> Result<Snapshot> {
+        let domains = domain::Entity::find()
+            .filter(domain::Column::Name.eq(name))
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let subdomains = subdomain::Entity::find()
+            .filter(subdomain::Column::Domain.eq(name))
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let validities = validity::Entity::find()
+            .filter(validity::Column::Domain.eq(name))
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+        let data = data::Entity::find()
+            .filter(data::Column::Domain.eq(name))
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        let mut snapshots = group_snapshots(domains, subdomains, validities, data)?;
+
+        Ok(snapshots.remove(name).unwrap_or_else(|| Snapshot {
+            name: name.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    /// Replaces every row stored under the name of a snapshot with the rows of the snapshot.
+    async fn restore(&self, snapshot: Snapshot) -> Result<()> {
+        let name = snapshot.name.as_str();
+
+        // Rows that took over a key of the snapshot are replaced as well.
+        let domains: Vec<&str> = snapshot
+            .domains
+            .iter()
+            .map(|record| record.inscription.as_str())
+            .collect();
+        let subdomains: Vec<i32> = snapshot
+            .subdomains
+            .iter()
+            .map(|record| record.id as i32)
+            .collect();
+        let validities: Vec<&str> = snapshot
+            .validities
+            .iter()
+            .map(|record| record.inscription.as_str())
+            .collect();
+        let data: Vec<&str> = snapshot
+            .data
+            .iter()
+            .map(|record| record.inscription.as_str())
+            .collect();
+
+        domain::Entity::delete_many()
+            .filter(
+                domain::Column::Name
+                    .eq(name)
+                    .or(domain::Column::Inscription.is_in(domains)),
+            )
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        subdomain::Entity::delete_many()
+            .filter(
+                subdomain::Column::Domain
+                    .eq(name)
+                    .or(subdomain::Column::Id.is_in(subdomains)),
+            )
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        validity::Entity::delete_many()
+            .filter(
+                validity::Column::Domain
+                    .eq(name)
+                    .or(validity::Column::Inscription.is_in(validities)),
+            )
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        data::Entity::delete_many()
+            .filter(
+                data::Column::Domain
+                    .eq(name)
+                    .or(data::Column::Inscription.is_in(data)),
+            )
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+
+        for record in snapshot.domains {
+            domain::Entity::insert(domain::ActiveModel {
+                inscription: Set(record.inscription),
+                address: Set(record.address),
+                name: Set(record.name),
+                valid_from: Set(record.valid_from.to_string()),
+                last_renewal: Set(record.last_renewal),
+            })
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        }
+
+        for record in snapshot.subdomains {
+            let (rdata, priority) = match record.to_model()?.rdata {
+                SubDomainRData::MX { priority, host } => (host, Some(priority as i32)),
+                rdata => (rdata.to_string(), None),
+            };
+
+            subdomain::Entity::insert(subdomain::ActiveModel {
+                id: Set(record.id as i32),
+                inscription: Set(record.inscription),
+                address: Set(record.address),
+                domain: Set(record.domain),
+                subdomain: Set(record.subdomain),
+                rtype: Set(record.rtype),
+                class: Set(record.class),
+                ttl: Set(record.ttl as i32),
+                rdata: Set(rdata),
+                priority: Set(priority),
+            })
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        }
+
+        for record in snapshot.validities {
+            validity::Entity::insert(validity::ActiveModel {
+                inscription: Set(record.inscription),
+                address: Set(record.address),
+                domain: Set(record.domain),
+                algorithm: Set(record.algorithm),
+                public_key: Set(record.public_key),
+            })
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        }
+
+        for record in snapshot.data {
+            data::Entity::insert(data::ActiveModel {
+                inscription: Set(record.inscription),
+                address: Set(record.address),
+                domain: Set(record.domain),
+                data: Set(record.data),
+            })
+            .exec(self.connection)
+            .await
+            .map_err(backend_error)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+mod executor;
+mod journal;
+mod query;
 mod repository;
 
+pub(crate) use executor::{backend_error, parse_valid_from};
 pub use repository::SqliteRepository;
@@ -0,0 +1,225 @@
+use sea_orm::sea_query::{Expr, LikeExpr, SimpleExpr};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DbBackend, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+
+use entity::{domain, subdomain};
+use shared::common::Result;
+use shared::time::system_time_from_epoch_seconds;
+use xdns_data::models::{Domain, SubDomain};
+
+use super::executor::{backend_error, parse_valid_from, SqlExecutor};
+use crate::query::{NameFilter, Page};
+
+/// `valid_from` is stored as text, so it is compared as a number.
+/// Matches the expression index of the migration, which makes the expiry queries use it.
+fn valid_from() -> SimpleExpr {
+    Expr::cust("CAST(\"valid_from\" AS BIGINT)")
+}
+
+fn paged(page: &Page) -> Condition {
+    match &page.after {
+        Some(after) => Condition::all().add(domain::Column::Name.gt(after.as_str())),
+        None => Condition::all(),
+    }
+}
+
+/// Limits a search to the names the index of the filter finds, the `LIKE` of the filter still decides.
+///
+/// * A prefix is a range of the name index, from the prefix up to the first name after every name starting with it.
+/// * SQLite looks text of at least 3 characters up in the trigram table of the migration,
+///   Postgres serves the `LIKE` from its trigram index by itself. Shorter text matches too many names for an index to help.
+fn indexed(filter: &NameFilter, backend: DbBackend) -> Condition {
+    match filter {
+        NameFilter::Prefix(prefix) => {
+            let condition = Condition::all().add(domain::Column::Name.gte(prefix.as_str()));
+
+            match prefix_end(prefix) {
+                Some(end) => condition.add(domain::Column::Name.lt(end)),
+                None => condition,
+            }
+        }
+        NameFilter::Contains(text)
+            if backend == DbBackend::Sqlite && filter.trigram().is_some() =>
+        {
+            // A quoted phrase matches its trigrams in order, which is a substring match.
+            let phrase = format!("\"{}\"", text.replace('"', "\"\""));
+
+            Condition::all().add(Expr::cust_with_values(
+                r#""domain"."name" IN (SELECT "name" FROM "domain_name_search" WHERE "domain_name_search" MATCH ?)"#,
+                [phrase],
+            ))
+        }
+        NameFilter::Contains(_) => Condition::all(),
+    }
+}
+
+/// Gets the first name after every name that starts with a prefix, `None` if there is no such name.
+fn prefix_end(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+
+    while let Some(last) = chars.pop() {
+        // Skips the surrogates, which are not chars.
+        let next = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+
+    None
+}
+
+fn parse_domain(model: domain::Model) -> Result<(String, Domain)> {
+    Ok((
+        model.address,
+        Domain {
+            name: model.name,
+            valid_from: system_time_from_epoch_seconds(parse_valid_from(&model.valid_from)?),
+        },
+    ))
+}
+
+impl<'a, C: ConnectionTrait> SqlExecutor<'a, C> {
+    /// The condition for domains that are active, and expire within `within_days` if given.
+    fn live(&self, within_days: Option<u64>) -> Condition {
+        let range = self.policy.active_valid_from(within_days);
+        let condition = Condition::all().add(Expr::expr(valid_from()).gte(*range.start() as i64));
+
+        match within_days {
+            // Clamped, a range reaching far enough ahead ends beyond the largest number SQL can compare to.
+            Some(_) => condition
+                .add(Expr::expr(valid_from()).lte(i64::try_from(*range.end()).unwrap_or(i64::MAX))),
+            None => condition,
+        }
+    }
+
+    async fn find_domains(
+        &self,
+        condition: Condition,
+        limit: Option<usize>,
+    ) -> Result<Vec<(String, Domain)>> {
+        domain::Entity::find()
+            .filter(condition)
+            .order_by_asc(domain::Column::Name)
+            .limit(limit.map(|limit| limit as u64))
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?
+            .into_iter()
+            .map(parse_domain)
+            .collect()
+    }
+
+    async fn count_domains_by(&self, condition: Condition) -> Result<u64> {
+        Ok(domain::Entity::find()
+            .filter(condition)
+            .count(self.connection)
+            .await
+            .map_err(backend_error)?)
+    }
+
+    fn owned_subdomains(domain: &str, address: String) -> Condition {
+        Condition::all()
+            .add(subdomain::Column::Domain.eq(domain))
+            .add(subdomain::Column::Address.eq(address))
+    }
+
+    pub async fn list_domains_by_address(&self, address: &str) -> Result<Vec<Domain>> {
+        let condition = self.live(None).add(domain::Column::Address.eq(address));
+
+        Ok(self
+            .find_domains(condition, None)
+            .await?
+            .into_iter()
+            .map(|(_, domain)| domain)
+            .collect())
+    }
+
+    pub async fn list_domains(&self, page: &Page) -> Result<Vec<(String, Domain)>> {
+        self.find_domains(self.live(None).add(paged(page)), Some(page.limit))
+            .await
+    }
+
+    pub async fn search_domains(
+        &self,
+        filter: &NameFilter,
+        page: &Page,
+    ) -> Result<Vec<(String, Domain)>> {
+        let name = Expr::col((domain::Entity, domain::Column::Name))
+            .like(LikeExpr::new(filter.like_pattern()).escape('\\'));
+        let condition = self
+            .live(None)
+            .add(paged(page))
+            .add(indexed(filter, self.connection.get_database_backend()))
+            .add(name);
+
+        self.find_domains(condition, Some(page.limit)).await
+    }
+
+    pub async fn domains_expiring_within(&self, days: u64) -> Result<Vec<(String, Domain)>> {
+        domain::Entity::find()
+            .filter(self.live(Some(days)))
+            .order_by_asc(valid_from())
+            .order_by_asc(domain::Column::Name)
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?
+            .into_iter()
+            .map(parse_domain)
+            .collect()
+    }
+
+    pub async fn count_domains(&self) -> Result<u64> {
+        self.count_domains_by(self.live(None)).await
+    }
+
+    pub async fn count_domains_by_address(&self, address: &str) -> Result<u64> {
+        self.count_domains_by(self.live(None).add(domain::Column::Address.eq(address)))
+            .await
+    }
+
+    /// Gets the names of the released domains.
+    pub async fn released_names(&self) -> Result<Vec<String>> {
+        let until = match self.policy.released_until() {
+            Some(until) => until,
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(domain::Entity::find()
+            .filter(Expr::expr(valid_from()).lte(until as i64))
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?
+            .into_iter()
+            .map(|model| model.name)
+            .collect())
+    }
+
+    pub async fn list_subdomains(&self, domain: &str) -> Result<Vec<(String, SubDomain)>> {
+        let address = self.get_domain_address(domain).await?;
+
+        subdomain::Entity::find()
+            .filter(Self::owned_subdomains(domain, address))
+            .order_by_asc(subdomain::Column::Subdomain)
+            .order_by_asc(subdomain::Column::Id)
+            .all(self.connection)
+            .await
+            .map_err(backend_error)?
+            .into_iter()
+            .map(Self::parse_subdomain_model)
+            .collect()
+    }
+
+    pub async fn count_subdomains(&self, domain: &str) -> Result<u64> {
+        let address = self.get_domain_address(domain).await?;
+
+        Ok(subdomain::Entity::find()
+            .filter(Self::owned_subdomains(domain, address))
+            .count(self.connection)
+            .await
+            .map_err(backend_error)?)
+    }
+}
@@ -1,5 +1,5 @@
 use shared::common::Result;
-use xdns_data::models::Domain;
+use xdns_data::models::{Domain, DomainRenew, Signature};
 
 use crate::traits::Repository;
 
@@ -8,6 +8,14 @@ pub trait DomainRepository {
     async fn get(&mut self, domain: &str) -> Result<(String, Domain)>;
     async fn get_by_inscription(&mut self, inscription: &str) -> Result<(String, Domain)>;
     async fn get_by_address(&mut self, address: &str) -> Result<Domain>;
+    async fn list_by_address(&mut self, address: &str) -> Result<Vec<Domain>>;
+    async fn renew(
+        &mut self,
+        address: &str,
+        inscription: &str,
+        renewal: DomainRenew,
+        signature: Option<Signature>,
+    ) -> bool;
     async fn remove(&mut self, domain: &str) -> bool;
     async fn remove_by_inscription(&mut self, inscription: &str) -> bool;
 }
@@ -33,6 +41,23 @@ impl<T: Repository> DomainRepository for T {
         self.get_domain_by_address(address).await
     }
 
+    /// Type specific alias for [`Repository::list_domains_by_address`].
+    async fn list_by_address(&mut self, address: &str) -> Result<Vec<Domain>> {
+        self.list_domains_by_address(address).await
+    }
+
+    /// Type specific alias for [`Repository::renew_domain`].
+    async fn renew(
+        &mut self,
+        address: &str,
+        inscription: &str,
+        renewal: DomainRenew,
+        signature: Option<Signature>,
+    ) -> bool {
+        self.renew_domain(address, inscription, renewal, signature)
+            .await
+    }
+
     /// Type specific alias for [`Repository::remove_domain`].
     async fn remove(&mut self, domain: &str) -> bool {
         self.remove_domain(domain).await
@@ -1,12 +1,38 @@
-use shared::common::Result;
-use xdns_data::models::{Data, Domain, SubDomain, Validity, ValidityTransfer};
+use shared::common::{DbError, Error, Result};
 
+use crate::batch::{Batch, Operation};
+use crate::changes::ChangeFeed;
+use crate::commitment::{NameProof, StateRoot};
+use crate::expiry::ExpiryPolicy;
+use crate::history::HistoryEntry;
+use crate::journal::Block;
+use crate::query::{NameFilter, Page};
+use crate::snapshot::StateSnapshot;
+use xdns_data::models::{
+    Data, Domain, DomainRenew, Signature, SubDomain, Validity, ValidityTransfer,
+};
+
+/// A store of domains, subdomains, validities and data.
+///
+/// The standalone mutators (eg [`add_domain`](Self::add_domain)) commit a [`Batch`](Batch) of their single operation,
+/// so they are journaled, recorded in the history and published to the [changes](Self::changes) like any other batch.
 pub trait Repository
 where
     Self: Sized,
 {
-    async fn new() -> Self;
-    async fn new_memory() -> Self;
+    /// Create a repository from the environment.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The repository, or an error if it could not be configured or connected.
+    async fn new() -> Result<Self>;
+
+    /// Create a repository that only lives in memory, ready to be used.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The repository, or an error if it could not be created.
+    async fn new_memory() -> Result<Self>;
 
     /// Get an existing domain from the repository.
     ///
@@ -31,6 +57,7 @@ where
     async fn get_domain_by_inscription(&self, inscription: &str) -> Result<(String, Domain)>;
 
     /// Get an existing domain from the repository by address.
+    /// An address can own many domains, only one of them is returned, see [`list_domains_by_address`](Self::list_domains_by_address).
     ///
     /// # Arguments
     ///
@@ -41,6 +68,77 @@ where
     /// * `Result<Domain>` - The domain if it exists.
     async fn get_domain_by_address(&self, address: &str) -> Result<Domain>;
 
+    /// Get all domains owned by an address, ordered by name.
+    /// Expired domains are left out, but not removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address that owns the domains.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Domain>>` - The domains, empty if the address does not own any.
+    async fn list_domains_by_address(&self, address: &str) -> Result<Vec<Domain>>;
+
+    /// Get a page of all domains, ordered by name.
+    /// Expired domains are left out, but not removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page to get.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Address, Domain)>>` - The domains on the page.
+    async fn list_domains(&self, page: &Page) -> Result<Vec<(String, Domain)>>;
+
+    /// Get a page of the domains whose name matches a filter, ordered by name.
+    /// Expired domains are left out, but not removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - How the names are matched.
+    /// * `page` - The page to get.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Address, Domain)>>` - The matching domains on the page.
+    async fn search_domains(
+        &self,
+        filter: &NameFilter,
+        page: &Page,
+    ) -> Result<Vec<(String, Domain)>>;
+
+    /// Get the domains that have not expired yet, but will within a number of days.
+    /// Ordered by expiry, the domain that expires first comes first.
+    ///
+    /// # Arguments
+    ///
+    /// * `days` - The number of days from now.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Address, Domain)>>` - The expiring domains.
+    async fn domains_expiring_within(&self, days: u64) -> Result<Vec<(String, Domain)>>;
+
+    /// Count the domains that have not expired.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64>` - The number of domains.
+    async fn count_domains(&self) -> Result<u64>;
+
+    /// Count the domains owned by an address that have not expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address that owns the domains.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64>` - The number of domains.
+    async fn count_domains_by_address(&self, address: &str) -> Result<u64>;
+
     /// Get the address of the owner of a domain by the domain name.
     ///
     /// # Arguments
@@ -62,7 +160,15 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the domain was added.
-    async fn add_domain(&self, address: &str, inscription: &str, domain: Domain) -> bool;
+    async fn add_domain(&self, address: &str, inscription: &str, domain: Domain) -> bool {
+        let operation = Operation::AddDomain {
+            address: address.to_string(),
+            inscription: inscription.to_string(),
+            domain,
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Remove an existing domain from the repository.
     ///
@@ -73,7 +179,13 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the domain was removed.
-    async fn remove_domain(&self, domain: &str) -> bool;
+    async fn remove_domain(&self, domain: &str) -> bool {
+        let operation = Operation::RemoveDomain {
+            domain: domain.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Remove an existing domain from the repository by inscription id.
     ///
@@ -84,7 +196,73 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the domain was removed.
-    async fn remove_domain_by_inscription(&self, inscription: &str) -> bool;
+    async fn remove_domain_by_inscription(&self, inscription: &str) -> bool {
+        let operation = Operation::RemoveDomainByInscription {
+            inscription: inscription.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
+
+    /// Extend the registration of a domain by a number of lifetimes.
+    ///
+    /// # Restrictions
+    ///
+    /// * The domain must not be released, a domain in its grace period can still be renewed.
+    /// * The renewal must be inscribed by the owner, or signed with the validity key of the domain.
+    /// * The domain must not expire further ahead than [`ExpiryPolicy::max_renewal`](crate::ExpiryPolicy::max_renewal).
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address that inscribed the renewal.
+    /// * `inscription` - The inscription id of the renewal.
+    /// * `renewal` - The renewal to apply.
+    /// * `signature` - The signature of the inscription, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the domain was renewed.
+    async fn renew_domain(
+        &self,
+        address: &str,
+        inscription: &str,
+        renewal: DomainRenew,
+        signature: Option<Signature>,
+    ) -> bool {
+        let operation = Operation::RenewDomain {
+            address: address.to_string(),
+            inscription: inscription.to_string(),
+            renewal,
+            signature,
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
+
+    /// Remove a released domain, together with every subdomain, validity and data stored under its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain to remove.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the domain was removed, `false` if it does not exist or is not released.
+    async fn expire_domain(&self, domain: &str) -> bool {
+        let operation = Operation::ExpireDomain {
+            domain: domain.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
+
+    /// Remove every released domain like [`expire_domain`](Self::expire_domain), all in a single batch.
+    /// This is the only place expired domains are removed, reads leave them in place.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<String>>` - The names of the removed domains.
+    async fn sweep_expired(&self) -> Result<Vec<String>>;
 
     /// Add a new subdomain to the repository.
     ///
@@ -96,7 +274,15 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the subdomain was added.
-    async fn add_subdomain(&self, address: &str, inscription: &str, subdomain: SubDomain) -> bool;
+    async fn add_subdomain(&self, address: &str, inscription: &str, subdomain: SubDomain) -> bool {
+        let operation = Operation::AddSubdomain {
+            address: address.to_string(),
+            inscription: inscription.to_string(),
+            subdomain,
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Get all existing subdomains from the repository that match the given domain.
     ///
@@ -114,6 +300,42 @@ where
         subdomain: &str,
     ) -> Result<Vec<(String, SubDomain)>>;
 
+    /// Get the names of all subdomains with records for the given domain.
+    /// Only subdomains inscribed by the current owner of the domain are included.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the subdomains.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<String>>` - The distinct subdomain names if the domain exists. (can be empty)
+    async fn get_subdomain_names(&self, domain: &str) -> Result<Vec<String>>;
+
+    /// Get the records of all subdomains of the given domain, ordered by subdomain name.
+    /// Only subdomains inscribed by the current owner of the domain are included.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the subdomains.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Address, SubDomain)>>` - The records if the domain exists.
+    async fn list_subdomains(&self, domain: &str) -> Result<Vec<(String, SubDomain)>>;
+
+    /// Count the records of all subdomains of the given domain.
+    /// Only subdomains inscribed by the current owner of the domain are counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the subdomains.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64>` - The number of records if the domain exists.
+    async fn count_subdomains(&self, domain: &str) -> Result<u64>;
+
     /// Get an existing subdomain from the repository by inscription id.
     ///
     /// # Arguments
@@ -125,6 +347,23 @@ where
     /// * `Result<(Address, Subdomain)>` - The subdomain if it exists.
     async fn get_subdomain_by_inscription(&self, inscription: &str) -> Result<(String, SubDomain)>;
 
+    /// Get the inscription ids of the records of a subdomain, in the order of [`get_subdomain`](Self::get_subdomain).
+    /// Only records inscribed by the current owner of the domain are included.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the subdomain.
+    /// * `subdomain` - The subdomain of the records.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Inscription, Subdomain)>>` - The records if the domain exists.
+    async fn get_subdomain_inscriptions(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>>;
+
     /// Remove an existing subdomain from the repository.
     /// This will remove all subdomains that match the given domain and subdomain.
     ///
@@ -135,7 +374,14 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the subdomain was removed.
-    async fn remove_subdomains(&self, domain: &str, subdomain: &str) -> bool;
+    async fn remove_subdomains(&self, domain: &str, subdomain: &str) -> bool {
+        let operation = Operation::RemoveSubdomains {
+            domain: domain.to_string(),
+            subdomain: subdomain.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Remove an existing subdomain from the repository by inscription id.
     ///
@@ -146,7 +392,13 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the subdomain was removed.
-    async fn remove_subdomain(&self, inscription: &str) -> bool;
+    async fn remove_subdomain(&self, inscription: &str) -> bool {
+        let operation = Operation::RemoveSubdomain {
+            inscription: inscription.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Add a new validity to the repository.
     ///
@@ -158,7 +410,15 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the validity was added.
-    async fn add_validity(&self, address: &str, inscription: &str, validity: Validity) -> bool;
+    async fn add_validity(&self, address: &str, inscription: &str, validity: Validity) -> bool {
+        let operation = Operation::AddValidity {
+            address: address.to_string(),
+            inscription: inscription.to_string(),
+            validity,
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Get a validity from the repository by its domain.
     ///
@@ -191,7 +451,13 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the validity was removed.
-    async fn remove_validity(&self, domain: &str) -> bool;
+    async fn remove_validity(&self, domain: &str) -> bool {
+        let operation = Operation::RemoveValidity {
+            domain: domain.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Remove a validity from the repository by its inscription id.
     ///
@@ -202,9 +468,15 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the validity was removed.
-    async fn remove_validity_by_inscription(&self, inscription: &str) -> bool;
+    async fn remove_validity_by_inscription(&self, inscription: &str) -> bool {
+        let operation = Operation::RemoveValidityByInscription {
+            inscription: inscription.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
-    /// Update a validity in the repository.
+    /// Update a validity in the repository, on behalf of the current owner of the domain.
     ///
     /// # Arguments
     ///
@@ -213,7 +485,16 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the validity was updated.
-    async fn update_validity(&self, validity: ValidityTransfer) -> bool;
+    async fn update_validity(&self, validity: ValidityTransfer) -> bool {
+        let address = match self.get_domain_address(&validity.domain).await {
+            Ok(address) => address,
+            Err(_) => return false,
+        };
+
+        self.commit(Operation::UpdateValidity { address, validity }.into())
+            .await
+            .is_ok()
+    }
 
     /// Update a validity in the repository by its inscription id.
     ///
@@ -229,7 +510,15 @@ where
         address: &str,
         inscription: &str,
         validity: ValidityTransfer,
-    ) -> bool;
+    ) -> bool {
+        let operation = Operation::UpdateValidityByInscription {
+            address: address.to_string(),
+            inscription: inscription.to_string(),
+            validity,
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Add a new data to the repository.
     ///
@@ -241,7 +530,15 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the data was added.
-    async fn add_data(&self, address: &str, inscription: &str, data: Data) -> bool;
+    async fn add_data(&self, address: &str, inscription: &str, data: Data) -> bool {
+        let operation = Operation::AddData {
+            address: address.to_string(),
+            inscription: inscription.to_string(),
+            data,
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Get all existing data from the repository that match the given domain.
     ///
@@ -275,7 +572,13 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the data was removed.
-    async fn remove_data(&self, domain: &str) -> bool;
+    async fn remove_data(&self, domain: &str) -> bool {
+        let operation = Operation::RemoveData {
+            domain: domain.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Remove an existing data from the repository by inscription id.
     ///
@@ -286,7 +589,13 @@ where
     /// # Returns
     ///
     /// * `bool` - Whether the data was removed.
-    async fn remove_data_by_inscription(&self, inscription: &str) -> bool;
+    async fn remove_data_by_inscription(&self, inscription: &str) -> bool {
+        let operation = Operation::RemoveDataByInscription {
+            inscription: inscription.to_string(),
+        };
+
+        self.commit(operation.into()).await.is_ok()
+    }
 
     /// Attemts to transfer any inscription item to a new owner.
     /// If the inscription id is not found in the repository, an Err(()) is returned.
@@ -299,5 +608,191 @@ where
     /// # Returns
     ///
     /// * `Result<bool, ()>` - Whether the transfer was successful. The boolean represents if the item was found but the transfer failed.
-    async fn transfer_inscription(&self, inscription: &str, new_owner: &str) -> Result<bool>;
+    async fn transfer_inscription(&self, inscription: &str, new_owner: &str) -> Result<bool> {
+        let operation = Operation::TransferInscription {
+            inscription: inscription.to_string(),
+            new_owner: new_owner.to_string(),
+        };
+
+        match self.commit(operation.into()).await {
+            Ok(()) => Ok(true),
+            Err(Error::Db(DbError::Rejected { .. })) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Apply all operations of a batch, all together or not at all.
+    /// An operation is rejected when it can not be applied (eg a validity for a domain that already has one),
+    /// its standalone method returns `false` in that case.
+    /// The state it changes is journaled at its block, or the tip if it has none, so it can be rolled back,
+    /// a block below the [tip](Repository::get_tip) is refused with a [`DbError::BlockOrder`](shared::common::DbError::BlockOrder).
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The operations to apply, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Ok if every operation was applied, otherwise nothing was applied
+    ///   and the error is a [`DbError::Rejected`](shared::common::DbError::Rejected) for the first rejected operation,
+    ///   or the error that occurred.
+    async fn commit(&self, batch: Batch) -> Result<()>;
+
+    /// Get the block of the last journaled batch, the tip of the chain as far as the repository knows.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Block>>` - The block, or `None` if no batch was committed at a block (or all were rolled back).
+    ///   Once the whole journal was pruned, the newest pruned block is the tip.
+    async fn get_tip(&self) -> Result<Option<Block>>;
+
+    /// Undo every batch that was committed at a block above a height, newest first.
+    /// Domains, subdomains, validities, data and ownership are restored to exactly how they were
+    /// after the last batch at or below the height. (eg after a chain reorganization)
+    /// The journal of blocks deeper than the finality depth below the tip is pruned, they can not be undone anymore.
+    ///
+    /// # Arguments
+    ///
+    /// * `height` - The height of the last block to keep.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The amount of batches that were undone,
+    ///   or a [`DbError::Finalized`](shared::common::DbError::Finalized) if the height is below the newest pruned block.
+    async fn rollback_to(&self, height: u64) -> Result<usize>;
+
+    /// Get every recorded action that changed a domain, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain to get the history of.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<HistoryEntry>>` - The recorded actions, empty if the domain was never changed by a batch.
+    async fn domain_history(&self, domain: &str) -> Result<Vec<HistoryEntry>> {
+        Ok(self
+            .domain_history_positions(domain)
+            .await?
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    /// Get every recorded action that changed a domain with its position in the history, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain to get the history of.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(u64, HistoryEntry)>>` - The recorded actions with their positions, see [`history_after`](Self::history_after).
+    async fn domain_history_positions(&self, domain: &str) -> Result<Vec<(u64, HistoryEntry)>>;
+
+    /// Get the position of the last recorded action that changed a domain, without reading its whole history.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain to get the position of.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<u64>>` - The position, or `None` if the domain was never changed by a batch.
+    async fn domain_history_position(&self, domain: &str) -> Result<Option<u64>>;
+
+    /// Get every action recorded after a position in the history, of any domain, oldest first.
+    /// The history is stored with the rows, so unlike the [changes](Self::changes) it includes
+    /// the actions of every process using the same database (eg the indexer), see [`HistoryFollower`](crate::HistoryFollower).
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the last action that was seen, `0` to get every action.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(u64, HistoryEntry)>>` - The actions with their positions, which increase with every recorded action.
+    async fn history_after(&self, position: u64) -> Result<Vec<(u64, HistoryEntry)>>;
+
+    /// Get the position of the last recorded action, see [`history_after`](Self::history_after).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64>` - The position, `0` if nothing was recorded yet.
+    async fn history_position(&self) -> Result<u64>;
+
+    /// Get the records of a domain as they were after the last batch at or below a height.
+    /// Only the changes of journaled batches are taken into account, batches committed before the first block are not.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain to get the records of.
+    /// * `height` - The height to get the records at.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(Address, SubDomain)>>` - The records, or an error if the domain did not exist at that height
+    ///   or the height is below the newest pruned block.
+    async fn records_at(&self, domain: &str, height: u64) -> Result<Vec<(String, SubDomain)>>;
+
+    /// Export every domain, subdomain, validity and data row as stored, at the current tip.
+    /// Expired domains are exported as well, the history and journal are not.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<StateSnapshot>` - The snapshot, taken within a single transaction.
+    async fn export_snapshot(&self) -> Result<StateSnapshot>;
+
+    /// Load a snapshot into this empty repository, within a single transaction.
+    /// A snapshot that does not advertise the expected hash is refused, and the imported state is hashed
+    /// before it is committed, so a snapshot that does not reproduce its hash leaves the repository empty.
+    ///
+    /// Every imported name is recorded in the history, but nothing is journaled:
+    /// the block of the snapshot becomes the tip (and the newest pruned block),
+    /// so the repository can not be rolled back below it and indexing continues with the block after
+    /// [`StateSnapshot::block`](StateSnapshot::block).
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - The snapshot to import.
+    /// * `expected_hash` - The hex encoded hash of the snapshot, obtained from a trusted source.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the repository is not empty, a row could not be stored, or a hash does not match.
+    async fn import_snapshot(&self, snapshot: StateSnapshot, expected_hash: &str) -> Result<()>;
+
+    /// Get the root of the Merkle tree that commits to every domain, subdomain, validity and data row.
+    /// Repositories with the same rows have the same root, so nodes can compare roots to check they converged.
+    /// The tree is kept up to date on every mutation.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<StateRoot>` - The root, or an error if the rows could not be read.
+    async fn state_root(&self) -> Result<StateRoot>;
+
+    /// Prove the rows stored under a domain name, or that there are none, against the [`state_root`](Self::state_root).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The domain name to prove.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<NameProof>` - A proof of inclusion, or of non-inclusion if nothing is stored under the name.
+    async fn prove(&self, name: &str) -> Result<NameProof>;
+
+    /// Get the feed the changes of committed batches, rollbacks and sweeps are published to.
+    ///
+    /// # Returns
+    ///
+    /// * `&ChangeFeed` - The feed, [subscribe](ChangeFeed::subscribe) to it to receive the changes.
+    fn changes(&self) -> &ChangeFeed;
+
+    /// Get how long domain registrations stay valid in the repository.
+    ///
+    /// # Returns
+    ///
+    /// * `ExpiryPolicy` - The policy, domains are only resolved while they are active.
+    fn policy(&self) -> ExpiryPolicy;
 }
@@ -5,7 +5,14 @@ use xdns_data::models::SubDomain;
 pub trait SubdomainRepository {
     async fn add(&self, address: &str, inscription: &str, subdomain: SubDomain) -> bool;
     async fn get(&self, domain: &str, subdomain: &str) -> Result<Vec<(String, SubDomain)>>;
+    async fn get_names(&self, domain: &str) -> Result<Vec<String>>;
+    async fn list(&self, domain: &str) -> Result<Vec<(String, SubDomain)>>;
     async fn get_by_inscription(&self, inscription: &str) -> Result<(String, SubDomain)>;
+    async fn get_inscriptions(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>>;
     async fn remove_all(&self, domain: &str, subdomain: &str) -> bool;
     async fn remove(&self, inscription: &str) -> bool;
 }
@@ -21,11 +28,30 @@ impl<T: Repository> SubdomainRepository for T {
         self.get_subdomain(domain, subdomain).await
     }
 
+    /// Type specific alias for [`Repository::get_subdomain_names`].
+    async fn get_names(&self, domain: &str) -> Result<Vec<String>> {
+        self.get_subdomain_names(domain).await
+    }
+
+    /// Type specific alias for [`Repository::list_subdomains`].
+    async fn list(&self, domain: &str) -> Result<Vec<(String, SubDomain)>> {
+        self.list_subdomains(domain).await
+    }
+
     /// Type specific alias for [`Repository::get_subdomain_by_inscription`].
     async fn get_by_inscription(&self, inscription: &str) -> Result<(String, SubDomain)> {
         self.get_subdomain_by_inscription(inscription).await
     }
 
+    /// Type specific alias for [`Repository::get_subdomain_inscriptions`].
+    async fn get_inscriptions(
+        &self,
+        domain: &str,
+        subdomain: &str,
+    ) -> Result<Vec<(String, SubDomain)>> {
+        self.get_subdomain_inscriptions(domain, subdomain).await
+    }
+
     /// Type specific alias for [`Repository::remove_subdomains`].
     async fn remove_all(&self, domain: &str, subdomain: &str) -> bool {
         self.remove_subdomains(domain, subdomain).await
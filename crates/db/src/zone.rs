@@ -0,0 +1,467 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+use shared::common::Result;
+use xdns_data::models::subdomain::{RData, Type};
+use xdns_data::models::SubDomain;
+use xdns_data::traits::Parser;
+
+use crate::history::HistoryEntry;
+use crate::traits::Repository;
+
+/// The order record types are written in within an owner.
+const TYPE_ORDER: [Type; 5] = [Type::NS, Type::MX, Type::A, Type::AAAA, Type::CNAME];
+
+/// Writes the records of a domain as a zone file in RFC 1035 master format.
+///
+/// The zone has a synthesized `SOA` record, its serial is the position of the last recorded action
+/// of the domain in the history, so it increases with every committed change. (See [`history_after`](Repository::history_after).)
+///
+/// # Example
+///
+/// ```no_run
+/// # use db::{Repository, XDNSRepository, ZoneExporter};
+/// # async fn export(db: Repository) -> shared::common::Result<String> {
+/// ZoneExporter::new()
+///     .with_primary("ns1.example.o")
+///     .with_hostmaster("admin.example.o")
+///     .export(&db, "example.o")
+///     .await
+/// # }
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ZoneExporter {
+    /// The primary name server of the `SOA`, defaults to the first `NS` of the apex or the domain itself.
+    pub primary: Option<String>,
+    /// The mailbox of the person responsible for the zone, defaults to `hostmaster.<domain>`.
+    pub hostmaster: Option<String>,
+    /// Seconds after which secondary servers check for a new serial.
+    pub refresh: u32,
+    /// Seconds after which secondary servers retry a failed refresh.
+    pub retry: u32,
+    /// Seconds after which secondary servers stop answering when they can not refresh.
+    pub expire: u32,
+    /// Seconds negative answers are cached, also the ttl of the `SOA` itself.
+    pub minimum: u32,
+}
+
+impl Default for ZoneExporter {
+    fn default() -> Self {
+        Self {
+            primary: None,
+            hostmaster: None,
+            refresh: 3600,
+            retry: 600,
+            expire: 604800,
+            minimum: 60,
+        }
+    }
+}
+
+/// The start of authority of an exported zone.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Soa {
+    /// The absolute host of the primary name server. (eg `ns1.example.o.`)
+    pub primary: String,
+    /// The absolute mailbox of the person responsible for the zone. (eg `hostmaster.example.o.`)
+    pub hostmaster: String,
+    pub serial: u32,
+    pub refresh: u32,
+    pub retry: u32,
+    pub expire: u32,
+    pub minimum: u32,
+}
+
+/// The answer to a zone transfer request of a secondary server.
+#[derive(Debug, Clone)]
+pub enum ZoneTransfer {
+    /// Every record of the zone. (AXFR)
+    Full { soa: Soa, records: Vec<SubDomain> },
+    /// The records that changed since the serial of the secondary server. (IXFR)
+    Incremental {
+        /// The start of authority of the version the secondary server has.
+        from: Soa,
+        soa: Soa,
+        deleted: Vec<SubDomain>,
+        added: Vec<SubDomain>,
+    },
+    /// The secondary server already has the latest version.
+    UpToDate { soa: Soa },
+}
+
+/// Gets the position of the last recorded action of a domain, from its history with the positions of its actions.
+/// Every recorded action of the domain increases it, rollbacks included.
+fn last_position(history: &[(u64, HistoryEntry)]) -> u64 {
+    history.last().map_or(1, |(position, _)| *position)
+}
+
+/// Gets the serial of a zone from the position of the last recorded action of its domain.
+/// Serials are 32 bits, beyond that they wrap around. (See RFC 1982.)
+fn serial(position: u64) -> u32 {
+    position as u32
+}
+
+/// Rebuilds the records of a domain by replaying its history.
+///
+/// # Arguments
+///
+/// * `history` - The recorded actions of the domain, oldest first.
+///
+/// # Returns
+///
+/// The records after every action, or `None` if they can not be derived from the history. (eg after a rollback or an import)
+fn replay(history: &[(u64, HistoryEntry)]) -> Option<Vec<(Option<String>, SubDomain)>> {
+    let mut records: Vec<(Option<String>, SubDomain)> = Vec::new();
+
+    for (_, entry) in history {
+        let payload: Value = serde_json::from_str(&entry.payload).ok()?;
+        let field = |name: &str| payload[name].as_str().map(str::to_string);
+
+        match entry.action.as_str() {
+            "add_subdomain" => {
+                let record = SubDomain::parse(&format!(
+                    "DNS {} {} {} {} {} {}",
+                    field("domain")?,
+                    field("subdomain")?,
+                    field("rtype")?,
+                    field("class")?,
+                    payload["ttl"].as_u64()?,
+                    field("rdata")?
+                ))
+                .ok()?;
+
+                records.push((entry.inscription.clone(), record));
+            }
+            "remove_subdomain" | "drop" => {
+                records.retain(|(inscription, _)| *inscription != entry.inscription)
+            }
+            "remove_subdomains" => {
+                let subdomain = field("subdomain")?;
+                records.retain(|(_, record)| record.subdomain != subdomain);
+            }
+            "expire_domain" => records.clear(),
+            "add_domain"
+            | "renew_domain"
+            | "transfer_inscription"
+            | "add_validity"
+            | "remove_validity"
+            | "remove_validity_by_inscription"
+            | "update_validity"
+            | "update_validity_by_inscription"
+            | "add_data"
+            | "remove_data"
+            | "remove_data_by_inscription" => {}
+            // Anything else can not be replayed. (eg a rollback or an imported snapshot)
+            _ => return None,
+        }
+    }
+
+    Some(records)
+}
+
+/// Gets the records of one set that are not in the other, respecting duplicates.
+fn difference(records: &[SubDomain], other: &[SubDomain]) -> Vec<SubDomain> {
+    let mut other = other.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    records
+        .iter()
+        .filter(|record| {
+            let record = record.to_string();
+
+            match other.iter().position(|other| *other == record) {
+                Some(index) => {
+                    other.swap_remove(index);
+                    false
+                }
+                None => true,
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// Makes a host absolute, for use in a zone file.
+fn absolute(host: &str) -> String {
+    if host.ends_with('.') {
+        host.to_string()
+    } else {
+        format!("{}.", host)
+    }
+}
+
+/// Gets the owner of a subdomain relative to the origin of its zone. (eg `www.` becomes `www`, `@.` becomes `@`)
+fn owner(subdomain: &SubDomain) -> &str {
+    subdomain
+        .subdomain
+        .strip_suffix('.')
+        .unwrap_or(&subdomain.subdomain)
+}
+
+impl ZoneExporter {
+    /// Creates an exporter with the default `SOA` timers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the primary name server of the `SOA`.
+    ///
+    /// # Arguments
+    ///
+    /// * `primary` - The host of the name server. (eg `ns1.example.o`)
+    pub fn with_primary(mut self, primary: impl Into<String>) -> Self {
+        self.primary = Some(primary.into());
+        self
+    }
+
+    /// Sets the mailbox of the person responsible for the zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `hostmaster` - The mailbox, in domain name form. (eg `admin.example.o` for `admin@example.o`)
+    pub fn with_hostmaster(mut self, hostmaster: impl Into<String>) -> Self {
+        self.hostmaster = Some(hostmaster.into());
+        self
+    }
+
+    /// Sets the timers of the `SOA`, in seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `refresh` - When secondary servers check for a new serial.
+    /// * `retry` - When secondary servers retry a failed refresh.
+    /// * `expire` - When secondary servers stop answering when they can not refresh.
+    /// * `minimum` - How long negative answers are cached.
+    pub fn with_timers(mut self, refresh: u32, retry: u32, expire: u32, minimum: u32) -> Self {
+        self.refresh = refresh;
+        self.retry = retry;
+        self.expire = expire;
+        self.minimum = minimum;
+        self
+    }
+
+    /// Reads a domain from a repository and writes it as a zone file.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The repository to read the domain from.
+    /// * `domain` - The domain to export. (eg `example.o`)
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String>` - The zone file, or an error if the domain does not exist or is expired.
+    pub async fn export<R: Repository>(&self, db: &R, domain: &str) -> Result<String> {
+        db.get_domain(domain).await?;
+
+        let records = db
+            .list_subdomains(domain)
+            .await?
+            .into_iter()
+            .map(|(_, subdomain)| subdomain)
+            .collect::<Vec<_>>();
+        let serial = serial(last_position(&db.domain_history_positions(domain).await?));
+
+        Ok(self.write(domain, serial, &records))
+    }
+
+    /// Reads a domain from a repository to answer a zone transfer request.
+    ///
+    /// The changes since the serial of the secondary server are derived from the history of the domain,
+    /// when that is not possible *(eg after a rollback, an import or once the serial wrapped around)*
+    /// the whole zone is transferred instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The repository to read the domain from.
+    /// * `domain` - The domain to transfer. (eg `example.o`)
+    /// * `since` - The serial the secondary server has, `None` for a full transfer.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ZoneTransfer>` - The transfer, or an error if the domain does not exist or is expired.
+    pub async fn transfer<R: Repository>(
+        &self,
+        db: &R,
+        domain: &str,
+        since: Option<u32>,
+    ) -> Result<ZoneTransfer> {
+        db.get_domain(domain).await?;
+
+        let records = db
+            .list_subdomains(domain)
+            .await?
+            .into_iter()
+            .map(|(_, subdomain)| subdomain)
+            .collect::<Vec<_>>();
+        let history = db.domain_history_positions(domain).await?;
+        let position = last_position(&history);
+        let soa = self.soa(domain, serial(position), &records);
+
+        let since = match since {
+            // Once the serial wrapped around, it no longer tells which actions the secondary server has.
+            Some(_) if u32::try_from(position).is_err() => {
+                return Ok(ZoneTransfer::Full { soa, records })
+            }
+            Some(since) if since >= soa.serial => return Ok(ZoneTransfer::UpToDate { soa }),
+            Some(since) => since,
+            None => return Ok(ZoneTransfer::Full { soa, records }),
+        };
+        // The actions that were recorded at or before the serial of the secondary server.
+        let seen = history.partition_point(|(position, _)| *position <= since as u64);
+
+        let replayed = |until: usize| {
+            replay(&history[..until]).map(|records| {
+                records
+                    .into_iter()
+                    .map(|(_, record)| record)
+                    .collect::<Vec<_>>()
+            })
+        };
+        // Changes that were not recorded (eg rows imported from a snapshot) would be missed,
+        // so the history has to lead to the current records.
+        let complete = replayed(history.len()).is_some_and(|replayed| {
+            difference(&replayed, &records).is_empty() && difference(&records, &replayed).is_empty()
+        });
+
+        match replayed(seen) {
+            Some(old) if complete => Ok(ZoneTransfer::Incremental {
+                from: Soa {
+                    serial: since,
+                    ..soa.clone()
+                },
+                deleted: difference(&old, &records),
+                added: difference(&records, &old),
+                soa,
+            }),
+            _ => Ok(ZoneTransfer::Full { soa, records }),
+        }
+    }
+
+    /// Reads the start of authority of a zone from a repository, eg to answer a `SOA` query.
+    /// Unlike a [`transfer`](Self::transfer), only the apex records and the last position in the history of the domain are read.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The repository to read the domain from.
+    /// * `domain` - The domain of the zone. (eg `example.o`)
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Soa>` - The start of authority, or an error if the domain does not exist or is expired.
+    pub async fn soa_of<R: Repository>(&self, db: &R, domain: &str) -> Result<Soa> {
+        db.get_domain(domain).await?;
+
+        let apex = db
+            .get_subdomain(domain, "@.")
+            .await?
+            .into_iter()
+            .map(|(_, subdomain)| subdomain)
+            .collect::<Vec<_>>();
+        let position = db.domain_history_position(domain).await?.unwrap_or(1);
+
+        Ok(self.soa(domain, serial(position), &apex))
+    }
+
+    /// Synthesizes the start of authority of a zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the zone.
+    /// * `serial` - The serial of the zone.
+    /// * `records` - The subdomain records of the domain, the primary defaults to the first `NS` of the apex.
+    ///
+    /// # Returns
+    ///
+    /// The start of authority.
+    pub fn soa(&self, domain: &str, serial: u32, records: &[SubDomain]) -> Soa {
+        let primary = self
+            .primary
+            .clone()
+            .or_else(|| {
+                records
+                    .iter()
+                    .find(|record| owner(record) == "@" && record.rtype() == Type::NS)
+                    .and_then(|record| record.rdata.host().map(str::to_string))
+            })
+            .unwrap_or_else(|| domain.to_string());
+        let hostmaster = self
+            .hostmaster
+            .clone()
+            .unwrap_or_else(|| format!("hostmaster.{}", domain));
+
+        Soa {
+            primary: absolute(&primary),
+            hostmaster: absolute(&hostmaster),
+            serial,
+            refresh: self.refresh,
+            retry: self.retry,
+            expire: self.expire,
+            minimum: self.minimum,
+        }
+    }
+
+    /// Writes records as a zone file.
+    ///
+    /// The apex is written first and the other owners in alphabetical order, the records of an owner are grouped by type.
+    /// Records of the same owner and type keep their order.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the zone, the `$ORIGIN`.
+    /// * `serial` - The serial of the `SOA`.
+    /// * `records` - The subdomain records of the domain.
+    ///
+    /// # Returns
+    ///
+    /// The zone file.
+    pub fn write(&self, domain: &str, serial: u32, records: &[SubDomain]) -> String {
+        let soa = self.soa(domain, serial, records);
+        let mut records = records.iter().collect::<Vec<_>>();
+        records.sort_by_key(|record| {
+            (
+                owner(record) != "@",
+                owner(record),
+                TYPE_ORDER.iter().position(|rtype| *rtype == record.rtype()),
+            )
+        });
+
+        let mut zone = String::new();
+        // Writing to a string can not fail.
+        let _ = writeln!(zone, "$ORIGIN {}", absolute(domain));
+        let _ = writeln!(
+            zone,
+            "@\t{}\tIN\tSOA\t{} {} (\n\t\t\t\t{} ; serial\n\t\t\t\t{} ; refresh\n\t\t\t\t{} ; retry\n\t\t\t\t{} ; expire\n\t\t\t\t{} ) ; minimum",
+            soa.minimum,
+            soa.primary,
+            soa.hostmaster,
+            soa.serial,
+            soa.refresh,
+            soa.retry,
+            soa.expire,
+            soa.minimum
+        );
+
+        let mut previous = "@";
+
+        for record in records {
+            let rdata = match &record.rdata {
+                RData::NS(host) | RData::CNAME(host) => absolute(host),
+                RData::MX { priority, host } => format!("{} {}", priority, absolute(host)),
+                rdata => rdata.to_string(),
+            };
+            let owner = owner(record);
+
+            let _ = writeln!(
+                zone,
+                "{}\t{}\t{}\t{}\t{}",
+                if previous == owner { "" } else { owner },
+                record.ttl,
+                record.class.to_string(),
+                record.rtype().to_string(),
+                rdata
+            );
+            previous = owner;
+        }
+
+        zone
+    }
+}
@@ -0,0 +1,235 @@
+use db::{Batch, Operation, XDNSRepository};
+use shared::common::{DbError, Error};
+use xdns_data::models::subdomain::{Class, RData};
+use xdns_data::models::{Algorithm, Credentials, Data, DomainDrop, SubDomain, ValidityTransfer};
+use xdns_data::parser::DomainAction;
+
+#[macro_use]
+mod common;
+
+use common::{domain, inscription, validity, ADDRESS, INSCRIPTION_ID, OTHER_ADDRESS};
+
+fn subdomain() -> SubDomain {
+    SubDomain {
+        domain: "example.o".to_string(),
+        subdomain: "test".to_string(),
+        class: Class::IN,
+        ttl: 0,
+        rdata: RData::CNAME("example.o".to_string()),
+    }
+}
+
+async fn commit<R: XDNSRepository>(db: R) {
+    let mut batch = Batch::new();
+    batch
+        .push(Operation::AddDomain {
+            address: ADDRESS.to_string(),
+            inscription: INSCRIPTION_ID.to_string(),
+            domain: domain("example.o", 0),
+        })
+        .push(Operation::AddSubdomain {
+            address: ADDRESS.to_string(),
+            inscription: inscription(1),
+            subdomain: subdomain(),
+        })
+        .push(Operation::AddValidity {
+            address: ADDRESS.to_string(),
+            inscription: inscription(2),
+            validity: validity("xiler"),
+        });
+
+    assert_eq!(batch.len(), 3);
+    assert!(db.commit(batch).await.is_ok());
+
+    assert_eq!(db.get_domain_address("example.o").await.unwrap(), ADDRESS);
+    assert_eq!(
+        db.get_subdomain("example.o", "test").await.unwrap().len(),
+        1
+    );
+    assert!(db.get_validity("example.o").await.is_ok());
+}
+
+async fn commit_empty<R: XDNSRepository>(db: R) {
+    assert!(db.commit(Batch::new()).await.is_ok());
+}
+
+async fn rejected_batch_is_rolled_back<R: XDNSRepository>(db: R) {
+    let mut batch = Batch::new();
+    batch
+        .push(Operation::AddDomain {
+            address: ADDRESS.to_string(),
+            inscription: INSCRIPTION_ID.to_string(),
+            domain: domain("example.o", 0),
+        })
+        .push(Operation::AddValidity {
+            address: ADDRESS.to_string(),
+            inscription: inscription(1),
+            validity: validity("xiler"),
+        })
+        // The domain already has a validity.
+        .push(Operation::AddValidity {
+            address: ADDRESS.to_string(),
+            inscription: inscription(2),
+            validity: validity("xiler"),
+        });
+
+    let result = db.commit(batch).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::Rejected {
+            index: 2,
+            operation: "add_validity"
+        }))
+    ));
+
+    assert!(db.get_domain("example.o").await.unwrap_err().is_not_found());
+    assert!(db
+        .get_validity_by_inscription(&inscription(1))
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+async fn rejected_batch_keeps_existing_rows<R: XDNSRepository>(db: R) {
+    common::add_domain(&db).await;
+
+    let mut batch = Batch::new();
+    batch
+        .push(Operation::RemoveDomain {
+            domain: "example.o".to_string(),
+        })
+        // There is nothing to drop.
+        .push(Operation::Drop {
+            address: ADDRESS.to_string(),
+            inscription: inscription(1),
+        });
+
+    let result = db.commit(batch).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::Rejected {
+            index: 1,
+            operation: "drop"
+        }))
+    ));
+
+    assert!(db.get_domain("example.o").await.is_ok());
+}
+
+async fn from_actions<R: XDNSRepository>(db: R) {
+    common::add_domain(&db).await;
+
+    let batch = Batch::from_actions(
+        ADDRESS,
+        &inscription(1),
+        vec![
+            DomainAction::Subdomain(subdomain()),
+            DomainAction::Data(Data {
+                domain: "example.o".to_string(),
+                data: b"hello".to_vec(),
+            }),
+        ],
+    );
+
+    assert_eq!(batch.len(), 2);
+    assert!(db.commit(batch).await.is_ok());
+    assert_eq!(
+        db.get_subdomain("example.o", "test").await.unwrap().len(),
+        1
+    );
+
+    let batch = Batch::from_actions(
+        ADDRESS,
+        &inscription(2),
+        vec![DomainAction::Drop(DomainDrop {
+            inscription: inscription(1),
+        })],
+    );
+
+    assert!(db.commit(batch).await.is_ok());
+    assert!(db
+        .get_subdomain("example.o", "test")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(db.get_data("example.o").await.unwrap().is_empty());
+}
+
+async fn drop_by_non_owner_is_rejected<R: XDNSRepository>(db: R) {
+    common::add_domain(&db).await;
+
+    let batch = Batch::from_actions(
+        ADDRESS,
+        &inscription(1),
+        vec![DomainAction::Subdomain(subdomain())],
+    );
+    assert!(db.commit(batch).await.is_ok());
+
+    let batch = Batch::from_actions(
+        OTHER_ADDRESS,
+        &inscription(2),
+        vec![DomainAction::Drop(DomainDrop {
+            inscription: inscription(1),
+        })],
+    );
+
+    let result = db.commit(batch).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::Rejected {
+            index: 0,
+            operation: "drop"
+        }))
+    ));
+
+    assert_eq!(
+        db.get_subdomain("example.o", "test").await.unwrap().len(),
+        1
+    );
+}
+
+async fn validity_transfer_by_non_owner_is_rejected<R: XDNSRepository>(db: R) {
+    common::add_domain(&db).await;
+    assert!(
+        db.add_validity(ADDRESS, &inscription(1), validity("xiler"))
+            .await
+    );
+
+    let transfer = |address: &str| {
+        Batch::from_actions(
+            address,
+            &inscription(2),
+            vec![DomainAction::ValidityTransfer(ValidityTransfer {
+                domain: "example.o".to_string(),
+                new_credentials: Some(Credentials::new(Algorithm::Ed25519, "other".to_string())),
+            })],
+        )
+    };
+
+    let result = db.commit(transfer(OTHER_ADDRESS)).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::Rejected {
+            index: 0,
+            operation: "update_validity"
+        }))
+    ));
+
+    let (_, current) = db.get_validity("example.o").await.unwrap();
+    assert_eq!(current.credentials.public_key, "xiler");
+
+    assert!(db.commit(transfer(ADDRESS)).await.is_ok());
+
+    let (_, current) = db.get_validity("example.o").await.unwrap();
+    assert_eq!(current.credentials.public_key, "other");
+}
+
+conformance_tests!(
+    commit,
+    commit_empty,
+    rejected_batch_is_rolled_back,
+    rejected_batch_keeps_existing_rows,
+    from_actions,
+    drop_by_non_owner_is_rejected,
+    validity_transfer_by_non_owner_is_rejected,
+);
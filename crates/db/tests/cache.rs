@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use db::{Batch, CachedRepository, Operation, XDNSRepository};
+use xdns_data::models::subdomain::{Class, RData};
+use xdns_data::models::{Algorithm, Credentials, SubDomain, Validity};
+
+#[macro_use]
+mod common;
+
+use common::{domain, inscription, ADDRESS, LIFETIME};
+
+fn www(ip: &str) -> SubDomain {
+    SubDomain {
+        domain: "example.o".to_string(),
+        subdomain: "www.".to_string(),
+        class: Class::IN,
+        ttl: 30,
+        rdata: RData::A(ip.parse().unwrap()),
+    }
+}
+
+/// Adds example.o with a www record directly to the wrapped repository.
+async fn fill<R: XDNSRepository>(db: &CachedRepository<R>) {
+    assert!(
+        db.inner()
+            .add_domain(ADDRESS, &inscription(0), domain("example.o", 0))
+            .await
+    );
+    assert!(
+        db.inner()
+            .add_subdomain(ADDRESS, &inscription(1), www("127.0.0.1"))
+            .await
+    );
+}
+
+async fn www_records<R: XDNSRepository>(db: &CachedRepository<R>) -> Vec<String> {
+    db.get_subdomain("example.o", "www.")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(_, record)| record.rdata.to_string())
+        .collect()
+}
+
+async fn reads_through<R: XDNSRepository>(db: R) {
+    let db = CachedRepository::wrap(db);
+    fill(&db).await;
+
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1"]);
+    assert_eq!(db.get_domain("example.o").await.unwrap().0, ADDRESS);
+    assert_eq!(
+        db.get_subdomain_names("example.o").await.unwrap(),
+        vec!["www."]
+    );
+    assert_eq!(db.len(), 1);
+
+    // Changes that bypass the cache are not noticed until the name is invalidated.
+    assert!(db.inner().remove_subdomain(&inscription(1)).await);
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1"]);
+    assert_eq!(
+        db.get_subdomain_names("example.o").await.unwrap(),
+        vec!["www."]
+    );
+
+    db.invalidate("example.o");
+    assert!(www_records(&db).await.is_empty());
+}
+
+async fn writes_through<R: XDNSRepository>(db: R) {
+    let db = CachedRepository::wrap(db);
+    fill(&db).await;
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1"]);
+
+    assert!(
+        db.add_subdomain(ADDRESS, &inscription(2), www("10.0.0.1"))
+            .await
+    );
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1", "10.0.0.1"]);
+
+    assert!(db.remove_subdomain(&inscription(1)).await);
+    assert_eq!(www_records(&db).await, vec!["10.0.0.1"]);
+
+    assert!(db.remove_subdomains("example.o", "www.").await);
+    assert!(www_records(&db).await.is_empty());
+
+    let validity = Validity {
+        domain: "example.o".to_string(),
+        credentials: Credentials::new(Algorithm::Ed25519, "xiler".to_string()),
+    };
+    assert!(db.get_validity("example.o").await.is_err());
+    assert!(db.add_validity(ADDRESS, &inscription(3), validity).await);
+    assert_eq!(
+        db.get_validity("example.o")
+            .await
+            .unwrap()
+            .1
+            .credentials
+            .public_key,
+        "xiler"
+    );
+
+    assert!(db.remove_domain("example.o").await);
+    assert!(db.get_domain("example.o").await.is_err());
+}
+
+async fn commits_invalidate<R: XDNSRepository>(db: R) {
+    let db = CachedRepository::wrap(db);
+    fill(&db).await;
+    assert!(db.get_domain("example.o").await.is_ok());
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1"]);
+
+    let mut batch = Batch::new();
+    batch.push(Operation::AddSubdomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(2),
+        subdomain: www("10.0.0.1"),
+    });
+    db.commit(batch).await.unwrap();
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1", "10.0.0.1"]);
+
+    let mut batch = Batch::new();
+    batch.push(Operation::RemoveDomainByInscription {
+        inscription: inscription(0),
+    });
+    db.commit(batch).await.unwrap();
+    assert!(db.get_domain("example.o").await.is_err());
+}
+
+async fn errors_are_not_cached<R: XDNSRepository>(db: R) {
+    let db = CachedRepository::wrap(db);
+
+    assert!(db.get_domain("example.o").await.is_err());
+    assert!(db.is_empty());
+
+    fill(&db).await;
+    assert_eq!(db.get_domain("example.o").await.unwrap().0, ADDRESS);
+}
+
+async fn expires<R: XDNSRepository>(db: R) {
+    let db = CachedRepository::wrap(db).with_ttl(Duration::ZERO);
+    fill(&db).await;
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1"]);
+
+    assert!(db.inner().remove_subdomain(&inscription(1)).await);
+    assert!(www_records(&db).await.is_empty());
+}
+
+async fn bounded<R: XDNSRepository>(db: R) {
+    let db = CachedRepository::wrap(db).with_capacity(2);
+
+    for (i, name) in ["a.o", "b.o", "c.o"].into_iter().enumerate() {
+        assert!(
+            db.add_domain(ADDRESS, &inscription(i as u32), domain(name, 0))
+                .await
+        );
+        assert!(db.get_domain(name).await.is_ok());
+        assert!(db.len() <= 2);
+    }
+}
+
+async fn expires_with_domain<R: XDNSRepository>(db: R) {
+    let db = CachedRepository::wrap(db);
+
+    // Expires a second from now, long before the ttl.
+    assert!(
+        db.inner()
+            .add_domain(ADDRESS, &inscription(0), domain("example.o", LIFETIME - 1))
+            .await
+    );
+    assert!(
+        db.inner()
+            .add_subdomain(ADDRESS, &inscription(1), www("127.0.0.1"))
+            .await
+    );
+    assert_eq!(www_records(&db).await, vec!["127.0.0.1"]);
+
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+    assert!(db.get_domain("example.o").await.is_err());
+    assert!(db.get_subdomain("example.o", "www.").await.is_err());
+}
+
+conformance_tests!(
+    reads_through,
+    writes_through,
+    commits_invalidate,
+    errors_are_not_cached,
+    expires,
+    bounded,
+    expires_with_domain,
+);
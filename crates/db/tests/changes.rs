@@ -0,0 +1,277 @@
+use db::{
+    Change, ChangeFeed, ChangeKind, ChangeReceiver, DatabaseConfig, HistoryFollower,
+    MemoryRepository, Operation, SqliteRepository, XDNSRepository,
+};
+use tokio::sync::broadcast::error::TryRecvError;
+
+#[macro_use]
+mod common;
+
+use common::{add_validity, add_www, batch, block, register, DAY};
+
+/// Older than the default lifetime and grace period together.
+const RELEASED: i64 = 400 * DAY;
+
+fn change(domain: &str, subdomain: Option<&str>, kind: ChangeKind) -> Change {
+    Change {
+        domain: domain.to_string(),
+        subdomain: subdomain.map(str::to_string),
+        kind,
+    }
+}
+
+/// Receives everything that was published so far.
+fn received(changes: &mut ChangeReceiver) -> Vec<Change> {
+    let mut received = Vec::new();
+
+    loop {
+        match changes.try_recv() {
+            Ok(change) => received.push(change),
+            Err(TryRecvError::Empty) => return received,
+            Err(e) => panic!("unexpected receive error: {:?}", e),
+        }
+    }
+}
+
+async fn commit_publishes<R: XDNSRepository>(db: R) {
+    let mut changes = db.changes().subscribe();
+
+    db.commit(batch(vec![
+        register(0, "example.o", 0),
+        add_www(1, "example.o"),
+        add_validity(2),
+    ]))
+    .await
+    .unwrap();
+
+    assert_eq!(
+        received(&mut changes),
+        vec![
+            change("example.o", None, ChangeKind::Added),
+            change("example.o", Some("www."), ChangeKind::Added),
+            change("example.o", None, ChangeKind::Updated),
+        ]
+    );
+
+    db.commit(batch(vec![Operation::RemoveSubdomains {
+        domain: "example.o".to_string(),
+        subdomain: "www.".to_string(),
+    }]))
+    .await
+    .unwrap();
+
+    assert_eq!(
+        received(&mut changes),
+        vec![change("example.o", Some("www."), ChangeKind::Removed)]
+    );
+}
+
+async fn rejected_batch_publishes_nothing<R: XDNSRepository>(db: R) {
+    let mut changes = db.changes().subscribe();
+
+    // The second validity is rejected, so the domain is never committed.
+    let result = db
+        .commit(batch(vec![
+            register(0, "example.o", 0),
+            add_validity(1),
+            add_validity(2),
+        ]))
+        .await;
+
+    assert!(result.is_err());
+    assert!(received(&mut changes).is_empty());
+}
+
+async fn standalone_methods_publish<R: XDNSRepository>(db: R) {
+    let mut changes = db.changes().subscribe();
+
+    common::add_domain(&db).await;
+    assert!(db.remove_domain("example.o").await);
+
+    assert_eq!(
+        received(&mut changes),
+        vec![
+            change("example.o", None, ChangeKind::Added),
+            change("example.o", None, ChangeKind::Removed),
+        ]
+    );
+}
+
+async fn rollback_publishes<R: XDNSRepository>(db: R) {
+    db.commit(
+        batch(vec![register(0, "example.o", 0), add_www(1, "example.o")]).with_block(block(5)),
+    )
+    .await
+    .unwrap();
+
+    let mut changes = db.changes().subscribe();
+
+    assert_eq!(db.rollback_to(4).await.unwrap(), 1);
+    assert_eq!(
+        received(&mut changes),
+        vec![change("example.o", None, ChangeKind::RolledBack)]
+    );
+}
+
+async fn sweep_publishes<R: XDNSRepository>(db: R) {
+    db.commit(batch(vec![
+        register(0, "example.o", RELEASED),
+        register(1, "active.o", 0),
+    ]))
+    .await
+    .unwrap();
+
+    let mut changes = db.changes().subscribe();
+
+    assert_eq!(db.sweep_expired().await.unwrap(), vec!["example.o"]);
+    assert_eq!(
+        received(&mut changes),
+        vec![change("example.o", None, ChangeKind::Removed)]
+    );
+
+    // Nothing is left to sweep, so nothing is published.
+    assert!(db.sweep_expired().await.unwrap().is_empty());
+    assert!(received(&mut changes).is_empty());
+}
+
+async fn follower_publishes_recorded_changes<R: XDNSRepository>(db: R) {
+    db.commit(batch(vec![register(0, "old.o", 0)]))
+        .await
+        .unwrap();
+
+    let feed = ChangeFeed::new();
+    let mut followed = feed.subscribe();
+    let mut follower = HistoryFollower::new(&db, feed).await.unwrap();
+    let mut changes = db.changes().subscribe();
+
+    // Only what is recorded after the follower started is published.
+    assert_eq!(follower.poll(&db).await.unwrap(), 0);
+
+    db.commit(
+        batch(vec![
+            register(1, "example.o", 0),
+            add_www(2, "example.o"),
+            add_validity(3),
+        ])
+        .with_block(block(5)),
+    )
+    .await
+    .unwrap();
+    // Journaled at the tip, so both are undone.
+    assert!(db.remove_subdomains("example.o", "www.").await);
+    assert_eq!(db.rollback_to(4).await.unwrap(), 2);
+
+    assert_eq!(follower.poll(&db).await.unwrap(), 6);
+    assert_eq!(received(&mut followed), received(&mut changes));
+
+    assert_eq!(follower.poll(&db).await.unwrap(), 0);
+    assert!(received(&mut followed).is_empty());
+}
+
+conformance_tests!(
+    commit_publishes,
+    rejected_batch_publishes_nothing,
+    standalone_methods_publish,
+    rollback_publishes,
+    sweep_publishes,
+    follower_publishes_recorded_changes,
+);
+
+#[tokio::test]
+async fn shared_feed() {
+    let feed = ChangeFeed::new();
+    let mut changes = feed.subscribe();
+
+    // Eg a repository per request, publishing to a feed that outlives them.
+    for (i, name) in ["first.o", "second.o"].into_iter().enumerate() {
+        let db = MemoryRepository::default().with_changes(feed.clone());
+
+        db.commit(batch(vec![register(i as u32, name, 0)]))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        received(&mut changes),
+        vec![
+            change("first.o", None, ChangeKind::Added),
+            change("second.o", None, ChangeKind::Added),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn follower_notices_other_connections() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}?mode=rwc", dir.path().join("xdns.db").display());
+    let config = DatabaseConfig::new(url).with_migrate(true);
+
+    // Eg the indexer, writing to the database the server follows.
+    let indexer = SqliteRepository::connect(&config).await.unwrap();
+    let server = SqliteRepository::connect(&config).await.unwrap();
+
+    let feed = ChangeFeed::new();
+    let mut changes = feed.subscribe();
+    let mut follower = HistoryFollower::new(&server, feed).await.unwrap();
+
+    indexer
+        .commit(batch(vec![
+            register(0, "example.o", 0),
+            add_www(1, "example.o"),
+        ]))
+        .await
+        .unwrap();
+
+    assert_eq!(follower.poll(&server).await.unwrap(), 2);
+    assert_eq!(
+        received(&mut changes),
+        vec![
+            change("example.o", None, ChangeKind::Added),
+            change("example.o", Some("www."), ChangeKind::Added),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn follower_sees_interleaved_transactions() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}?mode=rwc", dir.path().join("xdns.db").display());
+    let config = DatabaseConfig::new(url).with_migrate(true);
+
+    // Eg two indexers, committing at the same time to the database the server follows.
+    let first = SqliteRepository::connect(&config).await.unwrap();
+    let second = SqliteRepository::connect(&config).await.unwrap();
+    let server = SqliteRepository::connect(&config).await.unwrap();
+
+    let feed = ChangeFeed::new();
+    let mut changes = feed.subscribe();
+    let mut follower = HistoryFollower::new(&server, feed).await.unwrap();
+
+    let write = |db: SqliteRepository, first: u32| async move {
+        for i in first..first + 10 {
+            let name = format!("name{}.o", i);
+            db.commit(batch(vec![register(i, &name, 0)])).await.unwrap();
+        }
+    };
+    let done = std::cell::Cell::new(false);
+    let writers = async {
+        tokio::join!(write(first, 0), write(second, 100));
+        done.set(true);
+    };
+    let reader = async {
+        while !done.get() {
+            follower.poll(&server).await.unwrap();
+            tokio::task::yield_now().await;
+        }
+    };
+    tokio::join!(writers, reader);
+    follower.poll(&server).await.unwrap();
+
+    let mut names: Vec<String> = received(&mut changes)
+        .into_iter()
+        .map(|change| change.domain)
+        .collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), 20);
+}
@@ -0,0 +1,395 @@
+use db::{
+    Block, DatabaseConfig, MemoryRepository, NameProof, Operation, SqliteRepository, StateRoot,
+    XDNSRepository,
+};
+use shared::time::system_time_from_epoch_seconds;
+use xdns_data::models::{Algorithm, Credentials, Data, Domain, Validity, ValidityTransfer};
+
+#[macro_use]
+mod common;
+
+use common::{add_www, batch, inscription, register_at, www, ADDRESS, DAY, OTHER_ADDRESS};
+
+/// A fixed registration time, so the rows of different repositories are equal.
+fn registered() -> u64 {
+    (chrono::Utc::now().timestamp() / DAY * DAY) as u64
+}
+
+fn domain(name: &str, valid_from: u64) -> Domain {
+    Domain {
+        name: name.to_string(),
+        valid_from: system_time_from_epoch_seconds(valid_from),
+    }
+}
+
+/// Builds the root from scratch, from a copy of the rows of a repository.
+async fn fresh_root<R: XDNSRepository>(db: &R) -> StateRoot {
+    let copy = MemoryRepository::default();
+    let snapshot = db.export_snapshot().await.unwrap();
+    let hash = snapshot.hash().to_string();
+    copy.import_snapshot(snapshot, &hash).await.unwrap();
+
+    copy.state_root().await.unwrap()
+}
+
+async fn assert_current<R: XDNSRepository>(db: &R) {
+    assert_eq!(db.state_root().await.unwrap(), fresh_root(db).await);
+}
+
+async fn empty<R: XDNSRepository>(db: R) {
+    let root = db.state_root().await.unwrap();
+    assert_eq!(root, StateRoot([0; 32]));
+
+    let proof = db.prove("example.o").await.unwrap();
+    assert!(!proof.exists());
+    assert!(proof.verify(&root));
+    assert!(proof.records().is_err());
+}
+
+async fn order_independent<R: XDNSRepository>(db: R) {
+    db.commit(batch(vec![
+        register_at(0, "a.o", registered()),
+        register_at(1, "b.o", registered()),
+        add_www(2, "b.o"),
+        register_at(3, "c.o", registered()),
+    ]))
+    .await
+    .unwrap();
+
+    let other = MemoryRepository::default();
+    other
+        .commit(batch(vec![
+            register_at(3, "c.o", registered()),
+            register_at(1, "b.o", registered()),
+        ]))
+        .await
+        .unwrap();
+    other
+        .commit(batch(vec![
+            add_www(2, "b.o"),
+            register_at(0, "a.o", registered()),
+        ]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        db.state_root().await.unwrap(),
+        other.state_root().await.unwrap()
+    );
+    assert_eq!(
+        db.state_root().await.unwrap().to_string(),
+        other.state_root().await.unwrap().to_string()
+    );
+}
+
+async fn changes_with_rows<R: XDNSRepository>(db: R) {
+    let empty = db.state_root().await.unwrap();
+
+    db.commit(batch(vec![register_at(0, "example.o", registered())]))
+        .await
+        .unwrap();
+    let added = db.state_root().await.unwrap();
+    assert_ne!(added, empty);
+
+    db.commit(batch(vec![add_www(1, "example.o")]))
+        .await
+        .unwrap();
+    assert_ne!(db.state_root().await.unwrap(), added);
+    assert_current(&db).await;
+
+    db.commit(batch(vec![Operation::RemoveSubdomain {
+        inscription: inscription(1),
+    }]))
+    .await
+    .unwrap();
+    assert_eq!(db.state_root().await.unwrap(), added);
+
+    assert!(db.remove_domain("example.o").await);
+    assert_eq!(db.state_root().await.unwrap(), empty);
+}
+
+async fn proves_inclusion<R: XDNSRepository>(db: R) {
+    db.commit(batch(vec![
+        register_at(0, "example.o", registered()),
+        add_www(1, "example.o"),
+        register_at(2, "other.o", registered()),
+    ]))
+    .await
+    .unwrap();
+
+    let root = db.state_root().await.unwrap();
+    let proof = db.prove("example.o").await.unwrap();
+
+    assert!(proof.exists());
+    assert!(proof.verify(&root));
+    assert_eq!(proof.name(), "example.o");
+    assert_eq!(proof.domain().unwrap().0, ADDRESS);
+    assert_eq!(proof.records().unwrap().len(), 1);
+    assert_eq!(proof.records().unwrap()[0].1.subdomain, "www.");
+
+    // Proofs survive being sent to a light client.
+    let decoded: NameProof = serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+    assert!(decoded.verify(&root));
+    assert!(!proof.verify(&StateRoot([1; 32])));
+
+    db.commit(batch(vec![register_at(3, "new.o", registered())]))
+        .await
+        .unwrap();
+    assert!(!proof.verify(&db.state_root().await.unwrap()));
+}
+
+async fn proves_non_inclusion<R: XDNSRepository>(db: R) {
+    for i in 0..16 {
+        db.commit(batch(vec![register_at(
+            i,
+            &format!("name{}.o", i),
+            registered(),
+        )]))
+        .await
+        .unwrap();
+    }
+
+    let root = db.state_root().await.unwrap();
+
+    for i in 16..32 {
+        let proof = db.prove(&format!("name{}.o", i)).await.unwrap();
+
+        assert!(!proof.exists());
+        assert!(proof.verify(&root));
+        assert!(proof.domain().is_none());
+    }
+}
+
+async fn rejects_tampered_proofs<R: XDNSRepository>(db: R) {
+    db.commit(batch(vec![
+        register_at(0, "example.o", registered()),
+        add_www(1, "example.o"),
+        register_at(2, "other.o", registered()),
+        register_at(3, "third.o", registered()),
+    ]))
+    .await
+    .unwrap();
+
+    let root = db.state_root().await.unwrap();
+    let proof = serde_json::to_value(db.prove("example.o").await.unwrap()).unwrap();
+    let verifies = |proof: serde_json::Value| {
+        serde_json::from_value::<NameProof>(proof)
+            .unwrap()
+            .verify(&root)
+    };
+
+    assert!(verifies(proof.clone()));
+
+    // Another owner.
+    let mut tampered = proof.clone();
+    tampered["snapshot"]["domains"][0]["address"] = OTHER_ADDRESS.into();
+    assert!(!verifies(tampered));
+
+    // Another record.
+    let mut tampered = proof.clone();
+    tampered["snapshot"]["subdomains"][0]["rdata"] = "10.0.0.1".into();
+    assert!(!verifies(tampered));
+
+    // Leaving the records out.
+    let mut tampered = proof.clone();
+    tampered["snapshot"] = serde_json::Value::Null;
+    assert!(!verifies(tampered));
+
+    // Claiming a registered name is absent, with the proof of an absent name.
+    let mut tampered = serde_json::to_value(db.prove("missing.o").await.unwrap()).unwrap();
+    assert!(verifies(tampered.clone()));
+    tampered["name"] = "example.o".into();
+    assert!(!verifies(tampered));
+}
+
+async fn standalone_mutations<R: XDNSRepository>(db: R) {
+    // The tree is built before anything is added, so every change has to be applied to it.
+    db.state_root().await.unwrap();
+
+    assert!(
+        db.add_domain(ADDRESS, &inscription(0), domain("example.o", registered()))
+            .await
+    );
+    assert_current(&db).await;
+
+    assert!(
+        db.add_subdomain(ADDRESS, &inscription(1), www("example.o"))
+            .await
+    );
+    assert_current(&db).await;
+
+    let validity = Validity {
+        domain: "example.o".to_string(),
+        credentials: Credentials::new(Algorithm::Ed25519, "xiler".to_string()),
+    };
+    assert!(db.add_validity(ADDRESS, &inscription(2), validity).await);
+    assert_current(&db).await;
+
+    let data = Data {
+        domain: "example.o".to_string(),
+        data: b"custom data".to_vec(),
+    };
+    assert!(db.add_data(ADDRESS, &inscription(3), data).await);
+    assert_current(&db).await;
+
+    let transfer = ValidityTransfer {
+        domain: "example.o".to_string(),
+        new_credentials: Some(Credentials::new(Algorithm::Ed25519, "hello".to_string())),
+    };
+    assert!(
+        db.update_validity_by_inscription(ADDRESS, &inscription(2), transfer)
+            .await
+    );
+    assert_current(&db).await;
+
+    assert!(db.remove_subdomain(&inscription(1)).await);
+    assert_current(&db).await;
+
+    assert!(db.remove_data_by_inscription(&inscription(3)).await);
+    assert_current(&db).await;
+
+    assert!(db
+        .transfer_inscription(&inscription(0), OTHER_ADDRESS)
+        .await
+        .unwrap());
+    assert_current(&db).await;
+
+    let proof = db.prove("example.o").await.unwrap();
+    assert!(proof.verify(&db.state_root().await.unwrap()));
+    assert_eq!(proof.domain().unwrap().0, OTHER_ADDRESS);
+}
+
+async fn rollback_and_sweep<R: XDNSRepository>(db: R) {
+    db.commit(
+        batch(vec![register_at(0, "example.o", registered())]).with_block(Block::new(1, "01")),
+    )
+    .await
+    .unwrap();
+    let first = db.state_root().await.unwrap();
+
+    db.commit(
+        batch(vec![
+            add_www(1, "example.o"),
+            register_at(2, "other.o", registered()),
+        ])
+        .with_block(Block::new(2, "02")),
+    )
+    .await
+    .unwrap();
+    assert_ne!(db.state_root().await.unwrap(), first);
+
+    assert_eq!(db.rollback_to(1).await.unwrap(), 1);
+    assert_eq!(db.state_root().await.unwrap(), first);
+
+    // Older than the default lifetime and grace period together.
+    let released = Operation::AddDomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(3),
+        domain: domain("released.o", registered() - 400 * DAY as u64),
+    };
+    db.commit(batch(vec![released])).await.unwrap();
+    assert_ne!(db.state_root().await.unwrap(), first);
+
+    assert_eq!(db.sweep_expired().await.unwrap(), vec!["released.o"]);
+    assert_eq!(db.state_root().await.unwrap(), first);
+    assert_current(&db).await;
+}
+
+conformance_tests!(
+    empty,
+    order_independent,
+    changes_with_rows,
+    proves_inclusion,
+    proves_non_inclusion,
+    rejects_tampered_proofs,
+    standalone_mutations,
+    rollback_and_sweep,
+);
+
+#[test]
+fn root_from_hex() {
+    let root = StateRoot([0xab; 32]);
+
+    assert_eq!(StateRoot::from_hex(&root.to_string()).unwrap(), root);
+    assert!(StateRoot::from_hex("abab").is_err());
+    assert!(StateRoot::from_hex("not hex").is_err());
+}
+
+#[tokio::test]
+async fn follows_other_connections() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}?mode=rwc", dir.path().join("xdns.db").display());
+    let config = DatabaseConfig::new(url).with_migrate(true);
+
+    // Eg the indexer, writing to the database the server proves from.
+    let indexer = SqliteRepository::connect(&config).await.unwrap();
+    let server = SqliteRepository::connect(&config).await.unwrap();
+
+    indexer
+        .commit(batch(vec![register_at(0, "example.o", registered())]))
+        .await
+        .unwrap();
+    let before = server.state_root().await.unwrap();
+    assert_eq!(before, fresh_root(&server).await);
+
+    indexer
+        .commit(batch(vec![
+            add_www(1, "example.o"),
+            register_at(2, "other.o", registered()),
+        ]))
+        .await
+        .unwrap();
+    assert!(indexer.remove_domain("example.o").await);
+
+    let root = server.state_root().await.unwrap();
+    assert_ne!(root, before);
+    assert_eq!(root, fresh_root(&server).await);
+
+    for name in ["example.o", "other.o"] {
+        assert!(server.prove(name).await.unwrap().verify(&root));
+    }
+    assert!(server.prove("example.o").await.unwrap().domain().is_none());
+}
+
+#[tokio::test]
+async fn follows_interleaved_transactions() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}?mode=rwc", dir.path().join("xdns.db").display());
+    let config = DatabaseConfig::new(url).with_migrate(true);
+
+    // Eg two indexers, committing at the same time to the database the server proves from.
+    let first = SqliteRepository::connect(&config).await.unwrap();
+    let second = SqliteRepository::connect(&config).await.unwrap();
+    let server = SqliteRepository::connect(&config).await.unwrap();
+    server.state_root().await.unwrap();
+
+    let write = |db: SqliteRepository, first: u32| async move {
+        for i in first..first + 10 {
+            let name = format!("name{}.o", i);
+            db.commit(batch(vec![register_at(i, &name, registered())]))
+                .await
+                .unwrap();
+        }
+    };
+    let done = std::cell::Cell::new(false);
+    let writers = async {
+        tokio::join!(write(first, 0), write(second, 100));
+        done.set(true);
+    };
+    let reader = async {
+        while !done.get() {
+            server.state_root().await.unwrap();
+            tokio::task::yield_now().await;
+        }
+    };
+    tokio::join!(writers, reader);
+
+    let root = server.state_root().await.unwrap();
+    assert_eq!(root, fresh_root(&server).await);
+
+    for i in (0..10).chain(100..110) {
+        let proof = server.prove(&format!("name{}.o", i)).await.unwrap();
+        assert!(proof.domain().is_some());
+        assert!(proof.verify(&root));
+    }
+}
@@ -0,0 +1,235 @@
+//! The fixtures shared by the repository tests, not every test uses all of them.
+#![allow(dead_code, unused_macros)]
+
+use db::{Batch, Block, Operation, XDNSRepository};
+use shared::time::system_time_from_epoch_seconds;
+use xdns_data::models::subdomain::{Class, RData};
+use xdns_data::models::{Algorithm, Credentials, Data, Domain, SubDomain, Validity};
+
+/// Generates a test for every repository backend from generic test functions.
+/// Each test function receives a fresh (and migrated) repository, so every backend
+/// is held to the exact same expectations.
+///
+/// # Example
+///
+/// ```ignore
+/// async fn add_and_get<R: XDNSRepository>(db: R) { ... }
+///
+/// conformance_tests!(add_and_get);
+/// ```
+macro_rules! conformance_tests {
+    ($($test:ident),* $(,)?) => {
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    let db = <db::SqliteRepository as db::XDNSRepository>::new_memory()
+                        .await
+                        .unwrap();
+                    super::$test(db).await;
+                }
+            )*
+        }
+
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    let db = <db::MemoryRepository as db::XDNSRepository>::new_memory()
+                        .await
+                        .unwrap();
+                    super::$test(db).await;
+                }
+            )*
+        }
+
+        mod kv {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    let db = <db::KvRepository as db::XDNSRepository>::new_memory()
+                        .await
+                        .unwrap();
+                    super::$test(db).await;
+                }
+            )*
+        }
+    };
+}
+
+pub const ADDRESS: &str = "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5";
+/// An address that owns nothing until something is transferred to it.
+pub const NEW_ADDRESS: &str = "tb1qxilerxilerxilerxilerxilerxilerxilerxilerxilerxiler";
+pub const OTHER_ADDRESS: &str = "bc1pxwn9duraglsgr9f7q8ua33sx0vkq5wjft575h662995zf5m27v2qqxlf3k";
+pub const INSCRIPTION_ID: &str =
+    "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0";
+pub const DAY: i64 = 86400;
+/// The default lifetime of a domain registration.
+pub const LIFETIME: i64 = 365 * DAY;
+/// The default grace period after the lifetime.
+pub const GRACE_PERIOD: i64 = 30 * DAY;
+
+/// The `i`th inscription id, distinct for every `i`.
+pub fn inscription(i: u32) -> String {
+    i.to_string() + INSCRIPTION_ID
+}
+
+/// A domain that was inscribed `age` seconds ago.
+pub fn domain(name: &str, age: i64) -> Domain {
+    Domain {
+        name: name.to_string(),
+        valid_from: system_time_from_epoch_seconds((chrono::Utc::now().timestamp() - age) as u64),
+    }
+}
+
+/// Registers a domain that was inscribed `age` seconds ago, in the `i`th inscription.
+pub fn register(i: u32, name: &str, age: i64) -> Operation {
+    Operation::AddDomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(i),
+        domain: domain(name, age),
+    }
+}
+
+/// Registers a domain that is valid from an epoch timestamp, in the `i`th inscription.
+pub fn register_at(i: u32, name: &str, valid_from: u64) -> Operation {
+    Operation::AddDomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(i),
+        domain: Domain {
+            name: name.to_string(),
+            valid_from: system_time_from_epoch_seconds(valid_from),
+        },
+    }
+}
+
+/// A block at a height, with a hash derived from the height.
+pub fn block(height: u64) -> Block {
+    Block::new(height, format!("{:064x}", height))
+}
+
+/// A batch without a block.
+pub fn batch(operations: Vec<Operation>) -> Batch {
+    let mut batch = Batch::new();
+
+    for operation in operations {
+        batch.push(operation);
+    }
+
+    batch
+}
+
+/// Commits operations at the block of a height.
+pub async fn commit_at<R: XDNSRepository>(db: &R, height: u64, operations: Vec<Operation>) {
+    let batch = batch(operations).with_block(block(height));
+    db.commit(batch).await.unwrap();
+}
+
+/// Adds `example.o`, inscribed by [`ADDRESS`] with [`INSCRIPTION_ID`].
+pub async fn add_domain<R: XDNSRepository>(db: &R) {
+    assert!(
+        db.add_domain(ADDRESS, INSCRIPTION_ID, domain("example.o", 0))
+            .await
+    );
+}
+
+/// Adds a record of `example.o` with a ttl of 30 seconds, in the `i`th inscription.
+pub fn add_subdomain(i: u32, subdomain: &str, rdata: RData) -> Operation {
+    Operation::AddSubdomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(i),
+        subdomain: SubDomain {
+            domain: "example.o".to_string(),
+            subdomain: subdomain.to_string(),
+            class: Class::IN,
+            ttl: 30,
+            rdata,
+        },
+    }
+}
+
+/// The mail exchanger of `example.o`, with a priority.
+pub fn mx(priority: u16) -> SubDomain {
+    match add_mx(0, priority) {
+        Operation::AddSubdomain { subdomain, .. } => subdomain,
+        _ => unreachable!(),
+    }
+}
+
+/// Adds the mail exchanger of `example.o` with a priority, in the `i`th inscription.
+pub fn add_mx(i: u32, priority: u16) -> Operation {
+    add_subdomain(
+        i,
+        "@.",
+        RData::MX {
+            priority,
+            host: "mail.example.o".to_string(),
+        },
+    )
+}
+
+/// The `www` record of a domain, pointing to localhost.
+pub fn www(name: &str) -> SubDomain {
+    SubDomain {
+        domain: name.to_string(),
+        subdomain: "www.".to_string(),
+        class: Class::IN,
+        ttl: 30,
+        rdata: RData::A("127.0.0.1".parse().unwrap()),
+    }
+}
+
+/// Adds the `www` record of a domain, in the `i`th inscription.
+pub fn add_www(i: u32, name: &str) -> Operation {
+    Operation::AddSubdomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(i),
+        subdomain: www(name),
+    }
+}
+
+/// The validity of `example.o`, with an Ed25519 public key.
+pub fn validity(public_key: &str) -> Validity {
+    Validity {
+        domain: "example.o".to_string(),
+        credentials: Credentials::new(Algorithm::Ed25519, public_key.to_string()),
+    }
+}
+
+/// Adds the validity of `example.o` with the public key `xiler`, in the `i`th inscription.
+pub fn add_validity(i: u32) -> Operation {
+    Operation::AddValidity {
+        address: ADDRESS.to_string(),
+        inscription: inscription(i),
+        validity: validity("xiler"),
+    }
+}
+
+/// Adds custom data to `example.o`, in the `i`th inscription.
+pub fn add_data(i: u32) -> Operation {
+    Operation::AddData {
+        address: ADDRESS.to_string(),
+        inscription: inscription(i),
+        data: Data {
+            domain: "example.o".to_string(),
+            data: b"custom data".to_vec(),
+        },
+    }
+}
+
+/// Commits `example.o` with a row of every kind at block 7: an MX and a www record,
+/// a validity and custom data, in the inscriptions 0 to 4.
+pub async fn fill<R: XDNSRepository>(db: &R) {
+    commit_at(
+        db,
+        7,
+        vec![
+            register(0, "example.o", 0),
+            add_mx(1, 10),
+            add_www(2, "example.o"),
+            add_validity(3),
+            add_data(4),
+        ],
+    )
+    .await;
+}
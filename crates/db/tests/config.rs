@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use db::{DatabaseBackend, DatabaseConfig, SqliteRepository, XDNSRepository};
+use shared::common::{DbError, Error};
+
+#[test]
+fn backend_from_url() {
+    let backends = [
+        ("sqlite://xdns.db?mode=rwc", DatabaseBackend::Sqlite),
+        ("sqlite::memory:", DatabaseBackend::Sqlite),
+        ("postgres://localhost/xdns", DatabaseBackend::Postgres),
+        ("postgresql://localhost/xdns", DatabaseBackend::Postgres),
+    ];
+
+    for (url, backend) in backends {
+        assert_eq!(DatabaseConfig::new(url).backend().unwrap(), backend);
+    }
+}
+
+#[test]
+fn backend_unsupported() {
+    let result = DatabaseConfig::new("mysql://localhost/xdns").backend();
+    assert!(matches!(result, Err(Error::Db(DbError::Configuration(_)))));
+}
+
+#[test]
+fn builder() {
+    let config = DatabaseConfig::new("sqlite::memory:")
+        .with_migrate(true)
+        .with_max_connections(4)
+        .with_min_connections(1)
+        .with_connect_timeout(Duration::from_secs(5))
+        .with_idle_timeout(Duration::from_secs(60))
+        .with_finality_depth(None);
+
+    assert!(config.migrate);
+    assert!(config.is_memory());
+    assert_eq!(config.max_connections, Some(4));
+    assert_eq!(config.min_connections, Some(1));
+    assert_eq!(config.connect_timeout, Some(Duration::from_secs(5)));
+    assert_eq!(config.idle_timeout, Some(Duration::from_secs(60)));
+    assert_eq!(config.finality_depth, None);
+}
+
+#[tokio::test]
+async fn connect_memory_migrated() {
+    // A pool with multiple connections would give every connection its own in-memory database.
+    let config = DatabaseConfig::memory().with_max_connections(8);
+    let db = SqliteRepository::connect(&config).await.unwrap();
+
+    let result = db.get_domain("example.o").await;
+    assert!(matches!(result, Err(Error::Db(DbError::NotFound { .. }))));
+}
+
+#[tokio::test]
+async fn connect_without_migration() {
+    let config = DatabaseConfig::new("sqlite::memory:");
+    let db = SqliteRepository::connect(&config).await.unwrap();
+
+    let result = db.get_domain("example.o").await;
+    assert!(matches!(result, Err(Error::Db(DbError::Backend(_)))));
+
+    db.migrate().await.unwrap();
+    assert!(db.get_domain("example.o").await.unwrap_err().is_not_found());
+}
+
+#[tokio::test]
+async fn connect_unsupported() {
+    let config = DatabaseConfig::new("mysql://localhost/xdns");
+    let result = SqliteRepository::connect(&config).await;
+
+    assert!(matches!(result, Err(Error::Db(DbError::Configuration(_)))));
+}
+
+#[tokio::test]
+async fn connect_failure() {
+    let config = DatabaseConfig::new("sqlite:///nonexistent/directory/xdns.db")
+        .with_connect_timeout(Duration::from_secs(1));
+    let result = SqliteRepository::connect(&config).await;
+
+    assert!(matches!(result, Err(Error::Db(DbError::Backend(_)))));
+}
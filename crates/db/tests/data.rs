@@ -1,17 +1,15 @@
 use db::XDNSRepository;
 use xdns_data::models::Data;
 
-#[path = "./domain.rs"]
-mod domain;
+#[macro_use]
+mod common;
 
-use domain::add_domain;
+use common::add_domain;
 
 const INSCRIPTION_ID: &'static str =
     "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0";
 
-async fn db_setup_data_boilerplate() -> db::Repository {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
+async fn db_setup_data_boilerplate<R: XDNSRepository>(db: R) -> R {
     add_domain(&db).await;
 
     for i in 0..10 {
@@ -33,14 +31,12 @@ async fn db_setup_data_boilerplate() -> db::Repository {
     db
 }
 
-#[tokio::test]
-async fn add_data() {
-    db_setup_data_boilerplate().await;
+async fn add_data<R: XDNSRepository>(db: R) {
+    db_setup_data_boilerplate(db).await;
 }
 
-#[tokio::test]
-async fn get_data() {
-    let db = db_setup_data_boilerplate().await;
+async fn get_data<R: XDNSRepository>(db: R) {
+    let db = db_setup_data_boilerplate(db).await;
 
     let result = db.get_data("example.o").await;
     assert!(result.is_ok());
@@ -58,9 +54,8 @@ async fn get_data() {
     }
 }
 
-#[tokio::test]
-async fn get_data_by_inscription_id() {
-    let db = db_setup_data_boilerplate().await;
+async fn get_data_by_inscription_id<R: XDNSRepository>(db: R) {
+    let db = db_setup_data_boilerplate(db).await;
 
     for i in 0..10 {
         let result = db
@@ -78,9 +73,8 @@ async fn get_data_by_inscription_id() {
     }
 }
 
-#[tokio::test]
-async fn remove_data() {
-    let db = db_setup_data_boilerplate().await;
+async fn remove_data<R: XDNSRepository>(db: R) {
+    let db = db_setup_data_boilerplate(db).await;
 
     let result = db.remove_data("example.o").await;
     assert!(result);
@@ -92,9 +86,8 @@ async fn remove_data() {
     assert_eq!(get_result.len(), 0);
 }
 
-#[tokio::test]
-async fn remove_data_by_inscription_id() {
-    let db = db_setup_data_boilerplate().await;
+async fn remove_data_by_inscription_id<R: XDNSRepository>(db: R) {
+    let db = db_setup_data_boilerplate(db).await;
 
     for i in 0..10 {
         let result = db
@@ -108,3 +101,11 @@ async fn remove_data_by_inscription_id() {
         assert!(get_result.is_err());
     }
 }
+
+conformance_tests!(
+    add_data,
+    get_data,
+    get_data_by_inscription_id,
+    remove_data,
+    remove_data_by_inscription_id,
+);
@@ -1,28 +1,14 @@
 use db::XDNSRepository;
+use shared::common::{DbError, Error};
 use shared::time::system_time_from_epoch_seconds;
-use xdns_data::models::Domain;
+use xdns_data::models::{Algorithm, Credentials, Domain, Validity};
 
-pub async fn add_domain(db: &db::Repository) {
-    let domain = Domain {
-        name: "example.o".to_string(),
-        valid_from: system_time_from_epoch_seconds(chrono::Utc::now().timestamp() as u64),
-    };
-
-    let result = db
-        .add_domain(
-            "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5",
-            "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0",
-            domain,
-        )
-        .await;
+#[macro_use]
+mod common;
 
-    assert!(result);
-}
+use common::add_domain;
 
-#[tokio::test]
-async fn add_and_get() {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
+async fn add_and_get<R: XDNSRepository>(db: R) {
     add_domain(&db).await;
 
     let result = db.get_domain("example.o").await;
@@ -31,10 +17,7 @@ async fn add_and_get() {
     assert_eq!(result.name, "example.o");
 }
 
-#[tokio::test]
-async fn test_add_after_existing() {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
+async fn test_add_after_existing<R: XDNSRepository>(db: R) {
     add_domain(&db).await;
 
     let domain = Domain {
@@ -53,12 +36,9 @@ async fn test_add_after_existing() {
     assert!(!result);
 }
 
-#[tokio::test]
-async fn get_by_inscription_id() {
+async fn get_by_inscription_id<R: XDNSRepository>(db: R) {
     let inscription_id = "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0";
     let valid_from = system_time_from_epoch_seconds(chrono::Utc::now().timestamp() as u64);
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
 
     let domain = Domain {
         name: "example.o".to_string(),
@@ -89,20 +69,36 @@ async fn get_by_inscription_id() {
     );
 }
 
-#[tokio::test]
-async fn get_non_existent() {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
-
+async fn get_non_existent<R: XDNSRepository>(db: R) {
     let result = db.get_domain("example.o").await;
     assert!(result.is_err());
+    assert!(matches!(
+        result.unwrap_err(),
+        Error::Db(DbError::NotFound {
+            entity: "domain",
+            ..
+        })
+    ));
 }
 
-#[tokio::test]
-async fn remove() {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
+async fn get_expired<R: XDNSRepository>(db: R) {
+    let domain = Domain {
+        name: "example.o".to_string(),
+        valid_from: system_time_from_epoch_seconds(1600000000),
+    };
+
+    db.add_domain(
+        "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5",
+        "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0",
+        domain,
+    )
+    .await;
 
+    let result = db.get_domain("example.o").await;
+    assert!(matches!(result, Err(Error::Db(DbError::Expired { .. }))));
+}
+
+async fn remove<R: XDNSRepository>(db: R) {
     let domain = Domain {
         name: "example.o".to_string(),
         valid_from: system_time_from_epoch_seconds(chrono::Utc::now().timestamp() as u64),
@@ -123,11 +119,8 @@ async fn remove() {
     assert!(result);
 }
 
-#[tokio::test]
-async fn remove_by_inscription() {
+async fn remove_by_inscription<R: XDNSRepository>(db: R) {
     let inscription_id = "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0";
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
 
     let domain = Domain {
         name: "example.o".to_string(),
@@ -147,12 +140,46 @@ async fn remove_by_inscription() {
     assert!(result);
 }
 
-#[tokio::test]
-async fn remove_non_existent() {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
-
+async fn remove_non_existent<R: XDNSRepository>(db: R) {
     let result = db.remove_domain("example.o").await;
 
     assert!(!result);
 }
+
+async fn transfer<R: XDNSRepository>(db: R) {
+    let inscription_id = "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0";
+    let new_address = "bc1pxwn9duraglsgr9f7q8ua33sx0vkq5wjft575h662995zf5m27v2qqxlf3k";
+    add_domain(&db).await;
+
+    let validity = Validity {
+        domain: "example.o".to_string(),
+        credentials: Credentials::new(Algorithm::Ed25519, "xiler".to_string()),
+    };
+    assert!(
+        db.add_validity(
+            "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5",
+            inscription_id,
+            validity,
+        )
+        .await
+    );
+
+    let result = db.transfer_inscription(inscription_id, new_address).await;
+    assert!(matches!(result, Ok(true)));
+
+    let result = db.get_domain("example.o").await.unwrap();
+    assert_eq!(result.0, new_address);
+    assert!(db.get_validity("example.o").await.is_err());
+}
+
+conformance_tests!(
+    add_and_get,
+    test_add_after_existing,
+    get_by_inscription_id,
+    get_non_existent,
+    get_expired,
+    remove,
+    remove_by_inscription,
+    remove_non_existent,
+    transfer,
+);
@@ -0,0 +1,232 @@
+use db::{DomainStatus, ExpiryPolicy, Operation, XDNSRepository};
+use shared::common::{DbError, Error};
+use std::time::Duration;
+use xdns_data::models::subdomain::{Class, RData};
+use xdns_data::models::{Algorithm, Credentials, Data, SubDomain, Validity};
+
+#[macro_use]
+mod common;
+
+use common::{
+    batch, block, commit_at, domain, inscription, register_at, ADDRESS, DAY, GRACE_PERIOD,
+    LIFETIME, OTHER_ADDRESS,
+};
+
+/// Add a domain together with a record, validity and data stored under its name.
+async fn add_domain_with_rows<R: XDNSRepository>(db: &R, i: u32, name: &str, age: i64) {
+    assert!(
+        db.add_domain(ADDRESS, &inscription(i), domain(name, age))
+            .await
+    );
+
+    let subdomain = SubDomain {
+        domain: name.to_string(),
+        subdomain: "www".to_string(),
+        class: Class::IN,
+        ttl: 0,
+        rdata: RData::CNAME(name.to_string()),
+    };
+    assert!(
+        db.add_subdomain(ADDRESS, &inscription(i + 1), subdomain)
+            .await
+    );
+
+    let validity = Validity {
+        domain: name.to_string(),
+        credentials: Credentials::new(Algorithm::Ed25519, "key".to_string()),
+    };
+    assert!(
+        db.add_validity(ADDRESS, &inscription(i + 2), validity)
+            .await
+    );
+
+    let data = Data {
+        domain: name.to_string(),
+        data: vec![1, 2, 3],
+    };
+    assert!(db.add_data(ADDRESS, &inscription(i + 3), data).await);
+}
+
+fn is_expired<T>(result: shared::common::Result<T>) -> bool {
+    matches!(result, Err(Error::Db(DbError::Expired { .. })))
+}
+
+async fn reads_do_not_remove<R: XDNSRepository>(db: R) {
+    add_domain_with_rows(&db, 0, "example.o", LIFETIME + DAY).await;
+
+    assert!(is_expired(db.get_domain("example.o").await));
+    assert!(is_expired(db.get_domain("example.o").await));
+    assert!(is_expired(db.get_domain_address("example.o").await));
+    assert!(is_expired(db.get_subdomain("example.o", "www").await));
+
+    // The rows are still stored, only the lookups by name refuse them.
+    assert!(is_expired(
+        db.get_domain_by_inscription(&inscription(0)).await
+    ));
+    assert!(db
+        .get_subdomain_by_inscription(&inscription(1))
+        .await
+        .is_ok());
+    assert!(db.get_data_by_inscription(&inscription(3)).await.is_ok());
+}
+
+async fn grace_period<R: XDNSRepository>(db: R) {
+    add_domain_with_rows(&db, 0, "example.o", LIFETIME + DAY).await;
+
+    // Only the previous owner can register the domain during the grace period.
+    assert!(
+        !db.add_domain(OTHER_ADDRESS, &inscription(10), domain("example.o", 0))
+            .await
+    );
+    assert!(
+        db.add_domain(ADDRESS, &inscription(11), domain("example.o", 0))
+            .await
+    );
+
+    let (address, _) = db.get_domain("example.o").await.unwrap();
+    assert_eq!(address, ADDRESS);
+
+    // A renewal keeps the records of the domain.
+    assert_eq!(db.get_subdomain("example.o", "www").await.unwrap().len(), 1);
+    assert!(db.get_validity("example.o").await.is_ok());
+}
+
+async fn released<R: XDNSRepository>(db: R) {
+    add_domain_with_rows(&db, 0, "example.o", LIFETIME + GRACE_PERIOD + DAY).await;
+
+    assert!(
+        db.add_domain(OTHER_ADDRESS, &inscription(10), domain("example.o", 0))
+            .await
+    );
+
+    let (address, _) = db.get_domain("example.o").await.unwrap();
+    assert_eq!(address, OTHER_ADDRESS);
+
+    // Nothing of the previous owner carries over to the new one.
+    assert!(db
+        .get_subdomain_by_inscription(&inscription(1))
+        .await
+        .is_err());
+    assert!(db
+        .get_validity_by_inscription(&inscription(2))
+        .await
+        .is_err());
+    assert!(db.get_data_by_inscription(&inscription(3)).await.is_err());
+}
+
+async fn sweep_expired<R: XDNSRepository>(db: R) {
+    add_domain_with_rows(&db, 0, "active.o", 0).await;
+    add_domain_with_rows(&db, 10, "grace.o", LIFETIME + DAY).await;
+    add_domain_with_rows(&db, 20, "released.o", LIFETIME + GRACE_PERIOD + DAY).await;
+
+    assert_eq!(db.sweep_expired().await.unwrap(), vec!["released.o"]);
+
+    assert!(db
+        .get_domain_by_inscription(&inscription(20))
+        .await
+        .unwrap_err()
+        .is_not_found());
+    assert!(db
+        .get_subdomain_by_inscription(&inscription(21))
+        .await
+        .is_err());
+    assert!(db
+        .get_validity_by_inscription(&inscription(22))
+        .await
+        .is_err());
+    assert!(db.get_data_by_inscription(&inscription(23)).await.is_err());
+
+    // The grace period has not passed yet, so the domain is kept for its owner.
+    assert!(is_expired(db.get_domain("grace.o").await));
+    assert!(db
+        .get_subdomain_by_inscription(&inscription(11))
+        .await
+        .is_ok());
+    assert!(db.get_domain("active.o").await.is_ok());
+
+    let history = db.domain_history("released.o").await.unwrap();
+    assert_eq!(history.last().unwrap().action, "expire_domain");
+
+    assert!(db.sweep_expired().await.unwrap().is_empty());
+}
+
+async fn expire_domain<R: XDNSRepository>(db: R) {
+    add_domain_with_rows(&db, 0, "active.o", 0).await;
+    add_domain_with_rows(&db, 10, "grace.o", LIFETIME + DAY).await;
+    add_domain_with_rows(&db, 20, "released.o", LIFETIME + GRACE_PERIOD + DAY).await;
+
+    assert!(!db.expire_domain("active.o").await);
+    assert!(!db.expire_domain("grace.o").await);
+    assert!(!db.expire_domain("unknown.o").await);
+    assert!(db.expire_domain("released.o").await);
+    assert!(!db.expire_domain("released.o").await);
+
+    assert!(db
+        .get_subdomain_by_inscription(&inscription(21))
+        .await
+        .is_err());
+}
+
+async fn evaluated_at_block_time<R: XDNSRepository>(db: R) {
+    let registered = chrono::Utc::now().timestamp() - LIFETIME - GRACE_PERIOD - DAY;
+    commit_at(&db, 1, vec![register_at(0, "example.o", registered as u64)]).await;
+
+    // Released by now, but still active at the time of the block that registers it again.
+    let reregister = Operation::AddDomain {
+        address: OTHER_ADDRESS.to_string(),
+        inscription: inscription(1),
+        domain: domain("example.o", LIFETIME),
+    };
+    let at = block(2).with_time((registered + DAY) as u64);
+    assert!(db
+        .commit(batch(vec![reregister]).with_block(at))
+        .await
+        .is_err());
+
+    assert!(is_expired(
+        db.get_domain_by_inscription(&inscription(0)).await
+    ));
+    assert!(db
+        .get_domain_by_inscription(&inscription(1))
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+conformance_tests!(
+    reads_do_not_remove,
+    grace_period,
+    released,
+    sweep_expired,
+    expire_domain,
+    evaluated_at_block_time,
+);
+
+#[test]
+fn policy_status() {
+    let policy = ExpiryPolicy::new(Duration::from_secs(100), Duration::from_secs(50));
+    let now = chrono::Utc::now().timestamp() as u64;
+
+    assert_eq!(policy.status(now, now), DomainStatus::Active);
+    assert_eq!(policy.status(now - 90, now), DomainStatus::Active);
+    assert_eq!(policy.status(now - 120, now), DomainStatus::Grace);
+    assert_eq!(policy.status(now - 200, now), DomainStatus::Released);
+    assert_eq!(policy.status(now - 200, now - 150), DomainStatus::Active);
+
+    let policy = policy.with_grace_period(Duration::ZERO);
+    assert_eq!(policy.status(now - 120, now), DomainStatus::Released);
+}
+
+#[tokio::test]
+async fn custom_policy() {
+    let policy = ExpiryPolicy::new(Duration::from_secs(DAY as u64), Duration::ZERO);
+    let db = <db::MemoryRepository as XDNSRepository>::new_memory()
+        .await
+        .unwrap()
+        .with_policy(policy);
+
+    add_domain_with_rows(&db, 0, "example.o", 2 * DAY).await;
+
+    assert!(is_expired(db.get_domain("example.o").await));
+    assert_eq!(db.sweep_expired().await.unwrap(), vec!["example.o"]);
+}
@@ -0,0 +1,325 @@
+use db::{Batch, HistoryEntry, Operation, XDNSRepository};
+use xdns_data::models::subdomain::RData;
+use xdns_data::models::{Algorithm, Credentials, Data, DomainRenew, SubDomain, ValidityTransfer};
+
+#[macro_use]
+mod common;
+
+use common::{
+    add_mx, block, commit_at, domain, inscription, mx, register, validity, ADDRESS, DAY,
+    GRACE_PERIOD, LIFETIME, NEW_ADDRESS,
+};
+
+fn transfer(public_key: &str) -> ValidityTransfer {
+    ValidityTransfer {
+        domain: "example.o".to_string(),
+        new_credentials: Some(Credentials::new(Algorithm::Ed25519, public_key.to_string())),
+    }
+}
+
+fn data(data: &[u8]) -> Data {
+    Data {
+        domain: "example.o".to_string(),
+        data: data.to_vec(),
+    }
+}
+
+fn actions(history: &[HistoryEntry]) -> Vec<&str> {
+    history.iter().map(|entry| entry.action.as_str()).collect()
+}
+
+fn priorities(records: Vec<(String, SubDomain)>) -> Vec<u16> {
+    let mut priorities: Vec<u16> = records
+        .into_iter()
+        .map(|(_, subdomain)| match subdomain.rdata {
+            RData::MX { priority, .. } => priority,
+            rdata => panic!("unexpected rdata {:?}", rdata),
+        })
+        .collect();
+    priorities.sort();
+    priorities
+}
+
+async fn domain_history<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0), add_mx(1, 10)]).await;
+    commit_at(
+        &db,
+        2,
+        vec![
+            Operation::AddData {
+                address: ADDRESS.to_string(),
+                inscription: inscription(2),
+                data: Data {
+                    domain: "example.o".to_string(),
+                    data: b"hello".to_vec(),
+                },
+            },
+            Operation::TransferInscription {
+                inscription: inscription(0),
+                new_owner: NEW_ADDRESS.to_string(),
+            },
+        ],
+    )
+    .await;
+
+    let history = db.domain_history("example.o").await.unwrap();
+    let actions: Vec<&str> = history.iter().map(|entry| entry.action.as_str()).collect();
+    assert_eq!(
+        actions,
+        vec![
+            "add_domain",
+            "add_subdomain",
+            "add_data",
+            "transfer_inscription"
+        ]
+    );
+
+    assert_eq!(
+        history[0].inscription.as_deref(),
+        Some(inscription(0).as_str())
+    );
+    assert_eq!(history[0].address.as_deref(), Some(ADDRESS));
+    assert_eq!(history[0].block, Some(block(1)));
+    assert!(history[0]
+        .payload
+        .starts_with(r#"{"name":"example.o","valid_from":"#));
+
+    assert_eq!(history[2].block, Some(block(2)));
+    assert_eq!(
+        history[2].payload,
+        r#"{"data":"68656c6c6f","domain":"example.o"}"#
+    );
+    assert_eq!(history[3].address.as_deref(), Some(NEW_ADDRESS));
+
+    assert!(db.domain_history("other.o").await.unwrap().is_empty());
+}
+
+async fn unjournaled_batches_are_recorded<R: XDNSRepository>(db: R) {
+    let mut batch = Batch::new();
+    batch.push(register(0, "example.o", 0));
+    db.commit(batch).await.unwrap();
+
+    let history = db.domain_history("example.o").await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].action, "add_domain");
+    assert_eq!(history[0].block, None);
+}
+
+async fn rejected_batch_is_not_recorded<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+
+    let mut batch = Batch::new().with_block(block(2));
+    batch.push(add_mx(1, 10)).push(Operation::Drop {
+        address: ADDRESS.to_string(),
+        // There is nothing to drop.
+        inscription: inscription(2),
+    });
+
+    assert!(db.commit(batch).await.is_err());
+    assert_eq!(db.domain_history("example.o").await.unwrap().len(), 1);
+}
+
+async fn rollback_is_recorded<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+    commit_at(&db, 2, vec![add_mx(1, 10)]).await;
+
+    assert_eq!(db.rollback_to(1).await.unwrap(), 1);
+
+    // History is append-only, the undone action stays.
+    let history = db.domain_history("example.o").await.unwrap();
+    let actions: Vec<&str> = history.iter().map(|entry| entry.action.as_str()).collect();
+    assert_eq!(actions, vec!["add_domain", "add_subdomain", "rollback"]);
+    assert_eq!(history[2].block, Some(block(2)));
+    assert_eq!(history[2].payload, r#"{"height":1}"#);
+}
+
+async fn records_at<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0), add_mx(1, 10)]).await;
+    commit_at(&db, 2, vec![add_mx(2, 20)]).await;
+    commit_at(
+        &db,
+        3,
+        vec![Operation::Drop {
+            address: ADDRESS.to_string(),
+            inscription: inscription(1),
+        }],
+    )
+    .await;
+
+    assert_eq!(
+        priorities(db.records_at("example.o", 1).await.unwrap()),
+        vec![10]
+    );
+    assert_eq!(
+        priorities(db.records_at("example.o", 2).await.unwrap()),
+        vec![10, 20]
+    );
+    assert_eq!(
+        priorities(db.records_at("example.o", 3).await.unwrap()),
+        vec![20]
+    );
+    assert_eq!(
+        priorities(db.records_at("example.o", 100).await.unwrap()),
+        vec![20]
+    );
+}
+
+async fn records_at_removed_domain<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0), add_mx(1, 10)]).await;
+    commit_at(
+        &db,
+        2,
+        vec![Operation::RemoveDomain {
+            domain: "example.o".to_string(),
+        }],
+    )
+    .await;
+
+    assert_eq!(
+        priorities(db.records_at("example.o", 1).await.unwrap()),
+        vec![10]
+    );
+    assert!(db
+        .records_at("example.o", 2)
+        .await
+        .unwrap_err()
+        .is_not_found());
+    assert!(db
+        .records_at("example.o", 0)
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+async fn standalone_domain_methods_are_recorded<R: XDNSRepository>(db: R) {
+    let renewal = DomainRenew {
+        domain: "example.o".to_string(),
+        periods: 1,
+    };
+
+    assert!(
+        db.add_domain(ADDRESS, &inscription(0), domain("example.o", 0))
+            .await
+    );
+    assert!(
+        db.renew_domain(ADDRESS, &inscription(1), renewal, None)
+            .await
+    );
+    assert!(db
+        .transfer_inscription(&inscription(0), NEW_ADDRESS)
+        .await
+        .unwrap());
+    assert!(db.remove_domain_by_inscription(&inscription(0)).await);
+    assert!(
+        db.add_domain(ADDRESS, &inscription(2), domain("example.o", 0))
+            .await
+    );
+    assert!(db.remove_domain("example.o").await);
+
+    let history = db.domain_history("example.o").await.unwrap();
+    assert_eq!(
+        actions(&history),
+        vec![
+            "add_domain",
+            "renew_domain",
+            "transfer_inscription",
+            "remove_domain_by_inscription",
+            "add_domain",
+            "remove_domain",
+        ]
+    );
+    assert!(history.iter().all(|entry| entry.block.is_none()));
+    assert_eq!(
+        history[1].inscription.as_deref(),
+        Some(inscription(1).as_str())
+    );
+    assert_eq!(history[2].address.as_deref(), Some(NEW_ADDRESS));
+
+    let released = LIFETIME + GRACE_PERIOD + DAY;
+    commit_at(&db, 1, vec![register(3, "released.o", released)]).await;
+    assert!(db.expire_domain("released.o").await);
+
+    let history = db.domain_history("released.o").await.unwrap();
+    assert_eq!(actions(&history), vec!["add_domain", "expire_domain"]);
+    assert_eq!(history[1].block, None);
+}
+
+async fn standalone_record_methods_are_recorded<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+
+    assert!(db.add_subdomain(ADDRESS, &inscription(1), mx(10)).await);
+    assert!(db.remove_subdomain(&inscription(1)).await);
+    assert!(db.add_subdomain(ADDRESS, &inscription(2), mx(20)).await);
+    assert!(db.remove_subdomains("example.o", "@.").await);
+
+    assert!(
+        db.add_validity(ADDRESS, &inscription(3), validity("xiler"))
+            .await
+    );
+    assert!(db.update_validity(transfer("hello")).await);
+    assert!(
+        db.update_validity_by_inscription(ADDRESS, &inscription(3), transfer("world"))
+            .await
+    );
+    assert!(db.remove_validity("example.o").await);
+    assert!(
+        db.add_validity(ADDRESS, &inscription(4), validity("xiler"))
+            .await
+    );
+    assert!(db.remove_validity_by_inscription(&inscription(4)).await);
+
+    assert!(db.add_data(ADDRESS, &inscription(5), data(b"hello")).await);
+    assert!(db.remove_data("example.o").await);
+    assert!(db.add_data(ADDRESS, &inscription(6), data(b"world")).await);
+    assert!(db.remove_data_by_inscription(&inscription(6)).await);
+
+    let history = db.domain_history("example.o").await.unwrap();
+    assert_eq!(
+        actions(&history[1..]),
+        vec![
+            "add_subdomain",
+            "remove_subdomain",
+            "add_subdomain",
+            "remove_subdomains",
+            "add_validity",
+            "update_validity",
+            "update_validity_by_inscription",
+            "remove_validity",
+            "add_validity",
+            "remove_validity_by_inscription",
+            "add_data",
+            "remove_data",
+            "add_data",
+            "remove_data_by_inscription",
+        ]
+    );
+    assert!(history[1..].iter().all(|entry| entry.block.is_none()));
+    assert_eq!(history[1].address.as_deref(), Some(ADDRESS));
+}
+
+async fn rejected_standalone_methods_are_not_recorded<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+
+    assert!(!db.remove_domain("other.o").await);
+    assert!(!db.update_validity(transfer("hello")).await);
+    assert!(!db.remove_subdomain(&inscription(1)).await);
+    assert!(db
+        .transfer_inscription(&inscription(1), NEW_ADDRESS)
+        .await
+        .is_err());
+
+    assert_eq!(db.domain_history("example.o").await.unwrap().len(), 1);
+    assert!(db.domain_history("other.o").await.unwrap().is_empty());
+}
+
+conformance_tests!(
+    domain_history,
+    unjournaled_batches_are_recorded,
+    rejected_batch_is_not_recorded,
+    rollback_is_recorded,
+    records_at,
+    records_at_removed_domain,
+    standalone_domain_methods_are_recorded,
+    standalone_record_methods_are_recorded,
+    rejected_standalone_methods_are_not_recorded,
+);
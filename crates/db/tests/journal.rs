@@ -0,0 +1,256 @@
+use db::{Batch, Block, MemoryRepository, Operation, XDNSRepository, DEFAULT_FINALITY_DEPTH};
+use shared::common::{DbError, Error};
+use xdns_data::models::subdomain::RData;
+use xdns_data::models::Data;
+
+#[macro_use]
+mod common;
+
+use common::{
+    add_mx, add_validity, block, commit_at, inscription, register, validity, ADDRESS, NEW_ADDRESS,
+};
+
+async fn get_tip<R: XDNSRepository>(db: R) {
+    assert_eq!(db.get_tip().await.unwrap(), None);
+
+    commit_at(&db, 5, vec![register(0, "example.o", 0)]).await;
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(5)));
+
+    // Batches without a block are journaled at the tip.
+    let mut batch = Batch::new();
+    batch.push(add_validity(1));
+    db.commit(batch).await.unwrap();
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(5)));
+
+    commit_at(&db, 5, vec![]).await;
+    commit_at(&db, 6, vec![]).await;
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(6)));
+}
+
+async fn rollback_to<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0), add_validity(1)]).await;
+    commit_at(
+        &db,
+        2,
+        vec![
+            add_mx(2, 10),
+            Operation::AddData {
+                address: ADDRESS.to_string(),
+                inscription: inscription(3),
+                data: Data {
+                    domain: "example.o".to_string(),
+                    data: b"hello".to_vec(),
+                },
+            },
+        ],
+    )
+    .await;
+    // The transfer removes the validity as well.
+    commit_at(
+        &db,
+        3,
+        vec![Operation::TransferInscription {
+            inscription: inscription(0),
+            new_owner: NEW_ADDRESS.to_string(),
+        }],
+    )
+    .await;
+
+    assert_eq!(
+        db.get_domain_address("example.o").await.unwrap(),
+        NEW_ADDRESS
+    );
+    assert!(db.get_validity("example.o").await.is_err());
+
+    assert_eq!(db.rollback_to(1).await.unwrap(), 2);
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(1)));
+
+    assert_eq!(db.get_domain_address("example.o").await.unwrap(), ADDRESS);
+    assert_eq!(db.get_validity("example.o").await.unwrap().0, ADDRESS);
+    assert!(db
+        .get_subdomain_by_inscription(&inscription(2))
+        .await
+        .unwrap_err()
+        .is_not_found());
+    assert!(db
+        .get_data_by_inscription(&inscription(3))
+        .await
+        .unwrap_err()
+        .is_not_found());
+
+    assert_eq!(db.rollback_to(0).await.unwrap(), 1);
+    assert_eq!(db.get_tip().await.unwrap(), None);
+    assert!(db.get_domain("example.o").await.unwrap_err().is_not_found());
+    assert!(db
+        .get_validity_by_inscription(&inscription(1))
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+async fn rollback_undoes_standalone_methods<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+    assert!(
+        db.add_validity(ADDRESS, &inscription(1), validity("xiler"))
+            .await
+    );
+
+    commit_at(&db, 2, vec![add_mx(2, 10)]).await;
+    assert!(db.remove_validity("example.o").await);
+
+    // The block 2 batch and the removal journaled at it.
+    assert_eq!(db.rollback_to(1).await.unwrap(), 2);
+    assert_eq!(db.get_validity("example.o").await.unwrap().0, ADDRESS);
+    assert!(db
+        .get_subdomain_by_inscription(&inscription(2))
+        .await
+        .unwrap_err()
+        .is_not_found());
+
+    assert_eq!(db.rollback_to(0).await.unwrap(), 2);
+    assert!(db.get_domain("example.o").await.unwrap_err().is_not_found());
+    assert!(db.get_validity("example.o").await.is_err());
+}
+
+async fn rollback_restores_removed_rows<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0), add_mx(2, 10)]).await;
+    commit_at(
+        &db,
+        2,
+        vec![
+            Operation::Drop {
+                address: ADDRESS.to_string(),
+                inscription: inscription(2),
+            },
+            Operation::RemoveDomain {
+                domain: "example.o".to_string(),
+            },
+        ],
+    )
+    .await;
+
+    assert!(db.get_domain("example.o").await.is_err());
+    assert_eq!(db.rollback_to(1).await.unwrap(), 1);
+
+    let (address, subdomain) = db
+        .get_subdomain_by_inscription(&inscription(2))
+        .await
+        .unwrap();
+    assert_eq!(address, ADDRESS);
+    assert_eq!(
+        subdomain.rdata,
+        RData::MX {
+            priority: 10,
+            host: "mail.example.o".to_string(),
+        }
+    );
+    assert_eq!(db.get_subdomain("example.o", "@.").await.unwrap().len(), 1);
+}
+
+async fn rollback_to_tip<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+
+    assert_eq!(db.rollback_to(1).await.unwrap(), 0);
+    assert_eq!(db.rollback_to(10).await.unwrap(), 0);
+    assert!(db.get_domain("example.o").await.is_ok());
+}
+
+async fn block_below_tip<R: XDNSRepository>(db: R) {
+    commit_at(&db, 5, vec![register(0, "example.o", 0)]).await;
+
+    let result = db.commit(Batch::new().with_block(block(4))).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::BlockOrder { height: 4, tip: 5 }))
+    ));
+
+    // Another block at the height of the tip replaces it, which is a reorganization as well.
+    let orphan = Block::new(5, "f".repeat(64));
+    let result = db.commit(Batch::new().with_block(orphan)).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::BlockOrder { height: 5, tip: 5 }))
+    ));
+
+    // More batches can be committed at the tip itself.
+    commit_at(&db, 5, vec![add_validity(1)]).await;
+
+    assert_eq!(db.rollback_to(3).await.unwrap(), 2);
+    commit_at(&db, 4, vec![register(0, "example.o", 0)]).await;
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(4)));
+}
+
+async fn rejected_batch_is_not_journaled<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0), add_validity(1)]).await;
+
+    let mut batch = Batch::new().with_block(block(2));
+    batch.push(add_mx(2, 10)).push(add_validity(3));
+
+    assert!(db.commit(batch).await.is_err());
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(1)));
+    assert!(db
+        .get_subdomain("example.o", "@.")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn prunes_finalized_blocks<R: XDNSRepository>(db: R) {
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+    commit_at(&db, 2, vec![add_validity(1)]).await;
+
+    // Block 2 is final once a block at its finality depth is committed.
+    let tip = 2 + DEFAULT_FINALITY_DEPTH;
+    commit_at(&db, tip, vec![add_mx(2, 10)]).await;
+
+    assert!(matches!(
+        db.rollback_to(1).await,
+        Err(Error::Db(DbError::Finalized {
+            height: 1,
+            finalized: 2
+        }))
+    ));
+    assert!(matches!(
+        db.records_at("example.o", 0).await,
+        Err(Error::Db(DbError::Finalized { .. }))
+    ));
+
+    // The finalized block itself can still be rolled back to.
+    assert_eq!(db.rollback_to(2).await.unwrap(), 1);
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(2)));
+    assert!(db.get_validity("example.o").await.is_ok());
+    assert!(db
+        .get_subdomain("example.o", "@.")
+        .await
+        .unwrap()
+        .is_empty());
+
+    let result = db.commit(Batch::new().with_block(block(1))).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::BlockOrder { height: 1, tip: 2 }))
+    ));
+}
+
+conformance_tests!(
+    get_tip,
+    rollback_to,
+    rollback_undoes_standalone_methods,
+    rollback_restores_removed_rows,
+    rollback_to_tip,
+    block_below_tip,
+    rejected_batch_is_not_journaled,
+    prunes_finalized_blocks,
+);
+
+#[tokio::test]
+async fn keeps_journal_without_finality_depth() {
+    let db = MemoryRepository::default().with_finality_depth(None);
+
+    commit_at(&db, 1, vec![register(0, "example.o", 0)]).await;
+    commit_at(&db, 1 + 10 * DEFAULT_FINALITY_DEPTH, vec![add_validity(1)]).await;
+
+    assert_eq!(db.rollback_to(0).await.unwrap(), 2);
+    assert_eq!(db.get_tip().await.unwrap(), None);
+    assert!(db.get_domain("example.o").await.is_err());
+}
@@ -0,0 +1,100 @@
+use db::{KvRepository, SqliteRepository, XDNSRepository};
+use xdns_data::models::subdomain::RData;
+use xdns_data::models::{Algorithm, Credentials, Validity, ValidityTransfer};
+
+mod common;
+
+use common::{fill, ADDRESS};
+
+async fn assert_filled<R: XDNSRepository>(db: &R) {
+    assert_eq!(db.get_domain("example.o").await.unwrap().0, ADDRESS);
+    assert_eq!(
+        db.get_domain_by_address(ADDRESS).await.unwrap().name,
+        "example.o"
+    );
+
+    let subdomains = db.get_subdomain("example.o", "@.").await.unwrap();
+    assert_eq!(subdomains.len(), 1);
+    assert_eq!(
+        subdomains[0].1.rdata,
+        RData::MX {
+            priority: 10,
+            host: "mail.example.o".to_string(),
+        }
+    );
+
+    let validity = db.get_validity("example.o").await.unwrap().1;
+    assert_eq!(validity.credentials.public_key, "xiler");
+
+    let data = db.get_data("example.o").await.unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0].1.data, b"custom data");
+}
+
+#[tokio::test]
+async fn persists_across_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("xdns.redb");
+
+    {
+        let db = KvRepository::open(&path).unwrap();
+        fill(&db).await;
+    }
+
+    let db = KvRepository::open(&path).unwrap();
+    assert_filled(&db).await;
+}
+
+#[tokio::test]
+async fn failed_write_is_rolled_back() {
+    let db = KvRepository::new_memory().await.unwrap();
+    fill(&db).await;
+
+    let validity = Validity {
+        domain: "other.o".to_string(),
+        credentials: Credentials::new(Algorithm::Ed25519, "other".to_string()),
+    };
+    assert!(db.add_validity(ADDRESS, "other", validity).await);
+
+    // The update removes the old validity before inserting the new one, which conflicts
+    // with the domain of the existing validity. Neither change may be stored.
+    let transfer = ValidityTransfer {
+        domain: "example.o".to_string(),
+        new_credentials: Some(Credentials::new(Algorithm::Ed25519, "hello".to_string())),
+    };
+    assert!(
+        !db.update_validity_by_inscription(ADDRESS, "other", transfer)
+            .await
+    );
+
+    let validity = db.get_validity_by_inscription("other").await.unwrap().1;
+    assert_eq!(validity.domain, "other.o");
+    assert_eq!(validity.credentials.public_key, "other");
+}
+
+#[tokio::test]
+async fn import_sql() {
+    let source = SqliteRepository::new_memory().await.unwrap();
+    fill(&source).await;
+
+    let target = KvRepository::new_memory().await.unwrap();
+    let summary = target.import_sql(&source).await.unwrap();
+
+    assert_eq!(summary.domains, 1);
+    assert_eq!(summary.subdomains, 2);
+    assert_eq!(summary.validities, 1);
+    assert_eq!(summary.data, 1);
+    assert_eq!(summary.skipped, 0);
+    assert_filled(&target).await;
+}
+
+#[tokio::test]
+async fn import_sql_into_non_empty_store() {
+    let source = SqliteRepository::new_memory().await.unwrap();
+    fill(&source).await;
+
+    let target = KvRepository::new_memory().await.unwrap();
+    fill(&target).await;
+
+    assert!(target.import_sql(&source).await.is_err());
+}
@@ -0,0 +1,228 @@
+use db::{NameFilter, Page, XDNSRepository};
+use shared::common::{DbError, Error};
+use xdns_data::models::subdomain::{Class, RData};
+use xdns_data::models::{Domain, SubDomain};
+
+#[macro_use]
+mod common;
+
+use common::{domain, inscription, ADDRESS, DAY, LIFETIME, OTHER_ADDRESS};
+
+/// Add a domain that was inscribed `age` seconds ago.
+async fn add_domain<R: XDNSRepository>(db: &R, address: &str, i: u32, name: &str, age: i64) {
+    assert!(
+        db.add_domain(address, &inscription(i), domain(name, age))
+            .await
+    );
+}
+
+async fn add_record<R: XDNSRepository>(db: &R, address: &str, i: u32, subdomain: &str) {
+    let subdomain = SubDomain {
+        domain: "example.o".to_string(),
+        subdomain: subdomain.to_string(),
+        class: Class::IN,
+        ttl: 0,
+        rdata: RData::CNAME("example.o".to_string()),
+    };
+
+    assert!(db.add_subdomain(address, &inscription(i), subdomain).await);
+}
+
+fn names(domains: Vec<(String, Domain)>) -> Vec<String> {
+    domains.into_iter().map(|(_, domain)| domain.name).collect()
+}
+
+async fn list_domains_by_address<R: XDNSRepository>(db: R) {
+    add_domain(&db, ADDRESS, 0, "b.o", 0).await;
+    add_domain(&db, ADDRESS, 1, "a.o", 0).await;
+    add_domain(&db, OTHER_ADDRESS, 2, "c.o", 0).await;
+    add_domain(&db, ADDRESS, 3, "expired.o", LIFETIME + DAY).await;
+
+    let domains = db.list_domains_by_address(ADDRESS).await.unwrap();
+    let names: Vec<&str> = domains.iter().map(|domain| domain.name.as_str()).collect();
+    assert_eq!(names, vec!["a.o", "b.o"]);
+
+    assert_eq!(db.count_domains_by_address(ADDRESS).await.unwrap(), 2);
+    assert_eq!(db.count_domains_by_address(OTHER_ADDRESS).await.unwrap(), 1);
+    assert!(db
+        .list_domains_by_address("unknown")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn list_domains<R: XDNSRepository>(db: R) {
+    for (i, name) in ["d.o", "a.o", "c.o", "e.o", "b.o"].iter().enumerate() {
+        add_domain(&db, ADDRESS, i as u32, name, 0).await;
+    }
+    add_domain(&db, ADDRESS, 5, "bb.o", LIFETIME + DAY).await;
+
+    let first = db.list_domains(&Page::first(2)).await.unwrap();
+    assert_eq!(first[0].0, ADDRESS);
+
+    let next = Page::first(2).next(&first).unwrap();
+    let second = db.list_domains(&next).await.unwrap();
+    let last_page = next.next(&second).unwrap();
+    let last = db.list_domains(&last_page).await.unwrap();
+
+    assert_eq!(names(first), vec!["a.o", "b.o"]);
+    assert_eq!(names(second), vec!["c.o", "d.o"]);
+    assert_eq!(last_page.next(&last), None);
+    assert_eq!(names(last), vec!["e.o"]);
+
+    assert_eq!(db.count_domains().await.unwrap(), 5);
+
+    // Expired domains are left in place, a lookup still reports them as expired.
+    assert!(matches!(
+        db.get_domain("bb.o").await,
+        Err(Error::Db(DbError::Expired { .. }))
+    ));
+}
+
+async fn search_domains<R: XDNSRepository>(db: R) {
+    for (i, name) in ["example.o", "exam.o", "other.o", "sample.o", "ex_ample.o"]
+        .iter()
+        .enumerate()
+    {
+        add_domain(&db, ADDRESS, i as u32, name, 0).await;
+    }
+
+    let prefix = NameFilter::Prefix("exam".to_string());
+    let found = db.search_domains(&prefix, &Page::first(10)).await.unwrap();
+    assert_eq!(names(found), vec!["exam.o", "example.o"]);
+
+    let found = db
+        .search_domains(&prefix, &Page::after("exam.o", 10))
+        .await
+        .unwrap();
+    assert_eq!(names(found), vec!["example.o"]);
+
+    let contains = NameFilter::Contains("ample".to_string());
+    let found = db
+        .search_domains(&contains, &Page::first(10))
+        .await
+        .unwrap();
+    assert_eq!(names(found), vec!["ex_ample.o", "example.o", "sample.o"]);
+
+    let found = db.search_domains(&contains, &Page::first(1)).await.unwrap();
+    assert_eq!(names(found), vec!["ex_ample.o"]);
+
+    // Wildcards are matched literally.
+    let underscore = NameFilter::Contains("_".to_string());
+    let found = db
+        .search_domains(&underscore, &Page::first(10))
+        .await
+        .unwrap();
+    assert_eq!(names(found), vec!["ex_ample.o"]);
+
+    let percent = NameFilter::Prefix("%".to_string());
+    assert!(db
+        .search_domains(&percent, &Page::first(10))
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn domains_expiring_within<R: XDNSRepository>(db: R) {
+    add_domain(&db, ADDRESS, 0, "fresh.o", 0).await;
+    add_domain(&db, ADDRESS, 1, "soon.o", LIFETIME - 10 * DAY).await;
+    add_domain(&db, OTHER_ADDRESS, 2, "sooner.o", LIFETIME - 2 * DAY).await;
+    add_domain(&db, ADDRESS, 3, "expired.o", LIFETIME + DAY).await;
+
+    let expiring = db.domains_expiring_within(30).await.unwrap();
+    assert_eq!(expiring[0].0, OTHER_ADDRESS);
+    assert_eq!(names(expiring), vec!["sooner.o", "soon.o"]);
+
+    let expiring = db.domains_expiring_within(5).await.unwrap();
+    assert_eq!(names(expiring), vec!["sooner.o"]);
+
+    assert!(db.domains_expiring_within(0).await.unwrap().is_empty());
+
+    // Every active domain expires within the largest amount of days.
+    let expiring = db.domains_expiring_within(u64::MAX).await.unwrap();
+    assert_eq!(names(expiring), vec!["sooner.o", "soon.o", "fresh.o"]);
+}
+
+async fn list_subdomains<R: XDNSRepository>(db: R) {
+    add_domain(&db, ADDRESS, 0, "example.o", 0).await;
+    add_record(&db, ADDRESS, 1, "www").await;
+    add_record(&db, ADDRESS, 2, "api").await;
+    add_record(&db, ADDRESS, 3, "www").await;
+    // Inscribed by someone else than the owner.
+    add_record(&db, OTHER_ADDRESS, 4, "mail").await;
+
+    let subdomains = db.list_subdomains("example.o").await.unwrap();
+    let labels: Vec<&str> = subdomains
+        .iter()
+        .map(|(_, subdomain)| subdomain.subdomain.as_str())
+        .collect();
+    assert_eq!(labels, vec!["api", "www", "www"]);
+    assert!(subdomains.iter().all(|(address, _)| address == ADDRESS));
+
+    assert_eq!(db.count_subdomains("example.o").await.unwrap(), 3);
+
+    assert!(db
+        .list_subdomains("unknown.o")
+        .await
+        .unwrap_err()
+        .is_not_found());
+    assert!(db
+        .count_subdomains("unknown.o")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+async fn search_follows_changes<R: XDNSRepository>(db: R) {
+    for (i, name) in ["example.o", "sample.o", "ample.o", "other.o"]
+        .iter()
+        .enumerate()
+    {
+        add_domain(&db, ADDRESS, i as u32, name, 0).await;
+    }
+
+    let contains = NameFilter::Contains("ample".to_string());
+    let found = db.search_domains(&contains, &Page::first(2)).await.unwrap();
+    assert_eq!(names(found), vec!["ample.o", "example.o"]);
+
+    let found = db
+        .search_domains(&contains, &Page::after("example.o", 2))
+        .await
+        .unwrap();
+    assert_eq!(names(found), vec!["sample.o"]);
+
+    // Removed names are no longer found, names added later are.
+    assert!(db.remove_domain("example.o").await);
+    add_domain(&db, ADDRESS, 4, "trample.o", 0).await;
+
+    let found = db
+        .search_domains(&contains, &Page::first(10))
+        .await
+        .unwrap();
+    assert_eq!(names(found), vec!["ample.o", "sample.o", "trample.o"]);
+
+    // Text shorter than a trigram is still matched.
+    let short = NameFilter::Contains("tr".to_string());
+    let found = db.search_domains(&short, &Page::first(10)).await.unwrap();
+    assert_eq!(names(found), vec!["trample.o"]);
+
+    let prefix = NameFilter::Prefix("o".to_string());
+    let found = db.search_domains(&prefix, &Page::first(10)).await.unwrap();
+    assert_eq!(names(found), vec!["other.o"]);
+}
+
+async fn empty<R: XDNSRepository>(db: R) {
+    assert!(db.list_domains(&Page::first(10)).await.unwrap().is_empty());
+    assert_eq!(db.count_domains().await.unwrap(), 0);
+    assert!(db.domains_expiring_within(365).await.unwrap().is_empty());
+}
+
+conformance_tests!(
+    list_domains_by_address,
+    list_domains,
+    search_domains,
+    search_follows_changes,
+    domains_expiring_within,
+    list_subdomains,
+    empty,
+);
@@ -0,0 +1,258 @@
+use std::time::SystemTime;
+
+use db::{Batch, Block, Operation, XDNSRepository};
+use ed25519_dalek::{Signer, SigningKey};
+use shared::common::{DbError, Error};
+use shared::time::system_time_from_epoch_seconds;
+use xdns_data::models::{Algorithm, Credentials, Domain, DomainRenew, Validity};
+use xdns_data::parser::ActionParser;
+use xdns_data::traits::Parser;
+
+#[macro_use]
+mod common;
+
+use common::{inscription, ADDRESS, DAY, GRACE_PERIOD, LIFETIME, OTHER_ADDRESS};
+
+const PRIVATE_KEY: [u8; 32] = [7; 32];
+fn renewal(periods: u32) -> DomainRenew {
+    DomainRenew {
+        domain: "example.o".to_string(),
+        periods,
+    }
+}
+
+fn epoch_seconds(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Add `example.o`, inscribed `age` seconds ago, and return its `valid_from`.
+async fn add_domain<R: XDNSRepository>(db: &R, age: i64) -> i64 {
+    let valid_from = chrono::Utc::now().timestamp() - age;
+    let domain = Domain {
+        name: "example.o".to_string(),
+        valid_from: system_time_from_epoch_seconds(valid_from as u64),
+    };
+
+    assert!(db.add_domain(ADDRESS, &inscription(0), domain).await);
+    valid_from
+}
+
+/// The `valid_from` of `example.o`, found without the expiry check.
+async fn valid_from<R: XDNSRepository>(db: &R) -> i64 {
+    let (_, domain) = db.get_domain_by_inscription(&inscription(0)).await.unwrap();
+    epoch_seconds(domain.valid_from)
+}
+
+/// Parse a renewal of `example.o` signed with a key, over the last inscription of the domain.
+fn signed_renewal(key: &SigningKey, last_id: &str) -> ActionParser {
+    let content = format!("{} {}", renewal(1), last_id);
+    let signature = hex::encode(key.sign(content.as_bytes()).to_bytes());
+
+    ActionParser::parse(&format!("{} {}", content, signature)).unwrap()
+}
+
+async fn renew_by_owner<R: XDNSRepository>(db: R) {
+    let original = add_domain(&db, 0).await;
+
+    assert!(
+        db.renew_domain(ADDRESS, &inscription(1), renewal(2), None)
+            .await
+    );
+    assert_eq!(valid_from(&db).await, original + 2 * LIFETIME);
+}
+
+async fn renew_by_other<R: XDNSRepository>(db: R) {
+    let original = add_domain(&db, 0).await;
+
+    assert!(
+        !db.renew_domain(OTHER_ADDRESS, &inscription(1), renewal(1), None)
+            .await
+    );
+    assert_eq!(valid_from(&db).await, original);
+}
+
+async fn renew_during_grace_period<R: XDNSRepository>(db: R) {
+    let original = add_domain(&db, LIFETIME + DAY).await;
+
+    assert!(matches!(
+        db.get_domain("example.o").await,
+        Err(Error::Db(DbError::Expired { .. }))
+    ));
+    assert!(
+        db.renew_domain(ADDRESS, &inscription(1), renewal(1), None)
+            .await
+    );
+
+    assert_eq!(valid_from(&db).await, original + LIFETIME);
+    assert!(db.get_domain("example.o").await.is_ok());
+}
+
+async fn renew_released<R: XDNSRepository>(db: R) {
+    add_domain(&db, LIFETIME + GRACE_PERIOD + DAY).await;
+
+    assert!(
+        !db.renew_domain(ADDRESS, &inscription(1), renewal(1), None)
+            .await
+    );
+}
+
+async fn renew_beyond_limit<R: XDNSRepository>(db: R) {
+    let original = add_domain(&db, 0).await;
+
+    // The default policy allows registrations to run up to 10 lifetimes ahead.
+    assert!(
+        !db.renew_domain(ADDRESS, &inscription(1), renewal(10), None)
+            .await
+    );
+    assert!(
+        db.renew_domain(ADDRESS, &inscription(1), renewal(9), None)
+            .await
+    );
+    assert!(
+        !db.renew_domain(ADDRESS, &inscription(2), renewal(1), None)
+            .await
+    );
+    assert_eq!(valid_from(&db).await, original + 9 * LIFETIME);
+}
+
+async fn renew_unknown<R: XDNSRepository>(db: R) {
+    assert!(
+        !db.renew_domain(ADDRESS, &inscription(1), renewal(1), None)
+            .await
+    );
+}
+
+/// Add the validity key of `example.o` and return it.
+async fn add_validity_key<R: XDNSRepository>(db: &R) -> SigningKey {
+    let key = SigningKey::from_bytes(&PRIVATE_KEY);
+    let validity = Validity {
+        domain: "example.o".to_string(),
+        credentials: Credentials::new(
+            Algorithm::Ed25519,
+            hex::encode(key.verifying_key().to_bytes()),
+        ),
+    };
+    assert!(db.add_validity(ADDRESS, &inscription(1), validity).await);
+    key
+}
+
+async fn renew_with_validity_key<R: XDNSRepository>(db: R) {
+    let original = add_domain(&db, 0).await;
+    let key = add_validity_key(&db).await;
+
+    let other_key = SigningKey::from_bytes(&[8; 32]);
+    let batch = Batch::from_parser(
+        OTHER_ADDRESS,
+        &inscription(2),
+        signed_renewal(&other_key, &inscription(0)),
+    );
+    assert!(db.commit(batch).await.is_err());
+
+    let batch = Batch::from_parser(
+        OTHER_ADDRESS,
+        &inscription(3),
+        signed_renewal(&key, &inscription(0)),
+    );
+    db.commit(batch).await.unwrap();
+
+    assert_eq!(valid_from(&db).await, original + LIFETIME);
+
+    let history = db.domain_history("example.o").await.unwrap();
+    let entry = history.last().unwrap();
+    assert_eq!(entry.action, "renew_domain");
+    assert_eq!(entry.address.as_deref(), Some(OTHER_ADDRESS));
+}
+
+async fn renew_replayed<R: XDNSRepository>(db: R) {
+    let original = add_domain(&db, 0).await;
+    let key = add_validity_key(&db).await;
+
+    // Signed without the last inscription of the domain, or over an inscription that is not a renewal of it.
+    for last_id in ["null", &inscription(1)] {
+        let batch = Batch::from_parser(
+            OTHER_ADDRESS,
+            &inscription(2),
+            signed_renewal(&key, last_id),
+        );
+        assert!(db.commit(batch).await.is_err());
+    }
+
+    let batch = Batch::from_parser(
+        OTHER_ADDRESS,
+        &inscription(3),
+        signed_renewal(&key, &inscription(0)),
+    );
+    db.commit(batch).await.unwrap();
+
+    // The same signed renewal can't renew the domain again, the next one has to name the last renewal.
+    let batch = Batch::from_parser(
+        OTHER_ADDRESS,
+        &inscription(4),
+        signed_renewal(&key, &inscription(0)),
+    );
+    assert!(db.commit(batch).await.is_err());
+    assert_eq!(valid_from(&db).await, original + LIFETIME);
+
+    let batch = Batch::from_parser(
+        OTHER_ADDRESS,
+        &inscription(5),
+        signed_renewal(&key, &inscription(3)),
+    );
+    db.commit(batch).await.unwrap();
+    assert_eq!(valid_from(&db).await, original + 2 * LIFETIME);
+}
+
+async fn renew_rolled_back<R: XDNSRepository>(db: R) {
+    let original = add_domain(&db, 0).await;
+
+    let mut batch = Batch::new().with_block(Block::new(1, "a".repeat(64)));
+    batch.push(Operation::RenewDomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(1),
+        renewal: renewal(1),
+        signature: None,
+    });
+    db.commit(batch).await.unwrap();
+    assert_eq!(valid_from(&db).await, original + LIFETIME);
+
+    db.rollback_to(0).await.unwrap();
+    assert_eq!(valid_from(&db).await, original);
+}
+
+async fn renew_at_block_time<R: XDNSRepository>(db: R) {
+    // Released by now, so only a renewal evaluated at the time of its block is accepted.
+    let original = add_domain(&db, LIFETIME + GRACE_PERIOD + DAY).await;
+    let renew_at = |height: u64, periods: u32| {
+        let mut batch = Batch::new().with_block(
+            Block::new(height, format!("{:064x}", height)).with_time((original + DAY) as u64),
+        );
+        batch.push(Operation::RenewDomain {
+            address: ADDRESS.to_string(),
+            inscription: inscription(height as u32),
+            renewal: renewal(periods),
+            signature: None,
+        });
+        batch
+    };
+
+    // The limit of 10 lifetimes ahead is counted from the time of the block as well.
+    assert!(db.commit(renew_at(1, 10)).await.is_err());
+    db.commit(renew_at(2, 9)).await.unwrap();
+
+    assert_eq!(valid_from(&db).await, original + 9 * LIFETIME);
+}
+
+conformance_tests!(
+    renew_by_owner,
+    renew_by_other,
+    renew_during_grace_period,
+    renew_released,
+    renew_beyond_limit,
+    renew_unknown,
+    renew_with_validity_key,
+    renew_replayed,
+    renew_rolled_back,
+    renew_at_block_time,
+);
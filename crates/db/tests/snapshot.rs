@@ -0,0 +1,313 @@
+use std::io::Write;
+
+use db::{KvRepository, MemoryRepository, SqliteRepository, StateSnapshot, XDNSRepository};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use shared::common::{DbError, Error};
+use xdns_data::models::subdomain::RData;
+
+#[macro_use]
+mod common;
+
+use common::{
+    add_mx, add_subdomain, add_www, batch, block, fill, inscription, register, register_at,
+    ADDRESS, DAY,
+};
+
+/// Commits [`fill`], and an expired domain without a block.
+async fn fill_with_expired<R: XDNSRepository>(db: &R) {
+    fill(db).await;
+
+    db.commit(batch(vec![register(5, "expired.o", 380 * DAY)]))
+        .await
+        .unwrap();
+}
+
+/// Imports a snapshot and checks the target ends up with the same state.
+async fn import_into<R: XDNSRepository>(target: R, snapshot: &StateSnapshot) {
+    target
+        .import_snapshot(snapshot.clone(), snapshot.hash())
+        .await
+        .unwrap();
+
+    assert_eq!(target.get_tip().await.unwrap(), Some(block(7)));
+    assert_eq!(target.get_domain("example.o").await.unwrap().0, ADDRESS);
+    assert_eq!(target.list_subdomains("example.o").await.unwrap().len(), 2);
+    assert_eq!(
+        target.get_subdomain("example.o", "@.").await.unwrap()[0]
+            .1
+            .rdata
+            .to_string(),
+        "10 mail.example.o"
+    );
+    assert_eq!(target.get_validity("example.o").await.unwrap().0, ADDRESS);
+    assert_eq!(
+        target.get_data("example.o").await.unwrap()[0].1.data,
+        b"custom data"
+    );
+    assert!(target.get_domain("expired.o").await.is_err());
+
+    assert_eq!(
+        target.export_snapshot().await.unwrap().hash(),
+        snapshot.hash()
+    );
+
+    // New records get ids after the imported ones.
+    target
+        .commit(batch(vec![add_subdomain(
+            6,
+            "mail.",
+            RData::A("10.0.0.25".parse().unwrap()),
+        )]))
+        .await
+        .unwrap();
+    assert_eq!(target.list_subdomains("example.o").await.unwrap().len(), 3);
+}
+
+async fn round_trip<R: XDNSRepository>(db: R) {
+    fill_with_expired(&db).await;
+
+    let snapshot = db.export_snapshot().await.unwrap();
+    assert_eq!(snapshot.height(), Some(7));
+    assert_eq!(
+        snapshot.names().collect::<Vec<&str>>(),
+        vec!["example.o", "expired.o"]
+    );
+
+    let decoded = StateSnapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.block(), snapshot.block());
+    assert_eq!(decoded.hash(), snapshot.hash());
+
+    // Snapshots can be imported into any backend.
+    import_into(SqliteRepository::new_memory().await.unwrap(), &decoded).await;
+    import_into(MemoryRepository::new_memory().await.unwrap(), &decoded).await;
+    import_into(KvRepository::new_memory().await.unwrap(), &decoded).await;
+}
+
+async fn empty<R: XDNSRepository>(db: R) {
+    let snapshot = db.export_snapshot().await.unwrap();
+
+    assert!(snapshot.is_empty());
+    assert_eq!(snapshot.block(), None);
+
+    let target = MemoryRepository::default();
+    target
+        .import_snapshot(snapshot.clone(), snapshot.hash())
+        .await
+        .unwrap();
+    assert_eq!(target.get_tip().await.unwrap(), None);
+    assert_eq!(
+        target.export_snapshot().await.unwrap().hash(),
+        snapshot.hash()
+    );
+}
+
+async fn import_into_non_empty<R: XDNSRepository>(db: R) {
+    fill_with_expired(&db).await;
+
+    let source = MemoryRepository::default();
+    source
+        .commit(batch(vec![register(10, "other.o", 0)]))
+        .await
+        .unwrap();
+
+    let before = db.export_snapshot().await.unwrap();
+    let snapshot = source.export_snapshot().await.unwrap();
+    let hash = snapshot.hash().to_string();
+    let result = db.import_snapshot(snapshot, &hash).await;
+
+    assert!(result.is_err());
+    assert!(db.get_domain("other.o").await.is_err());
+    assert_eq!(db.export_snapshot().await.unwrap().hash(), before.hash());
+}
+
+async fn hash_ignores_ids<R: XDNSRepository>(db: R) {
+    let now = chrono::Utc::now().timestamp() as u64;
+
+    // The ids of the records differ, their order does not.
+    db.commit(batch(vec![
+        register_at(0, "example.o", now),
+        add_www(9, "example.o"),
+    ]))
+    .await
+    .unwrap();
+    assert!(db.remove_subdomain(&inscription(9)).await);
+    db.commit(batch(vec![add_mx(1, 10), add_www(2, "example.o")]))
+        .await
+        .unwrap();
+
+    let other = MemoryRepository::default();
+    other
+        .commit(batch(vec![
+            register_at(0, "example.o", now),
+            add_mx(1, 10),
+            add_www(2, "example.o"),
+        ]))
+        .await
+        .unwrap();
+
+    let (snapshot, other) = (
+        db.export_snapshot().await.unwrap(),
+        other.export_snapshot().await.unwrap(),
+    );
+    assert_eq!(snapshot.hash(), other.hash());
+
+    let reordered = MemoryRepository::default();
+    reordered
+        .commit(batch(vec![
+            register_at(0, "example.o", now),
+            add_www(2, "example.o"),
+            add_mx(1, 10),
+        ]))
+        .await
+        .unwrap();
+    assert_ne!(
+        reordered.export_snapshot().await.unwrap().hash(),
+        snapshot.hash()
+    );
+}
+
+async fn mismatch_leaves_target_empty<R: XDNSRepository>(db: R) {
+    let source = MemoryRepository::default();
+    fill_with_expired(&source).await;
+
+    let mut snapshot = serde_json::to_value(source.export_snapshot().await.unwrap()).unwrap();
+    snapshot["hash"] = serde_json::Value::String("0".repeat(64));
+    let snapshot: StateSnapshot = serde_json::from_value(snapshot).unwrap();
+
+    // Even when the altered hash is the one that is expected.
+    let result = db.import_snapshot(snapshot, &"0".repeat(64)).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::SnapshotMismatch { .. }))
+    ));
+    assert!(db.export_snapshot().await.unwrap().is_empty());
+}
+
+async fn refuses_unexpected_hash<R: XDNSRepository>(db: R) {
+    let source = MemoryRepository::default();
+    fill_with_expired(&source).await;
+
+    let result = db
+        .import_snapshot(source.export_snapshot().await.unwrap(), &"0".repeat(64))
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::SnapshotMismatch { .. }))
+    ));
+    assert!(db.export_snapshot().await.unwrap().is_empty());
+    assert_eq!(db.get_tip().await.unwrap(), None);
+}
+
+async fn altered_block_is_refused<R: XDNSRepository>(db: R) {
+    let source = MemoryRepository::default();
+    fill_with_expired(&source).await;
+
+    let snapshot = source.export_snapshot().await.unwrap();
+    let mut altered = serde_json::to_value(&snapshot).unwrap();
+    altered["block"]["height"] = serde_json::Value::from(8);
+
+    assert!(matches!(
+        StateSnapshot::from_bytes(&encode(b"XDNSSNAP", StateSnapshot::VERSION, &altered)),
+        Err(Error::Db(DbError::SnapshotMismatch { .. }))
+    ));
+
+    // The advertised hash is still the expected one, the block no longer hashes to it.
+    let altered: StateSnapshot = serde_json::from_value(altered).unwrap();
+    let result = db.import_snapshot(altered, snapshot.hash()).await;
+    assert!(matches!(
+        result,
+        Err(Error::Db(DbError::SnapshotMismatch { .. }))
+    ));
+    assert!(db.export_snapshot().await.unwrap().is_empty());
+    assert_eq!(db.get_tip().await.unwrap(), None);
+}
+
+async fn continues_after_block<R: XDNSRepository>(db: R) {
+    let source = MemoryRepository::default();
+    fill_with_expired(&source).await;
+
+    let snapshot = source.export_snapshot().await.unwrap();
+    let hash = snapshot.hash().to_string();
+    db.import_snapshot(snapshot, &hash).await.unwrap();
+
+    // Blocks up to the snapshot were indexed already, and can not be rolled back.
+    let result = db
+        .commit(batch(vec![register(6, "other.o", 0)]).with_block(block(6)))
+        .await;
+    assert!(matches!(result, Err(Error::Db(DbError::BlockOrder { .. }))));
+    assert!(matches!(
+        db.rollback_to(6).await,
+        Err(Error::Db(DbError::Finalized { .. }))
+    ));
+
+    db.commit(batch(vec![register(6, "other.o", 0)]).with_block(block(8)))
+        .await
+        .unwrap();
+    assert_eq!(db.get_tip().await.unwrap(), Some(block(8)));
+    assert_eq!(db.rollback_to(7).await.unwrap(), 1);
+    assert!(db.get_domain("other.o").await.is_err());
+}
+
+conformance_tests!(
+    round_trip,
+    empty,
+    import_into_non_empty,
+    hash_ignores_ids,
+    mismatch_leaves_target_empty,
+    refuses_unexpected_hash,
+    altered_block_is_refused,
+    continues_after_block,
+);
+
+/// Encodes a snapshot the way [`StateSnapshot::write_to`] does, with any header and body.
+fn encode(magic: &[u8], version: u32, body: &serde_json::Value) -> Vec<u8> {
+    let mut bytes = magic.to_vec();
+    bytes.extend(version.to_le_bytes());
+
+    let mut encoder = GzEncoder::new(bytes, Compression::default());
+    encoder
+        .write_all(serde_json::to_string(body).unwrap().as_bytes())
+        .unwrap();
+    encoder.finish().unwrap()
+}
+
+fn is_invalid(result: shared::common::Result<StateSnapshot>) -> bool {
+    matches!(result, Err(Error::Db(DbError::InvalidSnapshot(_))))
+}
+
+#[tokio::test]
+async fn decode_rejects_invalid_snapshots() {
+    let source = MemoryRepository::default();
+    fill_with_expired(&source).await;
+
+    let snapshot = source.export_snapshot().await.unwrap();
+    let body = serde_json::to_value(&snapshot).unwrap();
+
+    assert!(StateSnapshot::from_bytes(&encode(b"XDNSSNAP", StateSnapshot::VERSION, &body)).is_ok());
+    assert!(is_invalid(StateSnapshot::from_bytes(b"XDNS")));
+    assert!(is_invalid(StateSnapshot::from_bytes(&encode(
+        b"NOTASNAP",
+        StateSnapshot::VERSION,
+        &body
+    ))));
+    assert!(is_invalid(StateSnapshot::from_bytes(&encode(
+        b"XDNSSNAP",
+        StateSnapshot::VERSION + 1,
+        &body
+    ))));
+
+    let bytes = snapshot.to_bytes().unwrap();
+    assert!(is_invalid(StateSnapshot::from_bytes(
+        &bytes[..bytes.len() / 2]
+    )));
+
+    // Altered rows no longer hash to the advertised hash.
+    let mut altered = body.clone();
+    altered["domains"][0]["domains"][0]["address"] = serde_json::Value::String("bc1q".to_string());
+
+    assert!(matches!(
+        StateSnapshot::from_bytes(&encode(b"XDNSSNAP", StateSnapshot::VERSION, &altered)),
+        Err(Error::Db(DbError::SnapshotMismatch { .. }))
+    ));
+}
@@ -1,17 +1,16 @@
+use std::net::Ipv4Addr;
+
 use db::XDNSRepository;
-use xdns_data::models::subdomain::{Class, Type};
+use xdns_data::models::subdomain::{Class, RData};
 use xdns_data::models::SubDomain;
 
-#[path = "./domain.rs"]
-mod domain;
+#[macro_use]
+mod common;
 
-use domain::add_domain;
+use common::add_domain;
 
-#[tokio::test]
-async fn add_subdomain() {
+async fn add_subdomain<R: XDNSRepository>(db: R) {
     let inscription_id_2 = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403i0";
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
     add_domain(&db).await;
 
     let result = db
@@ -21,10 +20,9 @@ async fn add_subdomain() {
             SubDomain {
                 domain: "example.o".to_string(),
                 subdomain: "test".to_string(),
-                rtype: Type::CNAME,
                 class: Class::IN,
                 ttl: 0,
-                rdata: "example.o".to_string(),
+                rdata: RData::CNAME("example.o".to_string()),
             },
         )
         .await;
@@ -32,12 +30,9 @@ async fn add_subdomain() {
     assert!(result);
 }
 
-#[tokio::test]
-async fn get_subdomain() {
+async fn get_subdomain<R: XDNSRepository>(db: R) {
     let inscription_id_2 = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403i0";
     let inscription_id_3 = "c17dd02a7f216f4b438ab1a303f518abfc4d4d01dcff8f023cf87c4403cb54cai0";
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
     add_domain(&db).await;
 
     let result = db
@@ -47,10 +42,9 @@ async fn get_subdomain() {
             SubDomain {
                 domain: "example.o".to_string(),
                 subdomain: "test".to_string(),
-                rtype: Type::CNAME,
                 class: Class::IN,
                 ttl: 0,
-                rdata: "example.o".to_string(),
+                rdata: RData::CNAME("example.o".to_string()),
             },
         )
         .await;
@@ -62,10 +56,9 @@ async fn get_subdomain() {
             SubDomain {
                 domain: "example.o".to_string(),
                 subdomain: "test".to_string(),
-                rtype: Type::CNAME,
                 class: Class::IN,
                 ttl: 0,
-                rdata: "example2.o".to_string(),
+                rdata: RData::CNAME("example2.o".to_string()),
             },
         )
         .await;
@@ -84,7 +77,7 @@ async fn get_subdomain() {
 
     'data: for d in data.iter() {
         for (_, subdomain) in subdomains.iter() {
-            if subdomain.rdata == *d {
+            if subdomain.rdata.to_string() == *d {
                 assert!(true);
                 continue 'data;
             }
@@ -93,11 +86,8 @@ async fn get_subdomain() {
     }
 }
 
-#[tokio::test]
-async fn get_subdomain_by_inscription() {
+async fn get_subdomain_by_inscription<R: XDNSRepository>(db: R) {
     let inscription_id_2 = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403i0";
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
     add_domain(&db).await;
 
     let result = db
@@ -107,10 +97,9 @@ async fn get_subdomain_by_inscription() {
             SubDomain {
                 domain: "example.o".to_string(),
                 subdomain: "test".to_string(),
-                rtype: Type::CNAME,
                 class: Class::IN,
                 ttl: 0,
-                rdata: "example.o".to_string(),
+                rdata: RData::CNAME("example.o".to_string()),
             },
         )
         .await;
@@ -121,15 +110,101 @@ async fn get_subdomain_by_inscription() {
     assert!(subdomain.is_ok());
 
     let subdomain = subdomain.unwrap().1;
-    assert_eq!(subdomain.rdata, "example.o");
+    assert_eq!(subdomain.rdata, RData::CNAME("example.o".to_string()));
+}
+
+async fn get_subdomain_inscriptions<R: XDNSRepository>(db: R) {
+    let inscription_id_2 = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403i0";
+    let inscription_id_3 = "c17dd02a7f216f4b438ab1a303f518abfc4d4d01dcff8f023cf87c4403cb54cai0";
+    add_domain(&db).await;
+
+    for (inscription, addr) in [
+        (inscription_id_2, Ipv4Addr::new(10, 0, 0, 1)),
+        (inscription_id_3, Ipv4Addr::new(10, 0, 0, 2)),
+    ] {
+        let result = db
+            .add_subdomain(
+                "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5",
+                inscription,
+                SubDomain {
+                    domain: "example.o".to_string(),
+                    subdomain: "www.".to_string(),
+                    class: Class::IN,
+                    ttl: 0,
+                    rdata: RData::A(addr),
+                },
+            )
+            .await;
+
+        assert!(result);
+    }
+
+    let mut inscriptions = db
+        .get_subdomain_inscriptions("example.o", "www.")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(inscription, subdomain)| (inscription, subdomain.rdata))
+        .collect::<Vec<_>>();
+    inscriptions.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(
+        inscriptions,
+        vec![
+            (
+                inscription_id_2.to_string(),
+                RData::A(Ipv4Addr::new(10, 0, 0, 1))
+            ),
+            (
+                inscription_id_3.to_string(),
+                RData::A(Ipv4Addr::new(10, 0, 0, 2))
+            ),
+        ]
+    );
+    assert!(db
+        .get_subdomain_inscriptions("example.o", "missing.")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(db
+        .get_subdomain_inscriptions("missing.o", "www.")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+async fn get_subdomain_mx_priority<R: XDNSRepository>(db: R) {
+    let inscription_id_2 = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403i0";
+    add_domain(&db).await;
+
+    let rdata = RData::MX {
+        priority: 10,
+        host: "mail.example.o".to_string(),
+    };
+
+    let result = db
+        .add_subdomain(
+            "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5",
+            inscription_id_2,
+            SubDomain {
+                domain: "example.o".to_string(),
+                subdomain: "@.".to_string(),
+                class: Class::IN,
+                ttl: 0,
+                rdata: rdata.clone(),
+            },
+        )
+        .await;
+
+    assert!(result);
+
+    let subdomain = db.get_subdomain_by_inscription(inscription_id_2).await;
+    assert_eq!(subdomain.unwrap().1.rdata, rdata);
 }
 
-#[tokio::test]
-async fn remove_subdomains() {
+async fn remove_subdomains<R: XDNSRepository>(db: R) {
     let inscription_id_2 = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403i0";
     let inscription_id_3 = "c17dd02a7f216f4b438ab1a303f518abfc4d4d01dcff8f023cf87c4403cb54cai0";
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
     add_domain(&db).await;
 
     let result = db
@@ -139,10 +214,9 @@ async fn remove_subdomains() {
             SubDomain {
                 domain: "example.o".to_string(),
                 subdomain: "test".to_string(),
-                rtype: Type::CNAME,
                 class: Class::IN,
                 ttl: 0,
-                rdata: "example.o".to_string(),
+                rdata: RData::CNAME("example.o".to_string()),
             },
         )
         .await;
@@ -154,10 +228,9 @@ async fn remove_subdomains() {
             SubDomain {
                 domain: "example.o".to_string(),
                 subdomain: "test".to_string(),
-                rtype: Type::CNAME,
                 class: Class::IN,
                 ttl: 0,
-                rdata: "example2.o".to_string(),
+                rdata: RData::CNAME("example2.o".to_string()),
             },
         )
         .await;
@@ -169,11 +242,8 @@ async fn remove_subdomains() {
     assert!(result);
 }
 
-#[tokio::test]
-async fn remove_subdomain() {
+async fn remove_subdomain<R: XDNSRepository>(db: R) {
     let inscription_id_2 = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403i0";
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
     add_domain(&db).await;
 
     let result = db
@@ -183,10 +253,9 @@ async fn remove_subdomain() {
             SubDomain {
                 domain: "example.o".to_string(),
                 subdomain: "test".to_string(),
-                rtype: Type::CNAME,
                 class: Class::IN,
                 ttl: 0,
-                rdata: "example.o".to_string(),
+                rdata: RData::CNAME("example.o".to_string()),
             },
         )
         .await;
@@ -196,3 +265,52 @@ async fn remove_subdomain() {
     let result = db.remove_subdomain(inscription_id_2).await;
     assert!(result);
 }
+
+async fn get_subdomain_names<R: XDNSRepository>(db: R) {
+    add_domain(&db).await;
+
+    let inscriptions = [
+        "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813404i0",
+        "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813405i0",
+        "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813406i0",
+    ];
+
+    for (inscription, subdomain) in inscriptions.iter().zip(["*.", "www.", "www."]) {
+        let result = db
+            .add_subdomain(
+                "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5",
+                inscription,
+                SubDomain {
+                    domain: "example.o".to_string(),
+                    subdomain: subdomain.to_string(),
+                    class: Class::IN,
+                    ttl: 0,
+                    rdata: RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+                },
+            )
+            .await;
+
+        assert!(result);
+    }
+
+    let mut names = db.get_subdomain_names("example.o").await.unwrap();
+    names.sort();
+
+    assert_eq!(names, vec!["*.".to_string(), "www.".to_string()]);
+    assert!(db
+        .get_subdomain_names("missing.o")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+conformance_tests!(
+    add_subdomain,
+    get_subdomain,
+    get_subdomain_by_inscription,
+    get_subdomain_inscriptions,
+    get_subdomain_mx_priority,
+    remove_subdomains,
+    remove_subdomain,
+    get_subdomain_names,
+);
@@ -1,24 +1,22 @@
 use db::XDNSRepository;
 use xdns_data::models::{Algorithm, Credentials, Validity, ValidityTransfer};
 
-#[path = "./domain.rs"]
-mod domain;
+#[macro_use]
+mod common;
 
-use domain::add_domain;
+use common::add_domain;
 
 const INSCRIPTION_ID: &'static str =
     "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0";
 
-async fn db_setup_boilerplate() -> db::Repository {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
+async fn db_setup_boilerplate<R: XDNSRepository>(db: R) -> R {
     add_domain(&db).await;
     add_validity_helper(INSCRIPTION_ID, &db).await;
 
     db
 }
 
-async fn add_validity_helper(id: &str, db: &db::Repository) {
+async fn add_validity_helper<R: XDNSRepository>(id: &str, db: &R) {
     let validity = Validity {
         domain: "example.o".to_string(),
         credentials: Credentials::new(Algorithm::Ed25519, "xiler".to_string()),
@@ -34,11 +32,7 @@ async fn add_validity_helper(id: &str, db: &db::Repository) {
     assert!(result);
 }
 
-#[tokio::test]
-async fn add_validity() {
-    let db = db::Repository::new_memory().await;
-    db.migrate().await;
-
+async fn add_validity<R: XDNSRepository>(db: R) {
     for i in 0..2 {
         let validity = Validity {
             domain: "example.o".to_string(),
@@ -56,9 +50,8 @@ async fn add_validity() {
     }
 }
 
-#[tokio::test]
-async fn get_validity() {
-    let db = db_setup_boilerplate().await;
+async fn get_validity<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     let result = db.get_validity("example.o").await;
     assert!(result.is_ok());
@@ -69,9 +62,8 @@ async fn get_validity() {
     assert_eq!(result.1.credentials.public_key, "xiler");
 }
 
-#[tokio::test]
-async fn get_validity_by_inscription() {
-    let db = db_setup_boilerplate().await;
+async fn get_validity_by_inscription<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     let result = db.get_validity_by_inscription(INSCRIPTION_ID).await;
     assert!(result.is_ok());
@@ -82,9 +74,8 @@ async fn get_validity_by_inscription() {
     assert_eq!(result.1.credentials.public_key, "xiler");
 }
 
-#[tokio::test]
-async fn remove_validity() {
-    let db = db_setup_boilerplate().await;
+async fn remove_validity<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     for i in 0..2 {
         let result = db.remove_validity("example.o").await;
@@ -92,9 +83,8 @@ async fn remove_validity() {
     }
 }
 
-#[tokio::test]
-async fn remove_validity_by_inscription() {
-    let db = db_setup_boilerplate().await;
+async fn remove_validity_by_inscription<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     for i in 0..2 {
         let result = db.remove_validity_by_inscription(INSCRIPTION_ID).await;
@@ -102,9 +92,8 @@ async fn remove_validity_by_inscription() {
     }
 }
 
-#[tokio::test]
-async fn update_validity() {
-    let db = db_setup_boilerplate().await;
+async fn update_validity<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     let transfer = ValidityTransfer {
         domain: "example.o".to_string(),
@@ -123,9 +112,8 @@ async fn update_validity() {
     assert_eq!(result.1.credentials.public_key, "hello");
 }
 
-#[tokio::test]
-async fn update_validity_delete() {
-    let db = db_setup_boilerplate().await;
+async fn update_validity_delete<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     let transfer = ValidityTransfer {
         domain: "example.o".to_string(),
@@ -139,9 +127,8 @@ async fn update_validity_delete() {
     assert!(result.is_err());
 }
 
-#[tokio::test]
-async fn update_validity_by_inscription() {
-    let db = db_setup_boilerplate().await;
+async fn update_validity_by_inscription<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     let transfer = ValidityTransfer {
         domain: "xiler.o".to_string(),
@@ -166,9 +153,8 @@ async fn update_validity_by_inscription() {
     assert_eq!(result.1.credentials.public_key, "hello");
 }
 
-#[tokio::test]
-async fn update_validity_by_inscription_delete() {
-    let db = db_setup_boilerplate().await;
+async fn update_validity_by_inscription_delete<R: XDNSRepository>(db: R) {
+    let db = db_setup_boilerplate(db).await;
 
     let transfer = ValidityTransfer {
         domain: "xiler.o".to_string(),
@@ -187,3 +173,15 @@ async fn update_validity_by_inscription_delete() {
     let result = db.get_validity_by_inscription(INSCRIPTION_ID).await;
     assert!(result.is_err());
 }
+
+conformance_tests!(
+    add_validity,
+    get_validity,
+    get_validity_by_inscription,
+    remove_validity,
+    remove_validity_by_inscription,
+    update_validity,
+    update_validity_delete,
+    update_validity_by_inscription,
+    update_validity_by_inscription_delete,
+);
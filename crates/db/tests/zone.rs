@@ -0,0 +1,392 @@
+use db::{Batch, Block, MemoryRepository, Operation, XDNSRepository, ZoneExporter, ZoneTransfer};
+use xdns_data::models::subdomain::{Class, RData};
+use xdns_data::models::{SubDomain, Zone};
+
+#[macro_use]
+mod common;
+
+use common::{inscription, register, ADDRESS};
+
+fn record(subdomain: &str, ttl: u32, rdata: RData) -> SubDomain {
+    SubDomain {
+        domain: "example.o".to_string(),
+        subdomain: subdomain.to_string(),
+        class: Class::IN,
+        ttl,
+        rdata,
+    }
+}
+
+fn records() -> Vec<SubDomain> {
+    vec![
+        record("www.", 30, RData::CNAME("example.o".to_string())),
+        record("mail.", 300, RData::A("10.0.0.25".parse().unwrap())),
+        record("@.", 60, RData::A("93.184.216.34".parse().unwrap())),
+        record(
+            "@.",
+            3600,
+            RData::MX {
+                priority: 10,
+                host: "mail.example.o".to_string(),
+            },
+        ),
+        record("mail.", 300, RData::AAAA("2001:db8::25".parse().unwrap())),
+        record("@.", 3600, RData::NS("ns1.provider.net".to_string())),
+        record("*.dev.", 600, RData::A("10.0.0.1".parse().unwrap())),
+    ]
+}
+
+async fn fill<R: XDNSRepository>(db: &R) {
+    let mut batch = Batch::new();
+    batch.push(register(0, "example.o", 0));
+
+    for (i, subdomain) in records().into_iter().enumerate() {
+        batch.push(Operation::AddSubdomain {
+            address: ADDRESS.to_string(),
+            inscription: inscription(i as u32 + 1),
+            subdomain,
+        });
+    }
+
+    db.commit(batch).await.unwrap();
+}
+
+async fn exports_zone<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+
+    let zone = ZoneExporter::new().export(&db, "example.o").await.unwrap();
+    let lines = zone.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines[0], "$ORIGIN example.o.");
+    assert_eq!(
+        lines[1],
+        "@\t60\tIN\tSOA\tns1.provider.net. hostmaster.example.o. ("
+    );
+    assert_eq!(lines[2].trim(), "8 ; serial");
+    assert_eq!(
+        lines[7..],
+        [
+            "\t3600\tIN\tNS\tns1.provider.net.",
+            "\t3600\tIN\tMX\t10 mail.example.o.",
+            "\t60\tIN\tA\t93.184.216.34",
+            "*.dev\t600\tIN\tA\t10.0.0.1",
+            "mail\t300\tIN\tA\t10.0.0.25",
+            "\t300\tIN\tAAAA\t2001:db8::25",
+            "www\t30\tIN\tCNAME\texample.o.",
+        ]
+    );
+}
+
+async fn round_trips<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+
+    let zone = ZoneExporter::new()
+        .with_primary("ns1.example.o")
+        .with_timers(7200, 900, 1209600, 300)
+        .export(&db, "example.o")
+        .await
+        .unwrap();
+    let zone = Zone::parse_with_origin(&zone, None).unwrap();
+
+    let soa = &zone.records[0];
+    assert_eq!(soa.rtype, "SOA");
+    assert_eq!(soa.ttl, 300);
+    assert_eq!(
+        soa.rdata,
+        vec![
+            "ns1.example.o.",
+            "hostmaster.example.o.",
+            "8",
+            "7200",
+            "900",
+            "1209600",
+            "300"
+        ]
+    );
+
+    let import = zone.import("example.o").unwrap();
+    assert_eq!(import.skipped.len(), 1);
+
+    let mut expected = records()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let mut actions = import.actions();
+    expected.sort();
+    actions.sort();
+    assert_eq!(actions, expected);
+}
+
+async fn serial_increases<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+    let serial = |zone: String| zone.lines().nth(2).unwrap().trim().to_string();
+
+    let before = ZoneExporter::new().export(&db, "example.o").await.unwrap();
+
+    let mut batch = Batch::new();
+    batch.push(Operation::RemoveSubdomain {
+        inscription: inscription(1),
+    });
+    db.commit(batch).await.unwrap();
+
+    let after = ZoneExporter::new().export(&db, "example.o").await.unwrap();
+    assert_eq!(serial(before), "8 ; serial");
+    assert_eq!(serial(after), "9 ; serial");
+}
+
+async fn serial_follows_history<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+    let exporter = ZoneExporter::new();
+    let serial = |transfer: ZoneTransfer| match transfer {
+        ZoneTransfer::Full { soa, .. }
+        | ZoneTransfer::Incremental { soa, .. }
+        | ZoneTransfer::UpToDate { soa } => soa.serial,
+    };
+
+    // Changes to other domains advance the history, but not the serial of the zone.
+    let mut batch = Batch::new();
+    batch.push(register(9, "other.o", 0));
+    db.commit(batch).await.unwrap();
+    assert_eq!(
+        serial(exporter.transfer(&db, "example.o", None).await.unwrap()),
+        8
+    );
+
+    assert!(db.remove_subdomains("example.o", "www.").await);
+
+    match exporter.transfer(&db, "example.o", Some(8)).await.unwrap() {
+        ZoneTransfer::Incremental {
+            from,
+            soa,
+            deleted,
+            added,
+        } => {
+            assert_eq!((from.serial, soa.serial), (8, 10));
+            assert_eq!(deleted.len(), 1);
+            assert!(added.is_empty());
+        }
+        other => panic!("expected an incremental transfer, got {:?}", other),
+    }
+}
+
+async fn missing_domain<R: XDNSRepository>(db: R) {
+    let error = ZoneExporter::new()
+        .export(&db, "example.o")
+        .await
+        .unwrap_err();
+    assert!(error.is_not_found());
+}
+
+async fn transfers_zone<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+    let exporter = ZoneExporter::new();
+
+    match exporter.transfer(&db, "example.o", None).await.unwrap() {
+        ZoneTransfer::Full { soa, records } => {
+            assert_eq!(soa.serial, 8);
+            assert_eq!(soa.primary, "ns1.provider.net.");
+            assert_eq!(records.len(), 7);
+        }
+        other => panic!("expected a full transfer, got {:?}", other),
+    }
+
+    assert!(matches!(
+        exporter.transfer(&db, "example.o", Some(8)).await.unwrap(),
+        ZoneTransfer::UpToDate { soa } if soa.serial == 8
+    ));
+    assert!(exporter.transfer(&db, "other.o", None).await.is_err());
+}
+
+async fn reads_soa<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+    let exporter = ZoneExporter::new();
+
+    let soa = exporter.soa_of(&db, "example.o").await.unwrap();
+    assert_eq!(soa.serial, 8);
+    assert_eq!(soa.primary, "ns1.provider.net.");
+
+    assert!(db.remove_subdomains("example.o", "www.").await);
+    match exporter.transfer(&db, "example.o", None).await.unwrap() {
+        ZoneTransfer::Full { soa, .. } => {
+            assert_eq!(exporter.soa_of(&db, "example.o").await.unwrap(), soa)
+        }
+        other => panic!("expected a full transfer, got {:?}", other),
+    }
+
+    assert!(exporter
+        .soa_of(&db, "other.o")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+async fn transfers_changes<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+
+    let mut batch = Batch::new();
+    batch.push(Operation::RemoveSubdomain {
+        inscription: inscription(1),
+    });
+    batch.push(Operation::AddSubdomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(9),
+        subdomain: record("www.", 30, RData::A("10.0.0.80".parse().unwrap())),
+    });
+    db.commit(batch).await.unwrap();
+
+    let exporter = ZoneExporter::new();
+
+    match exporter.transfer(&db, "example.o", Some(8)).await.unwrap() {
+        ZoneTransfer::Incremental {
+            from,
+            soa,
+            deleted,
+            added,
+        } => {
+            assert_eq!((from.serial, soa.serial), (8, 10));
+            assert_eq!(deleted.len(), 1);
+            assert_eq!(deleted[0].to_string(), records()[0].to_string());
+            assert_eq!(added.len(), 1);
+            assert_eq!(added[0].to_string(), "DNS example.o www. A IN 30 10.0.0.80");
+        }
+        other => panic!("expected an incremental transfer, got {:?}", other),
+    }
+
+    // Only the domain was registered at the first serial.
+    match exporter.transfer(&db, "example.o", Some(1)).await.unwrap() {
+        ZoneTransfer::Incremental { deleted, added, .. } => {
+            assert!(deleted.is_empty());
+            assert_eq!(added.len(), 7);
+        }
+        other => panic!("expected an incremental transfer, got {:?}", other),
+    }
+}
+
+async fn transfers_standalone_changes_incrementally<R: XDNSRepository>(db: R) {
+    fill(&db).await;
+
+    // Standalone methods are recorded in the history like any batch.
+    assert!(
+        db.add_subdomain(
+            ADDRESS,
+            &inscription(9),
+            record("www.", 30, RData::A("10.0.0.80".parse().unwrap())),
+        )
+        .await
+    );
+
+    match ZoneExporter::new()
+        .transfer(&db, "example.o", Some(8))
+        .await
+        .unwrap()
+    {
+        ZoneTransfer::Incremental { deleted, added, .. } => {
+            assert!(deleted.is_empty());
+            assert_eq!(added.len(), 1);
+            assert_eq!(added[0].to_string(), "DNS example.o www. A IN 30 10.0.0.80");
+        }
+        other => panic!("expected an incremental transfer, got {:?}", other),
+    }
+}
+
+async fn transfers_rolled_back_changes_in_full<R: XDNSRepository>(db: R) {
+    let mut batch = Batch::new().with_block(Block::new(1, "01"));
+    batch.push(register(0, "example.o", 0));
+    db.commit(batch).await.unwrap();
+
+    let mut batch = Batch::new().with_block(Block::new(2, "02"));
+    batch.push(Operation::AddSubdomain {
+        address: ADDRESS.to_string(),
+        inscription: inscription(1),
+        subdomain: records().remove(0),
+    });
+    db.commit(batch).await.unwrap();
+    db.rollback_to(1).await.unwrap();
+
+    match ZoneExporter::new()
+        .transfer(&db, "example.o", Some(2))
+        .await
+        .unwrap()
+    {
+        ZoneTransfer::Full { soa, records } => {
+            assert_eq!(soa.serial, 3);
+            assert!(records.is_empty());
+        }
+        other => panic!("expected a full transfer, got {:?}", other),
+    }
+}
+
+async fn transfers_imported_changes_in_full<R: XDNSRepository>(db: R) {
+    let source = MemoryRepository::default();
+    fill(&source).await;
+
+    let snapshot = source.export_snapshot().await.unwrap();
+    let hash = snapshot.hash().to_string();
+    db.import_snapshot(snapshot, &hash).await.unwrap();
+
+    let exporter = ZoneExporter::new();
+    let imported = match exporter.transfer(&db, "example.o", None).await.unwrap() {
+        ZoneTransfer::Full { soa, .. } => soa.serial,
+        other => panic!("expected a full transfer, got {:?}", other),
+    };
+
+    // Without the imported rows, replaying the removals would wrongly lead to the current records.
+    for subdomain in ["www.", "mail.", "@.", "*.dev."] {
+        assert!(db.remove_subdomains("example.o", subdomain).await);
+    }
+
+    match exporter
+        .transfer(&db, "example.o", Some(imported))
+        .await
+        .unwrap()
+    {
+        ZoneTransfer::Full { soa, records } => {
+            assert!(soa.serial > imported);
+            assert!(records.is_empty());
+        }
+        other => panic!("expected a full transfer, got {:?}", other),
+    }
+}
+
+conformance_tests!(
+    exports_zone,
+    round_trips,
+    serial_increases,
+    serial_follows_history,
+    missing_domain,
+    transfers_zone,
+    reads_soa,
+    transfers_changes,
+    transfers_standalone_changes_incrementally,
+    transfers_rolled_back_changes_in_full,
+    transfers_imported_changes_in_full,
+);
+
+#[test]
+fn writes_without_records() {
+    let zone = ZoneExporter::new().write("example.o", 1, &[]);
+
+    assert!(
+        zone.starts_with("$ORIGIN example.o.\n@\t60\tIN\tSOA\texample.o. hostmaster.example.o. (")
+    );
+    assert_eq!(zone.lines().count(), 7);
+}
+
+#[test]
+fn types_are_grouped() {
+    let records = vec![
+        record("@.", 30, RData::A("10.0.0.1".parse().unwrap())),
+        record("@.", 30, RData::NS("ns1.example.o".to_string())),
+        record("@.", 30, RData::A("10.0.0.2".parse().unwrap())),
+    ];
+    let zone = ZoneExporter::new().write("example.o", 1, &records);
+
+    assert_eq!(
+        zone.lines().skip(7).collect::<Vec<_>>(),
+        [
+            "\t30\tIN\tNS\tns1.example.o.",
+            "\t30\tIN\tA\t10.0.0.1",
+            "\t30\tIN\tA\t10.0.0.2",
+        ]
+    );
+}
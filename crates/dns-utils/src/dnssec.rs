@@ -0,0 +1,699 @@
+use std::cmp::Ordering;
+
+use ed25519_dalek::SigningKey;
+use ring::digest;
+use ring::signature::{
+    RsaPublicKeyComponents, UnparsedPublicKey, ECDSA_P256_SHA256_FIXED, ED25519,
+    RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY,
+};
+use sha2::{Digest, Sha256};
+use shared::prelude::*;
+use xdns_data::traits::Sign;
+
+use crate::packets::byte_packet_buffer::{BytePacketBuffer, TCP_BUFFER_MAX_SIZE};
+use crate::packets::dns_record::DnsRecord;
+use crate::packets::query_type::QueryType;
+
+/// The DNSSEC algorithm number of RSA/SHA-256. (RFC 5702)
+pub const ALGORITHM_RSASHA256: u8 = 8;
+/// The DNSSEC algorithm number of ECDSA with curve P-256 and SHA-256. (RFC 6605)
+pub const ALGORITHM_ECDSAP256SHA256: u8 = 13;
+/// The DNSSEC algorithm number of Ed25519. (RFC 8080)
+pub const ALGORITHM_ED25519: u8 = 15;
+/// The DS digest type of SHA-1. (RFC 4034)
+pub const DIGEST_SHA1: u8 = 1;
+/// The DS digest type of SHA-256. (RFC 4509)
+pub const DIGEST_SHA256: u8 = 2;
+/// The flag of a `DNSKEY` that signs the records of its zone.
+pub const ZONE_KEY_FLAG: u16 = 0x0100;
+/// The flag of an `NSEC3` record that may skip unsigned delegations. (RFC 5155 section 3.1.2.1)
+pub const OPT_OUT_FLAG: u8 = 0x01;
+/// The flags of a key that signs a whole zone and is its secure entry point. (RFC 4034 section 2.1.1)
+pub const DNSKEY_FLAGS: u16 = 257;
+/// The protocol field of a `DNSKEY` record, which is always 3.
+pub const DNSKEY_PROTOCOL: u8 = 3;
+/// The longest label a name can have.
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// The key a node signs its answers with, online, as the single key of every zone it serves.
+pub struct NodeKey {
+    key: SigningKey,
+}
+
+impl NodeKey {
+    /// Creates a node key from its 32 byte Ed25519 secret.
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(secret),
+        }
+    }
+
+    /// Creates a node key from its hex encoded 32 byte Ed25519 secret.
+    ///
+    /// # Returns
+    ///
+    /// The key, or an error if the secret is not 32 bytes of hex.
+    pub fn from_hex(secret: &str) -> Result<Self> {
+        let secret =
+            hex::decode(secret.trim()).map_err(|e| CryptoError::InvalidEncoding(e.to_string()))?;
+        let secret: [u8; 32] = secret.try_into().map_err(|secret: Vec<u8>| {
+            CryptoError::InvalidKey(format!("expected 32 bytes, got {}", secret.len()))
+        })?;
+
+        Ok(Self::from_bytes(&secret))
+    }
+
+    /// The `DNSKEY` record of the key, published at the apex of every zone it signs.
+    ///
+    /// # Arguments
+    ///
+    /// * `zone` - The apex of the zone. (eg `example.o`)
+    /// * `ttl` - The time to live of the record.
+    pub fn dnskey(&self, zone: &str, ttl: u32) -> DnsRecord {
+        DnsRecord::DNSKEY {
+            domain: zone.to_lowercase(),
+            flags: DNSKEY_FLAGS,
+            protocol: DNSKEY_PROTOCOL,
+            algorithm: ALGORITHM_ED25519,
+            public_key: self.key.verifying_key().to_bytes().to_vec(),
+            ttl,
+        }
+    }
+
+    /// The tag that identifies the key in signatures.
+    pub fn key_tag(&self) -> u16 {
+        // The tag does not depend on the owner of the key.
+        key_tag(&self.dnskey("", 0)).unwrap_or_default()
+    }
+
+    /// Signs a set of records with the same owner and type.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - The apex of the zone the records belong to.
+    /// * `rrset` - The records.
+    /// * `inception` - The start of the validity period, in seconds since the epoch.
+    /// * `expiration` - The end of the validity period, in seconds since the epoch.
+    ///
+    /// # Returns
+    ///
+    /// The `RRSIG` record, or an error if the set is empty.
+    pub fn sign(
+        &self,
+        signer: &str,
+        rrset: &[DnsRecord],
+        inception: u32,
+        expiration: u32,
+    ) -> Result<DnsRecord> {
+        let first = rrset
+            .first()
+            .ok_or_else(|| Error::Other("Cannot sign an empty set of records".to_string()))?;
+
+        let mut rrsig = DnsRecord::RRSIG {
+            domain: first.get_domain().to_lowercase(),
+            type_covered: first.type_of().to_num(),
+            algorithm: ALGORITHM_ED25519,
+            labels: labels(first.get_domain()),
+            original_ttl: first.get_ttl(),
+            expiration,
+            inception,
+            key_tag: self.key_tag(),
+            signer: signer.to_lowercase(),
+            signature: Vec::new(),
+            ttl: first.get_ttl(),
+        };
+
+        let data = signed_data(&rrsig, rrset)?;
+        if let DnsRecord::RRSIG {
+            ref mut signature, ..
+        } = rrsig
+        {
+            *signature = self.key.sign_detached(&data);
+        }
+
+        Ok(rrsig)
+    }
+}
+
+/// Writes a record in its canonical form, with lowercase names and without compression. (RFC 4034 section 6.2)
+///
+/// # Returns
+///
+/// The whole record and its record data.
+fn canonical(record: &DnsRecord) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut record = record.clone();
+    match record {
+        DnsRecord::A { ref mut domain, .. }
+        | DnsRecord::AAAA { ref mut domain, .. }
+        | DnsRecord::TXT { ref mut domain, .. }
+        | DnsRecord::DS { ref mut domain, .. }
+        | DnsRecord::DNSKEY { ref mut domain, .. }
+        | DnsRecord::NSEC3 { ref mut domain, .. }
+        | DnsRecord::UNKNOWN { ref mut domain, .. } => *domain = domain.to_lowercase(),
+        DnsRecord::NS {
+            ref mut domain,
+            ref mut host,
+            ..
+        }
+        | DnsRecord::CNAME {
+            ref mut domain,
+            ref mut host,
+            ..
+        }
+        | DnsRecord::MX {
+            ref mut domain,
+            ref mut host,
+            ..
+        }
+        | DnsRecord::NSEC {
+            ref mut domain,
+            next: ref mut host,
+            ..
+        }
+        | DnsRecord::RRSIG {
+            ref mut domain,
+            signer: ref mut host,
+            ..
+        } => {
+            *domain = domain.to_lowercase();
+            *host = host.to_lowercase();
+        }
+        DnsRecord::SOA {
+            ref mut domain,
+            ref mut primary,
+            ref mut hostmaster,
+            ..
+        } => {
+            *domain = domain.to_lowercase();
+            *primary = primary.to_lowercase();
+            *hostmaster = hostmaster.to_lowercase();
+        }
+        DnsRecord::OPT { .. } => {}
+    }
+
+    let mut buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+    let len = record.write(&mut buffer)?;
+    let wire = buffer.get_range(0, len)?.to_vec();
+
+    // The record data follows the owner, type, class, ttl and length.
+    buffer.seek(0)?;
+    buffer.read_qname(&mut String::new())?;
+    buffer.step(8)?;
+    let data_len = buffer.read_u16()? as usize;
+    let rdata = buffer.read_bytes(data_len)?;
+
+    Ok((wire, rdata))
+}
+
+/// The data an `RRSIG` signs: its own record data without the signature, followed by the
+/// canonical records in canonical order. (RFC 4034 section 3.1.8.1)
+/// Records synthesized from a wildcard are signed with the wildcard as their owner.
+///
+/// # Arguments
+///
+/// * `rrsig` - The signature, its signature field is ignored.
+/// * `rrset` - The signed records.
+///
+/// # Returns
+///
+/// The signed data, or an error if the record is not an `RRSIG`.
+pub fn signed_data(rrsig: &DnsRecord, rrset: &[DnsRecord]) -> Result<Vec<u8>> {
+    let (original_ttl, signed_labels) = match rrsig {
+        DnsRecord::RRSIG {
+            original_ttl,
+            labels,
+            ..
+        } => (*original_ttl, *labels as usize),
+        _ => return Err(Error::Other("Expected an RRSIG record".to_string())),
+    };
+
+    let mut unsigned = rrsig.clone();
+    if let DnsRecord::RRSIG {
+        ref mut signature, ..
+    } = unsigned
+    {
+        signature.clear();
+    }
+    let (_, mut data) = canonical(&unsigned)?;
+
+    let mut records = rrset
+        .iter()
+        .map(|record| {
+            let mut record = record.clone();
+            record.set_ttl(original_ttl);
+            if (labels(record.get_domain()) as usize) > signed_labels {
+                let owner = wildcard_of(record.get_domain(), signed_labels);
+                record.set_domain(&owner);
+            }
+            canonical(&record)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    records.sort_by(|a, b| a.1.cmp(&b.1));
+    records.dedup_by(|a, b| a.1 == b.1);
+
+    for (wire, _) in records {
+        data.extend(wire);
+    }
+
+    Ok(data)
+}
+
+/// Calculates the tag of a `DNSKEY` record. (RFC 4034 appendix B)
+///
+/// # Returns
+///
+/// The tag, or an error if the record is not a `DNSKEY`.
+pub fn key_tag(dnskey: &DnsRecord) -> Result<u16> {
+    if !matches!(dnskey, DnsRecord::DNSKEY { .. }) {
+        return Err(Error::Other("Expected a DNSKEY record".to_string()));
+    }
+
+    let (_, rdata) = canonical(dnskey)?;
+    let mut sum = rdata.iter().enumerate().fold(0u32, |sum, (i, byte)| {
+        sum + if i % 2 == 0 {
+            (*byte as u32) << 8
+        } else {
+            *byte as u32
+        }
+    });
+    sum += (sum >> 16) & 0xFFFF;
+
+    Ok((sum & 0xFFFF) as u16)
+}
+
+/// Calculates the digest of a `DNSKEY` that a `DS` record holds.
+///
+/// # Arguments
+///
+/// * `dnskey` - The key, owned by the apex of its zone.
+/// * `digest_type` - The digest type. (eg [`DIGEST_SHA256`])
+///
+/// # Returns
+///
+/// The digest, none if the digest type is not supported, or an error if the record is not a `DNSKEY`.
+pub fn ds_digest(dnskey: &DnsRecord, digest_type: u8) -> Result<Option<Vec<u8>>> {
+    if !matches!(dnskey, DnsRecord::DNSKEY { .. }) {
+        return Err(Error::Other("Expected a DNSKEY record".to_string()));
+    }
+
+    let mut buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+    buffer.write_qname(&dnskey.get_domain().to_lowercase())?;
+    let len = buffer.pos();
+    let mut data = buffer.get_range(0, len)?.to_vec();
+    data.extend(canonical(dnskey)?.1);
+
+    Ok(match digest_type {
+        DIGEST_SHA1 => Some(
+            digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &data)
+                .as_ref()
+                .to_vec(),
+        ),
+        DIGEST_SHA256 => Some(Sha256::digest(&data).to_vec()),
+        _ => None,
+    })
+}
+
+/// Creates the `DS` record the parent zone publishes for a `DNSKEY`. (RFC 4034 section 5.1.4)
+///
+/// # Arguments
+///
+/// * `dnskey` - The key of the child zone, owned by its apex.
+/// * `ttl` - The time to live of the record.
+///
+/// # Returns
+///
+/// The record, or an error if the record is not a `DNSKEY`.
+pub fn ds(dnskey: &DnsRecord, ttl: u32) -> Result<DnsRecord> {
+    let algorithm = match dnskey {
+        DnsRecord::DNSKEY { algorithm, .. } => *algorithm,
+        _ => return Err(Error::Other("Expected a DNSKEY record".to_string())),
+    };
+
+    Ok(DnsRecord::DS {
+        domain: dnskey.get_domain().to_lowercase(),
+        key_tag: key_tag(dnskey)?,
+        algorithm,
+        digest_type: DIGEST_SHA256,
+        digest: ds_digest(dnskey, DIGEST_SHA256)?.unwrap_or_default(),
+        ttl,
+    })
+}
+
+/// Whether a `DS` record holds the digest of a `DNSKEY`.
+pub fn ds_matches(ds: &DnsRecord, dnskey: &DnsRecord) -> Result<bool> {
+    let (tag, algorithm, digest_type, digest) = match ds {
+        DnsRecord::DS {
+            key_tag,
+            algorithm,
+            digest_type,
+            digest,
+            ..
+        } => (*key_tag, *algorithm, *digest_type, digest),
+        _ => return Err(Error::Other("Expected a DS record".to_string())),
+    };
+
+    Ok(match dnskey {
+        DnsRecord::DNSKEY {
+            algorithm: key_algorithm,
+            ..
+        } => {
+            *key_algorithm == algorithm
+                && tag == key_tag(dnskey)?
+                && ds.get_domain().eq_ignore_ascii_case(dnskey.get_domain())
+                && ds_digest(dnskey, digest_type)?.as_ref() == Some(digest)
+        }
+        _ => false,
+    })
+}
+
+/// Parses a `DS` record in presentation format, as trust anchors are published.
+/// (eg `. IN DS 20326 8 2 E06D44B8...`)
+///
+/// # Returns
+///
+/// The record, or an error if the text is not a `DS` record.
+pub fn parse_ds(text: &str) -> Result<DnsRecord> {
+    let invalid = |reason: &str| ParseError::new("trust anchor", text, reason);
+    let tokens = text.split_whitespace().collect::<Vec<_>>();
+
+    let position = tokens
+        .iter()
+        .position(|token| token.eq_ignore_ascii_case("DS"))
+        .ok_or_else(|| invalid("expected a DS record"))?;
+    if position == 0 || tokens.len() < position + 5 {
+        return Err(
+            invalid("expected an owner, key tag, algorithm, digest type and digest").into(),
+        );
+    }
+
+    let ttl = tokens[1..position]
+        .iter()
+        .find_map(|token| token.parse().ok())
+        .unwrap_or(0);
+    let number = |token: &str| token.parse().map_err(|_| invalid("invalid number"));
+    let digest =
+        hex::decode(tokens[position + 4..].concat()).map_err(|_| invalid("invalid digest"))?;
+
+    Ok(DnsRecord::DS {
+        domain: tokens[0].trim_end_matches('.').to_lowercase(),
+        key_tag: number(tokens[position + 1])?,
+        algorithm: number(tokens[position + 2])? as u8,
+        digest_type: number(tokens[position + 3])? as u8,
+        digest,
+        ttl,
+    })
+}
+
+/// Verifies a signature with the public key of a `DNSKEY`. (RFC 3110, RFC 6605, RFC 8080)
+fn verify_signature(
+    algorithm: u8,
+    public_key: &[u8],
+    data: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    match algorithm {
+        ALGORITHM_RSASHA256 => {
+            // The exponent length is a single byte, or two bytes after a zero.
+            let (len, offset) = match public_key {
+                [0, high, low, ..] => (u16::from_be_bytes([*high, *low]) as usize, 3),
+                [len, ..] => (*len as usize, 1),
+                [] => return Err(CryptoError::InvalidKey("empty RSA key".to_string()).into()),
+            };
+            if public_key.len() <= offset + len {
+                return Err(CryptoError::InvalidKey("truncated RSA key".to_string()).into());
+            }
+
+            let components = RsaPublicKeyComponents {
+                n: &public_key[offset + len..],
+                e: &public_key[offset..offset + len],
+            };
+            Ok(components
+                .verify(
+                    &RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY,
+                    data,
+                    signature,
+                )
+                .is_ok())
+        }
+        ALGORITHM_ECDSAP256SHA256 => {
+            // The key is the uncompressed point without its prefix.
+            let point = [&[0x04], public_key].concat();
+            Ok(UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, point)
+                .verify(data, signature)
+                .is_ok())
+        }
+        ALGORITHM_ED25519 => Ok(UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(data, signature)
+            .is_ok()),
+        _ => {
+            Err(CryptoError::UnsupportedAlgorithm(format!("DNSSEC algorithm {}", algorithm)).into())
+        }
+    }
+}
+
+/// Verifies the signature over a set of records, made with RSA/SHA-256, ECDSA P-256 or Ed25519.
+/// The validity period of the signature is not checked, see [`is_current`].
+///
+/// # Arguments
+///
+/// * `dnskey` - The key of the signer.
+/// * `rrsig` - The signature.
+/// * `rrset` - The signed records.
+///
+/// # Returns
+///
+/// Whether the signature is valid, or an error if the key is not supported.
+pub fn verify(dnskey: &DnsRecord, rrsig: &DnsRecord, rrset: &[DnsRecord]) -> Result<bool> {
+    let (public_key, algorithm) = match dnskey {
+        DnsRecord::DNSKEY {
+            public_key,
+            algorithm,
+            ..
+        } => (public_key, *algorithm),
+        _ => return Err(Error::Other("Expected a DNSKEY record".to_string())),
+    };
+    let (signature, signed_algorithm, signed_tag) = match rrsig {
+        DnsRecord::RRSIG {
+            signature,
+            algorithm,
+            key_tag,
+            ..
+        } => (signature, *algorithm, *key_tag),
+        _ => return Err(Error::Other("Expected an RRSIG record".to_string())),
+    };
+
+    if signed_algorithm != algorithm || signed_tag != key_tag(dnskey)? {
+        return Ok(false);
+    }
+
+    verify_signature(
+        algorithm,
+        public_key,
+        &signed_data(rrsig, rrset)?,
+        signature,
+    )
+}
+
+/// Whether the validity period of an `RRSIG` includes a moment.
+/// The times are compared in serial number arithmetic, so they keep working after they wrap around in 2106. (RFC 4034 section 3.1.5)
+///
+/// # Arguments
+///
+/// * `rrsig` - The signature.
+/// * `now` - The moment, in seconds since the epoch.
+pub fn is_current(rrsig: &DnsRecord, now: u32) -> bool {
+    match rrsig {
+        DnsRecord::RRSIG {
+            inception,
+            expiration,
+            ..
+        } => now.wrapping_sub(*inception) as i32 >= 0 && expiration.wrapping_sub(now) as i32 >= 0,
+        _ => false,
+    }
+}
+
+/// Hashes a name as the owners of `NSEC3` records are. (RFC 5155 section 5)
+///
+/// # Arguments
+///
+/// * `name` - The name.
+/// * `salt` - The salt of the zone.
+/// * `iterations` - The amount of additional times the hash is hashed.
+pub fn nsec3_hash(name: &str, salt: &[u8], iterations: u16) -> Result<Vec<u8>> {
+    let mut buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+    buffer.write_qname(&name.to_lowercase())?;
+    let len = buffer.pos();
+
+    let mut hash = buffer.get_range(0, len)?.to_vec();
+    for _ in 0..=iterations {
+        hash.extend_from_slice(salt);
+        hash = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &hash)
+            .as_ref()
+            .to_vec();
+    }
+
+    Ok(hash)
+}
+
+/// Encodes bytes in the base32 alphabet with the extended hex alphabet, without padding, as the
+/// hashes in the owners of `NSEC3` records are. (RFC 4648 section 7)
+pub fn base32hex(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(5) {
+        let mut block = [0u8; 5];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let bits = block
+            .iter()
+            .fold(0u64, |bits, byte| bits << 8 | *byte as u64);
+
+        for i in 0..(chunk.len() * 8).div_ceil(5) {
+            encoded.push(ALPHABET[(bits >> (35 - i * 5)) as usize & 0x1F] as char);
+        }
+    }
+
+    encoded
+}
+
+/// Groups records into sets with the same owner and type, in the order they first appear.
+/// Signatures and EDNS records are not part of any set.
+pub fn rrsets(records: &[DnsRecord]) -> Vec<Vec<DnsRecord>> {
+    let mut sets: Vec<Vec<DnsRecord>> = Vec::new();
+
+    for record in records
+        .iter()
+        .filter(|record| !matches!(record, DnsRecord::RRSIG { .. } | DnsRecord::OPT { .. }))
+    {
+        let set = sets.iter_mut().find(|set| {
+            set[0].type_of() == record.type_of()
+                && set[0]
+                    .get_domain()
+                    .eq_ignore_ascii_case(record.get_domain())
+        });
+
+        match set {
+            Some(set) => set.push(record.clone()),
+            None => sets.push(vec![record.clone()]),
+        }
+    }
+
+    sets
+}
+
+/// The amount of labels of a name, as counted by an `RRSIG`. A leading wildcard is not counted.
+pub fn labels(name: &str) -> u8 {
+    name.split('.')
+        .filter(|label| !label.is_empty())
+        .enumerate()
+        .filter(|(i, label)| !(*i == 0 && *label == "*"))
+        .count() as u8
+}
+
+/// The wildcard a name is synthesized from, given the amount of labels of the wildcard without its `*`.
+fn wildcard_of(name: &str, labels: usize) -> String {
+    let parts = name
+        .split('.')
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>();
+
+    std::iter::once("*")
+        .chain(parts[parts.len().saturating_sub(labels)..].iter().copied())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Compares two names in canonical order, label by label starting at the root. (RFC 4034 section 6.1)
+pub fn canonical_cmp(a: &str, b: &str) -> Ordering {
+    let labels = |name: &str| {
+        name.split('.')
+            .filter(|label| !label.is_empty())
+            .rev()
+            .map(|label| label.to_ascii_lowercase().into_bytes())
+            .collect::<Vec<_>>()
+    };
+
+    labels(a).cmp(&labels(b))
+}
+
+/// Splits a name into its first label and the rest of the name, which includes the leading dot.
+fn split_first_label(name: &str) -> (&str, &str) {
+    match name.find('.') {
+        Some(i) => (&name[..i], &name[i..]),
+        None => (name, ""),
+    }
+}
+
+/// A name that comes right before a name in canonical order, with the same parent.
+/// The last character of the first label is decremented and followed by `~` characters, so no
+/// name consisting of letters, digits, hyphens and underscores sorts in between. (RFC 4470)
+pub fn predecessor(name: &str) -> String {
+    let (label, rest) = split_first_label(name);
+    let mut bytes = label.to_ascii_lowercase().into_bytes();
+
+    match bytes.last_mut() {
+        Some(last) => *last -= 1,
+        None => return name.to_string(),
+    }
+    bytes.resize(MAX_LABEL_LENGTH, b'~');
+
+    format!("{}{}", String::from_utf8_lossy(&bytes), rest)
+}
+
+/// The name that comes right after a name and all of its descendants in canonical order,
+/// a zero byte is appended to its first label. (RFC 4470)
+pub fn successor(name: &str) -> String {
+    let (label, rest) = split_first_label(name);
+    let mut bytes = label.to_ascii_lowercase().into_bytes();
+
+    if bytes.len() < MAX_LABEL_LENGTH {
+        bytes.push(0);
+    } else if let Some(last) = bytes.last_mut() {
+        *last += 1;
+    }
+
+    format!("{}{}", String::from_utf8_lossy(&bytes), rest)
+}
+
+/// The name that comes right after a name in canonical order, its child with a single zero byte
+/// label. Unlike [`successor`] no descendant of the name sorts in between. (RFC 4470 section 3)
+pub fn immediate_successor(name: &str) -> String {
+    format!("\0.{}", name.to_lowercase())
+}
+
+/// A minimally covering `NSEC` record, proving a name does not exist without revealing the other
+/// names of the zone. (RFC 4470)
+///
+/// # Arguments
+///
+/// * `name` - The name that does not exist.
+/// * `ttl` - The time to live of the record, the minimum of the `SOA`.
+pub fn covering_nsec(name: &str, ttl: u32) -> DnsRecord {
+    DnsRecord::NSEC {
+        domain: predecessor(name),
+        next: successor(name),
+        types: vec![QueryType::RRSIG.to_num(), QueryType::NSEC.to_num()],
+        ttl,
+    }
+}
+
+/// An `NSEC` record owned by a name, proving which types exist at it and that no other type does.
+///
+/// # Arguments
+///
+/// * `name` - The name that exists.
+/// * `types` - The types of the records at the name.
+/// * `ttl` - The time to live of the record, the minimum of the `SOA`.
+pub fn nodata_nsec(name: &str, types: &[QueryType], ttl: u32) -> DnsRecord {
+    let mut types = types.iter().map(QueryType::to_num).collect::<Vec<_>>();
+    types.extend([QueryType::RRSIG.to_num(), QueryType::NSEC.to_num()]);
+    types.sort_unstable();
+    types.dedup();
+
+    DnsRecord::NSEC {
+        domain: name.to_lowercase(),
+        next: immediate_successor(name),
+        types,
+        ttl,
+    }
+}
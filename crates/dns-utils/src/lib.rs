@@ -1,11 +1,19 @@
 extern crate shared;
 
 pub mod bitwise;
+pub mod dnssec;
 pub mod packets;
+pub mod validator;
 
 pub mod prelude {
+    pub use crate::dnssec::NodeKey;
     pub use crate::packets::{
-        byte_packet_buffer::BytePacketBuffer, dns_packet::DnsPacket, dns_question::DnsQuestion,
-        dns_record::DnsRecord, query_type::QueryType, result_code::ResultCode,
+        byte_packet_buffer::{BytePacketBuffer, TCP_BUFFER_MAX_SIZE},
+        dns_header::{OPCODE_NOTIFY, OPCODE_QUERY},
+        dns_packet::DnsPacket,
+        dns_question::DnsQuestion,
+        dns_record::{DnsRecord, EDNS_DO_FLAG, MAX_CHARACTER_STRING},
+        query_type::QueryType,
+        result_code::ResultCode,
     };
 }
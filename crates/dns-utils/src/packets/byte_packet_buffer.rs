@@ -4,19 +4,28 @@ use crate::bitwise::{
     has_flag, merge_u16_as_u32, merge_u8_as_u16, split_32_as_u8s, split_u16_as_u8s, JUMP_FLAG,
 };
 
+/// The maximum size of a message over UDP.
 const BUFFER_MAX_SIZE: usize = 512;
+/// The maximum size of a message over TCP, which is prefixed with its length as two bytes.
+pub const TCP_BUFFER_MAX_SIZE: usize = u16::MAX as usize;
 const MAX_JUMP_COUNT: usize = 5;
 
 /// Hold track of the packet contents and where we are
 pub struct BytePacketBuffer {
-    pub buf: [u8; BUFFER_MAX_SIZE],
+    pub buf: Vec<u8>,
     pos: usize,
 }
 
 impl BytePacketBuffer {
+    /// Creates a buffer for a message over UDP.
     pub fn new() -> BytePacketBuffer {
+        Self::with_size(BUFFER_MAX_SIZE)
+    }
+
+    /// Creates a buffer of a specific size. (eg [`TCP_BUFFER_MAX_SIZE`] for a message over TCP)
+    pub fn with_size(size: usize) -> BytePacketBuffer {
         BytePacketBuffer {
-            buf: [0; BUFFER_MAX_SIZE],
+            buf: vec![0; size],
             pos: 0,
         }
     }
@@ -28,7 +37,7 @@ impl BytePacketBuffer {
 
     /// Validate if a requested position is valid
     pub fn is_valid_pos(&self, pos: usize) -> bool {
-        pos <= BUFFER_MAX_SIZE
+        pos <= self.buf.len()
     }
 
     /// Validate whether a requested position is valid and
@@ -38,7 +47,7 @@ impl BytePacketBuffer {
             return Ok(());
         }
 
-        Err("Invalid position (maybe the end of the buffer has been reached?)".into())
+        Err(PacketError::BufferOverflow { position: pos }.into())
     }
 
     /// Step the buffer position forward with a specific number steps
@@ -55,7 +64,7 @@ impl BytePacketBuffer {
 
     /// Get a single byte from the buffer without advancing the position
     pub fn get(&mut self, pos: usize) -> Result<u8> {
-        self.validate_position(pos)?;
+        self.validate_position(pos + 1)?;
         Ok(self.buf[pos])
     }
 
@@ -83,6 +92,13 @@ impl BytePacketBuffer {
         Ok(merge_u16_as_u32(self.read_u16()?, self.read_u16()?))
     }
 
+    /// Read a specific number of bytes, stepping as many steps forward
+    pub fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let bytes = self.get_range(self.pos, len)?.to_vec();
+        self.step(len)?;
+        Ok(bytes)
+    }
+
     /// Reads a domain name into the outstr buffer, returning the domain name taking labels into consideration.
     pub fn read_qname(&mut self, outstr: &mut String) -> Result<()> {
         let mut pos = self.pos();
@@ -95,9 +111,10 @@ impl BytePacketBuffer {
         loop {
             // Prevent DNS cycle attacks
             if jumps_performed > MAX_JUMP_COUNT {
-                return Err(
-                    format!("Exceeded limit of allowed jumps. ({})", MAX_JUMP_COUNT).into(),
-                );
+                return Err(PacketError::TooManyJumps {
+                    limit: MAX_JUMP_COUNT,
+                }
+                .into());
             }
 
             let len = self.get(pos)?;
@@ -141,8 +158,8 @@ impl BytePacketBuffer {
 
     // Write a single byte to the buffer
     fn write(&mut self, val: u8) -> Result<()> {
-        if self.pos >= 512 {
-            return Err("End of buffer reached".into());
+        if self.pos >= self.buf.len() {
+            return Err(PacketError::BufferOverflow { position: self.pos }.into());
         }
 
         self.buf[self.pos] = val;
@@ -161,6 +178,11 @@ impl BytePacketBuffer {
         val.into_iter().try_for_each(|byte| self.write_u8(*byte))
     }
 
+    // Write raw bytes to the buffer (eg a key or signature)
+    pub fn write_bytes(&mut self, val: &[u8]) -> Result<()> {
+        self.write_u8_slice(val)
+    }
+
     // Write a u16 to the buffer (converts to two u8s)
     pub fn write_u16(&mut self, val: u16) -> Result<()> {
         self.write_u8_slice(&split_u16_as_u8s(val))
@@ -171,13 +193,16 @@ impl BytePacketBuffer {
         self.write_u8_slice(&split_32_as_u8s(val))
     }
 
-    // Write a domain name to the buffer
+    // Write a domain name to the buffer, the root is written as an empty name
     pub fn write_qname(&mut self, qname: &str) -> Result<()> {
-        for label in qname.split('.') {
+        for label in qname.split('.').filter(|label| !label.is_empty()) {
             let len = label.len();
 
             if len > 0x3F {
-                return Err("Single label exceeds maximum length of 63 characters".into());
+                return Err(PacketError::LabelTooLong {
+                    label: label.to_string(),
+                }
+                .into());
             }
 
             self.write_u8(len as u8)?;
@@ -190,6 +215,7 @@ impl BytePacketBuffer {
 
     // Set a single byte in the buffer at a specific position
     fn set(&mut self, pos: usize, val: u8) -> Result<()> {
+        self.validate_position(pos + 1)?;
         self.buf[pos] = val;
 
         Ok(())
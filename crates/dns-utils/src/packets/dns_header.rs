@@ -3,6 +3,11 @@ use shared::prelude::*;
 use crate::packets::byte_packet_buffer::BytePacketBuffer;
 use crate::packets::result_code::ResultCode;
 
+/// The opcode of a standard query.
+pub const OPCODE_QUERY: u8 = 0;
+/// The opcode of a notification that a zone changed. (RFC 1996)
+pub const OPCODE_NOTIFY: u8 = 4;
+
 #[derive(Clone, Debug)]
 pub struct DnsHeader {
     pub id: u16,
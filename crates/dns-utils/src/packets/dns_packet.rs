@@ -4,7 +4,7 @@ use std::mem;
 use crate::packets::byte_packet_buffer::BytePacketBuffer;
 use crate::packets::dns_header::DnsHeader;
 use crate::packets::dns_question::DnsQuestion;
-use crate::packets::dns_record::DnsRecord;
+use crate::packets::dns_record::{DnsRecord, EDNS_DO_FLAG};
 use crate::packets::query_type::QueryType;
 
 #[derive(Clone, Debug)]
@@ -81,6 +81,26 @@ impl DnsPacket {
         Ok(())
     }
 
+    /// The EDNS pseudo-record of the message, if the sender supports EDNS. (RFC 6891)
+    pub fn edns(&self) -> Option<&DnsRecord> {
+        self.resources
+            .iter()
+            .find(|record| matches!(record, DnsRecord::OPT { .. }))
+    }
+
+    /// Whether the sender wants the records to be signed, the DO bit. (RFC 3225)
+    pub fn dnssec_ok(&self) -> bool {
+        matches!(self.edns(), Some(DnsRecord::OPT { flags, .. }) if flags & EDNS_DO_FLAG != 0)
+    }
+
+    /// The largest UDP response the sender can receive, at least 512 bytes.
+    pub fn max_payload_size(&self) -> usize {
+        match self.edns() {
+            Some(DnsRecord::OPT { payload_size, .. }) => (*payload_size as usize).max(512),
+            _ => 512,
+        }
+    }
+
     pub fn make_returnable(mut self) -> DnsPacket {
         if self.original_questions.is_some() {
             self.questions = mem::replace(&mut self.original_questions, None).unwrap();
@@ -41,6 +41,132 @@ pub enum DnsRecord {
         addr: Ipv6Addr,
         ttl: u32,
     },
+    SOA {
+        domain: String,
+        primary: String,
+        hostmaster: String,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+        ttl: u32,
+    },
+    TXT {
+        domain: String,
+        /// The character strings, of at most [`MAX_CHARACTER_STRING`] bytes each.
+        data: Vec<String>,
+        ttl: u32,
+    },
+    /// The EDNS pseudo-record of a message, its owner is always the root. (RFC 6891)
+    OPT {
+        /// The largest UDP payload the sender can receive.
+        payload_size: u16,
+        /// The extended result code, version and flags (eg [`EDNS_DO_FLAG`]), sent as the ttl.
+        flags: u32,
+    },
+    DS {
+        domain: String,
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: Vec<u8>,
+        ttl: u32,
+    },
+    RRSIG {
+        domain: String,
+        /// The type of the signed records.
+        type_covered: u16,
+        algorithm: u8,
+        /// The amount of labels of the owner, without a leading wildcard.
+        labels: u8,
+        original_ttl: u32,
+        /// The end of the validity period, in seconds since the epoch.
+        expiration: u32,
+        /// The start of the validity period, in seconds since the epoch.
+        inception: u32,
+        key_tag: u16,
+        /// The apex of the zone that signed the records.
+        signer: String,
+        signature: Vec<u8>,
+        ttl: u32,
+    },
+    NSEC {
+        domain: String,
+        /// The next owner in the zone, every name in between does not exist.
+        next: String,
+        /// The types present at the owner.
+        types: Vec<u16>,
+        ttl: u32,
+    },
+    DNSKEY {
+        domain: String,
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        public_key: Vec<u8>,
+        ttl: u32,
+    },
+    NSEC3 {
+        domain: String,
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: Vec<u8>,
+        next_hashed: Vec<u8>,
+        types: Vec<u16>,
+        ttl: u32,
+    },
+}
+
+/// The `DNSSEC OK` flag of an [`DnsRecord::OPT`] record, set when the sender wants signatures. (RFC 3225)
+pub const EDNS_DO_FLAG: u32 = 0x8000;
+
+/// The longest character string of a [`DnsRecord::TXT`] record, its length is a single byte.
+pub const MAX_CHARACTER_STRING: usize = 255;
+
+/// Reads the type bit maps of an `NSEC` or `NSEC3` record. (RFC 4034 section 4.1.2)
+fn read_type_bitmap(buffer: &mut BytePacketBuffer, len: usize) -> Result<Vec<u16>> {
+    let end = buffer.pos() + len;
+    let mut types = Vec::new();
+
+    while buffer.pos() < end {
+        let window = buffer.read()? as u16;
+        let len = buffer.read()? as usize;
+        let bitmap = buffer.read_bytes(len)?;
+
+        for (i, byte) in bitmap.iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    types.push(window << 8 | (i * 8 + bit) as u16);
+                }
+            }
+        }
+    }
+
+    Ok(types)
+}
+
+/// Writes the type bit maps of an `NSEC` or `NSEC3` record. (RFC 4034 section 4.1.2)
+fn write_type_bitmap(buffer: &mut BytePacketBuffer, types: &[u16]) -> Result<()> {
+    let mut types = types.to_vec();
+    types.sort_unstable();
+    types.dedup();
+
+    for window in types.chunk_by(|a, b| a >> 8 == b >> 8) {
+        let len = (window[window.len() - 1] & 0xFF) as usize / 8 + 1;
+        let mut bitmap = vec![0u8; len];
+        for rtype in window {
+            let low = (rtype & 0xFF) as usize;
+            bitmap[low / 8] |= 0x80 >> (low % 8);
+        }
+
+        buffer.write_u8((window[0] >> 8) as u8)?;
+        buffer.write_u8(len as u8)?;
+        buffer.write_bytes(&bitmap)?;
+    }
+
+    Ok(())
 }
 
 impl DnsRecord {
@@ -50,9 +176,14 @@ impl DnsRecord {
 
         let qtype_num = buffer.read_u16()?;
         let qtype = QueryType::from_num(qtype_num);
-        let _ = buffer.read_u16()?;
+        let class = buffer.read_u16()?;
         let ttl = buffer.read_u32()?;
         let data_len = buffer.read_u16()?;
+        let data_start = buffer.pos();
+        // The length of the record data that is left after the current position.
+        let remaining = |buffer: &BytePacketBuffer| {
+            (data_start + data_len as usize).saturating_sub(buffer.pos())
+        };
 
         match qtype {
             QueryType::SUB(subdomain::Type::A) => {
@@ -108,7 +239,133 @@ impl DnsRecord {
                     ttl,
                 })
             }
-            QueryType::UNKNOWN(_) => {
+            QueryType::SOA => {
+                let mut primary = String::new();
+                buffer.read_qname(&mut primary)?;
+                let mut hostmaster = String::new();
+                buffer.read_qname(&mut hostmaster)?;
+
+                Ok(DnsRecord::SOA {
+                    domain,
+                    primary,
+                    hostmaster,
+                    serial: buffer.read_u32()?,
+                    refresh: buffer.read_u32()?,
+                    retry: buffer.read_u32()?,
+                    expire: buffer.read_u32()?,
+                    minimum: buffer.read_u32()?,
+                    ttl,
+                })
+            }
+            QueryType::TXT => {
+                let mut data = Vec::new();
+                while remaining(buffer) > 0 {
+                    let len = buffer.read()? as usize;
+                    let bytes = buffer.read_bytes(len)?;
+                    data.push(String::from_utf8_lossy(&bytes).into_owned());
+                }
+
+                Ok(DnsRecord::TXT { domain, data, ttl })
+            }
+            QueryType::OPT => {
+                // The options are not used.
+                buffer.step(data_len as usize)?;
+
+                Ok(DnsRecord::OPT {
+                    payload_size: class,
+                    flags: ttl,
+                })
+            }
+            QueryType::DS => {
+                let key_tag = buffer.read_u16()?;
+                let algorithm = buffer.read()?;
+                let digest_type = buffer.read()?;
+                let digest = buffer.read_bytes(remaining(buffer))?;
+
+                Ok(DnsRecord::DS {
+                    domain,
+                    key_tag,
+                    algorithm,
+                    digest_type,
+                    digest,
+                    ttl,
+                })
+            }
+            QueryType::RRSIG => {
+                let type_covered = buffer.read_u16()?;
+                let algorithm = buffer.read()?;
+                let labels = buffer.read()?;
+                let original_ttl = buffer.read_u32()?;
+                let expiration = buffer.read_u32()?;
+                let inception = buffer.read_u32()?;
+                let key_tag = buffer.read_u16()?;
+                let mut signer = String::new();
+                buffer.read_qname(&mut signer)?;
+                let signature = buffer.read_bytes(remaining(buffer))?;
+
+                Ok(DnsRecord::RRSIG {
+                    domain,
+                    type_covered,
+                    algorithm,
+                    labels,
+                    original_ttl,
+                    expiration,
+                    inception,
+                    key_tag,
+                    signer,
+                    signature,
+                    ttl,
+                })
+            }
+            QueryType::NSEC => {
+                let mut next = String::new();
+                buffer.read_qname(&mut next)?;
+                let types = read_type_bitmap(buffer, remaining(buffer))?;
+
+                Ok(DnsRecord::NSEC {
+                    domain,
+                    next,
+                    types,
+                    ttl,
+                })
+            }
+            QueryType::DNSKEY => {
+                let flags = buffer.read_u16()?;
+                let protocol = buffer.read()?;
+                let algorithm = buffer.read()?;
+                let public_key = buffer.read_bytes(remaining(buffer))?;
+
+                Ok(DnsRecord::DNSKEY {
+                    domain,
+                    flags,
+                    protocol,
+                    algorithm,
+                    public_key,
+                    ttl,
+                })
+            }
+            QueryType::NSEC3 => {
+                let hash_algorithm = buffer.read()?;
+                let flags = buffer.read()?;
+                let iterations = buffer.read_u16()?;
+                let salt_len = buffer.read()? as usize;
+                let salt = buffer.read_bytes(salt_len)?;
+                let hash_len = buffer.read()? as usize;
+                let next_hashed = buffer.read_bytes(hash_len)?;
+                let types = read_type_bitmap(buffer, remaining(buffer))?;
+
+                Ok(DnsRecord::NSEC3 {
+                    domain,
+                    hash_algorithm,
+                    flags,
+                    iterations,
+                    salt,
+                    next_hashed,
+                    types,
+                    ttl,
+                })
+            }
+            QueryType::UNKNOWN(_) | QueryType::IXFR | QueryType::AXFR => {
                 buffer.step(data_len as usize)?;
 
                 Ok(DnsRecord::UNKNOWN {
@@ -213,6 +470,197 @@ impl DnsRecord {
                     buffer.write_u16(*octet)?;
                 }
             }
+            DnsRecord::SOA {
+                ref domain,
+                ref primary,
+                ref hostmaster,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::SOA.to_num())?;
+                buffer.write_u16(1)?;
+                buffer.write_u32(ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_qname(primary)?;
+                buffer.write_qname(hostmaster)?;
+                buffer.write_u32(serial)?;
+                buffer.write_u32(refresh)?;
+                buffer.write_u32(retry)?;
+                buffer.write_u32(expire)?;
+                buffer.write_u32(minimum)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            DnsRecord::TXT {
+                ref domain,
+                ref data,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::TXT.to_num())?;
+                buffer.write_u16(1)?;
+                buffer.write_u32(ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                for string in data {
+                    if string.len() > MAX_CHARACTER_STRING {
+                        return Err(PacketError::Malformed(format!(
+                            "character string of {} bytes",
+                            string.len()
+                        ))
+                        .into());
+                    }
+
+                    buffer.write_u8(string.len() as u8)?;
+                    buffer.write_bytes(string.as_bytes())?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            DnsRecord::OPT {
+                payload_size,
+                flags,
+            } => {
+                buffer.write_qname("")?;
+                buffer.write_u16(QueryType::OPT.to_num())?;
+                buffer.write_u16(payload_size)?;
+                buffer.write_u32(flags)?;
+                buffer.write_u16(0)?;
+            }
+            DnsRecord::DS {
+                ref domain,
+                key_tag,
+                algorithm,
+                digest_type,
+                ref digest,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::DS.to_num())?;
+                buffer.write_u16(1)?;
+                buffer.write_u32(ttl)?;
+                buffer.write_u16(4 + digest.len() as u16)?;
+
+                buffer.write_u16(key_tag)?;
+                buffer.write_u8(algorithm)?;
+                buffer.write_u8(digest_type)?;
+                buffer.write_bytes(digest)?;
+            }
+            DnsRecord::RRSIG {
+                ref domain,
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                ref signer,
+                ref signature,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::RRSIG.to_num())?;
+                buffer.write_u16(1)?;
+                buffer.write_u32(ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16(type_covered)?;
+                buffer.write_u8(algorithm)?;
+                buffer.write_u8(labels)?;
+                buffer.write_u32(original_ttl)?;
+                buffer.write_u32(expiration)?;
+                buffer.write_u32(inception)?;
+                buffer.write_u16(key_tag)?;
+                buffer.write_qname(signer)?;
+                buffer.write_bytes(signature)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            DnsRecord::NSEC {
+                ref domain,
+                ref next,
+                ref types,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::NSEC.to_num())?;
+                buffer.write_u16(1)?;
+                buffer.write_u32(ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_qname(next)?;
+                write_type_bitmap(buffer, types)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            DnsRecord::DNSKEY {
+                ref domain,
+                flags,
+                protocol,
+                algorithm,
+                ref public_key,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::DNSKEY.to_num())?;
+                buffer.write_u16(1)?;
+                buffer.write_u32(ttl)?;
+                buffer.write_u16(4 + public_key.len() as u16)?;
+
+                buffer.write_u16(flags)?;
+                buffer.write_u8(protocol)?;
+                buffer.write_u8(algorithm)?;
+                buffer.write_bytes(public_key)?;
+            }
+            DnsRecord::NSEC3 {
+                ref domain,
+                hash_algorithm,
+                flags,
+                iterations,
+                ref salt,
+                ref next_hashed,
+                ref types,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::NSEC3.to_num())?;
+                buffer.write_u16(1)?;
+                buffer.write_u32(ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u8(hash_algorithm)?;
+                buffer.write_u8(flags)?;
+                buffer.write_u16(iterations)?;
+                buffer.write_u8(salt.len() as u8)?;
+                buffer.write_bytes(salt)?;
+                buffer.write_u8(next_hashed.len() as u8)?;
+                buffer.write_bytes(next_hashed)?;
+                write_type_bitmap(buffer, types)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
             DnsRecord::UNKNOWN { .. } => {
                 println!("Skipping record: {:?}", self);
             }
@@ -228,6 +676,14 @@ impl DnsRecord {
             DnsRecord::NS { .. } => QueryType::SUB(subdomain::Type::NS),
             DnsRecord::CNAME { .. } => QueryType::SUB(subdomain::Type::CNAME),
             DnsRecord::MX { .. } => QueryType::SUB(subdomain::Type::MX),
+            DnsRecord::SOA { .. } => QueryType::SOA,
+            DnsRecord::TXT { .. } => QueryType::TXT,
+            DnsRecord::OPT { .. } => QueryType::OPT,
+            DnsRecord::DS { .. } => QueryType::DS,
+            DnsRecord::RRSIG { .. } => QueryType::RRSIG,
+            DnsRecord::NSEC { .. } => QueryType::NSEC,
+            DnsRecord::DNSKEY { .. } => QueryType::DNSKEY,
+            DnsRecord::NSEC3 { .. } => QueryType::NSEC3,
             DnsRecord::UNKNOWN { qtype, .. } => QueryType::UNKNOWN(qtype),
         }
     }
@@ -248,6 +704,14 @@ impl DnsRecord {
             DnsRecord::NS { ttl, .. } => ttl,
             DnsRecord::CNAME { ttl, .. } => ttl,
             DnsRecord::MX { ttl, .. } => ttl,
+            DnsRecord::SOA { ttl, .. } => ttl,
+            DnsRecord::TXT { ttl, .. } => ttl,
+            DnsRecord::OPT { flags, .. } => flags,
+            DnsRecord::DS { ttl, .. } => ttl,
+            DnsRecord::RRSIG { ttl, .. } => ttl,
+            DnsRecord::NSEC { ttl, .. } => ttl,
+            DnsRecord::DNSKEY { ttl, .. } => ttl,
+            DnsRecord::NSEC3 { ttl, .. } => ttl,
             DnsRecord::UNKNOWN { ttl, .. } => ttl,
         }
     }
@@ -259,6 +723,14 @@ impl DnsRecord {
             DnsRecord::NS { ref domain, .. } => domain,
             DnsRecord::CNAME { ref domain, .. } => domain,
             DnsRecord::MX { ref domain, .. } => domain,
+            DnsRecord::SOA { ref domain, .. } => domain,
+            DnsRecord::TXT { ref domain, .. } => domain,
+            DnsRecord::OPT { .. } => "",
+            DnsRecord::DS { ref domain, .. } => domain,
+            DnsRecord::RRSIG { ref domain, .. } => domain,
+            DnsRecord::NSEC { ref domain, .. } => domain,
+            DnsRecord::DNSKEY { ref domain, .. } => domain,
+            DnsRecord::NSEC3 { ref domain, .. } => domain,
             DnsRecord::UNKNOWN { ref domain, .. } => domain,
         }
     }
@@ -270,7 +742,35 @@ impl DnsRecord {
             DnsRecord::NS { ref mut ttl, .. } => *ttl = new_ttl,
             DnsRecord::CNAME { ref mut ttl, .. } => *ttl = new_ttl,
             DnsRecord::MX { ref mut ttl, .. } => *ttl = new_ttl,
+            DnsRecord::SOA { ref mut ttl, .. } => *ttl = new_ttl,
+            DnsRecord::TXT { ref mut ttl, .. } => *ttl = new_ttl,
+            DnsRecord::OPT { ref mut flags, .. } => *flags = new_ttl,
+            DnsRecord::DS { ref mut ttl, .. } => *ttl = new_ttl,
+            DnsRecord::RRSIG { ref mut ttl, .. } => *ttl = new_ttl,
+            DnsRecord::NSEC { ref mut ttl, .. } => *ttl = new_ttl,
+            DnsRecord::DNSKEY { ref mut ttl, .. } => *ttl = new_ttl,
+            DnsRecord::NSEC3 { ref mut ttl, .. } => *ttl = new_ttl,
             DnsRecord::UNKNOWN { ref mut ttl, .. } => *ttl = new_ttl,
         }
     }
+
+    /// Changes the owner of the record, the owner of an `OPT` record is always the root.
+    pub fn set_domain(&mut self, new_domain: &str) {
+        match *self {
+            DnsRecord::A { ref mut domain, .. }
+            | DnsRecord::AAAA { ref mut domain, .. }
+            | DnsRecord::NS { ref mut domain, .. }
+            | DnsRecord::CNAME { ref mut domain, .. }
+            | DnsRecord::MX { ref mut domain, .. }
+            | DnsRecord::SOA { ref mut domain, .. }
+            | DnsRecord::TXT { ref mut domain, .. }
+            | DnsRecord::DS { ref mut domain, .. }
+            | DnsRecord::RRSIG { ref mut domain, .. }
+            | DnsRecord::NSEC { ref mut domain, .. }
+            | DnsRecord::DNSKEY { ref mut domain, .. }
+            | DnsRecord::NSEC3 { ref mut domain, .. }
+            | DnsRecord::UNKNOWN { ref mut domain, .. } => *domain = new_domain.to_string(),
+            DnsRecord::OPT { .. } => {}
+        }
+    }
 }
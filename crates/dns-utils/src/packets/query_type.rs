@@ -3,6 +3,25 @@ use xdns_data::prelude::Type;
 #[derive(PartialEq, Eq, Debug, Clone, Hash, Copy)]
 pub enum QueryType {
     SUB(Type),
+    SOA,
+    /// Free form text, as character strings. (RFC 1035)
+    TXT,
+    /// The EDNS pseudo-record, carrying the DO bit. (RFC 6891)
+    OPT,
+    /// A delegation signer. (RFC 4034)
+    DS,
+    /// A signature over a set of records. (RFC 4034)
+    RRSIG,
+    /// Authenticated denial of existence. (RFC 4034)
+    NSEC,
+    /// A public key of a zone. (RFC 4034)
+    DNSKEY,
+    /// Hashed authenticated denial of existence. (RFC 5155)
+    NSEC3,
+    /// An incremental zone transfer. (RFC 1995)
+    IXFR,
+    /// A full zone transfer. (RFC 5936)
+    AXFR,
     UNKNOWN(u16),
 }
 
@@ -15,6 +34,16 @@ impl QueryType {
             QueryType::SUB(Type::CNAME) => 5,
             QueryType::SUB(Type::MX) => 15,
             QueryType::SUB(Type::AAAA) => 28,
+            QueryType::SOA => 6,
+            QueryType::TXT => 16,
+            QueryType::OPT => 41,
+            QueryType::DS => 43,
+            QueryType::RRSIG => 46,
+            QueryType::NSEC => 47,
+            QueryType::DNSKEY => 48,
+            QueryType::NSEC3 => 50,
+            QueryType::IXFR => 251,
+            QueryType::AXFR => 252,
         }
     }
 
@@ -25,6 +54,16 @@ impl QueryType {
             5 => QueryType::SUB(Type::CNAME),
             15 => QueryType::SUB(Type::MX),
             28 => QueryType::SUB(Type::AAAA),
+            6 => QueryType::SOA,
+            16 => QueryType::TXT,
+            41 => QueryType::OPT,
+            43 => QueryType::DS,
+            46 => QueryType::RRSIG,
+            47 => QueryType::NSEC,
+            48 => QueryType::DNSKEY,
+            50 => QueryType::NSEC3,
+            251 => QueryType::IXFR,
+            252 => QueryType::AXFR,
             _ => QueryType::UNKNOWN(num),
         }
     }
@@ -0,0 +1,928 @@
+use std::cmp::Ordering as NameOrdering;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use shared::prelude::*;
+use tokio::net::UdpSocket;
+use xdns_data::prelude::Type;
+
+use crate::dnssec::{self, OPT_OUT_FLAG, ZONE_KEY_FLAG};
+use crate::packets::byte_packet_buffer::BytePacketBuffer;
+use crate::packets::dns_packet::DnsPacket;
+use crate::packets::dns_question::DnsQuestion;
+use crate::packets::dns_record::{DnsRecord, EDNS_DO_FLAG};
+use crate::packets::query_type::QueryType;
+use crate::packets::result_code::ResultCode;
+
+/// The largest UDP response the resolver asks for.
+const PAYLOAD_SIZE: u16 = 4096;
+/// How long the resolver waits for a response.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the keys of a zone are remembered at most, or that a zone is not signed.
+const MAX_KEYS_AGE: Duration = Duration::from_secs(3600);
+/// The hash algorithm of `NSEC3` records, SHA-1 is the only one defined.
+const NSEC3_SHA1: u8 = 1;
+
+/// The outcome of validating a response. (RFC 4035 section 4.3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Security {
+    /// Every record is signed by a chain of trust starting at a trust anchor.
+    Secure,
+    /// Some records belong to a zone that is provably not signed.
+    Insecure,
+    /// Signatures are missing, invalid or expired, the response can not be trusted.
+    Bogus(String),
+}
+
+/// Sends queries to a DNS server, asking for the DNSSEC records of the answers.
+pub trait Resolve {
+    /// Resolves a name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The queried name. (eg `example.com`, the root is empty)
+    /// * `qtype` - The queried type.
+    ///
+    /// # Returns
+    ///
+    /// The response, or an error if the server could not be reached.
+    async fn resolve(&self, name: &str, qtype: QueryType) -> Result<DnsPacket>;
+}
+
+/// Resolves names with a recursive resolver over UDP.
+#[derive(Debug, Clone)]
+pub struct UdpResolver {
+    server: SocketAddr,
+    timeout: Duration,
+}
+
+impl UdpResolver {
+    /// Creates a resolver that sends its queries to a server.
+    pub fn new(server: SocketAddr) -> Self {
+        Self {
+            server,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets how long the resolver waits for a response.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Resolve for UdpResolver {
+    async fn resolve(&self, name: &str, qtype: QueryType) -> Result<DnsPacket> {
+        static NEXT_ID: AtomicU16 = AtomicU16::new(0);
+
+        let mut packet = DnsPacket::new();
+        packet.header.id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        packet.header.recursion_desired = true;
+        // The server returns records it considers bogus as well, they are validated here.
+        packet.header.checking_disabled = true;
+        packet
+            .questions
+            .push(DnsQuestion::new(name.to_string(), qtype));
+        packet.resources.push(DnsRecord::OPT {
+            payload_size: PAYLOAD_SIZE,
+            flags: EDNS_DO_FLAG,
+        });
+
+        let mut req_buffer = BytePacketBuffer::new();
+        packet.write(&mut req_buffer)?;
+
+        let local: SocketAddr = match self.server {
+            SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+            SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket
+            .send_to(&req_buffer.buf[0..req_buffer.pos()], self.server)
+            .await?;
+
+        let mut res_buffer = BytePacketBuffer::with_size(PAYLOAD_SIZE as usize);
+        tokio::time::timeout(self.timeout, socket.recv_from(&mut res_buffer.buf))
+            .await
+            .map_err(|_| Error::Other(format!("Timed out resolving {:?} {:?}", name, qtype)))??;
+
+        let response = DnsPacket::from_buffer(&mut res_buffer)?;
+        if response.header.id != packet.header.id {
+            return Err(PacketError::Malformed("response to another query".to_string()).into());
+        }
+        if response.header.truncated_message {
+            return Err(PacketError::Malformed("truncated response".to_string()).into());
+        }
+
+        Ok(response)
+    }
+}
+
+/// The keys of a zone, none if the zone is provably not signed.
+type ZoneKeys = Option<Vec<DnsRecord>>;
+
+/// Whether a name is a delegation to another zone, as proven by the absence of its `DS` records.
+enum Delegation {
+    /// The name is in the same zone as its parent.
+    None,
+    /// The name is the apex of a zone that is not signed.
+    Insecure,
+}
+
+/// Validates responses by building the chain of trust from trust anchors through the `DS` and
+/// `DNSKEY` records of every zone. (RFC 4035 section 5)
+///
+/// Denial of existence is checked with `NSEC` and `NSEC3` records, and so is the absence of the
+/// names that records are synthesized for from a wildcard.
+pub struct Validator<R: Resolve> {
+    resolver: R,
+    /// The `DS` records of the trust anchors, usually of the root.
+    anchors: Vec<DnsRecord>,
+    /// The validated keys of zones, with when they expire.
+    zones: Mutex<HashMap<String, (ZoneKeys, Instant)>>,
+}
+
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as u32
+}
+
+fn bogus(reason: String) -> Error {
+    Error::Other(reason)
+}
+
+/// Whether a name is a zone or below it, every name is below the root.
+fn is_within(name: &str, zone: &str) -> bool {
+    zone.is_empty() || name.eq_ignore_ascii_case(zone) || name.ends_with(&format!(".{}", zone))
+}
+
+/// The parent of a name, none for the root.
+fn parent_of(name: &str) -> Option<&str> {
+    match name {
+        "" => None,
+        name => Some(name.split_once('.').map_or("", |(_, parent)| parent)),
+    }
+}
+
+/// The `NSEC` and `NSEC3` records of a response that may prove something about a name.
+#[derive(Default)]
+struct Proof {
+    /// The closest zone the name belongs to that signed any of the records.
+    zone: String,
+    /// The records signed by that zone.
+    records: Vec<DnsRecord>,
+}
+
+/// The signatures over a set of records.
+fn signatures<'a>(records: &'a [DnsRecord], rrset: &[DnsRecord]) -> Vec<&'a DnsRecord> {
+    let owner = rrset[0].get_domain();
+    let rtype = rrset[0].type_of().to_num();
+
+    records
+        .iter()
+        .filter(|record| match record {
+            DnsRecord::RRSIG {
+                domain,
+                type_covered,
+                ..
+            } => domain.eq_ignore_ascii_case(owner) && *type_covered == rtype,
+            _ => false,
+        })
+        .collect()
+}
+
+fn signer_of(rrsig: &DnsRecord) -> &str {
+    match rrsig {
+        DnsRecord::RRSIG { signer, .. } => signer,
+        _ => "",
+    }
+}
+
+/// The amount of labels of the wildcard a set of records was synthesized from, as signed, none if
+/// the signature was made over their own name.
+fn wildcard_labels(rrsig: &DnsRecord, owner: &str) -> Option<u8> {
+    match rrsig {
+        DnsRecord::RRSIG { labels, .. } if *labels < dnssec::labels(owner) => Some(*labels),
+        _ => None,
+    }
+}
+
+/// Whether a set of records is signed by a zone, with one of its keys and a current signature.
+fn is_signed_by(
+    records: &[DnsRecord],
+    rrset: &[DnsRecord],
+    zone: &str,
+    keys: &[DnsRecord],
+) -> bool {
+    let now = now();
+
+    signatures(records, rrset).into_iter().any(|rrsig| {
+        signer_of(rrsig).eq_ignore_ascii_case(zone)
+            && dnssec::is_current(rrsig, now)
+            && keys
+                .iter()
+                .any(|key| dnssec::verify(key, rrsig, rrset).unwrap_or(false))
+    })
+}
+
+/// Whether a name of a zone sorts between two names, or after the first when the second is the
+/// apex of the zone and the range wraps around.
+fn is_between(owner: &str, name: &str, next: &str, zone: &str) -> bool {
+    if !is_within(name, zone) {
+        return false;
+    }
+
+    let after = dnssec::canonical_cmp(owner, name) == NameOrdering::Less;
+    let before = dnssec::canonical_cmp(name, next) == NameOrdering::Less;
+
+    if dnssec::canonical_cmp(owner, next) == NameOrdering::Less {
+        after && before
+    } else {
+        after && next.eq_ignore_ascii_case(zone)
+    }
+}
+
+/// Whether the types of a name are those of a delegation, as seen from the parent zone.
+fn is_delegation(types: &[u16]) -> bool {
+    types.contains(&QueryType::SUB(Type::NS).to_num()) && !types.contains(&QueryType::SOA.to_num())
+}
+
+/// Whether an `NSEC` proves a name of its zone does not exist. The `NSEC` of a delegation proves
+/// nothing about the names below it, they belong to the child zone. (RFC 6840 section 4.1)
+fn nsec_covers(owner: &str, next: &str, types: &[u16], name: &str, zone: &str) -> bool {
+    is_between(owner, name, next, zone) && !(is_delegation(types) && is_within(name, owner))
+}
+
+/// An `NSEC3` record with its owner hash decoded, and the hash of a name with its parameters.
+struct Nsec3<'a> {
+    /// The base32hex encoded hash in the first label of the owner.
+    owner: &'a str,
+    /// The zone the record belongs to.
+    zone: &'a str,
+    next: String,
+    flags: u8,
+    types: &'a [u16],
+    salt: &'a [u8],
+    iterations: u16,
+}
+
+impl<'a> Nsec3<'a> {
+    fn new(record: &'a DnsRecord) -> Option<Self> {
+        match record {
+            DnsRecord::NSEC3 {
+                domain,
+                hash_algorithm: NSEC3_SHA1,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                types,
+                ..
+            } => {
+                let (owner, zone) = domain.split_once('.').unwrap_or((domain, ""));
+                Some(Self {
+                    owner,
+                    zone,
+                    next: dnssec::base32hex(next_hashed),
+                    flags: *flags,
+                    types,
+                    salt,
+                    iterations: *iterations,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn hash(&self, name: &str) -> Option<String> {
+        if !is_within(name, self.zone) {
+            return None;
+        }
+
+        dnssec::nsec3_hash(name, self.salt, self.iterations)
+            .ok()
+            .map(|hash| dnssec::base32hex(&hash))
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.hash(name)
+            .is_some_and(|hash| hash.eq_ignore_ascii_case(self.owner))
+    }
+
+    fn covers(&self, name: &str) -> bool {
+        self.hash(name).is_some_and(|hash| {
+            let (owner, next) = (self.owner.to_ascii_lowercase(), self.next.as_str());
+            if owner.as_str() < next {
+                owner < hash && hash.as_str() < next
+            } else {
+                owner < hash || hash.as_str() < next
+            }
+        })
+    }
+}
+
+/// Whether `NSEC` or `NSEC3` records of a zone prove a name exists without a type.
+/// (RFC 4035 section 3.1.3.1)
+fn proves_nodata(name: &str, qtype: QueryType, zone: &str, proof: &[DnsRecord]) -> bool {
+    // At a delegation only the DS records are the parent's, it knows nothing of the other types.
+    let lacks = |types: &[u16]| {
+        !types.contains(&qtype.to_num())
+            && !types.contains(&QueryType::SUB(Type::CNAME).to_num())
+            && (qtype == QueryType::DS || !is_delegation(types))
+    };
+
+    proof.iter().any(|record| match record {
+        DnsRecord::NSEC {
+            domain,
+            next,
+            types,
+            ..
+        } => {
+            (domain.eq_ignore_ascii_case(name) && lacks(types))
+                // An empty non-terminal, the next name is below it.
+                || (nsec_covers(domain, next, types, name, zone) && is_within(next, name))
+        }
+        record => Nsec3::new(record).is_some_and(|nsec3| nsec3.matches(name) && lacks(nsec3.types)),
+    })
+}
+
+/// Whether `NSEC` or `NSEC3` records of a zone prove a name and the wildcard that could match it
+/// do not exist. (RFC 4035 section 3.1.3.2, RFC 5155 section 8.4)
+fn proves_nxdomain(name: &str, zone: &str, proof: &[DnsRecord]) -> bool {
+    let nsecs = proof
+        .iter()
+        .filter_map(|record| match record {
+            DnsRecord::NSEC {
+                domain,
+                next,
+                types,
+                ..
+            } => Some((domain.as_str(), next.as_str(), types.as_slice())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let covering = |name: &str| {
+        nsecs
+            .iter()
+            .find(|(owner, next, types)| nsec_covers(owner, next, types, name, zone))
+    };
+
+    if let Some((owner, next, _)) = covering(name) {
+        // The closest encloser is the longest ancestor shared with the covering record.
+        let encloser = std::iter::successors(parent_of(name), |name| parent_of(name))
+            .find(|ancestor| is_within(owner, ancestor) || is_within(next, ancestor))
+            .unwrap_or("");
+        let wildcard = format!("*.{}", encloser);
+        let wildcard = wildcard.trim_end_matches('.');
+
+        return covering(wildcard).is_some();
+    }
+
+    let nsec3s = proof.iter().filter_map(Nsec3::new).collect::<Vec<_>>();
+    let mut closer = name;
+    for encloser in std::iter::successors(parent_of(name), |name| parent_of(name)) {
+        if let Some(nsec3) = nsec3s.iter().find(|nsec3| nsec3.matches(encloser)) {
+            // A delegation is not the closest encloser of the names of the child zone.
+            if is_delegation(nsec3.types) {
+                return false;
+            }
+
+            let wildcard = format!("*.{}", encloser);
+            let wildcard = wildcard.trim_end_matches('.');
+
+            return nsec3s.iter().any(|nsec3| nsec3.covers(closer))
+                && nsec3s.iter().any(|nsec3| nsec3.covers(wildcard));
+        }
+        closer = encloser;
+    }
+
+    false
+}
+
+/// Whether `NSEC` or `NSEC3` records prove no closer match than a wildcard exists for a name, so
+/// records synthesized from the wildcard may answer it. (RFC 4035 section 5.3.4, RFC 5155 section 8.8)
+///
+/// # Arguments
+///
+/// * `name` - The owner of the synthesized records.
+/// * `labels` - The amount of labels of the wildcard without its `*`, as signed.
+/// * `zone` - The zone that signed the proof.
+/// * `proof` - The verified `NSEC` and `NSEC3` records.
+fn proves_expansion(name: &str, labels: u8, zone: &str, proof: &[DnsRecord]) -> bool {
+    // The name one label below the closest encloser, the parent of the wildcard.
+    let parts = name
+        .split('.')
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>();
+    let next_closer = parts[parts.len().saturating_sub(labels as usize + 1)..].join(".");
+
+    proof.iter().any(|record| match record {
+        // A next name below it would be an empty non-terminal, a closer encloser.
+        DnsRecord::NSEC {
+            domain,
+            next,
+            types,
+            ..
+        } => nsec_covers(domain, next, types, &next_closer, zone) && !is_within(next, &next_closer),
+        record => Nsec3::new(record).is_some_and(|nsec3| nsec3.covers(&next_closer)),
+    })
+}
+
+/// Whether `NSEC` or `NSEC3` records of a zone prove a name has no `DS` records, and whether it
+/// is a delegation to a zone that is not signed.
+fn ds_denial(name: &str, zone: &str, proof: &[DnsRecord]) -> Result<Delegation> {
+    let ds = QueryType::DS.to_num();
+    let delegation = |types: &[u16]| {
+        if types.contains(&ds) {
+            Err(bogus(format!("The DS records of {:?} are missing", name)))
+        } else if is_delegation(types) {
+            Ok(Delegation::Insecure)
+        } else {
+            Ok(Delegation::None)
+        }
+    };
+
+    for record in proof {
+        match record {
+            DnsRecord::NSEC { domain, types, .. } if domain.eq_ignore_ascii_case(name) => {
+                return delegation(types);
+            }
+            DnsRecord::NSEC {
+                domain,
+                next,
+                types,
+                ..
+            } if nsec_covers(domain, next, types, name, zone) => {
+                return Ok(Delegation::None);
+            }
+            _ => {}
+        }
+    }
+
+    let nsec3s = proof.iter().filter_map(Nsec3::new).collect::<Vec<_>>();
+    if let Some(nsec3) = nsec3s.iter().find(|nsec3| nsec3.matches(name)) {
+        return delegation(nsec3.types);
+    }
+    // Unsigned delegations may be left out of the chain of an opt-out zone.
+    if nsec3s
+        .iter()
+        .any(|nsec3| nsec3.covers(name) && nsec3.flags & OPT_OUT_FLAG != 0)
+    {
+        return Ok(Delegation::Insecure);
+    }
+
+    Err(bogus(format!("No proof that {:?} has no DS records", name)))
+}
+
+impl<R: Resolve> Validator<R> {
+    /// Creates a validator.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolver` - Resolves the records of the chain of trust.
+    /// * `anchors` - The `DS` records of the trust anchors, usually of the root. (see [`dnssec::parse_ds`])
+    pub fn new(resolver: R, anchors: Vec<DnsRecord>) -> Self {
+        Self {
+            resolver,
+            anchors,
+            zones: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Resolves a name and validates the response.
+    ///
+    /// # Returns
+    ///
+    /// The response with its security, or an error if the server could not be reached.
+    pub async fn resolve(&self, name: &str, qtype: QueryType) -> Result<(DnsPacket, Security)> {
+        let response = self.resolver.resolve(name, qtype).await?;
+        let security = self.validate(&response).await;
+
+        Ok((response, security))
+    }
+
+    /// Validates a response, resolving the keys of the zones that signed it.
+    /// Responses that are not answers or denials (eg `SERVFAIL`) are insecure.
+    pub async fn validate(&self, response: &DnsPacket) -> Security {
+        match self.try_validate(response).await {
+            Ok(security) => security,
+            Err(e) => Security::Bogus(e.to_string()),
+        }
+    }
+
+    async fn try_validate(&self, response: &DnsPacket) -> Result<Security> {
+        let question = match response.questions.first() {
+            Some(question) => question,
+            None => return Ok(Security::Insecure),
+        };
+        if !matches!(
+            response.header.rescode,
+            ResultCode::NOERROR | ResultCode::NXDOMAIN
+        ) {
+            return Ok(Security::Insecure);
+        }
+
+        let mut security = Security::Secure;
+        for rrset in dnssec::rrsets(&response.answers) {
+            let owner = rrset[0].get_domain();
+            match self.verify_rrset(&rrset, &response.answers).await? {
+                (Security::Secure, rrsig) => {
+                    if let Some(labels) = rrsig.and_then(|rrsig| wildcard_labels(rrsig, owner)) {
+                        let verified = self.verify_expansion(owner, labels, response).await?;
+                        if verified != Security::Secure {
+                            return Ok(verified);
+                        }
+                    }
+                }
+                (Security::Insecure, _) => security = Security::Insecure,
+                (bogus, _) => return Ok(bogus),
+            }
+        }
+
+        if !response.answers.is_empty() {
+            return Ok(security);
+        }
+
+        self.verify_denial(question, response).await
+    }
+
+    /// Verifies a set of records with the keys of the zone that signed it.
+    ///
+    /// # Returns
+    ///
+    /// The security of the records, with the signature that verified them if they are secure.
+    async fn verify_rrset<'a>(
+        &self,
+        rrset: &[DnsRecord],
+        records: &'a [DnsRecord],
+    ) -> Result<(Security, Option<&'a DnsRecord>)> {
+        let owner = rrset[0].get_domain();
+        let rrsigs = signatures(records, rrset);
+        if rrsigs.is_empty() {
+            return Ok((self.unsigned(owner).await?, None));
+        }
+
+        let now = now();
+        let mut reason = format!(
+            "No valid signature over {:?} {:?}",
+            owner,
+            rrset[0].type_of()
+        );
+        for rrsig in rrsigs {
+            let signer = signer_of(rrsig);
+            if !is_within(owner, signer) {
+                continue;
+            }
+            if !dnssec::is_current(rrsig, now) {
+                reason = format!("The signature over {:?} is not current", owner);
+                continue;
+            }
+
+            match self.zone_keys(signer).await? {
+                None => return Ok((Security::Insecure, None)),
+                Some(keys) => {
+                    if keys
+                        .iter()
+                        .any(|key| dnssec::verify(key, rrsig, rrset).unwrap_or(false))
+                    {
+                        return Ok((Security::Secure, Some(rrsig)));
+                    }
+                }
+            }
+        }
+
+        Ok((Security::Bogus(reason), None))
+    }
+
+    /// Verifies the `SOA`, `NSEC` and `NSEC3` records of a section, and keeps the `NSEC` and
+    /// `NSEC3` records of the closest zone a name belongs to. Those signed by another zone prove
+    /// nothing about the name, however valid their signatures.
+    ///
+    /// # Returns
+    ///
+    /// The proof, with the security of the first set that is not secure if there is one.
+    async fn verify_proof(&self, name: &str, records: &[DnsRecord]) -> Result<(Proof, Security)> {
+        let mut signed = Vec::new();
+        for rrset in dnssec::rrsets(records) {
+            let rtype = rrset[0].type_of();
+            if !matches!(rtype, QueryType::SOA | QueryType::NSEC | QueryType::NSEC3) {
+                continue;
+            }
+
+            match self.verify_rrset(&rrset, records).await? {
+                (Security::Secure, rrsig) => {
+                    if let Some(rrsig) = rrsig.filter(|_| rtype != QueryType::SOA) {
+                        signed.push((signer_of(rrsig).to_lowercase(), rrset));
+                    }
+                }
+                (security, _) => return Ok((Proof::default(), security)),
+            }
+        }
+
+        let zone = match signed
+            .iter()
+            .map(|(signer, _)| signer)
+            .filter(|signer| is_within(name, signer))
+            .max_by_key(|signer| dnssec::labels(signer))
+        {
+            Some(zone) => zone.clone(),
+            None => return Ok((Proof::default(), Security::Secure)),
+        };
+        let records = signed
+            .into_iter()
+            .filter(|(signer, _)| *signer == zone)
+            .flat_map(|(_, rrset)| rrset)
+            .collect();
+
+        Ok((Proof { zone, records }, Security::Secure))
+    }
+
+    /// Checks records synthesized from a wildcard come with a proof that their name does not exist.
+    async fn verify_expansion(
+        &self,
+        owner: &str,
+        labels: u8,
+        response: &DnsPacket,
+    ) -> Result<Security> {
+        let (proof, security) = self.verify_proof(owner, &response.authorities).await?;
+        if security != Security::Secure {
+            return Ok(security);
+        }
+
+        Ok(
+            if proves_expansion(owner, labels, &proof.zone, &proof.records) {
+                Security::Secure
+            } else {
+                Security::Bogus(format!(
+                    "No proof that {:?} does not exist, yet it is synthesized from a wildcard",
+                    owner
+                ))
+            },
+        )
+    }
+
+    /// Checks a response without answers proves the name or type does not exist.
+    async fn verify_denial(
+        &self,
+        question: &DnsQuestion,
+        response: &DnsPacket,
+    ) -> Result<Security> {
+        let records = &response.authorities;
+        if !records
+            .iter()
+            .any(|record| matches!(record, DnsRecord::RRSIG { .. }))
+        {
+            return self.unsigned(&question.name).await;
+        }
+
+        let (proof, security) = self.verify_proof(&question.name, records).await?;
+        if security != Security::Secure {
+            return Ok(security);
+        }
+
+        let proven = match response.header.rescode {
+            ResultCode::NXDOMAIN => proves_nxdomain(&question.name, &proof.zone, &proof.records),
+            _ => proves_nodata(&question.name, question.qtype, &proof.zone, &proof.records),
+        };
+
+        Ok(if proven {
+            Security::Secure
+        } else {
+            Security::Bogus(format!(
+                "No proof that {:?} {:?} does not exist",
+                question.name, question.qtype
+            ))
+        })
+    }
+
+    /// Decides whether records without signatures belong to a zone that is not signed.
+    async fn unsigned(&self, name: &str) -> Result<Security> {
+        // The SOA in the answer or authority section names the zone the name belongs to.
+        let response = self.resolver.resolve(name, QueryType::SOA).await?;
+        let zone = response
+            .answers
+            .iter()
+            .chain(response.authorities.iter())
+            .find(|record| {
+                matches!(record, DnsRecord::SOA { .. }) && is_within(name, record.get_domain())
+            })
+            .map_or("", |record| record.get_domain());
+
+        Ok(match self.zone_keys(zone).await? {
+            None => Security::Insecure,
+            Some(_) => Security::Bogus(format!("{:?} is not signed", name)),
+        })
+    }
+
+    fn cached(&self, zone: &str) -> Option<ZoneKeys> {
+        let zones = self.zones.lock().unwrap();
+        zones
+            .get(zone)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(keys, _)| keys.clone())
+    }
+
+    fn cache(&self, zone: &str, keys: &ZoneKeys) {
+        let ttl = keys
+            .iter()
+            .flatten()
+            .map(|key| Duration::from_secs(key.get_ttl() as u64))
+            .min()
+            .unwrap_or(MAX_KEYS_AGE)
+            .min(MAX_KEYS_AGE);
+
+        self.zones
+            .lock()
+            .unwrap()
+            .insert(zone.to_string(), (keys.clone(), Instant::now() + ttl));
+    }
+
+    /// Resolves the keys of a zone, validating the chain of trust from a trust anchor down to it.
+    async fn zone_keys(&self, zone: &str) -> Result<ZoneKeys> {
+        let zone = zone.trim_end_matches('.').to_lowercase();
+
+        // Up to a zone with known keys or a trust anchor, remembering the DS responses on the way.
+        let mut chain = Vec::new();
+        let mut name = zone;
+        let mut keys = loop {
+            if let Some(keys) = self.cached(&name) {
+                break keys;
+            }
+
+            let anchors = self
+                .anchors
+                .iter()
+                .filter(|anchor| anchor.get_domain().eq_ignore_ascii_case(&name))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !anchors.is_empty() {
+                let response = self.resolver.resolve(&name, QueryType::DNSKEY).await?;
+                let keys = Some(
+                    self.verify_dnskeys(&name, &response, &anchors)?
+                        .ok_or_else(|| {
+                            bogus(format!("The trust anchors of {:?} are not supported", name))
+                        })?,
+                );
+                self.cache(&name, &keys);
+                break keys;
+            }
+
+            let response = self.resolver.resolve(&name, QueryType::DS).await?;
+            let next = match parent_of(&name) {
+                Some(parent) => parent.to_string(),
+                None => return Err(bogus("No trust anchor for the root".to_string())),
+            };
+            // The DS records of a zone are served by its parent, which signs them.
+            let signer = response
+                .answers
+                .iter()
+                .chain(response.authorities.iter())
+                .find_map(|record| match record {
+                    DnsRecord::RRSIG { signer, .. }
+                        if is_within(&name, signer) && *signer != name =>
+                    {
+                        Some(signer.clone())
+                    }
+                    DnsRecord::SOA { domain, .. }
+                        if is_within(&name, domain) && *domain != name =>
+                    {
+                        Some(domain.clone())
+                    }
+                    _ => None,
+                })
+                .filter(|signer| is_within(&next, signer))
+                .unwrap_or(next);
+
+            chain.push((name, response));
+            name = signer;
+        };
+
+        // Back down, each zone is validated with the keys of its parent.
+        for (child, response) in chain.into_iter().rev() {
+            keys = match keys {
+                None => None,
+                Some(parent_keys) => {
+                    // Names that are not zone cuts share the keys of their zone.
+                    let parent = parent_keys
+                        .first()
+                        .map_or(String::new(), |key| key.get_domain().to_string());
+                    self.child_keys(&child, &response, &parent, parent_keys)
+                        .await?
+                }
+            };
+            self.cache(&child, &keys);
+        }
+
+        Ok(keys)
+    }
+
+    /// Validates the keys of a zone with the `DS` response of its parent.
+    async fn child_keys(
+        &self,
+        child: &str,
+        response: &DnsPacket,
+        parent: &str,
+        parent_keys: Vec<DnsRecord>,
+    ) -> Result<ZoneKeys> {
+        let ds = response
+            .answers
+            .iter()
+            .filter(|record| {
+                matches!(record, DnsRecord::DS { .. })
+                    && record.get_domain().eq_ignore_ascii_case(child)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !ds.is_empty() {
+            if !is_signed_by(&response.answers, &ds, parent, &parent_keys) {
+                return Err(bogus(format!("The DS records of {:?} are bogus", child)));
+            }
+
+            let response = self.resolver.resolve(child, QueryType::DNSKEY).await?;
+            return self.verify_dnskeys(child, &response, &ds);
+        }
+
+        // Without DS records, the parent proves they do not exist.
+        let proof = dnssec::rrsets(&response.authorities)
+            .into_iter()
+            .filter(|rrset| matches!(rrset[0].type_of(), QueryType::NSEC | QueryType::NSEC3))
+            .filter(|rrset| is_signed_by(&response.authorities, rrset, parent, &parent_keys))
+            .flatten()
+            .collect::<Vec<_>>();
+
+        Ok(match ds_denial(child, parent, &proof)? {
+            Delegation::None => Some(parent_keys),
+            Delegation::Insecure => None,
+        })
+    }
+
+    /// Validates the `DNSKEY` records of a zone with the `DS` records that refer to them.
+    ///
+    /// # Returns
+    ///
+    /// The zone keys, none if no `DS` record uses a supported algorithm, or an error if the keys are bogus.
+    fn verify_dnskeys(
+        &self,
+        zone: &str,
+        response: &DnsPacket,
+        ds: &[DnsRecord],
+    ) -> Result<ZoneKeys> {
+        let supported = ds
+            .iter()
+            .filter(|record| match record {
+                DnsRecord::DS {
+                    algorithm,
+                    digest_type,
+                    ..
+                } => {
+                    matches!(
+                        *algorithm,
+                        dnssec::ALGORITHM_RSASHA256
+                            | dnssec::ALGORITHM_ECDSAP256SHA256
+                            | dnssec::ALGORITHM_ED25519
+                    ) && matches!(*digest_type, dnssec::DIGEST_SHA1 | dnssec::DIGEST_SHA256)
+                }
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        if supported.is_empty() {
+            return Ok(None);
+        }
+
+        let dnskeys = response
+            .answers
+            .iter()
+            .filter(|record| {
+                matches!(record, DnsRecord::DNSKEY { .. })
+                    && record.get_domain().eq_ignore_ascii_case(zone)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let entry_keys = dnskeys
+            .iter()
+            .filter(|key| {
+                supported
+                    .iter()
+                    .any(|ds| dnssec::ds_matches(ds, key).unwrap_or(false))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if dnskeys.is_empty() || !is_signed_by(&response.answers, &dnskeys, zone, &entry_keys) {
+            return Err(bogus(format!("The DNSKEY records of {:?} are bogus", zone)));
+        }
+
+        Ok(Some(
+            dnskeys
+                .into_iter()
+                .filter(|key| matches!(key, DnsRecord::DNSKEY { flags, .. } if flags & ZONE_KEY_FLAG != 0))
+                .collect(),
+        ))
+    }
+}
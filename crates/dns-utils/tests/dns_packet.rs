@@ -53,3 +53,112 @@ fn test_create_dns_packet_from_buffer() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_write_and_read_soa_transfer() -> Result<()> {
+    let soa = DnsRecord::SOA {
+        domain: "example.o".to_string(),
+        primary: "ns1.example.o".to_string(),
+        hostmaster: "hostmaster.example.o".to_string(),
+        serial: 42,
+        refresh: 3600,
+        retry: 600,
+        expire: 604800,
+        minimum: 60,
+        ttl: 60,
+    };
+
+    let mut packet = DnsPacket::new();
+    packet.header.id = 1;
+    packet.header.response = true;
+    packet
+        .questions
+        .push(DnsQuestion::new("example.o".to_string(), QueryType::AXFR));
+    packet.answers.push(soa.clone());
+
+    // Enough records to overflow a UDP message.
+    for i in 0..40 {
+        packet.answers.push(DnsRecord::A {
+            domain: format!("host{}.example.o", i),
+            addr: Ipv4Addr::new(10, 0, 0, i),
+            ttl: 30,
+        });
+    }
+    packet.answers.push(soa.clone());
+
+    assert!(packet.write(&mut BytePacketBuffer::new()).is_err());
+
+    let mut buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+    packet.write(&mut buffer)?;
+    buffer.seek(0)?;
+
+    let read = DnsPacket::from_buffer(&mut buffer)?;
+    assert_eq!(read.questions[0].qtype, QueryType::AXFR);
+    assert_eq!(read.answers.len(), 42);
+    assert_eq!(read.answers[0], soa);
+    assert_eq!(read.answers[41], soa);
+    assert_eq!(read.answers[0].type_of(), QueryType::SOA);
+
+    Ok(())
+}
+
+#[test]
+fn test_notify_opcode() -> Result<()> {
+    let mut packet = DnsPacket::new();
+    packet.header.opcode = OPCODE_NOTIFY;
+    packet.header.authoritative_answer = true;
+    packet
+        .questions
+        .push(DnsQuestion::new("example.o".to_string(), QueryType::SOA));
+
+    let mut buffer = BytePacketBuffer::new();
+    packet.write(&mut buffer)?;
+    buffer.seek(0)?;
+
+    let read = DnsPacket::from_buffer(&mut buffer)?;
+    assert_eq!(read.header.opcode, OPCODE_NOTIFY);
+    assert!(read.header.authoritative_answer);
+    assert_eq!(read.questions[0].qtype, QueryType::SOA);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_and_read_txt() -> Result<()> {
+    let txt = DnsRecord::TXT {
+        domain: "_validity.example.o".to_string(),
+        data: vec![
+            "v=xdns1; a=ed25519".to_string(),
+            "k".repeat(MAX_CHARACTER_STRING),
+            String::new(),
+        ],
+        ttl: 3600,
+    };
+
+    let mut packet = DnsPacket::new();
+    packet.questions.push(DnsQuestion::new(
+        "_validity.example.o".to_string(),
+        QueryType::TXT,
+    ));
+    packet.answers.push(txt.clone());
+
+    let mut buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+    packet.write(&mut buffer)?;
+    buffer.seek(0)?;
+
+    let read = DnsPacket::from_buffer(&mut buffer)?;
+    assert_eq!(read.questions[0].qtype, QueryType::TXT);
+    assert_eq!(read.answers, vec![txt]);
+
+    // A character string is at most 255 bytes long.
+    packet.answers = vec![DnsRecord::TXT {
+        domain: "example.o".to_string(),
+        data: vec!["k".repeat(MAX_CHARACTER_STRING + 1)],
+        ttl: 3600,
+    }];
+    assert!(packet
+        .write(&mut BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE))
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,304 @@
+use std::cmp::Ordering;
+
+use dns_utils::dnssec::{self, ALGORITHM_ED25519, DIGEST_SHA256};
+use dns_utils::prelude::*;
+use shared::prelude::*;
+use xdns_data::prelude::Type;
+
+// The first example of RFC 8080 section 6, the secret is base64 encoded there.
+const SECRET: &str = "3832323630333834363238303830313232363435313930323034313432323632";
+const PUBLIC_KEY: &str = "974d96a22d224bc01adb915091477d44ccd91c9a41a11430010117d52c59240e";
+const DIGEST: &str = "3aa5ab37efce57f737fc1627013fee07bdf241bd10f3b1964ab55c78e79a304b";
+const SIGNATURE: &str = "a0bf64ac9ba7ef17c138859c1878bb99a839fe1759aca5b0d798cf1ab1e98d079102f4ddb3368f0fe40bb377f1f00e0cddedb799167d56b6e932783072ba8d02";
+
+fn mx() -> DnsRecord {
+    DnsRecord::MX {
+        domain: "example.com".to_string(),
+        priority: 10,
+        host: "mail.example.com".to_string(),
+        ttl: 3600,
+    }
+}
+
+fn round_trip(packet: &mut DnsPacket) -> Result<DnsPacket> {
+    let mut buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+    packet.write(&mut buffer)?;
+    buffer.seek(0)?;
+
+    DnsPacket::from_buffer(&mut buffer)
+}
+
+#[test]
+fn test_dnskey_and_ds() -> Result<()> {
+    let key = NodeKey::from_hex(SECRET)?;
+    let dnskey = key.dnskey("example.com", 3600);
+
+    match &dnskey {
+        DnsRecord::DNSKEY {
+            flags,
+            algorithm,
+            public_key,
+            ..
+        } => {
+            assert_eq!(*flags, 257);
+            assert_eq!(*algorithm, ALGORITHM_ED25519);
+            assert_eq!(hex::encode(public_key), PUBLIC_KEY);
+        }
+        other => panic!("expected a DNSKEY, got {:?}", other),
+    }
+    assert_eq!(key.key_tag(), 3613);
+    assert_eq!(dnssec::key_tag(&dnskey)?, 3613);
+
+    match dnssec::ds(&dnskey, 3600)? {
+        DnsRecord::DS {
+            domain,
+            key_tag,
+            algorithm,
+            digest_type,
+            digest,
+            ..
+        } => {
+            assert_eq!(domain, "example.com");
+            assert_eq!((key_tag, algorithm, digest_type), (3613, 15, DIGEST_SHA256));
+            assert_eq!(hex::encode(digest), DIGEST);
+        }
+        other => panic!("expected a DS, got {:?}", other),
+    }
+
+    assert!(NodeKey::from_hex("not hex").is_err());
+    assert!(NodeKey::from_hex("abcd").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_sign_and_verify() -> Result<()> {
+    let key = NodeKey::from_hex(SECRET)?;
+    let dnskey = key.dnskey("example.com", 3600);
+    let rrsig = key.sign("example.com", &[mx()], 1438207200, 1440021600)?;
+
+    // Ed25519 signatures are deterministic, so the signature of the RFC is reproduced.
+    match &rrsig {
+        DnsRecord::RRSIG {
+            type_covered,
+            labels,
+            original_ttl,
+            key_tag,
+            signer,
+            signature,
+            ..
+        } => {
+            assert_eq!(*type_covered, 15);
+            assert_eq!((*labels, *original_ttl, *key_tag), (2, 3600, 3613));
+            assert_eq!(signer, "example.com");
+            assert_eq!(hex::encode(signature), SIGNATURE);
+        }
+        other => panic!("expected an RRSIG, got {:?}", other),
+    }
+
+    assert!(dnssec::verify(&dnskey, &rrsig, &[mx()])?);
+
+    // The ttl of the records is not signed, their contents are.
+    let mut expiring = mx();
+    expiring.set_ttl(60);
+    assert!(dnssec::verify(&dnskey, &rrsig, &[expiring])?);
+
+    let mut tampered = mx();
+    if let DnsRecord::MX {
+        ref mut priority, ..
+    } = tampered
+    {
+        *priority = 20;
+    }
+    assert!(!dnssec::verify(&dnskey, &rrsig, &[tampered])?);
+
+    let other = NodeKey::from_bytes(&[7; 32]).dnskey("example.com", 3600);
+    assert!(!dnssec::verify(&other, &rrsig, &[mx()])?);
+    assert!(key.sign("example.com", &[], 0, 0).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_validity_period_wraps_around() -> Result<()> {
+    let key = NodeKey::from_hex(SECRET)?;
+    let rrsig = key.sign("example.com", &[mx()], 1438207200, 1440021600)?;
+
+    assert!(dnssec::is_current(&rrsig, 1438207200));
+    assert!(dnssec::is_current(&rrsig, 1440021600));
+    assert!(!dnssec::is_current(&rrsig, 1438207199));
+    assert!(!dnssec::is_current(&rrsig, 1440021601));
+
+    // A period around the moment the seconds since the epoch wrap around in 2106.
+    let rrsig = key.sign("example.com", &[mx()], u32::MAX - 3600, 3600)?;
+    assert!(dnssec::is_current(&rrsig, u32::MAX));
+    assert!(dnssec::is_current(&rrsig, 0));
+    assert!(!dnssec::is_current(&rrsig, 7200));
+
+    Ok(())
+}
+
+#[test]
+fn test_rrset_order_is_canonical() -> Result<()> {
+    let key = NodeKey::from_bytes(&[1; 32]);
+    let a = |addr: [u8; 4]| DnsRecord::A {
+        domain: "WWW.example.o".to_string(),
+        addr: addr.into(),
+        ttl: 60,
+    };
+
+    let rrsig = key.sign("example.o", &[a([10, 0, 0, 2]), a([10, 0, 0, 1])], 0, 1)?;
+    let dnskey = key.dnskey("example.o", 60);
+
+    assert_eq!(rrsig.get_domain(), "www.example.o");
+    assert!(dnssec::verify(
+        &dnskey,
+        &rrsig,
+        &[a([10, 0, 0, 1]), a([10, 0, 0, 2])]
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_and_read_dnssec_records() -> Result<()> {
+    let key = NodeKey::from_hex(SECRET)?;
+    let dnskey = key.dnskey("example.com", 3600);
+
+    let mut packet = DnsPacket::new();
+    packet.answers = vec![
+        dnskey.clone(),
+        dnssec::ds(&dnskey, 3600)?,
+        key.sign("example.com", &[mx()], 1438207200, 1440021600)?,
+        dnssec::nodata_nsec(
+            "example.com",
+            &[QueryType::SUB(Type::MX), QueryType::DNSKEY],
+            300,
+        ),
+        DnsRecord::NSEC3 {
+            domain: "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.com".to_string(),
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 12,
+            salt: vec![0xaa, 0xbb, 0xcc, 0xdd],
+            next_hashed: vec![0x11; 20],
+            types: vec![1, 46, 1234],
+            ttl: 300,
+        },
+    ];
+    packet.resources = vec![DnsRecord::OPT {
+        payload_size: 1232,
+        flags: EDNS_DO_FLAG,
+    }];
+
+    let read = round_trip(&mut packet)?;
+    assert_eq!(read.answers, packet.answers);
+    assert_eq!(read.resources, packet.resources);
+
+    match &read.answers[3] {
+        DnsRecord::NSEC { next, types, .. } => {
+            assert_eq!(next, "\0.example.com");
+            assert_eq!(types, &vec![15, 46, 47, 48]);
+        }
+        other => panic!("expected an NSEC, got {:?}", other),
+    }
+
+    assert!(read.dnssec_ok());
+    assert_eq!(read.max_payload_size(), 1232);
+
+    Ok(())
+}
+
+#[test]
+fn test_edns_defaults() -> Result<()> {
+    let mut packet = DnsPacket::new();
+    assert!(!packet.dnssec_ok());
+    assert_eq!(packet.max_payload_size(), 512);
+
+    packet.resources.push(DnsRecord::OPT {
+        payload_size: 100,
+        flags: 0,
+    });
+    let read = round_trip(&mut packet)?;
+
+    assert!(!read.dnssec_ok());
+    assert_eq!(read.max_payload_size(), 512);
+
+    Ok(())
+}
+
+#[test]
+fn test_covering_names() {
+    let name = "nope.example.o";
+    let nsec = dnssec::covering_nsec(name, 60);
+    let (owner, next) = match &nsec {
+        DnsRecord::NSEC { domain, next, .. } => (domain.as_str(), next.as_str()),
+        other => panic!("expected an NSEC, got {:?}", other),
+    };
+
+    assert_eq!(dnssec::canonical_cmp(owner, name), Ordering::Less);
+    assert_eq!(dnssec::canonical_cmp(next, name), Ordering::Greater);
+
+    // Names that can exist next to the denied name are not covered.
+    for existing in [
+        "nopd.example.o",
+        "nopdzzz.example.o",
+        "nope-.example.o",
+        "example.o",
+    ] {
+        let covered = dnssec::canonical_cmp(owner, existing) == Ordering::Less
+            && dnssec::canonical_cmp(existing, next) == Ordering::Less;
+        assert!(!covered, "{} is covered", existing);
+    }
+
+    // Descendants of the denied name do not exist either.
+    assert_eq!(
+        dnssec::canonical_cmp("a.nope.example.o", next),
+        Ordering::Less
+    );
+    assert_eq!(dnssec::labels("*.example.o"), 2);
+    assert_eq!(dnssec::labels("www.example.o"), 3);
+}
+
+#[test]
+fn test_nodata_does_not_cover_children() {
+    let nsec = dnssec::nodata_nsec("example.o", &[QueryType::SUB(Type::A)], 60);
+    let (owner, next) = match &nsec {
+        DnsRecord::NSEC { domain, next, .. } => (domain.as_str(), next.as_str()),
+        other => panic!("expected an NSEC, got {:?}", other),
+    };
+
+    assert_eq!(owner, "example.o");
+    for existing in [
+        "www.example.o",
+        "a.b.example.o",
+        "-.example.o",
+        "example0.o",
+    ] {
+        let covered = dnssec::canonical_cmp(owner, existing) == Ordering::Less
+            && dnssec::canonical_cmp(existing, next) == Ordering::Less;
+        assert!(!covered, "{} is covered", existing);
+    }
+}
+
+#[test]
+fn test_rrsets() {
+    let sets = dnssec::rrsets(&[
+        mx(),
+        DnsRecord::A {
+            domain: "example.com".to_string(),
+            addr: [10, 0, 0, 1].into(),
+            ttl: 60,
+        },
+        DnsRecord::MX {
+            domain: "Example.com".to_string(),
+            priority: 20,
+            host: "backup.example.com".to_string(),
+            ttl: 3600,
+        },
+    ]);
+
+    assert_eq!(sets.len(), 2);
+    assert_eq!(sets[0].len(), 2);
+    assert_eq!(sets[1][0].type_of(), QueryType::SUB(Type::A));
+}
@@ -0,0 +1,744 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dns_utils::dnssec::{
+    self, ALGORITHM_ECDSAP256SHA256, ALGORITHM_ED25519, ALGORITHM_RSASHA256, DNSKEY_FLAGS,
+};
+use dns_utils::prelude::*;
+use dns_utils::validator::{Security, UdpResolver, Validator};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair, RsaPublicKeyComponents,
+    ECDSA_P256_SHA256_FIXED_SIGNING, RSA_PKCS1_SHA256,
+};
+use shared::prelude::*;
+use tokio::net::UdpSocket;
+use xdns_data::prelude::Type;
+
+// The key of the fixture root zone, generated for these tests.
+const ROOT_KEY: &[u8] = include_bytes!("data/root_rsa.pk8");
+const TTL: u32 = 3600;
+
+/// Signs data with the private key of a zone.
+type Signer = Box<dyn Fn(&[u8]) -> Vec<u8>>;
+
+/// A signed fixture zone.
+struct Zone {
+    name: String,
+    dnskey: DnsRecord,
+    sign: Signer,
+}
+
+impl Zone {
+    fn rsa(name: &str) -> Zone {
+        let key = RsaKeyPair::from_pkcs8(ROOT_KEY).unwrap();
+        let public = RsaPublicKeyComponents::<Vec<u8>>::from(key.public());
+        let public_key = [
+            &[public.e.len() as u8],
+            public.e.as_slice(),
+            public.n.as_slice(),
+        ]
+        .concat();
+
+        Zone {
+            name: name.to_string(),
+            dnskey: dnskey(name, ALGORITHM_RSASHA256, public_key),
+            sign: Box::new(move |data| {
+                let mut signature = vec![0; key.public().modulus_len()];
+                key.sign(
+                    &RSA_PKCS1_SHA256,
+                    &SystemRandom::new(),
+                    data,
+                    &mut signature,
+                )
+                .unwrap();
+                signature
+            }),
+        }
+    }
+
+    fn ecdsa(name: &str) -> Zone {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        // The key is the uncompressed point without its prefix.
+        let public_key = key.public_key().as_ref()[1..].to_vec();
+
+        Zone {
+            name: name.to_string(),
+            dnskey: dnskey(name, ALGORITHM_ECDSAP256SHA256, public_key),
+            sign: Box::new(move |data| {
+                key.sign(&SystemRandom::new(), data)
+                    .unwrap()
+                    .as_ref()
+                    .to_vec()
+            }),
+        }
+    }
+
+    fn ed25519(name: &str) -> Zone {
+        let key = Ed25519KeyPair::from_seed_unchecked(&[3; 32]).unwrap();
+        let public_key = key.public_key().as_ref().to_vec();
+
+        Zone {
+            name: name.to_string(),
+            dnskey: dnskey(name, ALGORITHM_ED25519, public_key),
+            sign: Box::new(move |data| key.sign(data).as_ref().to_vec()),
+        }
+    }
+
+    fn sign_between(&self, rrset: &[DnsRecord], inception: u32, expiration: u32) -> DnsRecord {
+        let mut rrsig = DnsRecord::RRSIG {
+            domain: rrset[0].get_domain().to_string(),
+            type_covered: rrset[0].type_of().to_num(),
+            algorithm: match &self.dnskey {
+                DnsRecord::DNSKEY { algorithm, .. } => *algorithm,
+                _ => unreachable!(),
+            },
+            labels: dnssec::labels(rrset[0].get_domain()),
+            original_ttl: rrset[0].get_ttl(),
+            expiration,
+            inception,
+            key_tag: dnssec::key_tag(&self.dnskey).unwrap(),
+            signer: self.name.clone(),
+            signature: Vec::new(),
+            ttl: rrset[0].get_ttl(),
+        };
+
+        let data = dnssec::signed_data(&rrsig, rrset).unwrap();
+        if let DnsRecord::RRSIG {
+            ref mut signature, ..
+        } = rrsig
+        {
+            *signature = (self.sign)(&data);
+        }
+
+        rrsig
+    }
+
+    /// Signs a set of records, valid for an hour around now.
+    fn sign(&self, rrset: &[DnsRecord]) -> DnsRecord {
+        let now = now();
+        self.sign_between(rrset, now - 3600, now + 3600)
+    }
+
+    /// A set of records followed by its signature.
+    fn signed(&self, rrset: Vec<DnsRecord>) -> Vec<DnsRecord> {
+        let rrsig = self.sign(&rrset);
+        rrset.into_iter().chain([rrsig]).collect()
+    }
+
+    fn ds(&self) -> DnsRecord {
+        dnssec::ds(&self.dnskey, TTL).unwrap()
+    }
+
+    fn soa(&self) -> DnsRecord {
+        DnsRecord::SOA {
+            domain: self.name.clone(),
+            primary: format!("ns.{}", self.name),
+            hostmaster: format!("hostmaster.{}", self.name),
+            serial: 1,
+            refresh: 3600,
+            retry: 600,
+            expire: 86400,
+            minimum: 300,
+            ttl: 300,
+        }
+    }
+}
+
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32
+}
+
+fn dnskey(zone: &str, algorithm: u8, public_key: Vec<u8>) -> DnsRecord {
+    DnsRecord::DNSKEY {
+        domain: zone.to_string(),
+        flags: DNSKEY_FLAGS,
+        protocol: 3,
+        algorithm,
+        public_key,
+        ttl: TTL,
+    }
+}
+
+fn a(domain: &str, addr: [u8; 4]) -> DnsRecord {
+    DnsRecord::A {
+        domain: domain.to_string(),
+        addr: addr.into(),
+        ttl: 300,
+    }
+}
+
+fn types(types: &[QueryType]) -> Vec<u16> {
+    let mut types = types.iter().map(|qtype| qtype.to_num()).collect::<Vec<_>>();
+    types.sort();
+    types
+}
+
+type Responses = HashMap<(String, u16), DnsPacket>;
+
+fn response(
+    rescode: ResultCode,
+    answers: Vec<DnsRecord>,
+    authorities: Vec<DnsRecord>,
+) -> DnsPacket {
+    let mut packet = DnsPacket::new();
+    packet.header.rescode = rescode;
+    packet.answers = answers;
+    packet.authorities = authorities;
+    packet
+}
+
+/// The responses of a resolver for the fixture zones: the root signed with RSA/SHA-256, `com`
+/// and `evil.com` with ECDSA P-256 and `example.com` with Ed25519, and `insecure.com` which is
+/// not signed.
+fn fixtures() -> (Zone, Responses) {
+    let root = Zone::rsa("");
+    let com = Zone::ecdsa("com");
+    let example = Zone::ed25519("example.com");
+    let evil = Zone::ecdsa("evil.com");
+
+    let mut responses = Responses::new();
+    let mut add = |name: &str, qtype: QueryType, packet: DnsPacket| {
+        responses.insert((name.to_string(), qtype.to_num()), packet);
+    };
+
+    for zone in [&root, &com, &example, &evil] {
+        add(
+            &zone.name,
+            QueryType::DNSKEY,
+            response(
+                ResultCode::NOERROR,
+                zone.signed(vec![zone.dnskey.clone()]),
+                vec![],
+            ),
+        );
+    }
+    add(
+        "com",
+        QueryType::DS,
+        response(ResultCode::NOERROR, root.signed(vec![com.ds()]), vec![]),
+    );
+    add(
+        "example.com",
+        QueryType::DS,
+        response(ResultCode::NOERROR, com.signed(vec![example.ds()]), vec![]),
+    );
+    add(
+        "evil.com",
+        QueryType::DS,
+        response(ResultCode::NOERROR, com.signed(vec![evil.ds()]), vec![]),
+    );
+
+    let a_query = QueryType::SUB(Type::A);
+    add(
+        "www.example.com",
+        a_query,
+        response(
+            ResultCode::NOERROR,
+            example.signed(vec![
+                a("www.example.com", [10, 0, 0, 1]),
+                a("www.example.com", [10, 0, 0, 2]),
+            ]),
+            vec![],
+        ),
+    );
+
+    let mut tampered = example.signed(vec![a("tampered.example.com", [10, 0, 0, 1])]);
+    tampered[0] = a("tampered.example.com", [10, 6, 6, 6]);
+    add(
+        "tampered.example.com",
+        a_query,
+        response(ResultCode::NOERROR, tampered, vec![]),
+    );
+
+    let record = a("expired.example.com", [10, 0, 0, 1]);
+    let rrsig = example.sign_between(std::slice::from_ref(&record), now() - 7200, now() - 3600);
+    add(
+        "expired.example.com",
+        a_query,
+        response(ResultCode::NOERROR, vec![record, rrsig], vec![]),
+    );
+
+    add(
+        "unsigned.example.com",
+        a_query,
+        response(
+            ResultCode::NOERROR,
+            vec![a("unsigned.example.com", [10, 0, 0, 1])],
+            vec![],
+        ),
+    );
+    add(
+        "unsigned.example.com",
+        QueryType::SOA,
+        response(
+            ResultCode::NOERROR,
+            vec![],
+            example.signed(vec![example.soa()]),
+        ),
+    );
+
+    // The apex of example.com sorts before the missing name, and www after it.
+    let nsec = |owner: &str, next: &str, covered: &[QueryType]| DnsRecord::NSEC {
+        domain: owner.to_string(),
+        next: next.to_string(),
+        types: types(covered),
+        ttl: 300,
+    };
+    let apex = nsec(
+        "example.com",
+        "www.example.com",
+        &[
+            QueryType::SOA,
+            QueryType::DNSKEY,
+            QueryType::NSEC,
+            QueryType::RRSIG,
+        ],
+    );
+    add(
+        "nope.example.com",
+        a_query,
+        response(
+            ResultCode::NXDOMAIN,
+            vec![],
+            [
+                example.signed(vec![example.soa()]),
+                example.signed(vec![apex.clone()]),
+            ]
+            .concat(),
+        ),
+    );
+
+    // An NSEC that covers the name but not the wildcard below the apex is not a proof.
+    let partial = nsec("m.example.com", "www.example.com", &[a_query]);
+    add(
+        "partial.example.com",
+        a_query,
+        response(
+            ResultCode::NXDOMAIN,
+            vec![],
+            [
+                example.signed(vec![example.soa()]),
+                example.signed(vec![partial]),
+            ]
+            .concat(),
+        ),
+    );
+
+    let www = nsec(
+        "www.example.com",
+        "example.com",
+        &[a_query, QueryType::NSEC, QueryType::RRSIG],
+    );
+    add(
+        "www.example.com",
+        QueryType::SUB(Type::AAAA),
+        response(
+            ResultCode::NOERROR,
+            vec![],
+            [
+                example.signed(vec![example.soa()]),
+                example.signed(vec![www.clone()]),
+            ]
+            .concat(),
+        ),
+    );
+    // The records of www.example.com exist, so the same proof does not deny them.
+    add(
+        "www.example.com",
+        QueryType::SUB(Type::MX),
+        response(
+            ResultCode::NOERROR,
+            vec![],
+            [
+                example.signed(vec![example.soa()]),
+                example.signed(vec![nsec(
+                    "www.example.com",
+                    "example.com",
+                    &[a_query, QueryType::SUB(Type::MX)],
+                )]),
+            ]
+            .concat(),
+        ),
+    );
+
+    // The last NSEC of evil.com wraps around to its apex, it denies nothing outside of evil.com.
+    let forged = nsec("zzz.evil.com", "evil.com", &[a_query]);
+    add(
+        "forged.example.com",
+        a_query,
+        response(
+            ResultCode::NXDOMAIN,
+            vec![],
+            [
+                example.signed(vec![example.soa()]),
+                evil.signed(vec![forged]),
+            ]
+            .concat(),
+        ),
+    );
+
+    // The NSEC of the delegation to sub.example.com only proves it has no DS records.
+    let delegation = nsec(
+        "sub.example.com",
+        "www.example.com",
+        &[QueryType::SUB(Type::NS), QueryType::NSEC, QueryType::RRSIG],
+    );
+    for (name, qtype, rescode) in [
+        ("sub.example.com", QueryType::DS, ResultCode::NOERROR),
+        ("sub.example.com", a_query, ResultCode::NOERROR),
+        ("www.sub.example.com", a_query, ResultCode::NXDOMAIN),
+    ] {
+        add(
+            name,
+            qtype,
+            response(
+                rescode,
+                vec![],
+                [
+                    example.signed(vec![example.soa()]),
+                    example.signed(vec![delegation.clone()]),
+                ]
+                .concat(),
+            ),
+        );
+    }
+
+    // Synthesized from *.example.com, which sorts right after the apex.
+    let wildcard = nsec("*.example.com", "www.example.com", &[a_query]);
+    let expanded = |name: &str| {
+        let mut records = example.signed(vec![a("*.example.com", [10, 0, 0, 4])]);
+        for record in records.iter_mut() {
+            record.set_domain(name);
+        }
+        records
+    };
+    add(
+        "wild.example.com",
+        a_query,
+        response(
+            ResultCode::NOERROR,
+            expanded("wild.example.com"),
+            example.signed(vec![wildcard]),
+        ),
+    );
+    // Without a proof the name does not exist, the wildcard may be replayed over an existing name,
+    // and a.unproven.example.com is below a name that exists.
+    add(
+        "unproven.example.com",
+        a_query,
+        response(
+            ResultCode::NOERROR,
+            expanded("unproven.example.com"),
+            vec![],
+        ),
+    );
+    add(
+        "a.unproven.example.com",
+        a_query,
+        response(
+            ResultCode::NOERROR,
+            expanded("a.unproven.example.com"),
+            example.signed(vec![nsec(
+                "unproven.example.com",
+                "www.example.com",
+                &[a_query],
+            )]),
+        ),
+    );
+
+    // com proves insecure.com is a delegation without DS records.
+    let salt = vec![0xab, 0xcd];
+    let hash = dnssec::nsec3_hash("insecure.com", &salt, 5).unwrap();
+    let mut next_hashed = hash.clone();
+    next_hashed[19] = next_hashed[19].wrapping_add(1);
+    let nsec3 = DnsRecord::NSEC3 {
+        domain: format!("{}.com", dnssec::base32hex(&hash)),
+        hash_algorithm: 1,
+        flags: 0,
+        iterations: 5,
+        salt,
+        next_hashed,
+        types: types(&[QueryType::SUB(Type::NS)]),
+        ttl: 300,
+    };
+    add(
+        "insecure.com",
+        QueryType::DS,
+        response(
+            ResultCode::NOERROR,
+            vec![],
+            [com.signed(vec![com.soa()]), com.signed(vec![nsec3])].concat(),
+        ),
+    );
+    add(
+        "www.insecure.com",
+        a_query,
+        response(
+            ResultCode::NOERROR,
+            vec![a("www.insecure.com", [10, 0, 0, 3])],
+            vec![],
+        ),
+    );
+    let insecure = Zone::ecdsa("insecure.com");
+    add(
+        "www.insecure.com",
+        QueryType::SOA,
+        response(ResultCode::NOERROR, vec![], vec![insecure.soa()]),
+    );
+
+    (root, responses)
+}
+
+/// Answers queries with the fixture responses, `SERVFAIL` for the others.
+async fn serve(responses: Responses) -> Result<SocketAddr> {
+    let socket = UdpSocket::bind("127.0.0.1:0").await?;
+    let addr = socket.local_addr()?;
+    let responses = Arc::new(responses);
+
+    tokio::spawn(async move {
+        loop {
+            let mut req_buffer = BytePacketBuffer::new();
+            let (_, src) = match socket.recv_from(&mut req_buffer.buf).await {
+                Ok(received) => received,
+                Err(_) => return,
+            };
+            let request = match DnsPacket::from_buffer(&mut req_buffer) {
+                Ok(request) => request,
+                Err(_) => continue,
+            };
+
+            let question = request.questions[0].clone();
+            let mut packet = responses
+                .get(&(question.name.clone(), question.qtype.to_num()))
+                .cloned()
+                .unwrap_or_else(|| response(ResultCode::SERVFAIL, vec![], vec![]));
+            packet.header.id = request.header.id;
+            packet.header.response = true;
+            packet.questions = vec![question];
+
+            let mut res_buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+            if packet.write(&mut res_buffer).is_ok() {
+                let _ = socket
+                    .send_to(&res_buffer.buf[0..res_buffer.pos()], src)
+                    .await;
+            }
+        }
+    });
+
+    Ok(addr)
+}
+
+async fn validator() -> Result<Validator<UdpResolver>> {
+    let (root, responses) = fixtures();
+    let server = serve(responses).await?;
+    let resolver = UdpResolver::new(server).with_timeout(Duration::from_secs(2));
+
+    Ok(Validator::new(resolver, vec![root.ds()]))
+}
+
+#[tokio::test]
+async fn test_secure_answer() -> Result<()> {
+    let validator = validator().await?;
+
+    let (response, security) = validator
+        .resolve("www.example.com", QueryType::SUB(Type::A))
+        .await?;
+    assert_eq!(security, Security::Secure);
+    assert_eq!(response.answers.len(), 3);
+
+    // The keys of the chain are remembered, the answer is validated again.
+    let (_, security) = validator
+        .resolve("www.example.com", QueryType::SUB(Type::A))
+        .await?;
+    assert_eq!(security, Security::Secure);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bogus_answers() -> Result<()> {
+    let validator = validator().await?;
+
+    for name in [
+        "tampered.example.com",
+        "expired.example.com",
+        "unsigned.example.com",
+    ] {
+        let (_, security) = validator.resolve(name, QueryType::SUB(Type::A)).await?;
+        assert!(
+            matches!(security, Security::Bogus(_)),
+            "{} is {:?}",
+            name,
+            security
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_untrusted_anchor() -> Result<()> {
+    let (_, responses) = fixtures();
+    let server = serve(responses).await?;
+    let other = Zone::rsa("");
+    let mut anchor = other.ds();
+    if let DnsRecord::DS { ref mut digest, .. } = anchor {
+        digest[0] ^= 0xff;
+    }
+    let validator = Validator::new(UdpResolver::new(server), vec![anchor]);
+
+    let (_, security) = validator
+        .resolve("www.example.com", QueryType::SUB(Type::A))
+        .await?;
+    assert!(matches!(security, Security::Bogus(_)), "{:?}", security);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insecure_delegation() -> Result<()> {
+    let validator = validator().await?;
+
+    let (response, security) = validator
+        .resolve("www.insecure.com", QueryType::SUB(Type::A))
+        .await?;
+    assert_eq!(security, Security::Insecure);
+    assert_eq!(response.answers.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_denial_of_existence() -> Result<()> {
+    let validator = validator().await?;
+
+    let (response, security) = validator
+        .resolve("nope.example.com", QueryType::SUB(Type::A))
+        .await?;
+    assert_eq!(response.header.rescode, ResultCode::NXDOMAIN);
+    assert_eq!(security, Security::Secure);
+
+    let (_, security) = validator
+        .resolve("www.example.com", QueryType::SUB(Type::AAAA))
+        .await?;
+    assert_eq!(security, Security::Secure);
+
+    for (name, qtype) in [
+        ("partial.example.com", QueryType::SUB(Type::A)),
+        ("www.example.com", QueryType::SUB(Type::MX)),
+    ] {
+        let (_, security) = validator.resolve(name, qtype).await?;
+        assert!(
+            matches!(security, Security::Bogus(_)),
+            "{} is {:?}",
+            name,
+            security
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_denial_of_another_zone() -> Result<()> {
+    let validator = validator().await?;
+
+    let (_, security) = validator
+        .resolve("forged.example.com", QueryType::SUB(Type::A))
+        .await?;
+    assert!(matches!(security, Security::Bogus(_)), "{:?}", security);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_denial_at_a_delegation() -> Result<()> {
+    let validator = validator().await?;
+
+    let (_, security) = validator.resolve("sub.example.com", QueryType::DS).await?;
+    assert_eq!(security, Security::Secure);
+
+    for name in ["sub.example.com", "www.sub.example.com"] {
+        let (_, security) = validator.resolve(name, QueryType::SUB(Type::A)).await?;
+        assert!(
+            matches!(security, Security::Bogus(_)),
+            "{} is {:?}",
+            name,
+            security
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wildcard_expansion() -> Result<()> {
+    let validator = validator().await?;
+
+    let (response, security) = validator
+        .resolve("wild.example.com", QueryType::SUB(Type::A))
+        .await?;
+    assert_eq!(security, Security::Secure);
+    assert_eq!(response.answers.len(), 2);
+
+    for name in ["unproven.example.com", "a.unproven.example.com"] {
+        let (_, security) = validator.resolve(name, QueryType::SUB(Type::A)).await?;
+        assert!(
+            matches!(security, Security::Bogus(_)),
+            "{} is {:?}",
+            name,
+            security
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_nsec3_hash() -> Result<()> {
+    // RFC 5155 appendix A.
+    let hash = dnssec::nsec3_hash("example", &[0xaa, 0xbb, 0xcc, 0xdd], 12)?;
+    assert_eq!(dnssec::base32hex(&hash), "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom");
+
+    let hash = dnssec::nsec3_hash("a.example", &[0xaa, 0xbb, 0xcc, 0xdd], 12)?;
+    assert_eq!(dnssec::base32hex(&hash), "35mthgpgcu1qg68fab165klnsnk3dpvl");
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_ds() -> Result<()> {
+    let ds = dnssec::parse_ds(
+        ". IN DS 20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+    )?;
+
+    match ds {
+        DnsRecord::DS {
+            domain,
+            key_tag,
+            algorithm,
+            digest_type,
+            digest,
+            ..
+        } => {
+            assert_eq!(domain, "");
+            assert_eq!((key_tag, algorithm, digest_type), (20326, 8, 2));
+            assert_eq!(digest.len(), 32);
+        }
+        other => panic!("expected a DS, got {:?}", other),
+    }
+
+    assert!(dnssec::parse_ds("example.com. IN DS 1 8").is_err());
+    assert!(dnssec::parse_ds("example.com. IN DS 1 8 2 zz").is_err());
+
+    Ok(())
+}
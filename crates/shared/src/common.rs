@@ -1,2 +1,3 @@
-pub type Error = Box<dyn std::error::Error>;
+pub use crate::error::{CryptoError, DbError, Error, PacketError, ParseError};
+
 pub type Result<T> = std::result::Result<T, Error>;
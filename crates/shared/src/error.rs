@@ -0,0 +1,326 @@
+use std::fmt::{self, Display};
+
+/// The error type shared by all xdns crates.
+///
+/// Every variant is `Send + Sync`, so errors can freely cross task boundaries.
+#[derive(Debug)]
+pub enum Error {
+    /// An inscription (or part of one) could not be parsed.
+    Parse(ParseError),
+    /// The repository could not fulfill a request.
+    Db(DbError),
+    /// A DNS packet could not be read or written.
+    Packet(PacketError),
+    /// A key or signature could not be processed.
+    Crypto(CryptoError),
+    /// An underlying I/O operation failed.
+    Io(std::io::Error),
+    /// Any other error, described by a message.
+    Other(String),
+}
+
+/// Describes why a specific field of an input could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The field that was being parsed. (eg `ttl`, `domain`, `algorithm`)
+    pub field: &'static str,
+    /// The input that was rejected.
+    pub input: String,
+    /// A human readable reason for the rejection.
+    pub reason: String,
+    /// The (1-based) line of the inscription the error occurred on, if known.
+    pub line: Option<usize>,
+    /// The (1-based) column of the rejected input within its line, if known.
+    pub column: Option<usize>,
+    /// The action keyword the rejected line looked like. (eg `DNS`, `DOMAIN`)
+    pub action: Option<&'static str>,
+}
+
+/// The errors a repository backend can return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbError {
+    /// The requested entity does not exist.
+    NotFound { entity: &'static str, key: String },
+    /// The domain exists but its registration has expired.
+    Expired { domain: String },
+    /// A stored value could not be converted back into its model.
+    InvalidData(String),
+    /// The backend itself failed. (eg connection loss, query error)
+    Backend(String),
+    /// An operation of a batch was rejected, so none of the batch was applied.
+    Rejected {
+        index: usize,
+        operation: &'static str,
+    },
+    /// A batch was committed at a block below the current tip, or at its height with another hash.
+    /// The tip has to be rolled back first.
+    BlockOrder { height: u64, tip: u64 },
+    /// A height below the finalized block was requested, the journal below it has been pruned.
+    Finalized { height: u64, finalized: u64 },
+    /// The repository is configured incorrectly. (eg a missing or unsupported database url)
+    Configuration(String),
+    /// A state snapshot could not be read. (eg an unsupported version or a corrupt file)
+    InvalidSnapshot(String),
+    /// The state does not hash to the value advertised by a snapshot.
+    SnapshotMismatch { expected: String, actual: String },
+}
+
+/// The errors that can occur while reading or writing a DNS packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    /// A read or write went past the end of the packet buffer.
+    BufferOverflow { position: usize },
+    /// A name contained more compression jumps than allowed.
+    TooManyJumps { limit: usize },
+    /// A single label exceeded 63 characters.
+    LabelTooLong { label: String },
+    /// The packet contents are not valid DNS.
+    Malformed(String),
+}
+
+/// The errors that can occur while handling keys and signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    /// The algorithm name is not known.
+    UnsupportedAlgorithm(String),
+    /// The public key is not valid for the algorithm.
+    InvalidKey(String),
+    /// The signature is not valid for the algorithm.
+    InvalidSignature(String),
+    /// A key or signature was not valid hexadecimal.
+    InvalidEncoding(String),
+}
+
+impl ParseError {
+    /// Creates a new parse error.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field that was being parsed.
+    /// * `input` - The input that was rejected.
+    /// * `reason` - Why the input was rejected.
+    pub fn new(field: &'static str, input: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            field,
+            input: input.into(),
+            reason: reason.into(),
+            line: None,
+            column: None,
+            action: None,
+        }
+    }
+
+    /// Sets the position of the error within the inscription.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The (1-based) line the error occurred on.
+    /// * `column` - The (1-based) column of the rejected input, if known.
+    pub fn at(mut self, line: usize, column: Option<usize>) -> Self {
+        self.line = Some(line);
+        self.column = column;
+        self
+    }
+
+    /// Sets the action keyword the rejected input looked like.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The detected action keyword.
+    pub fn for_action(mut self, action: &'static str) -> Self {
+        self.action = Some(action);
+        self
+    }
+}
+
+impl DbError {
+    /// Creates a new not found error.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The kind of entity that was looked up. (eg `domain`)
+    /// * `key` - The key that was used to look it up.
+    pub fn not_found(entity: &'static str, key: impl Into<String>) -> Self {
+        Self::NotFound {
+            entity,
+            key: key.into(),
+        }
+    }
+}
+
+impl Error {
+    /// Whether the error means the requested name or record does not exist.
+    /// This is the case for missing and expired entities.
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            Self::Db(DbError::NotFound { .. }) | Self::Db(DbError::Expired { .. })
+        )
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => e.fmt(f),
+            Self::Db(e) => e.fmt(f),
+            Self::Packet(e) => e.fmt(f),
+            Self::Crypto(e) => e.fmt(f),
+            Self::Io(e) => e.fmt(f),
+            Self::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "Line {}, column {}: ", line, column)?,
+            (Some(line), None) => write!(f, "Line {}: ", line)?,
+            _ => {}
+        }
+
+        if let Some(action) = self.action {
+            write!(f, "{} ", action)?;
+        }
+
+        write!(
+            f,
+            "Invalid {}: {} ({})",
+            self.field, self.reason, self.input
+        )
+    }
+}
+
+impl Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { entity, key } => write!(f, "{} not found: {}", entity, key),
+            Self::Expired { domain } => write!(f, "Domain expired: {}", domain),
+            Self::InvalidData(message) => write!(f, "Invalid stored data: {}", message),
+            Self::Backend(message) => write!(f, "Database error: {}", message),
+            Self::Rejected { index, operation } => write!(
+                f,
+                "Operation {} ({}) was rejected, nothing was applied",
+                index, operation
+            ),
+            Self::BlockOrder { height, tip } => write!(
+                f,
+                "Block {} does not follow the current tip {}, roll back first",
+                height, tip
+            ),
+            Self::Finalized { height, finalized } => write!(
+                f,
+                "Height {} is below the finalized block {}, its journal has been pruned",
+                height, finalized
+            ),
+            Self::Configuration(message) => {
+                write!(f, "Invalid database configuration: {}", message)
+            }
+            Self::InvalidSnapshot(message) => write!(f, "Invalid snapshot: {}", message),
+            Self::SnapshotMismatch { expected, actual } => write!(
+                f,
+                "The state hashes to {}, but the snapshot advertised {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BufferOverflow { position } => write!(
+                f,
+                "Invalid position {} (maybe the end of the buffer has been reached?)",
+                position
+            ),
+            Self::TooManyJumps { limit } => {
+                write!(f, "Exceeded limit of allowed jumps. ({})", limit)
+            }
+            Self::LabelTooLong { label } => write!(
+                f,
+                "Single label exceeds maximum length of 63 characters: {}",
+                label
+            ),
+            Self::Malformed(message) => write!(f, "Malformed packet: {}", message),
+        }
+    }
+}
+
+impl Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedAlgorithm(name) => write!(f, "Unsupported algorithm: {}", name),
+            Self::InvalidKey(message) => write!(f, "Invalid public key: {}", message),
+            Self::InvalidSignature(message) => write!(f, "Invalid signature: {}", message),
+            Self::InvalidEncoding(message) => write!(f, "Invalid hex encoding: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            Self::Db(e) => Some(e),
+            Self::Packet(e) => Some(e),
+            Self::Crypto(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+impl std::error::Error for DbError {}
+impl std::error::Error for PacketError {}
+impl std::error::Error for CryptoError {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl From<DbError> for Error {
+    fn from(e: DbError) -> Self {
+        Self::Db(e)
+    }
+}
+
+impl From<PacketError> for Error {
+    fn from(e: PacketError) -> Self {
+        Self::Packet(e)
+    }
+}
+
+impl From<CryptoError> for Error {
+    fn from(e: CryptoError) -> Self {
+        Self::Crypto(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<std::net::AddrParseError> for Error {
+    fn from(e: std::net::AddrParseError) -> Self {
+        Self::Parse(ParseError::new("address", "", e.to_string()))
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
@@ -1,5 +1,13 @@
 use std::time::SystemTime;
 
+/// Gets the current time as the number of whole seconds since the Unix epoch.
+pub fn epoch_seconds_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
 /// Converts a u64 integer representing seconds since the Unix epoch to a `SystemTime` object.
 ///
 /// # Arguments
@@ -1,14 +1,16 @@
 extern crate paste;
 
-use shared::common::Result;
+use shared::common::{CryptoError, Result};
 use std::fmt::{self, Debug};
 
+use crate::traits::sign::Sign;
 use crate::traits::verify::Verify;
 use pqcrypto::prelude::*;
 
 macro_rules! add_dilithium {
     ($version:ident) => {
         paste::item! {
+            use pqcrypto_dilithium::[<$version:lower _detached_sign>];
             use pqcrypto_dilithium::[<$version:lower _verify_detached_signature>];
 
             pub struct [<$version:camel>] {
@@ -24,15 +26,23 @@ macro_rules! add_dilithium {
             impl Verify for [<$version:camel>] {
                 fn new(public_key: &[u8]) -> Result<Self> {
                     Ok(Self {
-                        pubic_key: pqcrypto_dilithium::[<$version:lower>]::PublicKey::from_bytes(public_key)?,
+                        pubic_key: pqcrypto_dilithium::[<$version:lower>]::PublicKey::from_bytes(public_key)
+                            .map_err(|e| CryptoError::InvalidKey(e.to_string()))?,
                     })
                 }
 
                 fn try_is_valid(&self, data: &[u8], signature: &[u8]) -> Result<bool> {
-                    let signature = pqcrypto_dilithium::[<$version:lower>]::DetachedSignature::from_bytes(signature)?;
+                    let signature = pqcrypto_dilithium::[<$version:lower>]::DetachedSignature::from_bytes(signature)
+                        .map_err(|e| CryptoError::InvalidSignature(e.to_string()))?;
                     Ok([<$version:lower _verify_detached_signature>](&signature, data, &self.pubic_key).is_ok())
                 }
             }
+
+            impl Sign for pqcrypto_dilithium::[<$version:lower>]::SecretKey {
+                fn sign_detached(&self, data: &[u8]) -> Vec<u8> {
+                    [<$version:lower _detached_sign>](data, self).as_bytes().to_vec()
+                }
+            }
         }
     }
 }
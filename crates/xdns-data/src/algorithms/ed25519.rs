@@ -1,7 +1,8 @@
-use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 
-use shared::common::Result;
+use shared::common::{CryptoError, Result};
 
+use crate::traits::sign::Sign;
 use crate::traits::verify::Verify;
 
 #[derive(Debug)]
@@ -11,13 +12,25 @@ pub struct Ed25519 {
 
 impl Verify for Ed25519 {
     fn new(public_key: &[u8]) -> Result<Self> {
+        let public_key = public_key.try_into().map_err(|_| {
+            CryptoError::InvalidKey(format!("expected 32 bytes, got {}", public_key.len()))
+        })?;
+
         Ok(Self {
-            pubic_key: VerifyingKey::from_bytes(public_key.try_into()?)?,
+            pubic_key: VerifyingKey::from_bytes(public_key)
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?,
         })
     }
 
     fn try_is_valid(&self, data: &[u8], signature: &[u8]) -> Result<bool> {
-        let signature = Signature::try_from(signature)?;
+        let signature = Signature::try_from(signature)
+            .map_err(|e| CryptoError::InvalidSignature(e.to_string()))?;
         Ok(self.pubic_key.verify(data, &signature).is_ok())
     }
 }
+
+impl Sign for SigningKey {
+    fn sign_detached(&self, data: &[u8]) -> Vec<u8> {
+        self.sign(data).to_bytes().to_vec()
+    }
+}
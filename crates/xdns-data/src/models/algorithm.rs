@@ -1,7 +1,7 @@
 extern crate paste;
 
 use macro_rules_attribute::apply;
-use shared::common::Error;
+use shared::common::{CryptoError, Error};
 
 use crate::algorithms::dilithium::*;
 use crate::algorithms::ed25519::Ed25519;
@@ -44,7 +44,7 @@ macro_rules! generate_algorithm_implementations {
                 /// The [`Verify`](Verify) instance.
                 pub fn get_verifier(&self, public_key: &[u8]) -> Result<Box<dyn Verify>, Error> {
                     match self {
-                        $(Self::$v_name => Ok(Box::new($v_name::new(public_key.try_into()?)?)),)*
+                        $(Self::$v_name => Ok(Box::new($v_name::new(public_key)?)),)*
                     }
                 }
             }
@@ -64,7 +64,7 @@ macro_rules! generate_algorithm_implementations {
                 fn try_from(value: &str) -> Result<Self, Self::Error> {
                     match value {
                         $(stringify!([<$v_name:lower>]) => Ok(Self::$v_name),)*
-                        _ => Err(format!("Unsupported algorithm: {}", value).into()),
+                        _ => Err(CryptoError::UnsupportedAlgorithm(value.to_string()).into()),
                     }
                 }
             }
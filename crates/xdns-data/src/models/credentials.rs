@@ -1,6 +1,6 @@
 use crate::models::algorithm::Algorithm;
 use crate::traits::verify::Verify;
-use shared::common::Result;
+use shared::common::{CryptoError, Result};
 
 #[derive(Debug)]
 pub struct Credentials {
@@ -9,6 +9,13 @@ pub struct Credentials {
     pub verifier: Option<Box<dyn Verify>>,
 }
 
+/// The verifier is not cloned, the clone creates its own the first time it verifies a signature.
+impl Clone for Credentials {
+    fn clone(&self) -> Self {
+        Self::new(self.algorithm, self.public_key.to_string())
+    }
+}
+
 impl Credentials {
     pub fn new(algorithm: Algorithm, public_key: String) -> Self {
         Self {
@@ -19,7 +26,8 @@ impl Credentials {
     }
 
     fn set_verifier(&mut self) -> Result<()> {
-        let public_key_decoded = hex::decode(&self.public_key).unwrap();
+        let public_key_decoded = hex::decode(&self.public_key)
+            .map_err(|e| CryptoError::InvalidEncoding(e.to_string()))?;
         self.verifier = Some(self.algorithm.get_verifier(&public_key_decoded)?);
         Ok(())
     }
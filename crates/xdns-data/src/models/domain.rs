@@ -1,6 +1,6 @@
 use std::time::SystemTime;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Domain {
     pub name: String,
     pub valid_from: SystemTime,
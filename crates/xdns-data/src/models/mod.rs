@@ -3,22 +3,28 @@ pub use credentials::Credentials;
 pub use data::Data;
 pub use domain::Domain;
 pub use drop::DomainDrop;
+pub use renew::DomainRenew;
 pub use signature::Signature;
 pub use subdomain::SubDomain;
 pub use validity::Validity;
 pub use validity_transfer::ValidityTransfer;
+pub use wildcard::SubDomainMatch;
+pub use zone::{SkippedRecord, Zone, ZoneImport, ZoneRecord};
 
 pub mod algorithm;
 pub mod credentials;
 pub mod data;
 pub mod domain;
 pub mod drop;
+pub mod renew;
 pub mod signature;
 pub mod subdomain;
 pub mod validity;
 pub mod validity_transfer;
+pub mod wildcard;
+pub mod zone;
 
 pub mod prelude {
     pub use super::domain::Domain;
-    pub use super::subdomain::{Class, SubDomain, Type};
+    pub use super::subdomain::{Class, RData, SubDomain, Type};
 }
@@ -0,0 +1,20 @@
+use std::fmt::{self, Display};
+
+/// A renewal of a domain registration, extending it by a number of registration periods.
+#[derive(Debug)]
+pub struct DomainRenew {
+    pub domain: String,
+    pub periods: u32,
+}
+
+impl DomainRenew {
+    /// The maximum number of periods a single renewal can extend a registration by.
+    pub const MAX_PERIODS: u32 = 10;
+}
+
+impl Display for DomainRenew {
+    /// Formats the renewal as its canonical record. (eg `DOMAIN-RENEW example.o 1`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DOMAIN-RENEW {} {}", self.domain, self.periods)
+    }
+}
@@ -1,7 +1,8 @@
 use crate::models::Credentials;
-use std::fmt::Debug;
+use crate::traits::sign::Sign;
+use std::fmt::{self, Debug, Display};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Signature {
     pub content: Vec<String>,
     pub signature: String,
@@ -10,6 +11,53 @@ pub struct Signature {
 }
 
 impl Signature {
+    /// Signs the content of an inscription, the last id is placed on its own line.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The lines of the inscription.
+    /// * `last_id` - The id of the previous inscription of the domain, if any.
+    /// * `signer` - The private key to sign with.
+    ///
+    /// # Returns
+    ///
+    /// The signature over the content and last id.
+    pub fn sign(content: Vec<String>, last_id: Option<String>, signer: &dyn Sign) -> Self {
+        let mut signature = Self::unsigned(content, last_id);
+        signature.signature = hex::encode(signer.sign_detached(signature.message().as_bytes()));
+        signature
+    }
+
+    /// Creates a null signature for the content of an inscription.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The lines of the inscription.
+    /// * `last_id` - The id of the previous inscription of the domain, if any.
+    ///
+    /// # Returns
+    ///
+    /// The null signature.
+    pub fn unsigned(content: Vec<String>, last_id: Option<String>) -> Self {
+        Self {
+            content,
+            signature: "null".to_string(),
+            last_id,
+            last_id_on_separate_line: true,
+        }
+    }
+
+    /// The message the signature is made over, the content followed by the last id.
+    fn message(&self) -> String {
+        let separator = if self.last_id_on_separate_line {
+            "\n"
+        } else {
+            " "
+        };
+
+        self.content.join("\n") + separator + self.last_id.as_deref().unwrap_or("null")
+    }
+
     /// Checks whether the signature is valid for the given credentials.
     ///
     /// # Arguments
@@ -27,20 +75,7 @@ impl Signature {
         }
 
         let signature = signature.unwrap();
-        let separator = if self.last_id_on_separate_line {
-            "\n"
-        } else {
-            " "
-        };
-        let signature_content = self.content.join("\n")
-            + separator
-            + self
-                .last_id
-                .as_ref()
-                .unwrap_or(&"null".to_string())
-                .as_str();
-
-        let is_valid = credentials.try_is_valid(signature_content.as_bytes(), &signature);
+        let is_valid = credentials.try_is_valid(self.message().as_bytes(), &signature);
 
         is_valid.is_ok() && is_valid.unwrap()
     }
@@ -67,3 +102,10 @@ impl Signature {
         }
     }
 }
+
+impl Display for Signature {
+    /// Formats the signature as the inscription it signs, the content followed by the last id and signature.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.message(), self.signature)
+    }
+}
@@ -1,4 +1,7 @@
-use shared::common::Error;
+use std::fmt::{self, Display};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use shared::common::{Error, ParseError};
 
 #[derive(PartialEq, Eq, Debug, Clone, Hash, Copy)]
 pub enum Type {
@@ -15,14 +18,91 @@ pub enum Class {
     ANY,
 }
 
-#[derive(Debug)]
+/// The record data of a subdomain, typed according to its record type.
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+pub enum RData {
+    A(Ipv4Addr),
+    NS(String),
+    CNAME(String),
+    MX { priority: u16, host: String },
+    AAAA(Ipv6Addr),
+}
+
+#[derive(Debug, Clone)]
 pub struct SubDomain {
     pub domain: String,
     pub subdomain: String,
-    pub rtype: Type,
     pub class: Class,
     pub ttl: u32,
-    pub rdata: String,
+    pub rdata: RData,
+}
+
+impl RData {
+    /// Returns the record type this data belongs to.
+    ///
+    /// # Returns
+    ///
+    /// The type.
+    pub fn rtype(&self) -> Type {
+        match self {
+            Self::A(_) => Type::A,
+            Self::NS(_) => Type::NS,
+            Self::CNAME(_) => Type::CNAME,
+            Self::MX { .. } => Type::MX,
+            Self::AAAA(_) => Type::AAAA,
+        }
+    }
+
+    /// Returns the host the record points to, if the record type has one.
+    ///
+    /// # Returns
+    ///
+    /// The host for `NS`, `CNAME` and `MX` records.
+    pub fn host(&self) -> Option<&str> {
+        match self {
+            Self::NS(host) | Self::CNAME(host) | Self::MX { host, .. } => Some(host),
+            Self::A(_) | Self::AAAA(_) => None,
+        }
+    }
+}
+
+impl Display for RData {
+    /// Formats the record data in its inscription form.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::A(addr) => addr.fmt(f),
+            Self::AAAA(addr) => addr.fmt(f),
+            Self::NS(host) | Self::CNAME(host) => f.write_str(host),
+            Self::MX { priority, host } => write!(f, "{} {}", priority, host),
+        }
+    }
+}
+
+impl SubDomain {
+    /// Returns the record type of the subdomain, which is the type of its data.
+    ///
+    /// # Returns
+    ///
+    /// The type.
+    pub fn rtype(&self) -> Type {
+        self.rdata.rtype()
+    }
+}
+
+impl Display for SubDomain {
+    /// Formats the subdomain as its canonical record. (eg `DNS example.o www. A IN 30 127.0.0.1`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DNS {} {} {} {} {} {}",
+            self.domain,
+            self.subdomain,
+            self.rtype().to_string(),
+            self.class.to_string(),
+            self.ttl,
+            self.rdata
+        )
+    }
 }
 
 impl TryFrom<&str> for Type {
@@ -44,7 +124,7 @@ impl TryFrom<&str> for Type {
             "CNAME" => Ok(Self::CNAME),
             "MX" => Ok(Self::MX),
             "AAAA" => Ok(Self::AAAA),
-            _ => Err(format!("Unsupported type: {}", value).into()),
+            _ => Err(ParseError::new("type", value, "unsupported type").into()),
         }
     }
 }
@@ -64,7 +144,7 @@ impl TryFrom<&str> for Class {
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "IN" => Ok(Self::IN),
-            _ => Err(format!("Unsupported class: {}", value).into()),
+            _ => Err(ParseError::new("class", value, "unsupported class").into()),
         }
     }
 }
@@ -1,6 +1,6 @@
 use crate::models::credentials::Credentials;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Validity {
     pub domain: String,
     pub credentials: Credentials,
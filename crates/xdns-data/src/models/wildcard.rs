@@ -0,0 +1,85 @@
+/// The result of matching a queried subdomain against the subdomains (owner names)
+/// that exist within a domain, following the wildcard rules of RFC 4592.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum SubDomainMatch {
+    /// The queried subdomain exists, contains the owner name as stored.
+    Exact(String),
+    /// The queried subdomain does not exist but is covered by a wildcard.
+    /// Contains the owner name of the wildcard (the source of synthesis) as stored.
+    Wildcard(String),
+    /// The queried subdomain only exists because records exist below it. (empty non-terminal)
+    NoData,
+    /// The queried subdomain does not exist and is not covered by a wildcard.
+    NxDomain,
+}
+
+impl SubDomainMatch {
+    /// Splits a subdomain into its labels. The root subdomain `@.` has no labels.
+    fn labels(subdomain: &str) -> Vec<&str> {
+        let subdomain = subdomain.trim_end_matches('.');
+
+        if subdomain.is_empty() || subdomain == "@" {
+            return Vec::new();
+        }
+
+        subdomain.split('.').collect()
+    }
+
+    /// Finds how a queried subdomain should be answered.
+    ///
+    /// # Restrictions
+    ///
+    /// * An existing subdomain is always answered with its own records.
+    /// * A subdomain that only exists because a subdomain below it exists is never synthesized.
+    /// * Otherwise the closest encloser (the longest existing ancestor) is computed,
+    ///   and the wildcard directly below it (`*.<closest encloser>`) is used if it exists.
+    ///   So a subdomain that exists at or below the wildcard level blocks the wildcard.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The queried subdomain. (eg `www.`, `a.b.` or `@.`)
+    /// * `owners` - All subdomains that have records within the domain.
+    ///
+    /// # Returns
+    ///
+    /// The match.
+    pub fn find<S: AsRef<str>>(query: &str, owners: &[S]) -> Self {
+        let query = Self::labels(query);
+        let owners: Vec<(&str, Vec<&str>)> = owners
+            .iter()
+            .map(|owner| (owner.as_ref(), Self::labels(owner.as_ref())))
+            .collect();
+
+        let find_owner = |labels: &[&str]| {
+            owners
+                .iter()
+                .find(|(_, owner)| owner.as_slice() == labels)
+                .map(|(owner, _)| owner.to_string())
+        };
+        // The root of the domain always exists, as the domain itself is registered.
+        let exists = |labels: &[&str]| {
+            labels.is_empty() || owners.iter().any(|(_, owner)| owner.ends_with(labels))
+        };
+
+        if let Some(owner) = find_owner(&query) {
+            return Self::Exact(owner);
+        }
+
+        if exists(&query) {
+            return Self::NoData;
+        }
+
+        let closest_encloser = (1..=query.len())
+            .map(|i| &query[i..])
+            .find(|labels| exists(labels))
+            .unwrap_or_default();
+
+        let mut source_of_synthesis = vec!["*"];
+        source_of_synthesis.extend_from_slice(closest_encloser);
+
+        match find_owner(&source_of_synthesis) {
+            Some(owner) => Self::Wildcard(owner),
+            None => Self::NxDomain,
+        }
+    }
+}
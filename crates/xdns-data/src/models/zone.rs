@@ -0,0 +1,45 @@
+use crate::models::subdomain::SubDomain;
+
+/// A resource record read from a zone file, with every name made absolute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneRecord {
+    /// The (1-based) line the record starts on.
+    pub line: usize,
+    /// The lowercase, absolute owner name of the record. (eg `www.example.com.`)
+    pub name: String,
+    pub ttl: u32,
+    /// The class of the record, as written. (eg `IN`)
+    pub class: String,
+    /// The type of the record, as written. (eg `A`, `TXT`)
+    pub rtype: String,
+    /// The fields of the record data. Names in `NS`, `CNAME` and `MX` records are absolute.
+    pub rdata: Vec<String>,
+}
+
+/// A parsed zone file in RFC 1035 master format.
+#[derive(Debug, Clone, Default)]
+pub struct Zone {
+    /// The absolute name of the zone apex. (eg `example.com.`)
+    ///
+    /// This is the origin the zone was parsed with, the first `$ORIGIN` or the owner of the `SOA` record.
+    pub origin: Option<String>,
+    pub records: Vec<ZoneRecord>,
+}
+
+/// A zone record that could not be converted to a subdomain record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRecord {
+    /// The (1-based) line of the record in the zone file.
+    pub line: usize,
+    pub name: String,
+    pub rtype: String,
+    /// Why the record was skipped. (eg `unsupported type`)
+    pub reason: String,
+}
+
+/// The result of importing a zone into a `.o` domain.
+#[derive(Debug, Clone, Default)]
+pub struct ZoneImport {
+    pub subdomains: Vec<SubDomain>,
+    pub skipped: Vec<SkippedRecord>,
+}
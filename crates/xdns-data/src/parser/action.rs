@@ -1,6 +1,8 @@
-use shared::common::Result;
+use shared::common::{Error, ParseError, Result};
 
-use crate::models::{Data, Domain, DomainDrop, Signature, SubDomain, Validity, ValidityTransfer};
+use crate::models::{
+    Data, Domain, DomainDrop, DomainRenew, Signature, SubDomain, Validity, ValidityTransfer,
+};
 use crate::traits::Parser;
 
 #[derive(Debug)]
@@ -11,6 +13,19 @@ pub enum DomainAction {
     Validity(Validity),
     ValidityTransfer(ValidityTransfer),
     Data(Data),
+    Renew(DomainRenew),
+}
+
+/// The kind of action a line represents, detected by its leading keyword.
+#[derive(PartialEq, Eq, Debug, Clone, Hash, Copy)]
+pub enum ActionKind {
+    Domain,
+    Subdomain,
+    Drop,
+    Validity,
+    ValidityTransfer,
+    Data,
+    Renew,
 }
 
 pub struct ActionParser {
@@ -18,50 +33,149 @@ pub struct ActionParser {
     pub signature: Option<Signature>,
 }
 
-impl ActionParser {
-    fn parse_action(content: &str) -> Result<DomainAction> {
-        if let Ok(domain) = Domain::parse(content) {
-            return Ok(DomainAction::Domain(domain));
+impl ActionKind {
+    /// Returns the keyword that starts an action of this kind.
+    ///
+    /// # Returns
+    ///
+    /// The keyword. *(case sensitive)*
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Domain => "DOMAIN",
+            Self::Subdomain => "DNS",
+            Self::Drop => "DROP",
+            Self::Validity => "DOMAIN-VALIDITY",
+            Self::ValidityTransfer => "DOMAIN-VALIDATE-TRANSFER",
+            Self::Data => "DOMAIN-DATA",
+            Self::Renew => "DOMAIN-RENEW",
         }
+    }
 
-        if let Ok(subdomain) = SubDomain::parse(content) {
-            return Ok(DomainAction::Subdomain(subdomain));
+    /// Detects the kind of action a line represents by its first word.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The line to inspect.
+    ///
+    /// # Returns
+    ///
+    /// The action kind if the keyword is known.
+    pub fn detect(content: &str) -> Option<Self> {
+        match content.split_whitespace().next()? {
+            "DOMAIN" => Some(Self::Domain),
+            "DNS" => Some(Self::Subdomain),
+            "DROP" => Some(Self::Drop),
+            "DOMAIN-VALIDITY" => Some(Self::Validity),
+            "DOMAIN-VALIDATE-TRANSFER" => Some(Self::ValidityTransfer),
+            "DOMAIN-DATA" => Some(Self::Data),
+            "DOMAIN-RENEW" => Some(Self::Renew),
+            _ => None,
         }
+    }
+}
 
-        if let Ok(drop) = DomainDrop::parse(content) {
-            return Ok(DomainAction::Drop(drop));
+impl ActionParser {
+    /// Converts any error raised by an action parser into a [`ParseError`](ParseError).
+    /// Non parse errors (eg an unsupported algorithm) are attributed to the closest field.
+    fn as_parse_error(error: Error, content: &str) -> ParseError {
+        match error {
+            Error::Parse(e) => e,
+            Error::Crypto(e) => ParseError::new("algorithm", content, e.to_string()),
+            e => ParseError::new("action", content, e.to_string()),
         }
+    }
 
-        if let Ok(validity) = Validity::parse(content) {
-            return Ok(DomainAction::Validity(validity));
-        }
+    fn parse_action(content: &str) -> std::result::Result<DomainAction, ParseError> {
+        let kind = ActionKind::detect(content)
+            .ok_or_else(|| ParseError::new("action", content, "unknown action keyword"))?;
+
+        let action = match kind {
+            ActionKind::Domain => Domain::parse(content).map(DomainAction::Domain),
+            ActionKind::Subdomain => SubDomain::parse(content).map(DomainAction::Subdomain),
+            ActionKind::Drop => DomainDrop::parse(content).map(DomainAction::Drop),
+            ActionKind::Validity => Validity::parse(content).map(DomainAction::Validity),
+            ActionKind::ValidityTransfer => {
+                ValidityTransfer::parse(content).map(DomainAction::ValidityTransfer)
+            }
+            ActionKind::Data => Data::parse(content).map(DomainAction::Data),
+            ActionKind::Renew => DomainRenew::parse(content).map(DomainAction::Renew),
+        };
+
+        action.map_err(|e| Self::as_parse_error(e, content).for_action(kind.keyword()))
+    }
 
-        if let Ok(validity_transfer) = ValidityTransfer::parse(content) {
-            return Ok(DomainAction::ValidityTransfer(validity_transfer));
-        }
+    /// Gets the (1-based) line numbers of all non-empty lines in the input.
+    /// The signature content is built from these same lines, so the n-th content
+    /// line originates from the n-th entry.
+    fn content_line_numbers(input: &str) -> Vec<usize> {
+        input
+            .split_terminator('\n')
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    /// Gets the (1-based) column of a rejected input within a line.
+    /// Only whole words are matched, as that is how the action parsers split their input.
+    fn column_of(line: &str, input: &str) -> Option<usize> {
+        let line = line.trim_end();
+        let mut offset = 0;
+
+        for word in line.split_whitespace() {
+            let start = offset + line[offset..].find(word)?;
 
-        if let Ok(data) = Data::parse(content) {
-            return Ok(DomainAction::Data(data));
+            if word == input {
+                return Some(start + 1);
+            }
+
+            offset = start + word.len();
         }
 
-        Err("Invalid action".into())
+        None
     }
 
-    fn parse_actions(contents: &Vec<String>) -> Result<Vec<DomainAction>> {
+    fn parse_actions(
+        input: &str,
+        contents: &[String],
+        collect_all: bool,
+    ) -> std::result::Result<Vec<DomainAction>, Vec<ParseError>> {
+        let raw_lines: Vec<&str> = input.split_terminator('\n').collect();
+        let line_numbers = Self::content_line_numbers(input);
         let mut actions = Vec::with_capacity(contents.len());
-
-        for content in contents {
-            let action = Self::parse_action(content)?;
-            actions.push(action);
+        let mut errors = Vec::new();
+
+        for (i, content) in contents.iter().enumerate() {
+            match Self::parse_action(content) {
+                Ok(action) => actions.push(action),
+                Err(error) => {
+                    let error = match line_numbers.get(i) {
+                        Some(&line) => {
+                            let column = Self::column_of(raw_lines[line - 1], &error.input);
+                            error.at(line, column)
+                        }
+                        None => error,
+                    };
+
+                    errors.push(error);
+
+                    if !collect_all {
+                        break;
+                    }
+                }
+            }
         }
 
-        Ok(actions)
+        if errors.is_empty() {
+            Ok(actions)
+        } else {
+            Err(errors)
+        }
     }
-}
 
-impl Parser for ActionParser {
-    /// Attempts to parse content into its associated actions.
-    /// Fails if the content is not valid in a valid structure, does not take in mind the signature.
+    /// Attempts to parse content into its associated actions, collecting every error
+    /// instead of stopping at the first invalid line.
+    /// Does not take in mind the signature.
     ///
     /// # Arguments
     ///
@@ -69,14 +183,27 @@ impl Parser for ActionParser {
     ///
     /// # Returns
     ///
-    /// A `ActionParser` if the content was successfully parsed.
-    fn parse(input: &str) -> Result<Self> {
+    /// A `ActionParser` if the content was successfully parsed, otherwise all errors
+    /// in order of appearance, annotated with their line, column and detected action.
+    pub fn parse_all(input: &str) -> std::result::Result<Self, Vec<ParseError>> {
+        Self::parse_with(input, true)
+    }
+
+    fn parse_with(input: &str, collect_all: bool) -> std::result::Result<Self, Vec<ParseError>> {
         if input.is_empty() {
-            return Err("Input is empty".into());
+            return Err(vec![ParseError::new("content", input, "input is empty")]);
         }
 
-        let signature = Signature::parse(input)?;
-        let actions = Self::parse_actions(&signature.content)?;
+        let signature = Signature::parse(input).map_err(|e| {
+            let error = Self::as_parse_error(e, input);
+
+            match Self::content_line_numbers(input).last() {
+                Some(&line) => vec![error.at(line, None)],
+                None => vec![error],
+            }
+        })?;
+
+        let actions = Self::parse_actions(input, &signature.content, collect_all)?;
 
         Ok(Self {
             actions,
@@ -84,3 +211,20 @@ impl Parser for ActionParser {
         })
     }
 }
+
+impl Parser for ActionParser {
+    /// Attempts to parse content into its associated actions.
+    /// Fails if the content is not valid in a valid structure, does not take in mind the signature.
+    /// Use [`parse_all`](ActionParser::parse_all) to get all errors instead of the first one.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The content to parse.
+    ///
+    /// # Returns
+    ///
+    /// A `ActionParser` if the content was successfully parsed.
+    fn parse(input: &str) -> Result<Self> {
+        Self::parse_with(input, false).map_err(|mut errors| errors.remove(0).into())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::models::data::Data;
 use crate::prelude::{Domain, Parser};
-use shared::common::Result;
+use shared::common::{ParseError, Result};
 
 impl Parser for Data {
     /// Parse extra domain data, this is useful for when building custom protocols on top of DNS
@@ -24,21 +24,23 @@ impl Parser for Data {
         let mut parts = input.split_whitespace();
 
         if parts.next() != Some("DOMAIN-DATA") {
-            return Err(format!("Input is not a domain data record: {}", input).into());
+            return Err(ParseError::new("action", input, "not a domain data record").into());
         }
 
-        let domain = parts
-            .next()
-            .ok_or_else(|| format!("Domain data record is missing domain: {}", input))?;
+        let domain = parts.next().ok_or_else(|| {
+            ParseError::new("domain", input, "domain data record is missing domain")
+        })?;
 
         if !Domain::is_valid_domain_name(domain) {
-            return Err(format!("Invalid domain: {}", domain).into());
+            return Err(ParseError::new("domain", domain, "invalid domain name").into());
         }
 
         let data = parts.collect::<Vec<&str>>().join(" ").as_bytes().to_vec();
 
         if data.is_empty() {
-            return Err(format!("Domain data record is missing data: {}", input).into());
+            return Err(
+                ParseError::new("data", input, "domain data record is missing data").into(),
+            );
         }
 
         Ok(Self {
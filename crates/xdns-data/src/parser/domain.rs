@@ -1,6 +1,6 @@
 use std::ops::RangeInclusive;
 
-use shared::common::Result;
+use shared::common::{ParseError, Result};
 use shared::time::system_time_from_epoch_seconds;
 
 use crate::models::domain::Domain;
@@ -156,27 +156,29 @@ impl Parser for Domain {
         let mut parts = input.split_whitespace();
 
         if parts.next() != Some("DOMAIN") {
-            return Err(format!("Input is not a domain record: {}", input).into());
+            return Err(ParseError::new("action", input, "not a domain record").into());
         }
 
         let name = parts
             .next()
-            .ok_or_else(|| format!("Domain record is missing name: {}", input))?;
-        let valid_from = parts
-            .next()
-            .ok_or_else(|| format!("Domain record is missing valid_from: {}", input))?;
+            .ok_or_else(|| ParseError::new("name", input, "domain record is missing name"))?;
+        let valid_from = parts.next().ok_or_else(|| {
+            ParseError::new("valid_from", input, "domain record is missing valid_from")
+        })?;
 
         if parts.next().is_some() {
-            return Err(format!("Input is not a domain record: {}", input).into());
+            return Err(
+                ParseError::new("action", input, "domain record has too many parts").into(),
+            );
         }
 
         if !Domain::is_valid_domain_name(name) {
-            return Err(format!("Invalid domain name: {}", name).into());
+            return Err(ParseError::new("name", name, "invalid domain name").into());
         }
 
         let valid_from = valid_from
             .parse::<u64>()
-            .map_err(|e| format!("Invalid valid_from: {}: {}", valid_from, e))?;
+            .map_err(|e| ParseError::new("valid_from", valid_from, e.to_string()))?;
 
         Ok(Self {
             name: name.to_owned(),
@@ -1,6 +1,6 @@
 use crate::models::drop::DomainDrop;
 use crate::prelude::Parser;
-use shared::common::Result;
+use shared::common::{ParseError, Result};
 
 impl Parser for DomainDrop {
     /// Parse a drop record. This record can drop a subdomain or a domain all together.
@@ -20,15 +20,15 @@ impl Parser for DomainDrop {
         let mut parts = input.split_whitespace();
 
         if parts.next() != Some("DROP") {
-            return Err(format!("Input is not a drop record: {}", input).into());
+            return Err(ParseError::new("action", input, "not a drop record").into());
         }
 
-        let inscription = parts
-            .next()
-            .ok_or_else(|| format!("Drop record is missing inscription: {}", input))?;
+        let inscription = parts.next().ok_or_else(|| {
+            ParseError::new("inscription", input, "drop record is missing inscription")
+        })?;
 
         if parts.next().is_some() {
-            return Err(format!("Drop record has too many parts: {}", input).into());
+            return Err(ParseError::new("action", input, "drop record has too many parts").into());
         }
 
         Ok(Self {
@@ -1,10 +1,12 @@
-pub use action::{ActionParser, DomainAction};
+pub use action::{ActionKind, ActionParser, DomainAction};
 
 pub mod action;
 pub mod data;
 pub mod domain;
 pub mod drop;
+pub mod renew;
 pub mod signature;
 pub mod subdomain;
 pub mod validity;
 pub mod validity_transfer;
+pub mod zone;
@@ -0,0 +1,66 @@
+use crate::models::renew::DomainRenew;
+use crate::prelude::{Domain, Parser};
+use shared::common::{ParseError, Result};
+
+impl Parser for DomainRenew {
+    /// Parse a domain renewal record, which extends the registration of a domain.
+    ///
+    /// # Restrictions
+    ///
+    /// * The data must be in the format *(case sensitive)*: `DOMAIN-RENEW <domain> <periods>`.
+    /// * The domain must be valid. (See [`Domain::is_valid_domain_name`](Domain::is_valid_domain_name).)
+    /// * The periods must be a number from 1 up to [`MAX_PERIODS`](DomainRenew::MAX_PERIODS).
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input string to parse.
+    ///
+    /// # Returns
+    ///
+    /// The parsed renewal record.
+    fn parse(input: &str) -> Result<Self> {
+        let mut parts = input.split_whitespace();
+
+        if parts.next() != Some("DOMAIN-RENEW") {
+            return Err(ParseError::new("action", input, "not a domain renewal record").into());
+        }
+
+        let domain = parts.next().ok_or_else(|| {
+            ParseError::new("domain", input, "domain renewal record is missing domain")
+        })?;
+        let periods = parts.next().ok_or_else(|| {
+            ParseError::new("periods", input, "domain renewal record is missing periods")
+        })?;
+
+        if parts.next().is_some() {
+            return Err(ParseError::new(
+                "action",
+                input,
+                "domain renewal record has too many parts",
+            )
+            .into());
+        }
+
+        if !Domain::is_valid_domain_name(domain) {
+            return Err(ParseError::new("domain", domain, "invalid domain name").into());
+        }
+
+        let parsed = periods
+            .parse::<u32>()
+            .map_err(|e| ParseError::new("periods", periods, e.to_string()))?;
+
+        if !(1..=DomainRenew::MAX_PERIODS).contains(&parsed) {
+            return Err(ParseError::new(
+                "periods",
+                periods,
+                format!("periods must be from 1 up to {}", DomainRenew::MAX_PERIODS),
+            )
+            .into());
+        }
+
+        Ok(Self {
+            domain: domain.to_string(),
+            periods: parsed,
+        })
+    }
+}
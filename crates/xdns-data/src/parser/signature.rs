@@ -1,4 +1,4 @@
-use shared::common::Result;
+use shared::common::{ParseError, Result};
 
 use crate::models::signature::Signature;
 use crate::prelude::Parser;
@@ -30,20 +30,21 @@ impl Parser for Signature {
 
         let last_line = lines
             .next_back()
-            .ok_or_else(|| format!("Signature does not have content: {}", input))?;
+            .ok_or_else(|| ParseError::new("content", input, "signature does not have content"))?;
 
         let mut last_line_parts = last_line.split_whitespace();
         let signature = last_line_parts
             .next_back()
-            .ok_or_else(|| format!("Signature is missing signature: {}", input))?;
+            .ok_or_else(|| ParseError::new("signature", input, "signature is missing"))?;
         let last_id = last_line_parts
             .next_back()
-            .ok_or_else(|| format!("Signature is missing the last id: {}", input))?;
+            .ok_or_else(|| ParseError::new("last_id", input, "signature is missing the last id"))?;
 
         if !signature.chars().all(|c| c.is_ascii_hexdigit()) && signature != "null" {
-            return Err(format!(
-                "Signature must consist of all hexadecimal characters: {}",
-                input
+            return Err(ParseError::new(
+                "signature",
+                signature,
+                "signature must consist of all hexadecimal characters",
             )
             .into());
         }
@@ -1,4 +1,4 @@
-use shared::common::Result;
+use shared::common::{ParseError, Result};
 
 use crate::models::subdomain::*;
 use crate::prelude::Domain;
@@ -62,6 +62,110 @@ impl SubDomain {
     }
 }
 
+impl RData {
+    /// Checks if a hostname is valid to be used as record data.
+    ///
+    /// # Restrictions
+    ///
+    /// * The hostname may end with a `.` *(dot)*, which is ignored.
+    /// * The hostname must not be empty or longer than 253 characters.
+    /// * Every label must be between 1 and 63 characters long.
+    /// * Every label must only consist of letters, numbers and `-` *(hyphens)*.
+    /// * A label must not start or end with a `-` *(hyphen)*.
+    /// * The labels must not all be numeric. (eg `10` or `127.0.0.1`)
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The hostname to check.
+    ///
+    /// # Returns
+    ///
+    /// Whether the hostname is valid.
+    pub fn is_valid_hostname(host: &str) -> bool {
+        let host = host.strip_suffix('.').unwrap_or(host);
+
+        if host.is_empty() || host.len() > 253 {
+            return false;
+        }
+
+        let valid = host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+        // Otherwise an MX record with only its preference would point to it.
+        let numeric = host
+            .split('.')
+            .all(|label| label.chars().all(|c| c.is_ascii_digit()));
+
+        valid && !numeric
+    }
+
+    /// Parses record data for a given record type.
+    ///
+    /// # Restrictions
+    ///
+    /// * `A` records must contain an IPv4 address.
+    /// * `AAAA` records must contain an IPv6 address.
+    /// * `NS` and `CNAME` records must contain a valid hostname. (See [`is_valid_hostname`](RData::is_valid_hostname).)
+    /// * `MX` records must contain a valid hostname, optionally preceded by its preference: `[priority] <host>`.
+    ///   When the preference is omitted *(legacy inscriptions)* it defaults to `0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rtype` - The type of the record the data belongs to.
+    /// * `input` - The record data to parse.
+    ///
+    /// # Returns
+    ///
+    /// The typed record data.
+    pub fn parse(rtype: Type, input: &str) -> Result<Self> {
+        let invalid = |reason: &str| ParseError::new("rdata", input, reason);
+
+        match rtype {
+            Type::A => {
+                Ok(Self::A(input.parse().map_err(|_| {
+                    invalid("A record data must be an IPv4 address")
+                })?))
+            }
+            Type::AAAA => {
+                Ok(Self::AAAA(input.parse().map_err(|_| {
+                    invalid("AAAA record data must be an IPv6 address")
+                })?))
+            }
+            Type::MX => {
+                let mut parts = input.split_whitespace();
+                let (priority, host) = match (parts.next(), parts.next(), parts.next()) {
+                    (Some(host), None, None) => (0, host),
+                    (Some(priority), Some(host), None) => (
+                        priority
+                            .parse()
+                            .map_err(|_| invalid("MX priority must be a number from 0 to 65535"))?,
+                        host,
+                    ),
+                    _ => return Err(invalid("MX record data must be `[priority] <host>`").into()),
+                };
+
+                if !RData::is_valid_hostname(host) {
+                    return Err(invalid("record data must be a valid hostname").into());
+                }
+
+                Ok(Self::MX {
+                    priority,
+                    host: host.to_owned(),
+                })
+            }
+            Type::NS | Type::CNAME if !RData::is_valid_hostname(input) => {
+                Err(invalid("record data must be a valid hostname").into())
+            }
+            Type::NS => Ok(Self::NS(input.to_owned())),
+            Type::CNAME => Ok(Self::CNAME(input.to_owned())),
+        }
+    }
+}
+
 impl Parser for SubDomain {
     /// Parses a subdomain record from a string.
     ///
@@ -71,7 +175,9 @@ impl Parser for SubDomain {
     ///
     /// * The subdomain must be valid. (See [`is_valid_subdomain`](SubDomain::is_valid_subdomain).)
     /// * The domain must be valid. (See [`Domain::is_valid_domain_name`](Domain::is_valid_domain_name).)
+    /// * The rdata must be valid for the type. (See [`RData::parse`](RData::parse).)
     /// * The subdomain record must be in the format *(case sensitive)*: `DNS <domain> <subdomain> <type> <class> <ttl> <rdata>`
+    /// * Only `MX` records may have an rdata consisting of multiple parts. (`[priority] <host>`)
     ///
     /// # Arguments
     ///
@@ -84,47 +190,54 @@ impl Parser for SubDomain {
         let mut parts = input.split_whitespace();
 
         if parts.next() != Some("DNS") {
-            return Err(format!("Input is not a dns record: {}", input).into());
+            return Err(ParseError::new("action", input, "not a dns record").into());
         }
 
         let domain = parts
             .next()
-            .ok_or_else(|| format!("DNS record is missing domain: {}", input))?;
-        let subdomain = parts
-            .next()
-            .ok_or_else(|| format!("DNS record is missing subdomain: {}", input))?;
+            .ok_or_else(|| ParseError::new("domain", input, "dns record is missing domain"))?;
+        let subdomain = parts.next().ok_or_else(|| {
+            ParseError::new("subdomain", input, "dns record is missing subdomain")
+        })?;
         let rtype = parts
             .next()
-            .ok_or_else(|| format!("DNS record is missing type: {}", input))?;
+            .ok_or_else(|| ParseError::new("type", input, "dns record is missing type"))?;
         let class = parts
             .next()
-            .ok_or_else(|| format!("DNS record is missing class: {}", input))?;
+            .ok_or_else(|| ParseError::new("class", input, "dns record is missing class"))?;
         let ttl = parts
             .next()
-            .ok_or_else(|| format!("DNS record is missing ttl: {}", input))?;
-        let rdata = parts
-            .next()
-            .ok_or_else(|| format!("DNS record is missing rdata: {}", input))?;
+            .ok_or_else(|| ParseError::new("ttl", input, "dns record is missing ttl"))?;
+        let rdata = parts.collect::<Vec<&str>>();
 
-        if parts.next().is_some() {
-            return Err(format!("Input is not a dns record: {}", input).into());
+        if rdata.is_empty() {
+            return Err(ParseError::new("rdata", input, "dns record is missing rdata").into());
+        }
+
+        let rtype = Type::try_from(rtype)?;
+
+        if rdata.len() > 1 && rtype != Type::MX {
+            return Err(ParseError::new("action", input, "dns record has too many parts").into());
         }
 
         if !Domain::is_valid_domain_name(domain) {
-            return Err(format!("Invalid domain: {}", domain).into());
+            return Err(ParseError::new("domain", domain, "invalid domain name").into());
         }
 
         if !SubDomain::is_valid_subdomain(subdomain) {
-            return Err(format!("Invalid subdomain: {}", subdomain).into());
+            return Err(ParseError::new("subdomain", subdomain, "invalid subdomain").into());
         }
 
+        let ttl = ttl
+            .parse()
+            .map_err(|e: std::num::ParseIntError| ParseError::new("ttl", ttl, e.to_string()))?;
+
         Ok(Self {
             domain: domain.to_owned(),
             subdomain: subdomain.to_owned(),
-            rtype: rtype.try_into()?,
             class: class.try_into()?,
-            ttl: ttl.parse()?,
-            rdata: rdata.to_owned(),
+            ttl,
+            rdata: RData::parse(rtype, &rdata.join(" "))?,
         })
     }
 }
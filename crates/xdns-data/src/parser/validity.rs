@@ -1,4 +1,4 @@
-use shared::common::Result;
+use shared::common::{ParseError, Result};
 
 use crate::models::credentials::Credentials;
 use crate::models::validity::Validity;
@@ -25,25 +25,27 @@ impl Parser for Validity {
         let mut parts = input.split_whitespace();
 
         if parts.next() != Some("DOMAIN-VALIDITY") {
-            return Err(format!("Input is not a validity record: {}", input).into());
+            return Err(ParseError::new("action", input, "not a validity record").into());
         }
 
         let domain = parts
             .next()
-            .ok_or_else(|| format!("Validity record is missing domain: {}", input))?;
-        let algorithm = parts
-            .next()
-            .ok_or_else(|| format!("Validity record is missing algorithm: {}", input))?;
+            .ok_or_else(|| ParseError::new("domain", input, "validity record is missing domain"))?;
+        let algorithm = parts.next().ok_or_else(|| {
+            ParseError::new("algorithm", input, "validity record is missing algorithm")
+        })?;
         let key = parts
             .next()
-            .ok_or_else(|| format!("Validity record is missing key: {}", input))?;
+            .ok_or_else(|| ParseError::new("key", input, "validity record is missing key"))?;
 
         if parts.next().is_some() {
-            return Err(format!("Input is not a validity record: {}", input).into());
+            return Err(
+                ParseError::new("action", input, "validity record has too many parts").into(),
+            );
         }
 
         if !Domain::is_valid_domain_name(domain) {
-            return Err(format!("Invalid domain: {}", domain).into());
+            return Err(ParseError::new("domain", domain, "invalid domain name").into());
         }
 
         Ok(Self {
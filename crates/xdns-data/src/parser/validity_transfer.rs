@@ -1,5 +1,5 @@
 use crate::models::credentials::Credentials;
-use shared::common::Error;
+use shared::common::{Error, ParseError};
 
 use crate::models::validity_transfer::ValidityTransfer;
 use crate::prelude::{Domain, Parser};
@@ -28,16 +28,24 @@ impl Parser for ValidityTransfer {
         let mut parts = input.split_whitespace();
 
         if parts.next() != Some("DOMAIN-VALIDATE-TRANSFER") {
-            return Err(format!("Input is not a validity transfer record: {}", input).into());
+            return Err(ParseError::new("action", input, "not a validity transfer record").into());
         }
 
-        let domain = parts
-            .next()
-            .ok_or_else(|| format!("Validity transfer record is missing domain: {}", input))?;
+        let domain = parts.next().ok_or_else(|| {
+            ParseError::new(
+                "domain",
+                input,
+                "validity transfer record is missing domain",
+            )
+        })?;
 
         let new_credentials = if let Some(algorithm) = parts.next() {
             let public_key = parts.next().ok_or_else(|| {
-                format!("Validity transfer record is missing public key: {}", input)
+                ParseError::new(
+                    "key",
+                    input,
+                    "validity transfer record is missing public key",
+                )
             })?;
 
             Some(Credentials::new(algorithm.try_into()?, public_key.into()))
@@ -46,11 +54,16 @@ impl Parser for ValidityTransfer {
         };
 
         if parts.next().is_some() {
-            return Err(format!("Validity transfer record has too many parts: {}", input).into());
+            return Err(ParseError::new(
+                "action",
+                input,
+                "validity transfer record has too many parts",
+            )
+            .into());
         }
 
         if !Domain::is_valid_domain_name(domain) {
-            return Err(format!("Invalid domain: {}", domain).into());
+            return Err(ParseError::new("domain", domain, "invalid domain name").into());
         }
 
         Ok(Self {
@@ -0,0 +1,468 @@
+use shared::common::{Error, ParseError, Result};
+
+use crate::models::signature::Signature;
+use crate::models::subdomain::{SubDomain, Type};
+use crate::models::zone::*;
+use crate::prelude::Domain;
+use crate::traits::parser::Parser;
+use crate::traits::sign::Sign;
+
+/// The classes a zone file record can be in.
+const CLASSES: [&str; 5] = ["IN", "CS", "CH", "HS", "ANY"];
+
+/// A logical line of a zone file, which can span multiple lines within parentheses.
+struct Entry {
+    line: usize,
+    indented: bool,
+    tokens: Vec<String>,
+}
+
+impl Entry {
+    fn error(&self, field: &'static str, input: &str, reason: &str) -> Error {
+        ParseError::new(field, input, reason)
+            .at(self.line, None)
+            .into()
+    }
+}
+
+/// Splits a zone file in its logical lines, removing comments and joining parentheses.
+///
+/// # Arguments
+///
+/// * `input` - The zone file.
+///
+/// # Returns
+///
+/// The non empty logical lines.
+fn entries(input: &str) -> Result<Vec<Entry>> {
+    fn flush(token: &mut Option<String>, tokens: &mut Vec<String>) {
+        if let Some(token) = token.take() {
+            tokens.push(token);
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut current: Option<Entry> = None;
+    let mut depth = 0;
+    let mut opened_at = 0;
+
+    for (i, line) in input.lines().enumerate() {
+        let number = i + 1;
+        let entry = current.get_or_insert_with(|| Entry {
+            line: number,
+            indented: line.starts_with([' ', '\t']),
+            tokens: Vec::new(),
+        });
+        let mut chars = line.chars();
+        let mut token: Option<String> = None;
+        let mut quoted = false;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let token = token.get_or_insert_with(String::new);
+                    token.push(c);
+                    token.extend(chars.next());
+                }
+                '"' => {
+                    quoted = !quoted;
+                    token.get_or_insert_with(String::new);
+                }
+                _ if quoted => token.get_or_insert_with(String::new).push(c),
+                ';' => break,
+                '(' => {
+                    flush(&mut token, &mut entry.tokens);
+                    if depth == 0 {
+                        opened_at = number;
+                    }
+                    depth += 1;
+                }
+                ')' if depth == 0 => {
+                    return Err(ParseError::new("zone", line, "unbalanced parenthesis")
+                        .at(number, None)
+                        .into());
+                }
+                ')' => {
+                    flush(&mut token, &mut entry.tokens);
+                    depth -= 1;
+                }
+                _ if c.is_whitespace() => flush(&mut token, &mut entry.tokens),
+                _ => token.get_or_insert_with(String::new).push(c),
+            }
+        }
+
+        if quoted {
+            return Err(ParseError::new("zone", line, "unterminated string")
+                .at(number, None)
+                .into());
+        }
+
+        flush(&mut token, &mut entry.tokens);
+
+        if depth == 0 {
+            entries.extend(current.take().filter(|entry| !entry.tokens.is_empty()));
+        }
+    }
+
+    if depth > 0 {
+        return Err(ParseError::new("zone", "(", "unclosed parenthesis")
+            .at(opened_at, None)
+            .into());
+    }
+
+    Ok(entries)
+}
+
+/// Makes a name absolute and lowercase.
+///
+/// # Arguments
+///
+/// * `name` - The name, `@` for the origin.
+/// * `origin` - The absolute origin relative names are completed with.
+///
+/// # Returns
+///
+/// The absolute name, if it could be resolved.
+fn resolve(name: &str, origin: Option<&str>) -> Option<String> {
+    let name = name.to_lowercase();
+
+    match origin {
+        _ if name.ends_with('.') => Some(name),
+        Some(origin) if name == "@" => Some(origin.to_string()),
+        Some(".") => Some(name + "."),
+        Some(origin) => Some(format!("{}.{}", name, origin)),
+        None => None,
+    }
+}
+
+/// Parses a time to live, in seconds or with BIND units. (eg `3600`, `1h`, `1d12h`)
+///
+/// # Arguments
+///
+/// * `input` - The time to live.
+///
+/// # Returns
+///
+/// The time to live in seconds, if it is valid.
+fn parse_ttl(input: &str) -> Option<u32> {
+    if let Ok(ttl) = input.parse() {
+        return Some(ttl);
+    }
+
+    let mut ttl: u32 = 0;
+    let mut value: Option<u32> = None;
+
+    for c in input.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            value = Some(value.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+            continue;
+        }
+
+        let unit = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            _ => return None,
+        };
+
+        ttl = ttl.checked_add(value.take()?.checked_mul(unit)?)?;
+    }
+
+    if value.is_some() {
+        return None;
+    }
+
+    Some(ttl)
+}
+
+impl Zone {
+    /// Parses a zone file in RFC 1035 master format.
+    ///
+    /// # Restrictions
+    ///
+    /// * `$ORIGIN` and `$TTL` directives are supported, `$INCLUDE` and `$GENERATE` are not.
+    /// * Relative names *(and `@`)* require an origin, either passed in or set by `$ORIGIN`.
+    /// * A record without owner *(indented)* belongs to the owner of the previous record.
+    /// * A record without ttl takes the `$TTL`, or else the ttl of the previous record.
+    /// * Parentheses can be used to continue a record on the next lines, `;` starts a comment.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The zone file.
+    /// * `origin` - The origin of the zone, if it is not set in the file. (eg `example.com`)
+    ///
+    /// # Returns
+    ///
+    /// The zone with every name made absolute.
+    pub fn parse_with_origin(input: &str, origin: Option<&str>) -> Result<Self> {
+        let mut origin = origin.map(|origin| origin.trim_end_matches('.').to_lowercase() + ".");
+        let mut zone = Self {
+            origin: origin.clone(),
+            records: Vec::new(),
+        };
+        let mut default_ttl: Option<u32> = None;
+        let mut last_ttl: Option<u32> = None;
+        let mut last_class = "IN".to_string();
+        let mut owner: Option<String> = None;
+
+        for entry in entries(input)? {
+            let mut tokens = entry.tokens.as_slice();
+
+            if !entry.indented && tokens[0].starts_with('$') {
+                let argument = tokens.get(1).ok_or_else(|| {
+                    entry.error("directive", &tokens[0], "directive is missing its argument")
+                })?;
+
+                match tokens[0].to_uppercase().as_str() {
+                    "$ORIGIN" => {
+                        let resolved = resolve(argument, origin.as_deref()).ok_or_else(|| {
+                            entry.error("origin", argument, "relative origin without an origin")
+                        })?;
+                        zone.origin.get_or_insert_with(|| resolved.clone());
+                        origin = Some(resolved);
+                    }
+                    "$TTL" => {
+                        default_ttl = Some(
+                            parse_ttl(argument)
+                                .ok_or_else(|| entry.error("ttl", argument, "invalid ttl"))?,
+                        );
+                    }
+                    _ => return Err(entry.error("directive", &tokens[0], "unsupported directive")),
+                }
+
+                continue;
+            }
+
+            if !entry.indented {
+                owner = Some(resolve(&tokens[0], origin.as_deref()).ok_or_else(|| {
+                    entry.error("owner", &tokens[0], "relative name without an origin")
+                })?);
+                tokens = &tokens[1..];
+            }
+
+            let name = owner
+                .clone()
+                .ok_or_else(|| entry.error("owner", "", "record has no owner"))?;
+            let mut ttl = None;
+            let mut class = None;
+
+            while let Some(token) = tokens.first() {
+                if ttl.is_none() && token.starts_with(|c: char| c.is_ascii_digit()) {
+                    ttl = Some(
+                        parse_ttl(token).ok_or_else(|| entry.error("ttl", token, "invalid ttl"))?,
+                    );
+                } else if class.is_none() && CLASSES.contains(&token.to_uppercase().as_str()) {
+                    class = Some(token.to_uppercase());
+                } else {
+                    break;
+                }
+
+                tokens = &tokens[1..];
+            }
+
+            let (rtype, rdata) = tokens
+                .split_first()
+                .ok_or_else(|| entry.error("type", &name, "record is missing type"))?;
+            let rtype = rtype.to_uppercase();
+
+            if rdata.is_empty() {
+                return Err(entry.error("rdata", &name, "record is missing rdata"));
+            }
+
+            if ttl.is_some() {
+                last_ttl = ttl;
+            }
+
+            let ttl = ttl.or(default_ttl).or(last_ttl).ok_or_else(|| {
+                entry.error("ttl", &name, "record has no ttl and no $TTL was set")
+            })?;
+            let class = class.unwrap_or(last_class);
+            let mut rdata = rdata.to_vec();
+
+            if let ("NS" | "CNAME" | "MX", Some(host)) = (rtype.as_str(), rdata.last_mut()) {
+                *host = resolve(host, origin.as_deref())
+                    .ok_or_else(|| entry.error("rdata", host, "relative name without an origin"))?;
+            }
+
+            if rtype == "SOA" {
+                zone.origin.get_or_insert_with(|| name.clone());
+            }
+
+            last_class = class.clone();
+            zone.records.push(ZoneRecord {
+                line: entry.line,
+                name,
+                ttl,
+                class,
+                rtype,
+                rdata,
+            });
+        }
+
+        Ok(zone)
+    }
+
+    /// Converts the records of the zone to subdomain records of a `.o` domain.
+    ///
+    /// Names within the zone, including hosts in the record data, are moved to the domain.
+    /// (eg `www.example.com.` becomes `www.` of `example.o`)
+    /// Records that have no counterpart are skipped and reported, instead of failing the import.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain to import the zone into. (eg `example.o`)
+    ///
+    /// # Returns
+    ///
+    /// The subdomain records and the skipped records.
+    pub fn import(&self, domain: &str) -> Result<ZoneImport> {
+        if !Domain::is_valid_domain_name(domain) {
+            return Err(ParseError::new("domain", domain, "invalid domain name").into());
+        }
+
+        let origin = self
+            .origin
+            .as_deref()
+            .ok_or_else(|| ParseError::new("origin", "", "zone has no origin"))?;
+        let mut import = ZoneImport::default();
+
+        for record in &self.records {
+            match record.to_subdomain(origin, domain) {
+                Ok(subdomain) => import.subdomains.push(subdomain),
+                Err(reason) => import.skipped.push(SkippedRecord {
+                    line: record.line,
+                    name: record.name.clone(),
+                    rtype: record.rtype.clone(),
+                    reason,
+                }),
+            }
+        }
+
+        Ok(import)
+    }
+}
+
+impl Parser for Zone {
+    /// Parses a zone file in RFC 1035 master format, which has to set its own `$ORIGIN`.
+    /// (See [`parse_with_origin`](Zone::parse_with_origin).)
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The zone file.
+    ///
+    /// # Returns
+    ///
+    /// The parsed zone.
+    fn parse(input: &str) -> Result<Self> {
+        Self::parse_with_origin(input, None)
+    }
+}
+
+impl ZoneRecord {
+    /// Moves a name within the zone to the domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The absolute name.
+    /// * `origin` - The absolute origin of the zone.
+    ///
+    /// # Returns
+    ///
+    /// The part of the name before the origin, `None` for the origin itself or `Err` outside of the zone.
+    fn relative<'a>(name: &'a str, origin: &str) -> std::result::Result<Option<&'a str>, ()> {
+        if name == origin {
+            return Ok(None);
+        }
+
+        name.strip_suffix(origin)
+            .and_then(|prefix| prefix.strip_suffix('.'))
+            .filter(|prefix| !prefix.is_empty())
+            .map(Some)
+            .ok_or(())
+    }
+
+    /// Converts the record to a subdomain record of a domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The absolute origin of the zone.
+    /// * `domain` - The domain the record is moved to.
+    ///
+    /// # Returns
+    ///
+    /// The subdomain record or why it can not be converted.
+    fn to_subdomain(&self, origin: &str, domain: &str) -> std::result::Result<SubDomain, String> {
+        if self.class != "IN" {
+            return Err("unsupported class".to_string());
+        }
+
+        let rtype = Type::try_from(self.rtype.as_str()).map_err(|_| "unsupported type")?;
+        let subdomain = match Self::relative(&self.name, origin) {
+            Ok(Some(prefix)) => format!("{}.", prefix),
+            Ok(None) => "@.".to_string(),
+            Err(()) => return Err("outside of the zone".to_string()),
+        };
+        let mut rdata = self.rdata.clone();
+
+        if let (Type::NS | Type::CNAME | Type::MX, Some(host)) = (rtype, rdata.last_mut()) {
+            *host = match Self::relative(host, origin) {
+                Ok(Some(prefix)) => format!("{}.{}", prefix, domain),
+                Ok(None) => domain.to_string(),
+                Err(()) => host.trim_end_matches('.').to_string(),
+            };
+        }
+
+        SubDomain::parse(&format!(
+            "DNS {} {} {} IN {} {}",
+            domain,
+            subdomain,
+            self.rtype,
+            self.ttl,
+            rdata.join(" ")
+        ))
+        .map_err(|e| match e {
+            Error::Parse(e) => e.reason,
+            e => e.to_string(),
+        })
+    }
+}
+
+impl ZoneImport {
+    /// Returns the imported records as inscription actions.
+    ///
+    /// # Returns
+    ///
+    /// One `DNS` line per subdomain record.
+    pub fn actions(&self) -> Vec<String> {
+        self.subdomains.iter().map(ToString::to_string).collect()
+    }
+
+    /// Builds an unsigned inscription of the imported records.
+    ///
+    /// # Arguments
+    ///
+    /// * `last_id` - The id of the previous inscription of the domain, if any.
+    ///
+    /// # Returns
+    ///
+    /// The inscription.
+    pub fn inscription(&self, last_id: Option<&str>) -> String {
+        Signature::unsigned(self.actions(), last_id.map(str::to_string)).to_string()
+    }
+
+    /// Builds a signed inscription of the imported records.
+    ///
+    /// # Arguments
+    ///
+    /// * `last_id` - The id of the previous inscription of the domain, if any.
+    /// * `signer` - The private key matching the validity key of the domain.
+    ///
+    /// # Returns
+    ///
+    /// The inscription.
+    pub fn signed_inscription(&self, last_id: Option<&str>, signer: &dyn Sign) -> String {
+        Signature::sign(self.actions(), last_id.map(str::to_string), signer).to_string()
+    }
+}
@@ -1,5 +1,7 @@
 pub mod parser;
+pub mod sign;
 pub mod verify;
 
 pub use parser::Parser;
+pub use sign::Sign;
 pub use verify::Verify;
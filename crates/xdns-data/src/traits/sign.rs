@@ -0,0 +1,12 @@
+pub trait Sign {
+    /// Signs the data with the private key of the signer.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to sign.
+    ///
+    /// # Returns
+    ///
+    /// The detached signature.
+    fn sign_detached(&self, data: &[u8]) -> Vec<u8>;
+}
@@ -1,7 +1,7 @@
 use shared::common::Result;
 use std::fmt::Debug;
 
-pub trait Verify: Debug {
+pub trait Verify: Debug + Send + Sync {
     /// Creates a new instance of the verifier.
     /// This instance can be used to verify signatures.
     ///
@@ -1,6 +1,7 @@
+use shared::common::{Error, ParseError};
 use xdns_data::models::algorithm::Algorithm;
 use xdns_data::models::credentials::Credentials;
-use xdns_data::parser::{ActionParser, DomainAction};
+use xdns_data::parser::{ActionKind, ActionParser, DomainAction};
 use xdns_data::traits::Parser;
 
 // private key for adding tests: 8BC8BE4BB432DCABFFD48501B72E2CE6AA8B285EFC6048F23818DF1E1EB47689
@@ -73,6 +74,12 @@ fn parse_action_domain_data() {
     parse_action_with_signature!(input, DomainAction::Data(_));
 }
 
+#[test]
+fn parse_action_domain_renewal() {
+    let input = "DOMAIN-RENEW example.o 1 null null";
+    parse_action!(input, DomainAction::Renew(_));
+}
+
 #[test]
 fn parse_action_invalid() {
     let input = "Some action content here which is not a domain data record!";
@@ -135,3 +142,78 @@ fn parse_dns_records() {
     let parsed = parsed.unwrap();
     assert_eq!(parsed.actions.len(), 3);
 }
+
+#[test]
+fn parse_action_invalid_reports_position() {
+    let input = r#"
+        DNS example.o 1. CNAME IN 30 example.com
+        DNS example.o 2. CNAME IN abc example.com
+        null null
+    "#;
+
+    let errors = ActionParser::parse_all(input)
+        .err()
+        .expect("Input should fail");
+    assert_eq!(errors.len(), 1);
+
+    let error = &errors[0];
+    assert_eq!(error.line, Some(3));
+    assert_eq!(error.column, Some(35));
+    assert_eq!(error.field, "ttl");
+    assert_eq!(error.input, "abc");
+    assert_eq!(error.action, Some(ActionKind::Subdomain.keyword()));
+}
+
+#[test]
+fn parse_action_invalid_reports_unsupported_algorithm() {
+    let input = "DOMAIN-VALIDITY example.o rsa 66D8C046FD99D155338B40155D22E24229C1D4D897BC5B327414DFD8D0946D5E null null";
+
+    let errors = ActionParser::parse_all(input)
+        .err()
+        .expect("Input should fail");
+    assert_eq!(errors[0].line, Some(1));
+    assert_eq!(errors[0].field, "algorithm");
+    assert_eq!(errors[0].action, Some("DOMAIN-VALIDITY"));
+}
+
+#[test]
+fn parse_action_invalid_collects_all_errors() {
+    let input = r#"
+        DOMAIN Example.o 1688852425
+        DNS example.o 1. CNAME IN 30 example.com
+        UNKNOWN example.o
+        DNS example.o 2. CNAME IN abc example.com
+        null null
+    "#;
+
+    let errors = ActionParser::parse_all(input)
+        .err()
+        .expect("Input should fail");
+    assert_eq!(errors.len(), 3);
+
+    assert_eq!(errors[0].line, Some(2));
+    assert_eq!(errors[0].field, "name");
+    assert_eq!(errors[0].action, Some("DOMAIN"));
+
+    assert_eq!(errors[1].line, Some(4));
+    assert_eq!(errors[1].field, "action");
+    assert_eq!(errors[1].action, None);
+
+    assert_eq!(errors[2].line, Some(5));
+    assert_eq!(errors[2].field, "ttl");
+}
+
+#[test]
+fn parse_action_invalid_stops_at_first_error() {
+    let input = r#"
+        DOMAIN Example.o 1688852425
+        UNKNOWN example.o
+        null null
+    "#;
+
+    let error = ActionParser::parse(input).err().expect("Input should fail");
+    assert!(matches!(
+        error,
+        Error::Parse(ParseError { line: Some(2), .. })
+    ));
+}
@@ -0,0 +1,62 @@
+use xdns_data::models::renew::DomainRenew;
+use xdns_data::prelude::Parser;
+
+#[test]
+fn parse_renew() {
+    let input = "DOMAIN-RENEW example.o 2";
+    let renewal = DomainRenew::parse(input).unwrap();
+
+    assert_eq!(renewal.domain, "example.o");
+    assert_eq!(renewal.periods, 2);
+}
+
+#[test]
+fn parse_renew_round_trip() {
+    let input = "DOMAIN-RENEW  example.o   10";
+    let renewal = DomainRenew::parse(input).unwrap();
+
+    assert_eq!(renewal.to_string(), "DOMAIN-RENEW example.o 10");
+    assert!(DomainRenew::parse(&renewal.to_string()).is_ok());
+}
+
+#[test]
+fn parse_invalid_renew_missing_periods() {
+    let input = "DOMAIN-RENEW example.o";
+    let renewal = DomainRenew::parse(input);
+
+    assert!(renewal.is_err());
+}
+
+#[test]
+fn parse_invalid_renew_periods() {
+    for periods in ["0", "11", "-1", "one", "1.5"] {
+        let input = format!("DOMAIN-RENEW example.o {}", periods);
+        let renewal = DomainRenew::parse(&input);
+
+        assert!(renewal.is_err(), "{} should be rejected", periods);
+    }
+}
+
+#[test]
+fn parse_invalid_renew_domain() {
+    let input = "DOMAIN-RENEW example.com 1";
+    let renewal = DomainRenew::parse(input);
+
+    assert!(renewal.is_err());
+}
+
+#[test]
+fn parse_invalid_renew_with_too_many_parts() {
+    let input = "DOMAIN-RENEW example.o 1 2";
+    let renewal = DomainRenew::parse(input);
+
+    assert!(renewal.is_err());
+}
+
+#[test]
+fn parse_invalid_input() {
+    let input = "Some inscription content here which is not a renewal record!";
+    let parsed = DomainRenew::parse(input);
+
+    assert!(parsed.is_err());
+}
@@ -1,4 +1,6 @@
-use xdns_data::models::subdomain::SubDomain;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use xdns_data::models::subdomain::{RData, SubDomain};
 use xdns_data::prelude::Parser;
 
 #[test]
@@ -316,3 +318,143 @@ fn parse_invalid_input() {
 
     assert!(parsed.is_err());
 }
+
+#[test]
+fn parse_subdomain_typed_rdata_a() {
+    let input = "DNS example.o example. A IN 30 127.0.0.1";
+    let parsed = SubDomain::parse(input).unwrap();
+
+    assert_eq!(parsed.rdata, RData::A(Ipv4Addr::new(127, 0, 0, 1)));
+}
+
+#[test]
+fn parse_subdomain_typed_rdata_aaaa() {
+    let input = "DNS example.o example. AAAA IN 30 ::1";
+    let parsed = SubDomain::parse(input).unwrap();
+
+    assert_eq!(parsed.rdata, RData::AAAA(Ipv6Addr::LOCALHOST));
+}
+
+#[test]
+fn parse_subdomain_typed_rdata_cname() {
+    let input = "DNS example.o example. CNAME IN 30 example.com";
+    let parsed = SubDomain::parse(input).unwrap();
+
+    assert_eq!(parsed.rdata, RData::CNAME("example.com".to_string()));
+}
+
+#[test]
+fn parse_subdomain_invalid_a_rdata() {
+    let input = "DNS example.o example. A IN 30 example.com";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn parse_subdomain_invalid_aaaa_rdata() {
+    let input = "DNS example.o example. AAAA IN 30 127.0.0.1";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn parse_subdomain_invalid_cname_rdata() {
+    let input = "DNS example.o example. CNAME IN 30 exa_mple.com";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn parse_subdomain_invalid_ns_rdata() {
+    let input = "DNS example.o example. NS IN 30 -ns.example.com";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn valid_hostname_trailing_dot() {
+    assert!(RData::is_valid_hostname("mail.example.o."));
+}
+
+#[test]
+fn invalid_hostname_empty_label() {
+    assert!(!RData::is_valid_hostname("mail..example.o"));
+}
+
+#[test]
+fn invalid_hostname_label_too_long() {
+    let hostname = format!("{}.example.o", "a".repeat(64));
+
+    assert!(!RData::is_valid_hostname(&hostname));
+}
+
+#[test]
+fn parse_subdomain_mx_with_priority() {
+    let input = "DNS example.o @. MX IN 30 10 mail.example.o";
+    let parsed = SubDomain::parse(input).unwrap();
+
+    assert_eq!(
+        parsed.rdata,
+        RData::MX {
+            priority: 10,
+            host: "mail.example.o".to_string()
+        }
+    );
+}
+
+#[test]
+fn parse_subdomain_mx_legacy_without_priority() {
+    let input = "DNS example.o @. MX IN 30 mail.example.o";
+    let parsed = SubDomain::parse(input).unwrap();
+
+    assert_eq!(
+        parsed.rdata,
+        RData::MX {
+            priority: 0,
+            host: "mail.example.o".to_string()
+        }
+    );
+}
+
+#[test]
+fn parse_subdomain_mx_invalid_priority() {
+    let input = "DNS example.o @. MX IN 30 70000 mail.example.o";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn parse_subdomain_mx_legacy_only_priority() {
+    let input = "DNS example.o @. MX IN 30 10";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn invalid_hostname_numeric() {
+    assert!(!RData::is_valid_hostname("10"));
+    assert!(!RData::is_valid_hostname("127.0.0.1"));
+    assert!(RData::is_valid_hostname("10.example.o"));
+}
+
+#[test]
+fn parse_subdomain_mx_too_many_parts() {
+    let input = "DNS example.o @. MX IN 30 10 mail.example.o backup.example.o";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn parse_subdomain_multi_part_rdata_only_for_mx() {
+    let input = "DNS example.o @. CNAME IN 30 10 example.com";
+    let parsed = SubDomain::parse(input);
+
+    assert!(parsed.is_err());
+}
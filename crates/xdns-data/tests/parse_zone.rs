@@ -0,0 +1,185 @@
+use ed25519_dalek::SigningKey;
+use hex_literal::hex;
+use shared::common::Error;
+use xdns_data::models::algorithm::Algorithm;
+use xdns_data::models::credentials::Credentials;
+use xdns_data::models::zone::Zone;
+use xdns_data::parser::{ActionParser, DomainAction};
+use xdns_data::traits::Parser;
+
+const PRIVATE_KEY: [u8; 32] =
+    hex!("8BC8BE4BB432DCABFFD48501B72E2CE6AA8B285EFC6048F23818DF1E1EB47689");
+const PUBLIC_KEY: &str = "C0AB4030035B8DDA5E9F5BF3881B8E21603714674AF8099602F31F142D80BCFE";
+
+const ZONE: &str = r#"
+$ORIGIN example.com.
+$TTL 1h
+@       IN  SOA ns1 hostmaster (
+                2023060101 ; serial
+                7200       ; refresh
+                3600 1209600 300 )
+        IN  NS  ns1
+        IN  NS  ns.provider.net.
+        IN  MX  10 mail
+@       300 IN  A   93.184.216.34
+www         IN  CNAME @
+mail    60  IN  A   10.0.0.25
+            IN  AAAA 2001:db8::25
+        TXT "v=spf1 mx -all"
+*.dev   IN 600 A 10.0.0.1
+_dmarc      IN  TXT "v=DMARC1; p=none"
+other.org.  IN  A   10.0.0.2
+"#;
+
+#[test]
+fn parse_zone_directives_and_owners() {
+    let zone = Zone::parse(ZONE).unwrap();
+
+    assert_eq!(zone.origin.as_deref(), Some("example.com."));
+    assert_eq!(zone.records.len(), 12);
+
+    let soa = &zone.records[0];
+    assert_eq!(
+        (soa.line, soa.name.as_str(), soa.rtype.as_str()),
+        (4, "example.com.", "SOA")
+    );
+    assert_eq!(soa.rdata.len(), 7);
+    assert_eq!(soa.ttl, 3600);
+
+    // Omitted owners and classes are taken from the previous record.
+    let ns = &zone.records[1];
+    assert_eq!(
+        (ns.name.as_str(), ns.class.as_str()),
+        ("example.com.", "IN")
+    );
+    assert_eq!(ns.rdata, vec!["ns1.example.com."]);
+
+    let aaaa = &zone.records[7];
+    assert_eq!((aaaa.name.as_str(), aaaa.ttl), ("mail.example.com.", 3600));
+
+    // The ttl and class can be given in either order.
+    let wildcard = &zone.records[9];
+    assert_eq!(
+        (wildcard.name.as_str(), wildcard.ttl),
+        ("*.dev.example.com.", 600)
+    );
+
+    // Comment characters within strings are kept.
+    assert_eq!(zone.records[10].rdata, vec!["v=DMARC1; p=none"]);
+}
+
+#[test]
+fn parse_zone_with_origin() {
+    let zone = Zone::parse_with_origin("www 30 IN A 127.0.0.1", Some("Example.com")).unwrap();
+
+    assert_eq!(zone.origin.as_deref(), Some("example.com."));
+    assert_eq!(zone.records[0].name, "www.example.com.");
+
+    // The origin can also come from the SOA record.
+    let zone =
+        Zone::parse("example.com. 30 IN SOA ns1.example.com. hostmaster.example.com. 1 2 3 4 5")
+            .unwrap();
+    assert_eq!(zone.origin.as_deref(), Some("example.com."));
+}
+
+#[test]
+fn parse_zone_ttl_units() {
+    let zone =
+        Zone::parse("$TTL 1d12h\nexample.com. IN A 127.0.0.1\nexample.com. 1w IN A 127.0.0.2")
+            .unwrap();
+
+    assert_eq!(zone.records[0].ttl, 129600);
+    assert_eq!(zone.records[1].ttl, 604800);
+}
+
+#[test]
+fn parse_zone_invalid() {
+    let line = |input: &str| match Zone::parse(input) {
+        Err(Error::Parse(e)) => e.line,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+
+    assert_eq!(line("www 30 IN A 127.0.0.1"), Some(1));
+    assert_eq!(
+        line("$ORIGIN example.com.\n\n@ 30 IN SOA ( ns1 host"),
+        Some(3)
+    );
+    assert_eq!(line("$ORIGIN example.com.\n@ 30 IN A 127.0.0.1 )"), Some(2));
+    assert_eq!(line("$ORIGIN example.com.\n@ IN A 127.0.0.1"), Some(2));
+    assert_eq!(
+        line("$ORIGIN example.com.\n@ 30 IN TXT \"unterminated"),
+        Some(2)
+    );
+    assert_eq!(line("$INCLUDE other.zone"), Some(1));
+    assert_eq!(line("$TTL forever"), Some(1));
+    assert_eq!(line("  30 IN A 127.0.0.1"), Some(1));
+}
+
+#[test]
+fn import_zone() {
+    let import = Zone::parse(ZONE).unwrap().import("example.o").unwrap();
+
+    assert_eq!(
+        import.actions(),
+        vec![
+            "DNS example.o @. NS IN 3600 ns1.example.o",
+            "DNS example.o @. NS IN 3600 ns.provider.net",
+            "DNS example.o @. MX IN 3600 10 mail.example.o",
+            "DNS example.o @. A IN 300 93.184.216.34",
+            "DNS example.o www. CNAME IN 3600 example.o",
+            "DNS example.o mail. A IN 60 10.0.0.25",
+            "DNS example.o mail. AAAA IN 3600 2001:db8::25",
+            "DNS example.o *.dev. A IN 600 10.0.0.1",
+        ]
+    );
+
+    let skipped = import
+        .skipped
+        .iter()
+        .map(|record| (record.line, record.rtype.as_str(), record.reason.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        skipped,
+        vec![
+            (4, "SOA", "unsupported type"),
+            (15, "TXT", "unsupported type"),
+            (17, "TXT", "unsupported type"),
+            (18, "A", "outside of the zone"),
+        ]
+    );
+}
+
+#[test]
+fn import_zone_invalid_records() {
+    let zone = Zone::parse_with_origin(
+        "_sip 30 IN A 127.0.0.1\nwww 30 CH A 127.0.0.1",
+        Some("example.com"),
+    )
+    .unwrap();
+    let import = zone.import("example.o").unwrap();
+
+    assert!(import.subdomains.is_empty());
+    assert_eq!(import.skipped[0].reason, "invalid subdomain");
+    assert_eq!(import.skipped[1].reason, "unsupported class");
+
+    assert!(zone.import("not a domain").is_err());
+    assert!(Zone::default().import("example.o").is_err());
+}
+
+#[test]
+fn import_zone_inscription() {
+    let import = Zone::parse(ZONE).unwrap().import("example.o").unwrap();
+
+    let parsed = ActionParser::parse(&import.inscription(None)).unwrap();
+    assert_eq!(parsed.actions.len(), 8);
+    assert!(parsed.signature.is_none());
+
+    let key = SigningKey::from_bytes(&PRIVATE_KEY);
+    let parsed = ActionParser::parse(&import.signed_inscription(Some("1234i0"), &key)).unwrap();
+    assert_eq!(parsed.actions.len(), 8);
+    assert!(matches!(parsed.actions[0], DomainAction::Subdomain(_)));
+
+    let signature = parsed.signature.unwrap();
+    assert_eq!(signature.last_id.as_deref(), Some("1234i0"));
+    assert!(signature.is_valid(Credentials::new(Algorithm::Ed25519, PUBLIC_KEY.to_string())));
+}
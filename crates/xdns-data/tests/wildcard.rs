@@ -0,0 +1,93 @@
+use xdns_data::models::SubDomainMatch;
+
+const OWNERS: [&str; 6] = ["@.", "*.", "www.", "*.blog.", "a.sub.", "host.*.lab."];
+
+#[test]
+fn wildcard_exact_match() {
+    assert_eq!(
+        SubDomainMatch::find("www.", &OWNERS),
+        SubDomainMatch::Exact("www.".to_string())
+    );
+    assert_eq!(
+        SubDomainMatch::find("@.", &OWNERS),
+        SubDomainMatch::Exact("@.".to_string())
+    );
+}
+
+#[test]
+fn wildcard_exact_match_on_wildcard_owner() {
+    assert_eq!(
+        SubDomainMatch::find("*.", &OWNERS),
+        SubDomainMatch::Exact("*.".to_string())
+    );
+}
+
+#[test]
+fn wildcard_synthesized_at_root() {
+    assert_eq!(
+        SubDomainMatch::find("anything.", &OWNERS),
+        SubDomainMatch::Wildcard("*.".to_string())
+    );
+    assert_eq!(
+        SubDomainMatch::find("deep.anything.", &OWNERS),
+        SubDomainMatch::Wildcard("*.".to_string())
+    );
+}
+
+#[test]
+fn wildcard_closest_encloser() {
+    assert_eq!(
+        SubDomainMatch::find("post.blog.", &OWNERS),
+        SubDomainMatch::Wildcard("*.blog.".to_string())
+    );
+    assert_eq!(
+        SubDomainMatch::find("b.sub.", &OWNERS),
+        SubDomainMatch::NxDomain
+    );
+}
+
+#[test]
+fn wildcard_blocked_below_existing_name() {
+    // `www.` exists, so `x.www.` has `www.` as closest encloser and `*.www.` does not exist.
+    assert_eq!(
+        SubDomainMatch::find("x.www.", &OWNERS),
+        SubDomainMatch::NxDomain
+    );
+}
+
+#[test]
+fn wildcard_empty_non_terminal() {
+    // `sub.` and `blog.` only exist because of names below them.
+    assert_eq!(
+        SubDomainMatch::find("sub.", &OWNERS),
+        SubDomainMatch::NoData
+    );
+    assert_eq!(
+        SubDomainMatch::find("blog.", &OWNERS),
+        SubDomainMatch::NoData
+    );
+}
+
+#[test]
+fn wildcard_non_leftmost_asterisk_is_literal() {
+    assert_eq!(
+        SubDomainMatch::find("host.x.lab.", &OWNERS),
+        SubDomainMatch::NxDomain
+    );
+    assert_eq!(
+        SubDomainMatch::find("host.*.lab.", &OWNERS),
+        SubDomainMatch::Exact("host.*.lab.".to_string())
+    );
+}
+
+#[test]
+fn wildcard_no_wildcard() {
+    assert_eq!(
+        SubDomainMatch::find("anything.", &["@.", "www."]),
+        SubDomainMatch::NxDomain
+    );
+    assert_eq!(
+        SubDomainMatch::find::<&str>("www.", &[]),
+        SubDomainMatch::NxDomain
+    );
+}
@@ -1,4 +1,7 @@
+pub mod checkpoint;
 pub mod data;
 pub mod domain;
+pub mod history;
+pub mod journal;
 pub mod subdomain;
 pub mod validity;
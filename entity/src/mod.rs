@@ -2,7 +2,10 @@
 
 pub mod prelude;
 
+pub mod checkpoint;
 pub mod data;
 pub mod domain;
+pub mod history;
+pub mod journal;
 pub mod subdomain;
 pub mod validity;
@@ -1,6 +1,9 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.3
 
+pub use super::checkpoint::Entity as Checkpoint;
 pub use super::data::Entity as Data;
 pub use super::domain::Entity as Domain;
+pub use super::history::Entity as History;
+pub use super::journal::Entity as Journal;
 pub use super::subdomain::Entity as Subdomain;
 pub use super::validity::Entity as Validity;
@@ -15,6 +15,7 @@ pub struct Model {
     pub class: String,
     pub ttl: i32,
     pub rdata: String,
+    pub priority: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
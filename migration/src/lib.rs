@@ -4,6 +4,13 @@ mod m20220101_000001_create_domain_table;
 mod m20230702_073858_create_subdomain_table;
 mod m20230702_081359_create_validity_table;
 mod m20230702_081403_create_data_table;
+mod m20261019_000001_add_subdomain_priority;
+mod m20261019_000002_create_journal_table;
+mod m20261019_000003_create_history_table;
+mod m20261019_000004_add_listing_indexes;
+mod m20261019_000005_create_checkpoint_table;
+mod m20261019_000006_add_name_search_index;
+mod m20261019_000007_add_domain_last_renewal;
 
 pub struct Migrator;
 
@@ -15,6 +22,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20230702_073858_create_subdomain_table::Migration),
             Box::new(m20230702_081359_create_validity_table::Migration),
             Box::new(m20230702_081403_create_data_table::Migration),
+            Box::new(m20261019_000001_add_subdomain_priority::Migration),
+            Box::new(m20261019_000002_create_journal_table::Migration),
+            Box::new(m20261019_000003_create_history_table::Migration),
+            Box::new(m20261019_000004_add_listing_indexes::Migration),
+            Box::new(m20261019_000005_create_checkpoint_table::Migration),
+            Box::new(m20261019_000006_add_name_search_index::Migration),
+            Box::new(m20261019_000007_add_domain_last_renewal::Migration),
         ]
     }
 }
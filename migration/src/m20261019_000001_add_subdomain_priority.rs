@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Only used by MX records, legacy records without a preference stay NULL.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Subdomain::Table)
+                    .add_column(ColumnDef::new(Subdomain::Priority).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Subdomain::Table)
+                    .drop_column(Subdomain::Priority)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Subdomain {
+    Table,
+    #[iden(rename = "priority")]
+    Priority,
+}
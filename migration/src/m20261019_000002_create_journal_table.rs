@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One row per batch committed at a block, the snapshots are the json encoded
+        // state of every domain the batch changed, as it was before the batch.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Journal::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Journal::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Journal::Height).big_integer().not_null())
+                    .col(ColumnDef::new(Journal::Hash).string().not_null())
+                    .col(ColumnDef::new(Journal::Snapshots).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Journal::Table)
+                    .name("idx_journal_height")
+                    .col(Journal::Height)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Journal::Table).if_exists().to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Journal {
+    Table,
+    #[iden(rename = "id")]
+    Id,
+    #[iden(rename = "height")]
+    Height,
+    #[iden(rename = "hash")]
+    Hash,
+    #[iden(rename = "snapshots")]
+    Snapshots,
+}
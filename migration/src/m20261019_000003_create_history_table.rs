@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Append-only, one row per applied action and domain it changed.
+        manager
+            .create_table(
+                Table::create()
+                    .table(History::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(History::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(History::Domain).string().not_null())
+                    .col(ColumnDef::new(History::Action).string().not_null())
+                    .col(ColumnDef::new(History::Inscription).string().null())
+                    .col(ColumnDef::new(History::Address).string().null())
+                    .col(ColumnDef::new(History::Height).big_integer().null())
+                    .col(ColumnDef::new(History::Hash).string().null())
+                    .col(ColumnDef::new(History::Timestamp).big_integer().not_null())
+                    .col(ColumnDef::new(History::Payload).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(History::Table)
+                    .name("idx_history_domain")
+                    .col(History::Domain)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(History::Table).if_exists().to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum History {
+    Table,
+    #[iden(rename = "id")]
+    Id,
+    #[iden(rename = "domain")]
+    Domain,
+    #[iden(rename = "action")]
+    Action,
+    #[iden(rename = "inscription")]
+    Inscription,
+    #[iden(rename = "address")]
+    Address,
+    #[iden(rename = "height")]
+    Height,
+    #[iden(rename = "hash")]
+    Hash,
+    #[iden(rename = "timestamp")]
+    Timestamp,
+    #[iden(rename = "payload")]
+    Payload,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// `valid_from` is stored as text, expiry queries compare it as a number.
+/// The repository filters on this exact expression, so the index is used for them.
+const EXPIRY_INDEX: &str = r#"CREATE INDEX IF NOT EXISTS "idx_domain_expiry" ON "domain" ((CAST("valid_from" AS BIGINT)))"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Lists the domains of an address in name order.
+        manager
+            .create_index(
+                Index::create()
+                    .table(Domain::Table)
+                    .name("idx_domain_address_name")
+                    .col(Domain::Address)
+                    .col(Domain::Name)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(EXPIRY_INDEX)
+            .await?;
+
+        // Lists the records of a domain that belong to its owner in subdomain order.
+        manager
+            .create_index(
+                Index::create()
+                    .table(Subdomain::Table)
+                    .name("idx_subdomain_domain_address_subdomain")
+                    .col(Subdomain::Domain)
+                    .col(Subdomain::Address)
+                    .col(Subdomain::Name)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Subdomain::Table)
+                    .name("idx_subdomain_domain_address_subdomain")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Domain::Table)
+                    .name("idx_domain_expiry")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Domain::Table)
+                    .name("idx_domain_address_name")
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Domain {
+    Table,
+    #[iden(rename = "address")]
+    Address,
+    #[iden(rename = "name")]
+    Name,
+}
+
+#[derive(Iden)]
+enum Subdomain {
+    Table,
+    #[iden(rename = "domain")]
+    Domain,
+    #[iden(rename = "address")]
+    Address,
+    #[iden(rename = "subdomain")]
+    Name,
+}
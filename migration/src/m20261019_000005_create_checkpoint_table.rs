@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A single row holding the newest block whose journal was pruned,
+        // nothing at or below it can be rolled back anymore.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Checkpoint::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Checkpoint::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Checkpoint::Height).big_integer().not_null())
+                    .col(ColumnDef::new(Checkpoint::Hash).string().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(Checkpoint::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Checkpoint {
+    Table,
+    #[iden(rename = "id")]
+    Id,
+    #[iden(rename = "height")]
+    Height,
+    #[iden(rename = "hash")]
+    Hash,
+}
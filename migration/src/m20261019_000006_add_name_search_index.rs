@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// SQLite can not serve `LIKE '%text%'` from a b-tree, so the names are kept in a trigram
+/// full-text table next to the domain table, which the triggers keep in sync.
+const SQLITE_UP: &[&str] = &[
+    r#"CREATE VIRTUAL TABLE IF NOT EXISTS "domain_name_search" USING fts5("name", tokenize = 'trigram')"#,
+    r#"INSERT INTO "domain_name_search" ("name") SELECT "name" FROM "domain""#,
+    r#"CREATE TRIGGER IF NOT EXISTS "domain_name_search_insert" AFTER INSERT ON "domain" BEGIN
+        INSERT INTO "domain_name_search" ("name") VALUES (new."name");
+    END"#,
+    // The LIKE narrows the rows down with the trigram index, the equality makes the match exact.
+    r#"CREATE TRIGGER IF NOT EXISTS "domain_name_search_delete" AFTER DELETE ON "domain" BEGIN
+        DELETE FROM "domain_name_search" WHERE "name" LIKE old."name" AND "name" = old."name";
+    END"#,
+    r#"CREATE TRIGGER IF NOT EXISTS "domain_name_search_update" AFTER UPDATE OF "name" ON "domain" BEGIN
+        DELETE FROM "domain_name_search" WHERE "name" LIKE old."name" AND "name" = old."name";
+        INSERT INTO "domain_name_search" ("name") VALUES (new."name");
+    END"#,
+];
+
+const SQLITE_DOWN: &[&str] = &[
+    r#"DROP TRIGGER IF EXISTS "domain_name_search_update""#,
+    r#"DROP TRIGGER IF EXISTS "domain_name_search_delete""#,
+    r#"DROP TRIGGER IF EXISTS "domain_name_search_insert""#,
+    r#"DROP TABLE IF EXISTS "domain_name_search""#,
+];
+
+/// Postgres serves `LIKE '%text%'` from a trigram index on the name itself.
+const POSTGRES_UP: &[&str] = &[
+    r#"CREATE EXTENSION IF NOT EXISTS pg_trgm"#,
+    r#"CREATE INDEX IF NOT EXISTS "idx_domain_name_trgm" ON "domain" USING gin ("name" gin_trgm_ops)"#,
+];
+
+const POSTGRES_DOWN: &[&str] = &[r#"DROP INDEX IF EXISTS "idx_domain_name_trgm""#];
+
+async fn execute(manager: &SchemaManager<'_>, statements: &[&str]) -> Result<(), DbErr> {
+    for statement in statements {
+        manager
+            .get_connection()
+            .execute_unprepared(statement)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DatabaseBackend::Sqlite => execute(manager, SQLITE_UP).await,
+            DatabaseBackend::Postgres => execute(manager, POSTGRES_UP).await,
+            DatabaseBackend::MySql => Ok(()),
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DatabaseBackend::Sqlite => execute(manager, SQLITE_DOWN).await,
+            DatabaseBackend::Postgres => execute(manager, POSTGRES_DOWN).await,
+            DatabaseBackend::MySql => Ok(()),
+        }
+    }
+}
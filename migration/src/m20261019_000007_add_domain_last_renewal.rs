@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The inscription of the last renewal, signed renewals have to name it as their last id.
+        // Domains that were never renewed stay NULL.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Domain::Table)
+                    .add_column(ColumnDef::new(Domain::LastRenewal).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Domain::Table)
+                    .drop_column(Domain::LastRenewal)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Domain {
+    Table,
+    #[iden(rename = "last_renewal")]
+    LastRenewal,
+}
@@ -0,0 +1,385 @@
+use std::env;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use db::XDNSRepository;
+use dns_utils::dnssec;
+use dns_utils::prelude::*;
+use dns_utils::validator::{UdpResolver, Validator};
+use shared::prelude::*;
+
+use crate::ownership;
+use crate::transfer::{self, TransferConfig};
+use crate::Database;
+
+/// The environment variable holding the hex encoded Ed25519 secret of the node key, answers are not signed without it.
+pub const KEY_VAR: &str = "XDNS_DNSSEC_KEY";
+/// The environment variable enabling the validation of forwarded answers when set to `1` or `true`.
+pub const VALIDATE_VAR: &str = "XDNS_VALIDATE";
+/// The environment variable holding the comma separated `DS` records of the trust anchors.
+pub const TRUST_ANCHOR_VAR: &str = "XDNS_TRUST_ANCHOR";
+/// The key signing key of the root zone, since 2017.
+const ROOT_TRUST_ANCHOR: &str =
+    ". IN DS 20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D";
+/// The parent of every .o zone, which holds their `DS` records.
+const ROOT_ZONE: &str = "o";
+/// The time to live of the `DNSKEY` and `DS` records.
+const KEY_TTL: u32 = 3600;
+/// How long a signature is valid, answers are signed when they are sent.
+const SIGNATURE_VALIDITY: Duration = Duration::from_secs(7 * 24 * 3600);
+/// How long before being made a signature is valid, for resolvers with a clock that is behind.
+const CLOCK_SKEW: Duration = Duration::from_secs(3600);
+
+/// Reads the node key from the [`KEY_VAR`] environment variable.
+///
+/// # Returns
+///
+/// The key, none if the variable is not set, or an error if the secret is invalid.
+pub fn node_key_from_env() -> Result<Option<NodeKey>> {
+    match env::var(KEY_VAR) {
+        Ok(secret) => NodeKey::from_hex(&secret).map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Creates the validator of forwarded answers, from the [`VALIDATE_VAR`] and
+/// [`TRUST_ANCHOR_VAR`] environment variables. The root key is trusted when no anchor is set.
+///
+/// # Arguments
+///
+/// * `server` - The resolver the queries are forwarded to.
+///
+/// # Returns
+///
+/// The validator, none if validation is not enabled, or an error if a trust anchor is invalid.
+pub fn validator_from_env(server: SocketAddr) -> Result<Option<Validator<UdpResolver>>> {
+    let enabled = env::var(VALIDATE_VAR)
+        .is_ok_and(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true"));
+    if !enabled {
+        return Ok(None);
+    }
+
+    let anchors = env::var(TRUST_ANCHOR_VAR).unwrap_or_else(|_| ROOT_TRUST_ANCHOR.to_string());
+    let anchors = anchors
+        .split(',')
+        .map(str::trim)
+        .filter(|anchor| !anchor.is_empty())
+        .map(dnssec::parse_ds)
+        .collect::<Result<Vec<_>>>()?;
+    if anchors.is_empty() {
+        return Err(Error::Other(format!(
+            "No trust anchor in {}",
+            TRUST_ANCHOR_VAR
+        )));
+    }
+
+    Ok(Some(Validator::new(UdpResolver::new(server), anchors)))
+}
+
+/// Whether a name belongs to the .o tree signed by the node.
+pub fn is_signed(name: &str) -> bool {
+    name == ROOT_ZONE || name.ends_with(".o")
+}
+
+/// The apex of the zone a name belongs to. (eg `example.o` for `www.example.o`)
+fn zone_of(name: &str) -> String {
+    let labels = name.split('.').collect::<Vec<_>>();
+    labels[labels.len().saturating_sub(2)..].join(".")
+}
+
+/// The apex of the zone that signs a set of records, `DS` records are signed by the parent.
+fn signer_of(record: &DnsRecord) -> String {
+    match record {
+        DnsRecord::DS { .. } => ROOT_ZONE.to_string(),
+        _ => zone_of(record.get_domain()),
+    }
+}
+
+fn root_soa(config: &TransferConfig) -> DnsRecord {
+    transfer::soa_record(ROOT_ZONE, &config.exporter.soa(ROOT_ZONE, 1, &[]))
+}
+
+/// The names of the records of a zone, absolute. (eg `www.example.o`)
+async fn owners(db: &Database, zone: &str) -> Result<Vec<String>> {
+    Ok(db
+        .get_subdomain_names(zone)
+        .await?
+        .into_iter()
+        .map(|owner| match owner.trim_end_matches('.') {
+            "@" | "" => zone.to_string(),
+            owner => format!("{}.{}", owner, zone),
+        })
+        .collect())
+}
+
+/// The types of the records at a name, for the type bitmap of its `NSEC`.
+/// The apex of a zone also holds its `SOA` and `DNSKEY` records, and the well-known names of the [`ownership`]
+/// module the records synthesized for them, neither of which are in the repository.
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the zones.
+/// * `zone` - The apex of the zone the name belongs to.
+/// * `name` - The name, absolute. (eg `www.example.o`)
+async fn types_at(db: &Database, zone: &str, name: &str) -> Result<Vec<QueryType>> {
+    let mut types = if name == zone {
+        vec![QueryType::SOA, QueryType::DNSKEY]
+    } else {
+        Vec::new()
+    };
+    if zone == ROOT_ZONE {
+        return Ok(types);
+    }
+
+    let subdomain = match name.strip_suffix(zone) {
+        Some("") | None => "@.",
+        Some(subdomain) => subdomain,
+    };
+    let records = match db.get_subdomain(zone, subdomain).await {
+        Ok(records) => records,
+        Err(e) if e.is_not_found() => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    types.extend(
+        records
+            .into_iter()
+            .map(|(_, record)| QueryType::SUB(record.rtype())),
+    );
+
+    for synthesized in [QueryType::TXT, QueryType::DNSKEY] {
+        let answers = match ownership::answer(db, name, synthesized).await {
+            Ok(answers) => answers.unwrap_or_default(),
+            Err(e) if e.is_not_found() => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        if !answers.is_empty() {
+            types.push(synthesized);
+        }
+    }
+
+    Ok(types)
+}
+
+/// Answers the queries for the keys of the .o zones and for the apex of the .o tree itself.
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the zones.
+/// * `key` - The node key.
+/// * `config` - Describes the `SOA` of the zones.
+/// * `qname` - The queried name.
+/// * `qtype` - The queried type.
+///
+/// # Returns
+///
+/// The answers, none if the query is answered by the records of a zone.
+pub async fn keys(
+    db: &Database,
+    key: &NodeKey,
+    config: &TransferConfig,
+    qname: &str,
+    qtype: QueryType,
+) -> Result<Option<Vec<DnsRecord>>> {
+    if qname == ROOT_ZONE {
+        return Ok(Some(match qtype {
+            QueryType::DNSKEY => vec![key.dnskey(ROOT_ZONE, KEY_TTL)],
+            QueryType::SOA => vec![root_soa(config)],
+            _ => Vec::new(),
+        }));
+    }
+
+    if !matches!(qtype, QueryType::DNSKEY | QueryType::DS) || !transfer::is_zone(qname) {
+        return Ok(None);
+    }
+
+    match db.get_subdomain_names(qname).await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let dnskey = key.dnskey(qname, KEY_TTL);
+    Ok(Some(match qtype {
+        QueryType::DS => vec![dnssec::ds(&dnskey, KEY_TTL)?],
+        _ => vec![dnskey],
+    }))
+}
+
+/// Signs the answers of a response, and proves the queried name or type does not exist when
+/// there are none. (RFC 4035 section 3.1)
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the zones.
+/// * `key` - The node key.
+/// * `config` - Describes the `SOA` of the zones.
+/// * `qname` - The queried name, in the .o tree.
+/// * `packet` - The response.
+pub async fn sign(
+    db: &Database,
+    key: &NodeKey,
+    config: &TransferConfig,
+    qname: &str,
+    packet: &mut DnsPacket,
+) -> Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let inception = (now - CLOCK_SKEW).as_secs() as u32;
+    let expiration = (now + SIGNATURE_VALIDITY).as_secs() as u32;
+
+    // Records from outside of the .o tree (eg the target of a CNAME) are not ours to sign.
+    let signatures = dnssec::rrsets(&packet.answers)
+        .into_iter()
+        .filter(|rrset| is_signed(rrset[0].get_domain()))
+        .map(|rrset| key.sign(&signer_of(&rrset[0]), &rrset, inception, expiration))
+        .collect::<Result<Vec<_>>>()?;
+    packet.answers.extend(signatures);
+
+    let denied = packet.answers.is_empty()
+        && matches!(
+            packet.header.rescode,
+            ResultCode::NOERROR | ResultCode::NXDOMAIN
+        );
+    if !denied {
+        return Ok(());
+    }
+
+    let mut zone = zone_of(qname);
+    let names = match owners(db, &zone).await {
+        Ok(names) => names,
+        Err(e) if e.is_not_found() => {
+            zone = ROOT_ZONE.to_string();
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+
+    let soa = match zone.as_str() {
+        ROOT_ZONE => root_soa(config),
+        zone => transfer::soa(db, config, zone).await?,
+    };
+    let ttl = match soa {
+        DnsRecord::SOA { minimum, .. } => minimum,
+        _ => KEY_TTL,
+    };
+
+    let mut denial = vec![soa];
+    if packet.header.rescode == ResultCode::NXDOMAIN {
+        // The closest ancestor of the name that exists, the wildcard below it does not exist either.
+        let exists = |name: &str| {
+            name == zone
+                || names
+                    .iter()
+                    .any(|owner| owner == name || owner.ends_with(&format!(".{}", name)))
+        };
+        let encloser = std::iter::successors(Some(qname), |name| {
+            name.split_once('.').map(|(_, parent)| parent)
+        })
+        .skip(1)
+        .find(|name| exists(name))
+        .unwrap_or(ROOT_ZONE);
+
+        denial.push(dnssec::covering_nsec(qname, ttl));
+        denial.push(dnssec::covering_nsec(&format!("*.{}", encloser), ttl));
+        denial.dedup();
+    } else {
+        // Only the queried type is denied, every type at the name is listed.
+        let types = types_at(db, &zone, qname).await?;
+        denial.push(dnssec::nodata_nsec(qname, &types, ttl));
+    }
+
+    let signatures = dnssec::rrsets(&denial)
+        .into_iter()
+        .map(|rrset| key.sign(&zone, &rrset, inception, expiration))
+        .collect::<Result<Vec<_>>>()?;
+
+    packet.authorities.extend(denial);
+    packet.authorities.extend(signatures);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::database;
+    use xdns_data::models::subdomain::Type;
+
+    /// Signs an empty answer to a query, and gets the type bitmap of the `NSEC` that denies it.
+    async fn nodata_types(db: &Database, qname: &str) -> Vec<u16> {
+        let key = NodeKey::from_hex(&"01".repeat(32)).unwrap();
+        let mut packet = DnsPacket::new();
+
+        sign(db, &key, &TransferConfig::default(), qname, &mut packet)
+            .await
+            .unwrap();
+
+        packet
+            .authorities
+            .iter()
+            .find_map(|record| match record {
+                DnsRecord::NSEC { domain, types, .. } if domain == qname => Some(types.clone()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn nodata_lists_the_types_at_the_name() {
+        let db = database().await;
+        let types = |types: &[QueryType]| types.iter().map(QueryType::to_num).collect::<Vec<_>>();
+
+        assert_eq!(
+            nodata_types(&db, "www.example.o").await,
+            types(&[
+                QueryType::SUB(Type::A),
+                QueryType::SUB(Type::AAAA),
+                QueryType::RRSIG,
+                QueryType::NSEC,
+            ])
+        );
+        assert_eq!(
+            nodata_types(&db, "example.o").await,
+            types(&[
+                QueryType::SUB(Type::A),
+                QueryType::SUB(Type::NS),
+                QueryType::SOA,
+                QueryType::RRSIG,
+                QueryType::NSEC,
+                QueryType::DNSKEY,
+            ])
+        );
+        assert_eq!(
+            nodata_types(&db, "mail.example.o").await,
+            types(&[QueryType::RRSIG, QueryType::NSEC])
+        );
+    }
+
+    #[tokio::test]
+    async fn nodata_lists_the_synthesized_types() {
+        let db = database().await;
+        let types = |types: &[QueryType]| types.iter().map(QueryType::to_num).collect::<Vec<_>>();
+
+        assert_eq!(
+            nodata_types(&db, "_validity.example.o").await,
+            types(&[
+                QueryType::TXT,
+                QueryType::RRSIG,
+                QueryType::NSEC,
+                QueryType::DNSKEY,
+            ])
+        );
+        for qname in ["_inscription.www.example.o", "_inscription.example.o"] {
+            assert_eq!(
+                nodata_types(&db, qname).await,
+                types(&[QueryType::TXT, QueryType::RRSIG, QueryType::NSEC])
+            );
+        }
+        assert_eq!(
+            nodata_types(&db, "_inscription.mail.example.o").await,
+            types(&[QueryType::RRSIG, QueryType::NSEC])
+        );
+    }
+}
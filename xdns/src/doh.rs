@@ -0,0 +1,350 @@
+use std::convert::Infallible;
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use bytes::Bytes;
+use dns_utils::prelude::*;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Body, Incoming};
+use hyper::header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE};
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use rustls_pemfile::Item;
+use shared::prelude::*;
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::Database;
+
+/// The environment variable holding the address DNS over HTTPS is served on, it is not served without it. (eg `0.0.0.0:443`)
+pub const LISTEN_VAR: &str = "XDNS_DOH_LISTEN";
+/// The environment variable holding the path of the PEM certificate chain of the server.
+pub const CERT_VAR: &str = "XDNS_DOH_CERT";
+/// The environment variable holding the path of the PEM private key of the certificate.
+pub const KEY_VAR: &str = "XDNS_DOH_KEY";
+/// The path queries are sent to. (RFC 8484 section 4.1)
+const PATH: &str = "/dns-query";
+/// The media type of DNS messages. (RFC 8484 section 6)
+const DNS_MESSAGE: &str = "application/dns-message";
+
+/// Where DNS over HTTPS is served, and with which certificate.
+#[derive(Clone)]
+pub struct DohConfig {
+    /// The address the listener is bound to.
+    pub addr: SocketAddr,
+    /// The TLS configuration, negotiating HTTP/2 and falling back to HTTP/1.1.
+    pub tls: Arc<ServerConfig>,
+}
+
+impl DohConfig {
+    /// Reads the configuration from the [`LISTEN_VAR`], [`CERT_VAR`] and [`KEY_VAR`] environment variables.
+    ///
+    /// # Returns
+    ///
+    /// The configuration, none if no address is set, or an error if the address, certificate or key is invalid.
+    pub fn from_env() -> Result<Option<Self>> {
+        let addr = match env::var(LISTEN_VAR) {
+            Ok(addr) => addr.trim().parse().map_err(|_| {
+                Error::Other(format!("Invalid address in {}: {}", LISTEN_VAR, addr))
+            })?,
+            Err(_) => return Ok(None),
+        };
+        let path = |name: &str| {
+            env::var(name)
+                .map_err(|_| Error::Other(format!("{} must be set to serve DNS over HTTPS", name)))
+        };
+
+        let tls = tls_config(&path(CERT_VAR)?, &path(KEY_VAR)?)?;
+
+        Ok(Some(Self {
+            addr,
+            tls: Arc::new(tls),
+        }))
+    }
+}
+
+/// Loads the certificate chain and its private key, the first key of the file is used.
+fn tls_config(cert_path: &str, key_path: &str) -> Result<ServerConfig> {
+    let invalid =
+        |e: &dyn std::fmt::Display| Error::Other(format!("Invalid TLS configuration: {}", e));
+
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))?
+        .into_iter()
+        .map(Certificate)
+        .collect::<Vec<_>>();
+    let key = rustls_pemfile::read_all(&mut BufReader::new(File::open(key_path)?))?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| invalid(&format!("no private key in {}", key_path)))?;
+
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid(&e))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+/// Reads the DNS message of a request, from the `dns` parameter of a GET or the body of a POST.
+///
+/// # Returns
+///
+/// The message, or the status to reject the request with.
+async fn read_query<B>(request: Request<B>) -> std::result::Result<Bytes, StatusCode>
+where
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    if request.uri().path() != PATH {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    match *request.method() {
+        Method::GET => {
+            let dns = request
+                .uri()
+                .query()
+                .unwrap_or_default()
+                .split('&')
+                .find_map(|parameter| parameter.strip_prefix("dns="))
+                .ok_or(StatusCode::BAD_REQUEST)?;
+
+            // The message is encoded without padding, but padded messages are accepted too.
+            URL_SAFE_NO_PAD
+                .decode(dns.trim_end_matches('='))
+                .map(Bytes::from)
+                .map_err(|_| StatusCode::BAD_REQUEST)
+        }
+        Method::POST => {
+            let content_type = request.headers().get(CONTENT_TYPE);
+            if content_type != Some(&HeaderValue::from_static(DNS_MESSAGE)) {
+                return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            }
+
+            Limited::new(request.into_body(), TCP_BUFFER_MAX_SIZE)
+                .collect()
+                .await
+                .map(|body| body.to_bytes())
+                .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)
+        }
+        _ => Err(StatusCode::METHOD_NOT_ALLOWED),
+    }
+}
+
+/// How long a response may be cached, the lowest time to live of its records. (RFC 8484 section 5.1)
+/// Responses without records, like failures, are not cached.
+fn max_age(packet: &DnsPacket) -> u32 {
+    packet
+        .answers
+        .iter()
+        .chain(&packet.authorities)
+        .filter(|record| !matches!(record, DnsRecord::OPT { .. }))
+        .map(DnsRecord::get_ttl)
+        .min()
+        .unwrap_or(0)
+}
+
+/// Answers a single request, with the same pipeline as queries over UDP.
+async fn handle(db: &Database, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    let status = |status: StatusCode| {
+        let mut response = Response::new(Full::default());
+        *response.status_mut() = status;
+        response
+    };
+
+    let query = match read_query(request).await {
+        Ok(query) if query.len() <= TCP_BUFFER_MAX_SIZE => query,
+        Ok(_) => return status(StatusCode::PAYLOAD_TOO_LARGE),
+        Err(code) => return status(code),
+    };
+
+    let mut buffer = BytePacketBuffer::with_size(query.len());
+    buffer.buf.copy_from_slice(&query);
+    let request = match DnsPacket::from_buffer(&mut buffer) {
+        Ok(request) => request,
+        Err(_) => return status(StatusCode::BAD_REQUEST),
+    };
+
+    let mut packet = crate::respond(db, request).await;
+
+    let mut buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+    if let Err(e) = packet.write(&mut buffer) {
+        eprintln!("Failed to write the response over HTTPS: {}", e);
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let body = Bytes::copy_from_slice(&buffer.buf[0..buffer.pos()]);
+    let mut response = Response::new(Full::new(body));
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(DNS_MESSAGE));
+    let cache_control = format!("max-age={}", max_age(&packet));
+    headers.insert(
+        CACHE_CONTROL,
+        HeaderValue::from_str(&cache_control).expect("A max age is a valid header value"),
+    );
+
+    response
+}
+
+/// Serves DNS over HTTPS (RFC 8484), over HTTP/2 or HTTP/1.1 as negotiated with ALPN.
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the .o domains.
+/// * `config` - The certificate of the server.
+/// * `listener` - The bound TCP listener.
+pub async fn serve(db: Arc<Database>, config: DohConfig, listener: TcpListener) {
+    let acceptor = TlsAcceptor::from(config.tls);
+
+    loop {
+        let (stream, src) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Failed to accept an HTTPS connection: {}", e);
+                continue;
+            }
+        };
+
+        let db = db.clone();
+        let acceptor = acceptor.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("TLS handshake with {} failed: {}", src, e);
+                    return;
+                }
+            };
+
+            let service = service_fn(move |request| {
+                let db = db.clone();
+                async move { Ok::<_, Infallible>(handle(&db, request).await) }
+            });
+
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                eprintln!("HTTPS connection with {} failed: {}", src, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xdns_data::prelude::Type;
+
+    fn query() -> Bytes {
+        let mut packet = DnsPacket::new();
+        packet.header.id = 1;
+        packet.questions.push(DnsQuestion::new(
+            "example.o".to_string(),
+            QueryType::SUB(Type::A),
+        ));
+
+        let mut buffer = BytePacketBuffer::new();
+        packet.write(&mut buffer).unwrap();
+        Bytes::copy_from_slice(&buffer.buf[0..buffer.pos()])
+    }
+
+    fn get(uri: &str) -> Request<Full<Bytes>> {
+        Request::get(uri).body(Full::default()).unwrap()
+    }
+
+    fn post(content_type: &str, body: Bytes) -> Request<Full<Bytes>> {
+        Request::post(PATH)
+            .header(CONTENT_TYPE, content_type)
+            .body(Full::new(body))
+            .unwrap()
+    }
+
+    fn a(ttl: u32) -> DnsRecord {
+        DnsRecord::A {
+            domain: "example.o".to_string(),
+            addr: "10.0.0.1".parse().unwrap(),
+            ttl,
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_get_queries() {
+        let dns = URL_SAFE_NO_PAD.encode(query());
+
+        for uri in [
+            format!("{}?dns={}", PATH, dns),
+            format!("{}?ct=application/dns-message&dns={}", PATH, dns),
+            // Padding is accepted too.
+            format!("{}?dns={}==", PATH, dns),
+        ] {
+            assert_eq!(read_query(get(&uri)).await, Ok(query()));
+        }
+
+        assert_eq!(read_query(get(PATH)).await, Err(StatusCode::BAD_REQUEST));
+        assert_eq!(
+            read_query(get(&format!("{}?dns=not+base64", PATH))).await,
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            read_query(get(&format!("/other?dns={}", dns))).await,
+            Err(StatusCode::NOT_FOUND)
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_post_queries() {
+        assert_eq!(read_query(post(DNS_MESSAGE, query())).await, Ok(query()));
+    }
+
+    #[tokio::test]
+    async fn limits_the_body_size() {
+        let body = Bytes::from(vec![0; TCP_BUFFER_MAX_SIZE + 1]);
+
+        assert_eq!(
+            read_query(post(DNS_MESSAGE, body)).await,
+            Err(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_other_content() {
+        assert_eq!(
+            read_query(post("application/json", query())).await,
+            Err(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+        );
+
+        let request = Request::put(PATH).body(Full::new(query())).unwrap();
+        assert_eq!(
+            read_query(request).await,
+            Err(StatusCode::METHOD_NOT_ALLOWED)
+        );
+    }
+
+    #[test]
+    fn max_age_is_the_lowest_ttl() {
+        let mut packet = DnsPacket::new();
+        assert_eq!(max_age(&packet), 0);
+
+        packet.answers = vec![a(300), a(60)];
+        packet.authorities = vec![a(120)];
+        packet.resources = vec![DnsRecord::OPT {
+            payload_size: 4096,
+            flags: 0,
+        }];
+        assert_eq!(max_age(&packet), 60);
+    }
+}
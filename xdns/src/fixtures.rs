@@ -0,0 +1,59 @@
+//! The fixtures shared by the tests of the server, not every test uses all of them.
+#![allow(dead_code)]
+
+use std::time::SystemTime;
+
+use db::{Repository, XDNSRepository};
+use xdns_data::models::subdomain::{Class, RData};
+use xdns_data::models::{Algorithm, Credentials, Domain, SubDomain, Validity};
+
+use crate::Database;
+
+pub const ADDRESS: &str = "tb1pm3q4drt7suvdsfndz5uyge652xswl09nvshr7k00964xtchmnemqyuuvd5";
+/// The Ed25519 key of the validity credentials of `example.o`.
+pub const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+/// A record of `example.o`, with a ttl of a minute.
+pub fn record(subdomain: &str, rdata: RData) -> SubDomain {
+    SubDomain {
+        domain: "example.o".to_string(),
+        subdomain: subdomain.to_string(),
+        class: Class::IN,
+        ttl: 60,
+        rdata,
+    }
+}
+
+/// A repository holding `example.o` with its validity credentials, inscribed as `0i0` and `1i0`, and these records:
+///
+/// * `www.example.o` - `A 10.0.0.1` (`2i0`) and `AAAA 2001:db8::1` (`3i0`)
+/// * `example.o` - `A 10.0.0.2` (`4i0`) and `NS ns1.example.o` (`5i0`)
+pub async fn database() -> Database {
+    let db = Database::wrap(Repository::new_memory().await.unwrap());
+    let domain = Domain {
+        name: "example.o".to_string(),
+        valid_from: SystemTime::now(),
+    };
+    let validity = Validity {
+        domain: "example.o".to_string(),
+        credentials: Credentials::new(Algorithm::Ed25519, PUBLIC_KEY.to_string()),
+    };
+    assert!(db.add_domain(ADDRESS, "0i0", domain).await);
+    assert!(db.add_validity(ADDRESS, "1i0", validity).await);
+
+    let records = [
+        ("www.", RData::A("10.0.0.1".parse().unwrap())),
+        ("www.", RData::AAAA("2001:db8::1".parse().unwrap())),
+        ("@.", RData::A("10.0.0.2".parse().unwrap())),
+        ("@.", RData::NS("ns1.example.o".to_string())),
+    ];
+    for (i, (subdomain, rdata)) in records.into_iter().enumerate() {
+        let inscription = format!("{}i0", i + 2);
+        assert!(
+            db.add_subdomain(ADDRESS, &inscription, record(subdomain, rdata))
+                .await
+        );
+    }
+
+    db
+}
@@ -1,14 +1,25 @@
+mod dnssec;
+mod doh;
+#[cfg(test)]
+mod fixtures;
+mod ownership;
+mod transfer;
 mod utils;
 
 extern crate db;
 extern crate dns_utils;
 extern crate shared;
 
+use crate::transfer::TransferConfig;
 use crate::utils::subdomain_cast::SubDomainCast;
 use crate::utils::ExpiringMultiValueHashMap;
 use async_recursion::async_recursion;
-use db::{Repository, XDNSRepository};
+use db::{
+    CachedRepository, Change, ChangeFeed, ChangeReceiver, HistoryFollower, Repository,
+    XDNSRepository,
+};
 use dns_utils::prelude::*;
+use dns_utils::validator::{Security, UdpResolver, Validator};
 use lazy_static::lazy_static;
 use rayon::prelude::*;
 use shared::prelude::*;
@@ -18,19 +29,55 @@ use std::io::Read;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::net::UdpSocket;
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::Mutex;
+use xdns_data::models::SubDomainMatch;
 use xdns_data::prelude::Type;
 
 const SERVER: (&str, u16) = ("1.1.1.1", 53);
 const PORT: u16 = 53;
+/// The largest UDP response that is sent, however large the client says it can receive.
+const MAX_UDP_PAYLOAD_SIZE: u16 = 4096;
+#[cfg(not(test))]
 const BLACKLIST_FILE: &str = "blacklist.txt";
+/// Tests run from the directory of the crate, the blacklist is at the root of the repository.
+#[cfg(test)]
+const BLACKLIST_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../blacklist.txt");
+/// How often released domains are removed from the database.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+/// How often the history is checked for changes, eg of the indexer.
+const CHANGES_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The database connection of the server, shared by every query.
+type Database = CachedRepository<Repository>;
+
+/// A cached answer, with whether it was validated as [secure](Security::Secure).
+#[derive(Debug, Clone)]
+struct CachedAnswer {
+    record: DnsRecord,
+    secure: bool,
+}
 
 lazy_static! {
-    static ref CACHE: Mutex<ExpiringMultiValueHashMap<String, DnsRecord>> =
+    /// The answers sent to clients, evicted together with the lookups of the [`Database`] by [`evict_changes`].
+    static ref CACHE: Mutex<ExpiringMultiValueHashMap<String, CachedAnswer>> =
         Mutex::new(ExpiringMultiValueHashMap::new());
+    /// The changes of every process using the database, as followed from its history.
+    static ref CHANGES: ChangeFeed = ChangeFeed::new();
     static ref BLACKLIST: HashSet<String> =
         read_blacklisted_domains().expect("Failed to read blacklist file, make sure it exists");
+    /// Who may transfer the .o zones, and which secondary servers are notified of changes.
+    static ref TRANSFERS: TransferConfig =
+        TransferConfig::from_env().expect("Failed to read the zone transfer configuration");
+    /// Signs the answers of the .o tree, when configured.
+    static ref NODE_KEY: Option<NodeKey> =
+        dnssec::node_key_from_env().expect("Failed to read the DNSSEC node key");
+    /// Validates the answers forwarded to SERVER, when configured.
+    static ref VALIDATOR: Option<Validator<UdpResolver>> = dnssec::validator_from_env(
+        SocketAddr::new(SERVER.0.parse().expect("Invalid SERVER address"), SERVER.1)
+    )
+    .expect("Failed to read the DNSSEC trust anchors");
 }
 
 fn read_blacklisted_domains() -> Result<HashSet<String>> {
@@ -48,12 +95,125 @@ fn read_blacklisted_domains() -> Result<HashSet<String>> {
     Ok(domains)
 }
 
+/// Whether a change to the database may change the cached records of a name.
+fn is_affected(change: &Change, name: &str) -> bool {
+    let below = |owner: &str| name == owner || name.ends_with(&format!(".{}", owner));
+
+    match change.subdomain.as_deref() {
+        // Wildcards answer for any name below the domain that has no records of its own.
+        None => below(&change.domain),
+        Some(subdomain) if subdomain.starts_with("*.") => below(&change.domain),
+        Some("@.") => name == change.domain,
+        Some(subdomain) => below(&format!("{}{}", subdomain, change.domain)),
+    }
+}
+
+/// Publishes the changes recorded in the history to CHANGES, including those of the indexer.
+async fn follow_changes(db: Arc<Database>) {
+    let mut follower = loop {
+        match HistoryFollower::new(db.as_ref(), CHANGES.clone()).await {
+            Ok(follower) => break follower,
+            Err(e) => eprintln!("Failed to follow the history: {}", e),
+        }
+
+        tokio::time::sleep(CHANGES_INTERVAL).await;
+    };
+    let mut interval = tokio::time::interval(CHANGES_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = follower.poll(db.as_ref()).await {
+            eprintln!("Failed to read the history: {}", e);
+        }
+    }
+}
+
+/// Evicts the cached records and lookups of .o names as soon as they change in the database.
+async fn evict_changes(db: Arc<Database>, mut changes: ChangeReceiver) {
+    loop {
+        match changes.recv().await {
+            Ok(change) => {
+                db.invalidate(&change.domain);
+                let evicted = CACHE
+                    .lock()
+                    .await
+                    .remove_where(|name| is_affected(&change, name));
+
+                if evicted > 0 {
+                    println!(
+                        "Evicted {} cache entries after a change to {:?}",
+                        evicted, change.domain
+                    );
+                }
+            }
+            Err(RecvError::Lagged(missed)) => {
+                println!("Missed {} changes, clearing the cache", missed);
+                db.clear();
+                CACHE.lock().await.clear();
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
 fn get_blacklist() -> &'static HashSet<String> {
     &BLACKLIST
 }
 
+/// Caches the answers of a response, until their ttl runs out.
+///
+/// # Arguments
+///
+/// * `answers` - The answers, signatures included.
+/// * `secure` - Whether the answers were validated, so cache hits are answered with the AD bit.
+async fn cache_answers(answers: &[DnsRecord], secure: bool) {
+    let mut cache = CACHE.lock().await;
+
+    for answer in answers {
+        cache.insert(
+            answer.get_domain().to_string(),
+            CachedAnswer {
+                record: answer.clone(),
+                secure,
+            },
+            Duration::from_secs(answer.get_ttl() as u64),
+        );
+    }
+}
+
+/// Gets the cached answers of a name, with the ttl they have left.
+///
+/// # Returns
+///
+/// The answers and whether every one of them was validated, or none if nothing is cached.
+async fn cached_answers(qname: &str) -> Option<(Vec<DnsRecord>, bool)> {
+    let cached = CACHE.lock().await.get(qname)?;
+    let now = Instant::now();
+    let secure = cached.iter().all(|(answer, _)| answer.secure);
+
+    let records = cached
+        .into_iter()
+        .map(|(answer, ttl)| {
+            let mut record = answer.record;
+            let passed = ttl
+                .checked_duration_since(now)
+                .unwrap_or(Duration::from_secs(0));
+            record.set_ttl(passed.as_secs() as u32);
+            record
+        })
+        .collect();
+
+    Some((records, secure))
+}
+
 #[async_recursion]
-async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Result<DnsPacket> {
+async fn lookup(
+    db: &Database,
+    qname: &str,
+    qtype: QueryType,
+    packet: Option<DnsPacket>,
+) -> Result<DnsPacket> {
     println!("Looking up {:?} {:?}", qname, qtype);
 
     let mut packet = match packet {
@@ -78,23 +238,42 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
         return Ok(packet.make_returnable());
     }
 
-    let cache = CACHE.lock().await;
-    if cache.contains_key(qname) {
+    // The keys of the .o zones are not records in the repository, they are the node key.
+    if let Some(key) = NODE_KEY.as_ref() {
+        if let Some(answers) = dnssec::keys(db, key, &TRANSFERS, qname, qtype).await? {
+            packet.answers.extend(answers);
+            return Ok(packet.make_returnable());
+        }
+    }
+
+    // The credentials and inscriptions behind the records of the .o tree, so clients can check them.
+    match ownership::answer(db, qname, qtype).await {
+        Ok(Some(answers)) => {
+            packet.answers.extend(answers);
+            return Ok(packet.make_returnable());
+        }
+        Ok(None) => {}
+        Err(e) if e.is_not_found() => {
+            packet.header.rescode = ResultCode::NXDOMAIN;
+            return Ok(packet.make_returnable());
+        }
+        Err(e) => return Err(e),
+    }
+
+    // Secondary servers poll the SOA of a zone to know when to transfer it.
+    if qtype == QueryType::SOA && transfer::is_zone(qname) {
+        match transfer::soa(db, &TRANSFERS, qname).await {
+            Ok(soa) => packet.answers.push(soa),
+            Err(e) if e.is_not_found() => packet.header.rescode = ResultCode::NXDOMAIN,
+            Err(e) => return Err(e),
+        }
+
+        return Ok(packet.make_returnable());
+    }
+
+    if let Some((cached_records, secure)) = cached_answers(qname).await {
         println!("Cache hit for {:?}", qname);
-        let now = Instant::now();
-        let cached_records: Vec<DnsRecord> = cache
-            .get(qname)
-            .unwrap()
-            .iter()
-            .map(|(record, ttl)| {
-                let mut record = record.clone();
-                let passed = ttl
-                    .checked_duration_since(now)
-                    .unwrap_or(Duration::from_secs(0));
-                record.set_ttl(passed.as_secs() as u32);
-                record
-            })
-            .collect();
+        packet.header.authed_data = secure;
 
         packet
             .answers
@@ -112,8 +291,6 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
             .map(|record| record.get_domain())
             .collect();
 
-        drop(cache);
-
         if qtype != QueryType::SUB(Type::CNAME) {
             for record in cached_records.iter() {
                 if record.type_of() == QueryType::SUB(Type::CNAME) {
@@ -127,10 +304,16 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
                         qtype,
                     )];
                     tmp_packet.answers = Vec::new();
-                    let res =
-                        lookup(record.get_host().unwrap(), qtype, Some(tmp_packet.clone())).await;
+                    let res = lookup(
+                        db,
+                        record.get_host().unwrap(),
+                        qtype,
+                        Some(tmp_packet.clone()),
+                    )
+                    .await;
 
                     if let Ok(res) = res {
+                        packet.header.authed_data &= res.header.authed_data;
                         packet.answers.extend(res.answers);
                     }
                 }
@@ -139,10 +322,8 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
 
         return Ok(packet.make_returnable());
     }
-    drop(cache);
 
     if qname.ends_with(".o") {
-        let db = Repository::new().await;
         let segments = qname.split(".").collect::<Vec<&str>>();
         let domain = segments[segments.len() - 2..].join(".");
         let mut subdomain = segments[..segments.len() - 2].join(".") + ".";
@@ -151,10 +332,34 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
             subdomain = "@.".to_string();
         }
 
-        let subdomains = match db.get_subdomain(&domain, &subdomain).await {
-            Ok(subdomains) => subdomains,
-            Err(e) => {
-                println!("{:?}", e);
+        // A missing or expired domain is a definitive answer, any other error
+        // (eg the database being unreachable) is propagated and results in SERVFAIL.
+        let owners = match db.get_subdomain_names(&domain).await {
+            Ok(owners) => owners,
+            Err(e) if e.is_not_found() => {
+                println!("{}", e);
+                packet.header.rescode = ResultCode::NXDOMAIN;
+                return Ok(packet);
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Wildcards are resolved as described in RFC 4592, synthesized records
+        // take the queried name as their owner name.
+        let subdomains = match SubDomainMatch::find(&subdomain, &owners) {
+            SubDomainMatch::Exact(owner) => db.get_subdomain(&domain, &owner).await?,
+            SubDomainMatch::Wildcard(source) => db
+                .get_subdomain(&domain, &source)
+                .await?
+                .into_iter()
+                .map(|(address, mut record)| {
+                    record.subdomain = subdomain.clone();
+                    (address, record)
+                })
+                .collect(),
+            SubDomainMatch::NoData => Vec::new(),
+            SubDomainMatch::NxDomain => {
+                packet.header.rescode = ResultCode::NXDOMAIN;
                 return Ok(packet);
             }
         };
@@ -162,19 +367,11 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
         if subdomains.len() > 0 {
             let answers: Vec<DnsRecord> = subdomains
                 .into_iter()
-                .map(|s| SubDomainCast::from(s.1))
-                .map(|s| s.try_into())
-                .collect::<Result<Vec<DnsRecord>>>()?;
+                .map(|s| SubDomainCast::from(s.1).into())
+                .collect();
 
             packet.answers = answers;
-
-            for answer in packet.answers.iter() {
-                CACHE.lock().await.insert(
-                    answer.get_domain().to_string(),
-                    answer.clone(),
-                    Duration::from_secs(answer.get_ttl() as u64),
-                );
-            }
+            cache_answers(&packet.answers, false).await;
 
             if qtype != QueryType::SUB(Type::CNAME) {
                 let mut cname_resolves = Vec::new();
@@ -188,7 +385,8 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
                         )];
                         packet.answers = Vec::new();
                         let res =
-                            lookup(record.get_host().unwrap(), qtype, Some(packet.clone())).await;
+                            lookup(db, record.get_host().unwrap(), qtype, Some(packet.clone()))
+                                .await;
 
                         if let Ok(res) = res {
                             cname_resolves.push(res);
@@ -205,6 +403,26 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
             return Ok(packet);
         }
 
+        Ok(packet)
+    } else if let Some(validator) = VALIDATOR.as_ref() {
+        let (res_packet, security) = validator.resolve(qname, qtype).await?;
+        match security {
+            Security::Secure => packet.header.authed_data = true,
+            Security::Insecure => {}
+            Security::Bogus(reason) => {
+                eprintln!("Bogus answer for {:?} {:?}: {}", qname, qtype, reason);
+                packet.header.rescode = ResultCode::SERVFAIL;
+                return Ok(packet.make_returnable());
+            }
+        }
+
+        // The signatures are kept for clients that set the DO bit, the others never receive them.
+        packet.header.rescode = res_packet.header.rescode;
+        packet.answers.extend(res_packet.answers);
+
+        let packet = packet.make_returnable();
+        cache_answers(&packet.answers, security == Security::Secure).await;
+
         Ok(packet)
     } else {
         let mut req_buffer = BytePacketBuffer::new();
@@ -222,28 +440,23 @@ async fn lookup(qname: &str, qtype: QueryType, packet: Option<DnsPacket>) -> Res
         packet.answers.extend(res_packet.answers.clone());
 
         let packet = packet.make_returnable();
-
-        for answer in packet.answers.iter() {
-            CACHE.lock().await.insert(
-                answer.get_domain().to_string(),
-                answer.clone(),
-                Duration::from_secs(answer.get_ttl() as u64),
-            );
-        }
+        cache_answers(&packet.answers, false).await;
 
         Ok(packet)
     }
 }
 
-async fn handle_request(
-    socket: Arc<UdpSocket>,
-    mut req_buffer: BytePacketBuffer,
-    src: SocketAddr,
-) -> Result<()> {
-    // Next, `DnsPacket::from_buffer` is used to parse the raw bytes into
-    // a `DnsPacket`.
-    let mut request = DnsPacket::from_buffer(&mut req_buffer)?;
-
+/// Answers a query, whatever transport it was received over.
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the .o domains.
+/// * `request` - The query.
+///
+/// # Returns
+///
+/// The response, unbounded in size, with an `OPT` record for clients that support EDNS.
+pub async fn respond(db: &Database, mut request: DnsPacket) -> DnsPacket {
     // Create and initialize the response packet
     let mut packet = DnsPacket::new();
     packet.header.id = request.header.id;
@@ -260,11 +473,17 @@ async fn handle_request(
         // fail, in which case the `SERVFAIL` response code is set to indicate
         // as much to the client. If rather everything goes as planned, the
         // question and response records as copied into our response packet.
-        if let Ok(result) = lookup(&question.name, question.qtype, None).await {
+        if let Ok(result) = lookup(db, &question.name, question.qtype, None).await {
             packet.questions.push(question);
             packet.header.rescode = result.header.rescode;
+            packet.header.authed_data = result.header.authed_data;
 
-            for rec in result.answers {
+            // Signatures are only sent to clients that asked for them.
+            for rec in result
+                .answers
+                .into_iter()
+                .filter(|rec| request.dnssec_ok() || rec.type_of() != QueryType::RRSIG)
+            {
                 println!("Answer: {:?}", rec);
                 packet.answers.push(rec);
             }
@@ -287,8 +506,60 @@ async fn handle_request(
         packet.header.rescode = ResultCode::FORMERR;
     }
 
-    let mut res_buffer = BytePacketBuffer::new();
-    packet.write(&mut res_buffer)?;
+    // Answers of the .o tree are signed for clients that set the DO bit.
+    if let (Some(key), Some(question)) = (NODE_KEY.as_ref(), packet.questions.first()) {
+        if request.dnssec_ok()
+            && dnssec::is_signed(&question.name)
+            && packet.header.rescode != ResultCode::SERVFAIL
+        {
+            let qname = question.name.clone();
+            if let Err(e) = dnssec::sign(db, key, &TRANSFERS, &qname, &mut packet).await {
+                eprintln!("Failed to sign the answer for {:?}: {}", qname, e);
+                packet.header.rescode = ResultCode::SERVFAIL;
+                packet.answers.clear();
+                packet.authorities.clear();
+            }
+        }
+    }
+
+    // Clients that support EDNS are told so, and can receive larger responses.
+    if request.edns().is_some() {
+        packet.resources.push(DnsRecord::OPT {
+            payload_size: MAX_UDP_PAYLOAD_SIZE,
+            flags: if request.dnssec_ok() { EDNS_DO_FLAG } else { 0 },
+        });
+    }
+
+    packet
+}
+
+async fn handle_request(
+    db: Arc<Database>,
+    socket: Arc<UdpSocket>,
+    mut req_buffer: BytePacketBuffer,
+    src: SocketAddr,
+) -> Result<()> {
+    // Next, `DnsPacket::from_buffer` is used to parse the raw bytes into
+    // a `DnsPacket`.
+    let request = DnsPacket::from_buffer(&mut req_buffer)?;
+    let size = request
+        .max_payload_size()
+        .min(MAX_UDP_PAYLOAD_SIZE as usize);
+    let mut packet = respond(&db, request).await;
+
+    let mut res_buffer = BytePacketBuffer::with_size(size);
+    if packet.write(&mut res_buffer).is_err() {
+        // The response does not fit, only the header and question are sent.
+        packet.header.truncated_message = true;
+        packet.answers.clear();
+        packet.authorities.clear();
+        packet
+            .resources
+            .retain(|record| matches!(record, DnsRecord::OPT { .. }));
+
+        res_buffer = BytePacketBuffer::with_size(size);
+        packet.write(&mut res_buffer)?;
+    }
 
     let len = res_buffer.pos();
     let data = res_buffer.get_range(0, len)?;
@@ -299,7 +570,7 @@ async fn handle_request(
 }
 
 /// Handle a single incoming packet
-async fn handle_query(socket: Arc<UdpSocket>) -> Result<()> {
+async fn handle_query(db: Arc<Database>, socket: Arc<UdpSocket>) -> Result<()> {
     // With a socket ready, we can go ahead and read a packet. This will
     // block until one is received.
     let mut req_buffer = BytePacketBuffer::new();
@@ -311,7 +582,7 @@ async fn handle_query(socket: Arc<UdpSocket>) -> Result<()> {
     let (_, src) = socket.recv_from(&mut req_buffer.buf).await?;
 
     tokio::spawn(async move {
-        match handle_request(socket, req_buffer, src).await {
+        match handle_request(db, socket, req_buffer, src).await {
             Ok(_) => {}
             Err(e) => eprintln!("An error occurred: {}", e),
         }
@@ -323,9 +594,22 @@ async fn handle_query(socket: Arc<UdpSocket>) -> Result<()> {
 #[tokio::main]
 async fn main() -> Result<()> {
     get_blacklist();
+    let db = Arc::new(Database::wrap(Repository::new().await?));
     let socket = Arc::new(UdpSocket::bind(("127.0.0.1", PORT)).await?);
+    // Zone transfers do not fit in UDP messages, they are served over TCP with the answers that were truncated.
+    let listener = TcpListener::bind(("127.0.0.1", PORT)).await?;
     println!("XDNS listening on port {}", PORT);
 
+    tokio::spawn(transfer::serve(db.clone(), &TRANSFERS, listener));
+    tokio::spawn(transfer::notify_changes(&TRANSFERS, CHANGES.subscribe()));
+
+    // Browsers and mobile clients only resolve over HTTPS, which is served once it is configured.
+    if let Some(config) = doh::DohConfig::from_env()? {
+        let listener = TcpListener::bind(config.addr).await?;
+        println!("XDNS serving DNS over HTTPS on {}", config.addr);
+        tokio::spawn(doh::serve(db.clone(), config, listener));
+    }
+
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(1));
         loop {
@@ -345,11 +629,131 @@ async fn main() -> Result<()> {
         }
     });
 
+    tokio::spawn(follow_changes(db.clone()));
+    tokio::spawn(evict_changes(db.clone(), CHANGES.subscribe()));
+
+    let sweeper = db.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            match sweeper.sweep_expired().await {
+                Ok(names) if !names.is_empty() => {
+                    println!("Expiry sweep removed {} domains", names.len())
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Expiry sweep failed: {}", e),
+            }
+        }
+    });
+
     loop {
         let socket = socket.clone();
-        match handle_query(socket).await {
+        match handle_query(db.clone(), socket).await {
             Ok(_) => {}
             Err(e) => eprintln!("An error occurred: {}", e),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::ChangeKind;
+
+    fn change(subdomain: Option<&str>) -> Change {
+        Change {
+            domain: "example.o".to_string(),
+            subdomain: subdomain.map(str::to_string),
+            kind: ChangeKind::Updated,
+        }
+    }
+
+    fn a(domain: &str) -> DnsRecord {
+        DnsRecord::A {
+            domain: domain.to_string(),
+            addr: "93.184.216.34".parse().unwrap(),
+            ttl: 60,
+        }
+    }
+
+    fn rrsig(domain: &str) -> DnsRecord {
+        DnsRecord::RRSIG {
+            domain: domain.to_string(),
+            type_covered: QueryType::SUB(Type::A).to_num(),
+            algorithm: 15,
+            labels: 2,
+            original_ttl: 60,
+            expiration: u32::MAX,
+            inception: 0,
+            key_tag: 1,
+            signer: "example.com".to_string(),
+            signature: vec![1; 64],
+            ttl: 60,
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_hits_keep_the_validation_state() {
+        cache_answers(
+            &[a("secure.example.com"), rrsig("secure.example.com")],
+            true,
+        )
+        .await;
+        let (records, secure) = cached_answers("secure.example.com").await.unwrap();
+
+        assert!(secure);
+        assert_eq!(records.len(), 2);
+        assert!(records
+            .iter()
+            .any(|record| record.type_of() == QueryType::RRSIG));
+
+        // A single answer that was not validated makes the whole name insecure.
+        cache_answers(&[a("mixed.example.com")], true).await;
+        cache_answers(&[a("mixed.example.com")], false).await;
+        assert!(!cached_answers("mixed.example.com").await.unwrap().1);
+
+        assert!(cached_answers("missing.example.com").await.is_none());
+    }
+
+    #[test]
+    fn domain_change_affects_every_name_below() {
+        let change = change(None);
+
+        assert!(is_affected(&change, "example.o"));
+        assert!(is_affected(&change, "www.example.o"));
+        assert!(is_affected(&change, "a.b.example.o"));
+        assert!(!is_affected(&change, "otherexample.o"));
+        assert!(!is_affected(&change, "other.o"));
+    }
+
+    #[test]
+    fn subdomain_change_affects_its_name() {
+        let change = change(Some("www."));
+
+        assert!(is_affected(&change, "www.example.o"));
+        assert!(is_affected(&change, "a.www.example.o"));
+        assert!(!is_affected(&change, "example.o"));
+        assert!(!is_affected(&change, "mail.example.o"));
+        assert!(!is_affected(&change, "awww.example.o"));
+    }
+
+    #[test]
+    fn apex_change_affects_the_domain() {
+        let change = change(Some("@."));
+
+        assert!(is_affected(&change, "example.o"));
+        assert!(!is_affected(&change, "www.example.o"));
+    }
+
+    #[test]
+    fn wildcard_change_affects_every_name_below() {
+        let change = change(Some("*.dev."));
+
+        assert!(is_affected(&change, "dev.example.o"));
+        assert!(is_affected(&change, "api.dev.example.o"));
+        assert!(is_affected(&change, "www.example.o"));
+        assert!(!is_affected(&change, "other.o"));
+    }
+}
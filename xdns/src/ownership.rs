@@ -0,0 +1,290 @@
+use db::XDNSRepository;
+use dns_utils::dnssec::{ALGORITHM_ED25519, DNSKEY_PROTOCOL};
+use dns_utils::prelude::*;
+use shared::prelude::*;
+use xdns_data::models::{Algorithm, Validity};
+
+use crate::transfer;
+use crate::Database;
+
+/// The label below a domain holding its validity credentials. (eg `_validity.example.o`)
+pub const VALIDITY_LABEL: &str = "_validity";
+/// The label above a name holding the inscription ids of its records. (eg `_inscription.www.example.o`)
+pub const INSCRIPTION_LABEL: &str = "_inscription";
+/// The version tag every published text starts with.
+const VERSION: &str = "v=xdns1";
+/// The time to live of the validity credentials.
+const VALIDITY_TTL: u32 = 3600;
+/// The private algorithm of `DNSKEY` records identified by a domain name. (RFC 4034 appendix A.1.1)
+const ALGORITHM_PRIVATEDNS: u8 = 253;
+/// Names the algorithms without a DNSSEC number in `PRIVATEDNS` keys. (eg `dilithium2.xdns.o`)
+const PRIVATE_ALGORITHM_ZONE: &str = "xdns.o";
+
+/// Splits a .o name into its domain and subdomain, as stored in the repository. (eg `example.o` and `www.`)
+fn split(name: &str) -> (String, String) {
+    let labels = name.split('.').collect::<Vec<_>>();
+    let at = labels.len().saturating_sub(2);
+
+    let subdomain = match labels[..at].join(".") {
+        subdomain if subdomain.is_empty() => "@.".to_string(),
+        subdomain => format!("{}.", subdomain),
+    };
+
+    (labels[at..].join("."), subdomain)
+}
+
+/// Whether records are stored under a name, which then takes precedence over the well-known answers.
+///
+/// # Returns
+///
+/// Whether the name has records, or a not found error if its domain does not exist.
+async fn is_stored(db: &Database, name: &str) -> Result<bool> {
+    let (domain, subdomain) = split(name);
+
+    Ok(db.get_subdomain_names(&domain).await?.contains(&subdomain))
+}
+
+/// Creates a `TXT` record, splitting the text into as many character strings as it needs.
+/// Clients concatenate the strings to read the text back.
+fn txt(domain: &str, text: &str, ttl: u32) -> DnsRecord {
+    let mut data = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let mut end = rest.len().min(MAX_CHARACTER_STRING);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let (string, tail) = rest.split_at(end);
+        data.push(string.to_string());
+        rest = tail;
+    }
+
+    DnsRecord::TXT {
+        domain: domain.to_string(),
+        data,
+        ttl,
+    }
+}
+
+/// Publishes the credentials of a domain as a `DNSKEY` record that is not a zone key, so it is
+/// never used to validate DNSSEC signatures. Ed25519 keys use their DNSSEC algorithm, the others
+/// are `PRIVATEDNS` keys prefixed with the name of their algorithm.
+fn validity_dnskey(owner: &str, validity: &Validity) -> Result<DnsRecord> {
+    let credentials = &validity.credentials;
+    let key = hex::decode(&credentials.public_key)
+        .map_err(|e| CryptoError::InvalidEncoding(e.to_string()))?;
+
+    let (algorithm, public_key) = match credentials.algorithm {
+        Algorithm::Ed25519 => (ALGORITHM_ED25519, key),
+        algorithm => {
+            let mut buffer = BytePacketBuffer::new();
+            buffer.write_qname(&format!("{}.{}", algorithm.name(), PRIVATE_ALGORITHM_ZONE))?;
+            let name = buffer.buf[0..buffer.pos()].to_vec();
+
+            (ALGORITHM_PRIVATEDNS, [name, key].concat())
+        }
+    };
+
+    Ok(DnsRecord::DNSKEY {
+        domain: owner.to_string(),
+        flags: 0,
+        protocol: DNSKEY_PROTOCOL,
+        algorithm,
+        public_key,
+        ttl: VALIDITY_TTL,
+    })
+}
+
+/// Answers the queries for the well-known names exposing who may sign the inscriptions of a
+/// domain, and which inscription each record comes from, so clients can check an answer against
+/// the inscriptions themselves:
+///
+/// * `_validity.example.o TXT` - `v=xdns1; a=<algorithm>; o=<owner address>; k=<hex public key>`
+/// * `_validity.example.o DNSKEY` - The same public key, see [`validity_dnskey`].
+/// * `_inscription.www.example.o TXT` - `v=xdns1; t=<type>; i=<inscription id>; d=<record data>`,
+///   one record for every record of `www.example.o`, in the order they are served in.
+///
+/// Only the records of the name itself are listed, not those a wildcard synthesizes for it.
+///
+/// Records inscribed under a well-known name (eg `_validity.example.o A`) take precedence,
+/// the name is then answered from the repository like any other, without the synthesized answers.
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the domains.
+/// * `qname` - The queried name.
+/// * `qtype` - The queried type.
+///
+/// # Returns
+///
+/// The answers, empty if the name has none of the type, or none if the name is not well-known.
+/// A missing domain is a not found error.
+pub async fn answer(
+    db: &Database,
+    qname: &str,
+    qtype: QueryType,
+) -> Result<Option<Vec<DnsRecord>>> {
+    let (label, name) = match qname.split_once('.') {
+        Some((label, name)) if name.ends_with(".o") => (label, name),
+        _ => return Ok(None),
+    };
+
+    match label {
+        VALIDITY_LABEL if transfer::is_zone(name) => {
+            if is_stored(db, qname).await? {
+                return Ok(None);
+            }
+
+            // The domain itself must exist, a domain without credentials has no answers.
+            let address = db.get_domain_address(name).await?;
+            let validity = match db.get_validity(name).await {
+                Ok((_, validity)) => validity,
+                Err(e) if e.is_not_found() => return Ok(Some(Vec::new())),
+                Err(e) => return Err(e),
+            };
+
+            Ok(Some(match qtype {
+                QueryType::TXT => {
+                    let credentials = &validity.credentials;
+                    let text = format!(
+                        "{}; a={}; o={}; k={}",
+                        VERSION,
+                        credentials.algorithm.name(),
+                        address,
+                        credentials.public_key
+                    );
+
+                    vec![txt(qname, &text, VALIDITY_TTL)]
+                }
+                QueryType::DNSKEY => vec![validity_dnskey(qname, &validity)?],
+                _ => Vec::new(),
+            }))
+        }
+        INSCRIPTION_LABEL => {
+            if is_stored(db, qname).await? {
+                return Ok(None);
+            }
+
+            let (domain, subdomain) = split(name);
+            let records = db.get_subdomain_inscriptions(&domain, &subdomain).await?;
+
+            Ok(Some(match qtype {
+                QueryType::TXT => records
+                    .into_iter()
+                    .map(|(inscription, record)| {
+                        let text = format!(
+                            "{}; t={}; i={}; d={}",
+                            VERSION,
+                            record.rtype().to_string(),
+                            inscription,
+                            record.rdata
+                        );
+                        txt(qname, &text, record.ttl)
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{database, record, ADDRESS, PUBLIC_KEY};
+    use xdns_data::models::subdomain::RData;
+
+    fn texts(answers: Vec<DnsRecord>) -> Vec<String> {
+        answers
+            .into_iter()
+            .map(|answer| match answer {
+                DnsRecord::TXT { data, .. } => data.concat(),
+                other => panic!("expected a TXT record, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn answers_validity() {
+        let db = database().await;
+
+        let answers = answer(&db, "_validity.example.o", QueryType::TXT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            texts(answers),
+            vec![format!(
+                "v=xdns1; a={}; o={}; k={}",
+                Algorithm::Ed25519.name(),
+                ADDRESS,
+                PUBLIC_KEY
+            )]
+        );
+
+        let answers = answer(&db, "_validity.example.o", QueryType::DNSKEY)
+            .await
+            .unwrap()
+            .unwrap();
+        match &answers[..] {
+            [DnsRecord::DNSKEY {
+                domain,
+                flags,
+                algorithm,
+                public_key,
+                ..
+            }] => {
+                assert_eq!(domain, "_validity.example.o");
+                // Not a zone key, so it never validates signatures.
+                assert_eq!(*flags, 0);
+                assert_eq!(*algorithm, ALGORITHM_ED25519);
+                assert_eq!(hex::encode(public_key), PUBLIC_KEY);
+            }
+            other => panic!("expected a DNSKEY record, got {:?}", other),
+        }
+
+        assert!(answer(&db, "_validity.other.o", QueryType::TXT)
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[tokio::test]
+    async fn answers_inscriptions() {
+        let db = database().await;
+
+        let answers = answer(&db, "_inscription.www.example.o", QueryType::TXT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            texts(answers),
+            vec![
+                "v=xdns1; t=A; i=2i0; d=10.0.0.1",
+                "v=xdns1; t=AAAA; i=3i0; d=2001:db8::1",
+            ]
+        );
+
+        assert!(answer(&db, "www.example.o", QueryType::TXT)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn stored_records_take_precedence() {
+        let db = database().await;
+
+        for (i, subdomain) in ["_validity.", "_inscription.www."].into_iter().enumerate() {
+            let record = record(subdomain, RData::A("10.0.0.2".parse().unwrap()));
+            assert!(db.add_subdomain(ADDRESS, &format!("{}i1", i), record).await);
+        }
+
+        for qname in ["_validity.example.o", "_inscription.www.example.o"] {
+            assert!(answer(&db, qname, QueryType::TXT).await.unwrap().is_none());
+        }
+    }
+}
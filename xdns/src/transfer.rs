@@ -0,0 +1,493 @@
+use std::collections::HashSet;
+use std::env;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use db::{ChangeReceiver, Soa, ZoneExporter, ZoneTransfer};
+use dns_utils::prelude::*;
+use shared::prelude::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use xdns_data::models::SubDomain;
+
+use crate::utils::subdomain_cast::SubDomainCast;
+use crate::Database;
+
+/// The environment variable holding the addresses that may transfer zones, comma separated. (eg `192.0.2.1,2001:db8::1`)
+pub const TRANSFER_ALLOW_VAR: &str = "XDNS_TRANSFER_ALLOW";
+/// The environment variable holding the secondary servers to notify of changes, comma separated. (eg `192.0.2.1:53`)
+pub const NOTIFY_VAR: &str = "XDNS_NOTIFY";
+/// The maximum amount of records in a single message of a zone transfer.
+const RECORDS_PER_MESSAGE: usize = 100;
+/// How long a read or write of a transfer connection may take, an idle connection is closed after it.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which secondary servers zones are transferred to, and how the zones are described.
+#[derive(Debug, Clone, Default)]
+pub struct TransferConfig {
+    /// The addresses that may transfer zones, nobody when empty.
+    pub allow: HashSet<IpAddr>,
+    /// The secondary servers that are notified when the records of a domain change.
+    pub notify: Vec<SocketAddr>,
+    /// Synthesizes the `SOA` of the zones.
+    pub exporter: ZoneExporter,
+}
+
+impl TransferConfig {
+    /// Reads the configuration from the [`TRANSFER_ALLOW_VAR`] and [`NOTIFY_VAR`] environment variables.
+    /// Secondary servers without a port are notified on port 53.
+    ///
+    /// # Returns
+    ///
+    /// The configuration, or an error if an address is invalid.
+    pub fn from_env() -> Result<Self> {
+        let list = |name: &str| {
+            env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let invalid = |name: &str, value: &str| {
+            Error::Other(format!("Invalid address in {}: {}", name, value))
+        };
+
+        let allow = list(TRANSFER_ALLOW_VAR)
+            .iter()
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| invalid(TRANSFER_ALLOW_VAR, value))
+            })
+            .collect::<Result<_>>()?;
+        let notify = list(NOTIFY_VAR)
+            .iter()
+            .map(|value| {
+                value
+                    .parse()
+                    .or_else(|_| value.parse().map(|ip| SocketAddr::new(ip, 53)))
+                    .map_err(|_| invalid(NOTIFY_VAR, value))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            allow,
+            notify,
+            exporter: ZoneExporter::new(),
+        })
+    }
+
+    /// Whether an address may transfer zones.
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        self.allow.contains(ip)
+    }
+}
+
+/// Whether a name is the apex of a .o zone. (eg `example.o`)
+pub fn is_zone(name: &str) -> bool {
+    name.ends_with(".o") && name.split('.').count() == 2
+}
+
+/// Converts the `SOA` of a zone to a record.
+pub fn soa_record(domain: &str, soa: &Soa) -> DnsRecord {
+    DnsRecord::SOA {
+        domain: domain.to_string(),
+        primary: soa.primary.trim_end_matches('.').to_string(),
+        hostmaster: soa.hostmaster.trim_end_matches('.').to_string(),
+        serial: soa.serial,
+        refresh: soa.refresh,
+        retry: soa.retry,
+        expire: soa.expire,
+        minimum: soa.minimum,
+        ttl: soa.minimum,
+    }
+}
+
+fn records(subdomains: Vec<SubDomain>) -> impl Iterator<Item = DnsRecord> {
+    subdomains
+        .into_iter()
+        .map(|subdomain| SubDomainCast::from(subdomain).into())
+}
+
+/// Gets the current `SOA` record of a .o zone.
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the zone.
+/// * `config` - Describes the `SOA`.
+/// * `domain` - The apex of the zone. (eg `example.o`)
+///
+/// # Returns
+///
+/// The record, or an error if the domain does not exist.
+pub async fn soa(db: &Database, config: &TransferConfig, domain: &str) -> Result<DnsRecord> {
+    let soa = config.exporter.soa_of(db, domain).await?;
+
+    Ok(soa_record(domain, &soa))
+}
+
+/// Answers a zone transfer request. (AXFR or IXFR)
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the zones.
+/// * `config` - Who may transfer zones.
+/// * `ip` - The address of the client.
+/// * `request` - The request.
+///
+/// # Returns
+///
+/// The messages of the response, in order.
+pub async fn respond(
+    db: &Database,
+    config: &TransferConfig,
+    ip: IpAddr,
+    request: &DnsPacket,
+) -> Vec<DnsPacket> {
+    let mut packet = DnsPacket::new();
+    packet.header.id = request.header.id;
+    packet.header.response = true;
+    packet.header.authoritative_answer = true;
+    packet.questions = request.questions.clone();
+
+    let question = match request.questions.first() {
+        Some(question) if matches!(question.qtype, QueryType::AXFR | QueryType::IXFR) => question,
+        Some(_) => {
+            packet.header.rescode = ResultCode::NOTIMP;
+            return vec![packet];
+        }
+        None => {
+            packet.header.rescode = ResultCode::FORMERR;
+            return vec![packet];
+        }
+    };
+
+    if !config.is_allowed(&ip) || !is_zone(&question.name) {
+        println!("Refused transfer of {:?} to {}", question.name, ip);
+        packet.header.rescode = ResultCode::REFUSED;
+        return vec![packet];
+    }
+
+    // The serial the client has is the SOA in the authority section of an IXFR.
+    let since = match question.qtype {
+        QueryType::IXFR => request.authorities.iter().find_map(|record| match record {
+            DnsRecord::SOA { serial, .. } => Some(*serial),
+            _ => None,
+        }),
+        _ => None,
+    };
+
+    let domain = question.name.as_str();
+    let answers: Vec<DnsRecord> = match config.exporter.transfer(db, domain, since).await {
+        Ok(ZoneTransfer::Full { soa, records: rows }) => {
+            let soa = soa_record(domain, &soa);
+
+            std::iter::once(soa.clone())
+                .chain(records(rows))
+                .chain(std::iter::once(soa))
+                .collect()
+        }
+        Ok(ZoneTransfer::Incremental {
+            from,
+            soa,
+            deleted,
+            added,
+        }) => {
+            let soa = soa_record(domain, &soa);
+
+            [soa.clone(), soa_record(domain, &from)]
+                .into_iter()
+                .chain(records(deleted))
+                .chain(std::iter::once(soa.clone()))
+                .chain(records(added))
+                .chain(std::iter::once(soa))
+                .collect()
+        }
+        Ok(ZoneTransfer::UpToDate { soa }) => vec![soa_record(domain, &soa)],
+        Err(e) => {
+            println!("Transfer of {:?} failed: {}", domain, e);
+            packet.header.rescode = if e.is_not_found() {
+                ResultCode::REFUSED
+            } else {
+                ResultCode::SERVFAIL
+            };
+            return vec![packet];
+        }
+    };
+
+    println!(
+        "Transferring {:?} ({} records) to {}",
+        domain,
+        answers.len(),
+        ip
+    );
+
+    answers
+        .chunks(RECORDS_PER_MESSAGE)
+        .map(|chunk| {
+            let mut packet = packet.clone();
+            packet.answers = chunk.to_vec();
+            packet
+        })
+        .collect()
+}
+
+/// Runs a read or write of a transfer connection, failing with [`ErrorKind::TimedOut`] after [`TRANSFER_TIMEOUT`].
+async fn timed<T>(io: impl Future<Output = std::io::Result<T>>) -> std::io::Result<T> {
+    tokio::time::timeout(TRANSFER_TIMEOUT, io)
+        .await
+        .unwrap_or_else(|_| Err(ErrorKind::TimedOut.into()))
+}
+
+/// Answers the queries of a single TCP connection, until it is closed or idle for too long.
+/// Zone transfers are answered here, any other query (eg retried after a truncated UDP answer) like over UDP.
+async fn handle_connection(
+    db: Arc<Database>,
+    config: &TransferConfig,
+    mut stream: TcpStream,
+    src: SocketAddr,
+) -> Result<()> {
+    loop {
+        // Every message is prefixed with its length.
+        let len = match timed(stream.read_u16()).await {
+            Ok(len) => len as usize,
+            Err(e) if matches!(e.kind(), ErrorKind::UnexpectedEof | ErrorKind::TimedOut) => {
+                return Ok(())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut req_buffer = BytePacketBuffer::with_size(len);
+        timed(stream.read_exact(&mut req_buffer.buf)).await?;
+        let request = DnsPacket::from_buffer(&mut req_buffer)?;
+
+        let responses = match request.questions.first() {
+            Some(question) if matches!(question.qtype, QueryType::AXFR | QueryType::IXFR) => {
+                respond(db.as_ref(), config, src.ip(), &request).await
+            }
+            _ => vec![crate::respond(db.as_ref(), request).await],
+        };
+
+        for mut packet in responses {
+            let mut res_buffer = BytePacketBuffer::with_size(TCP_BUFFER_MAX_SIZE);
+            packet.write(&mut res_buffer)?;
+
+            let len = res_buffer.pos();
+            timed(stream.write_u16(len as u16)).await?;
+            timed(stream.write_all(res_buffer.get_range(0, len)?)).await?;
+        }
+    }
+}
+
+/// Accepts DNS connections over TCP, for zone transfers and for answers too large for UDP.
+///
+/// # Arguments
+///
+/// * `db` - The repository holding the zones.
+/// * `config` - Who may transfer zones.
+/// * `listener` - The bound TCP listener.
+pub async fn serve(db: Arc<Database>, config: &'static TransferConfig, listener: TcpListener) {
+    loop {
+        let (stream, src) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Failed to accept a transfer connection: {}", e);
+                continue;
+            }
+        };
+
+        let db = db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(db, config, stream, src).await {
+                eprintln!("Transfer connection with {} failed: {}", src, e);
+            }
+        });
+    }
+}
+
+/// Sends a NOTIFY for a zone to every configured secondary server. (RFC 1996)
+/// The notifications are not retried, a secondary server that misses one picks the change up at its next refresh.
+///
+/// # Arguments
+///
+/// * `config` - The secondary servers.
+/// * `domain` - The apex of the changed zone.
+///
+/// # Returns
+///
+/// The amount of secondary servers the NOTIFY was sent to, the failures are logged.
+pub async fn notify(config: &TransferConfig, domain: &str) -> Result<usize> {
+    static NEXT_ID: AtomicU16 = AtomicU16::new(0);
+
+    let mut packet = DnsPacket::new();
+    packet.header.id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    packet.header.opcode = OPCODE_NOTIFY;
+    packet.header.authoritative_answer = true;
+    packet
+        .questions
+        .push(DnsQuestion::new(domain.to_string(), QueryType::SOA));
+
+    let mut buffer = BytePacketBuffer::new();
+    packet.write(&mut buffer)?;
+
+    let mut sent = 0;
+
+    for secondary in &config.notify {
+        // The socket has to be of the same address family as the secondary server.
+        let bind = match secondary {
+            SocketAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+            SocketAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+        };
+        let result = match UdpSocket::bind(bind).await {
+            Ok(socket) => socket
+                .send_to(&buffer.buf[0..buffer.pos()], secondary)
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => sent += 1,
+            Err(e) => eprintln!("Failed to notify {} of {:?}: {}", secondary, domain, e),
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Notifies the secondary servers of every zone that changes, once per burst of changes.
+///
+/// # Arguments
+///
+/// * `config` - The secondary servers.
+/// * `changes` - The changes of every process using the database, as followed by a [`HistoryFollower`](db::HistoryFollower),
+///   so the inscriptions of the indexer are notified as well.
+pub async fn notify_changes(config: &TransferConfig, mut changes: ChangeReceiver) {
+    if config.notify.is_empty() {
+        return;
+    }
+
+    loop {
+        let mut domains = HashSet::new();
+
+        match changes.recv().await {
+            Ok(change) => domains.insert(change.domain),
+            Err(RecvError::Lagged(missed)) => {
+                println!(
+                    "Missed {} changes, secondaries catch up at their next refresh",
+                    missed
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        // A batch publishes a change per record, the zone only has to be notified once.
+        loop {
+            match changes.try_recv() {
+                Ok(change) => domains.insert(change.domain),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            };
+        }
+
+        for domain in domains.iter().filter(|domain| is_zone(domain)) {
+            match notify(config, domain).await {
+                Ok(sent) => println!(
+                    "Notified {} of {} secondaries of a change to {:?}",
+                    sent,
+                    config.notify.len(),
+                    domain
+                ),
+                Err(e) => eprintln!("Failed to notify secondaries of {:?}: {}", domain, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use xdns_data::prelude::Type;
+
+    #[tokio::test]
+    async fn notifies_every_family() {
+        let v4 = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let v6 = UdpSocket::bind("[::1]:0").await.unwrap();
+        // Nothing listens on port 0, sending to it fails without stopping the others.
+        let unreachable = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+        let config = TransferConfig {
+            notify: vec![
+                unreachable,
+                v6.local_addr().unwrap(),
+                v4.local_addr().unwrap(),
+            ],
+            ..TransferConfig::default()
+        };
+
+        assert_eq!(notify(&config, "example.o").await.unwrap(), 2);
+
+        for socket in [v4, v6] {
+            let mut buffer = BytePacketBuffer::new();
+            let received = tokio::time::timeout(TRANSFER_TIMEOUT, socket.recv(&mut buffer.buf))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(received > 0);
+
+            let packet = DnsPacket::from_buffer(&mut buffer).unwrap();
+            assert_eq!(packet.header.opcode, OPCODE_NOTIFY);
+            assert_eq!(packet.questions[0].name, "example.o");
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_queries_over_tcp() {
+        let db = Arc::new(fixtures::database().await);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let config = Box::leak(Box::new(TransferConfig::default()));
+        tokio::spawn(serve(db, config, listener));
+
+        let mut query = DnsPacket::new();
+        query.header.id = 7;
+        query.questions.push(DnsQuestion::new(
+            "www.example.o".to_string(),
+            QueryType::SUB(Type::A),
+        ));
+        let mut buffer = BytePacketBuffer::new();
+        query.write(&mut buffer).unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_u16(buffer.pos() as u16).await.unwrap();
+        stream
+            .write_all(buffer.get_range(0, buffer.pos()).unwrap())
+            .await
+            .unwrap();
+
+        let len = timed(stream.read_u16()).await.unwrap() as usize;
+        let mut buffer = BytePacketBuffer::with_size(len);
+        timed(stream.read_exact(&mut buffer.buf)).await.unwrap();
+        let response = DnsPacket::from_buffer(&mut buffer).unwrap();
+
+        assert_eq!(response.header.id, 7);
+        assert_eq!(response.header.rescode, ResultCode::NOERROR);
+        assert!(
+            response.answers.iter().any(|answer| matches!(
+                answer,
+                DnsRecord::A { domain, addr, .. }
+                    if domain == "www.example.o" && addr.to_string() == "10.0.0.1"
+            )),
+            "expected an A record, got {:?}",
+            response.answers
+        );
+    }
+}
@@ -27,13 +27,6 @@ where
         entry.push((value, expiration_time));
     }
 
-    pub fn contains_key<T>(&self, key: T) -> bool
-    where
-        T: Into<K> + Clone,
-    {
-        self.map.contains_key(&key.clone().into()) && self.get(key).is_some()
-    }
-
     pub fn get<T>(&self, key: T) -> Option<Vec<(V, Instant)>>
     where
         T: Into<K>,
@@ -54,6 +47,24 @@ where
         Some(values)
     }
 
+    /// Removes every key matching the predicate, regardless of its expiration time.
+    ///
+    /// # Returns
+    ///
+    /// The amount of removed values.
+    pub fn remove_where<F>(&mut self, mut predicate: F) -> usize
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let before = self.size();
+        self.map.retain(|key, _| !predicate(key));
+        before - self.size()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
     pub async fn cleanup(&mut self) {
         let now = Instant::now();
         self.map.retain(|_, entries| {
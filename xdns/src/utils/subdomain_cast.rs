@@ -1,7 +1,6 @@
 use dns_utils::prelude::DnsRecord;
-use shared::common::Error;
 use xdns_data::models::SubDomain;
-use xdns_data::prelude::Type;
+use xdns_data::prelude::RData;
 
 pub struct SubDomainCast(SubDomain);
 
@@ -21,37 +20,22 @@ impl From<SubDomain> for SubDomainCast {
     }
 }
 
-impl TryInto<DnsRecord> for SubDomainCast {
-    type Error = Error;
+impl From<SubDomainCast> for DnsRecord {
+    fn from(cast: SubDomainCast) -> Self {
+        let domain = cast.get_domain();
+        let ttl = cast.0.ttl;
 
-    fn try_into(self) -> Result<DnsRecord, Self::Error> {
-        Ok(match self.0.rtype {
-            Type::A => DnsRecord::A {
-                domain: self.get_domain(),
-                ttl: self.0.ttl,
-                addr: self.0.rdata.parse()?,
+        match cast.0.rdata {
+            RData::A(addr) => DnsRecord::A { domain, ttl, addr },
+            RData::AAAA(addr) => DnsRecord::AAAA { domain, ttl, addr },
+            RData::NS(host) => DnsRecord::NS { domain, ttl, host },
+            RData::CNAME(host) => DnsRecord::CNAME { domain, ttl, host },
+            RData::MX { priority, host } => DnsRecord::MX {
+                domain,
+                ttl,
+                priority,
+                host,
             },
-            Type::AAAA => DnsRecord::AAAA {
-                domain: self.get_domain(),
-                ttl: self.0.ttl,
-                addr: self.0.rdata.parse()?,
-            },
-            Type::NS => DnsRecord::NS {
-                domain: self.get_domain(),
-                ttl: self.0.ttl,
-                host: self.0.rdata,
-            },
-            Type::CNAME => DnsRecord::CNAME {
-                domain: self.get_domain(),
-                ttl: self.0.ttl,
-                host: self.0.rdata,
-            },
-            Type::MX => DnsRecord::MX {
-                domain: self.get_domain(),
-                ttl: self.0.ttl,
-                priority: 0,
-                host: self.0.rdata,
-            },
-        })
+        }
     }
 }